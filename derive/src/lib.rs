@@ -0,0 +1,124 @@
+//! Derive and attribute macros of [lw-webdriver](https://docs.rs/lw-webdriver), enabled by its `derive` feature.
+//!
+//! Use them through lw-webdriver (`lw_webdriver::page::PageObject`, `lw_webdriver::testing::webdriver_test`), the generated code refers to its items.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Field, Fields, FnArg, ItemFn, Lit, Meta, NestedMeta};
+
+/// Implement `lw_webdriver::page::PageObject` for a struct.
+///
+/// Fields annotated with `#[selector(css = "...")]` must be `LazyElement`s, located with the selector when they are used.
+/// The strategies are `css`, `xpath`, `tag_name`, `link_text`, `partial_link_text`, `text` and `contains_text`.
+/// Fields without the attribute must be page objects themselves (a header or a form shared by several pages).
+#[proc_macro_derive(PageObject, attributes(selector))]
+pub fn derive_page_object(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match page_object(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Turn a function taking a `&mut lw_webdriver::session::Session` into a test.
+///
+/// The session is opened by `lw_webdriver::testing::Fixture::from_env()` and closed after the test,
+/// the tabs being saved as screenshots if the test panics. The other attributes of the function are kept.
+#[proc_macro_attribute]
+pub fn webdriver_test(attribute: TokenStream, item: TokenStream) -> TokenStream {
+    let attribute = TokenStream2::from(attribute);
+    if !attribute.is_empty() {
+        return Error::new_spanned(attribute, "webdriver_test takes no arguments").to_compile_error().into();
+    }
+    let function = parse_macro_input!(item as ItemFn);
+    match webdriver_test_function(&function) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// -> take a function with a session argument
+/// -> return a test function running it with a session opened by the fixture
+fn webdriver_test_function(function: &ItemFn) -> Result<TokenStream2, Error> {
+    let signature = &function.sig;
+    let expected = "expected a single argument, like `session: &mut Session`";
+    let session = match (signature.inputs.len(), signature.inputs.first()) {
+        (1, Some(FnArg::Typed(argument))) => &argument.pat,
+        _ => return Err(Error::new_spanned(&signature.inputs, expected)),
+    };
+    if signature.asyncness.is_some() || !signature.generics.params.is_empty() {
+        return Err(Error::new_spanned(signature, "webdriver tests cannot be async or generic"));
+    }
+
+    let (attributes, visibility, name, body) = (&function.attrs, &function.vis, &signature.ident, &function.block);
+    Ok(quote! {
+        #[test]
+        #(#attributes)*
+        #visibility fn #name() {
+            ::lw_webdriver::testing::Fixture::from_env().run(stringify!(#name), |#session: &mut ::lw_webdriver::session::Session| #body);
+        }
+    })
+}
+
+/// -> take a struct
+/// -> return the implementation of PageObject
+fn page_object(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(Error::new_spanned(input, "PageObject can only be derived for structs")),
+    };
+    let initializers = fields.iter().map(initializer).collect::<Result<Vec<_>, Error>>()?;
+
+    let name = &input.ident;
+    let body = match fields {
+        Fields::Named(_) => {
+            let names = fields.iter().map(|field| &field.ident);
+            quote! { #name { #(#names: #initializers),* } }
+        },
+        Fields::Unnamed(_) => quote! { #name(#(#initializers),*) },
+        Fields::Unit => quote! { #name },
+    };
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::lw_webdriver::page::PageObject for #name #type_generics #where_clause {
+            fn new(tab: &::lw_webdriver::tab::Tab) -> Self {
+                #body
+            }
+        }
+    })
+}
+
+/// -> take a field of the struct
+/// -> return the expression building it from `tab`
+fn initializer(field: &Field) -> Result<TokenStream2, Error> {
+    let attribute = match field.attrs.iter().find(|attribute| attribute.path.is_ident("selector")) {
+        Some(attribute) => attribute,
+        None => return Ok(quote! { ::lw_webdriver::page::PageObject::new(tab) }),
+    };
+    let expected = "expected a selector like #[selector(css = \"...\")]";
+    let list = match attribute.parse_meta()? {
+        Meta::List(list) if list.nested.len() == 1 => list,
+        meta => return Err(Error::new_spanned(meta, expected)),
+    };
+    let (strategy, value) = match list.nested.first() {
+        Some(NestedMeta::Meta(Meta::NameValue(pair))) => match (pair.path.get_ident(), &pair.lit) {
+            (Some(strategy), Lit::Str(value)) => (strategy, value),
+            _ => return Err(Error::new_spanned(pair, expected)),
+        },
+        _ => return Err(Error::new_spanned(list, expected)),
+    };
+    let selector = match strategy.to_string().as_str() {
+        "css" => quote! { Css },
+        "xpath" => quote! { XPath },
+        "tag_name" => quote! { TagName },
+        "link_text" => quote! { LinkText },
+        "partial_link_text" => quote! { PartialLinkText },
+        "text" => quote! { Text },
+        "contains_text" => quote! { ContainsText },
+        _ => return Err(Error::new_spanned(strategy, "unknown strategy, expected css, xpath, tag_name, link_text, partial_link_text, text or contains_text")),
+    };
+    Ok(quote! { ::lw_webdriver::page::LazyElement::new(tab, ::lw_webdriver::enums::Selector::#selector, #value) })
+}
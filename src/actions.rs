@@ -0,0 +1,500 @@
+//! Low-level interactions with the mouse, the keyboard and the mouse wheel
+//!
+//! An [ActionChain](struct.ActionChain.html) is a list of actions performed one after the other
+//! with the [Actions API](https://www.w3.org/TR/webdriver/#actions) of the webdriver protocol.
+//! The browser receives real input events, unlike with scripts.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::{Browser, Selector}, actions::{ActionChain, MouseButton}};
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+//! let project = session.tabs[0].find(Selector::Css, ".project").unwrap().unwrap();
+//!
+//! let actions = ActionChain::new()
+//!     .scroll(0, 0, 0, 500)
+//!     .move_to_element(&project)
+//!     .click(MouseButton::Left);
+//! session.tabs[0].perform(&actions).unwrap();
+//! ```
+//!
+//! Pens and touch screens can be used too, and several fingers can touch the screen at the same time:
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::Browser, actions::ActionChain};
+//! use std::time::Duration;
+//!
+//! let mut session = Session::new(Browser::Chrome, false).unwrap();
+//! session.tabs[0].navigate("https://www.openstreetmap.org/").unwrap();
+//!
+//! // zoom in
+//! session.tabs[0].perform(&ActionChain::new().pinch(400, 300, 50, 200, Duration::from_millis(500))).unwrap();
+//! ```
+
+use std::time::Duration;
+use json::{JsonValue, object};
+use crate::elements::Element;
+
+/// A button of the mouse.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    Back,
+    Forward
+}
+
+impl MouseButton {
+    /// The button number used by the protocol.
+    pub fn to_u8(self) -> u8 {
+        match self {
+            MouseButton::Left => 0,
+            MouseButton::Middle => 1,
+            MouseButton::Right => 2,
+            MouseButton::Back => 3,
+            MouseButton::Forward => 4
+        }
+    }
+}
+
+/// A key of the keyboard, special keys being sent as the [codepoints](https://www.w3.org/TR/webdriver/#keyboard-actions)
+/// defined by the protocol.
+///
+/// Special keys can also be typed in texts:
+///
+/// ```rust
+/// # use lw_webdriver::actions::Keys;
+/// let text = format!("search terms{}", Keys::Enter.to_char());
+/// ```
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum Keys {
+    Backspace,
+    Tab,
+    Enter,
+    Shift,
+    Control,
+    Alt,
+    /// The Windows key, or the Command key on macOS.
+    Meta,
+    Escape,
+    Space,
+    PageUp,
+    PageDown,
+    End,
+    Home,
+    ArrowLeft,
+    ArrowUp,
+    ArrowRight,
+    ArrowDown,
+    Insert,
+    Delete,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    /// The key typing a character.
+    Char(char)
+}
+
+impl Keys {
+    pub fn to_char(self) -> char {
+        match self {
+            Keys::Backspace => '\u{E003}',
+            Keys::Tab => '\u{E004}',
+            Keys::Enter => '\u{E007}',
+            Keys::Shift => '\u{E008}',
+            Keys::Control => '\u{E009}',
+            Keys::Alt => '\u{E00A}',
+            Keys::Escape => '\u{E00C}',
+            Keys::Space => '\u{E00D}',
+            Keys::PageUp => '\u{E00E}',
+            Keys::PageDown => '\u{E00F}',
+            Keys::End => '\u{E010}',
+            Keys::Home => '\u{E011}',
+            Keys::ArrowLeft => '\u{E012}',
+            Keys::ArrowUp => '\u{E013}',
+            Keys::ArrowRight => '\u{E014}',
+            Keys::ArrowDown => '\u{E015}',
+            Keys::Insert => '\u{E016}',
+            Keys::Delete => '\u{E017}',
+            Keys::F1 => '\u{E031}',
+            Keys::F2 => '\u{E032}',
+            Keys::F3 => '\u{E033}',
+            Keys::F4 => '\u{E034}',
+            Keys::F5 => '\u{E035}',
+            Keys::F6 => '\u{E036}',
+            Keys::F7 => '\u{E037}',
+            Keys::F8 => '\u{E038}',
+            Keys::F9 => '\u{E039}',
+            Keys::F10 => '\u{E03A}',
+            Keys::F11 => '\u{E03B}',
+            Keys::F12 => '\u{E03C}',
+            Keys::Meta => '\u{E03D}',
+            Keys::Char(character) => character
+        }
+    }
+}
+
+impl From<char> for Keys {
+    fn from(character: char) -> Self {
+        Keys::Char(character)
+    }
+}
+
+/// The kind of device of a [Pointer](struct.Pointer.html).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum PointerType {
+    Mouse,
+    Pen,
+    Touch
+}
+
+impl PointerType {
+    pub fn to_string(self) -> &'static str {
+        match self {
+            PointerType::Mouse => "mouse",
+            PointerType::Pen => "pen",
+            PointerType::Touch => "touch"
+        }
+    }
+}
+
+/// A pointing device, like a pen or a finger. Pointers with different ids are different devices.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Pointer {
+    pub id: String,
+    pub pointer_type: PointerType
+}
+
+impl Pointer {
+    pub fn new(id: &str, pointer_type: PointerType) -> Self {
+        Pointer {
+            id: id.to_string(),
+            pointer_type
+        }
+    }
+
+    pub fn pen(id: &str) -> Self {
+        Pointer::new(id, PointerType::Pen)
+    }
+
+    /// A finger.
+    pub fn touch(id: &str) -> Self {
+        Pointer::new(id, PointerType::Touch)
+    }
+
+    fn to_json(&self) -> JsonValue {
+        object!{"type" => "pointer", "parameters" => object!{"pointerType" => self.pointer_type.to_string()}}
+    }
+}
+
+/// The [properties](https://w3c.github.io/pointerevents/#pointerevent-interface) of the pointer events of an action.
+/// Properties which are not set have their default value.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Default)]
+pub struct PointerProperties {
+    size: Option<(f64, f64)>,
+    pressure: Option<f64>,
+    tangential_pressure: Option<f64>,
+    tilt: Option<(i64, i64)>,
+    twist: Option<u16>
+}
+
+impl PointerProperties {
+    pub fn new() -> Self {
+        PointerProperties::default()
+    }
+
+    /// The width and the height of the contact area, in CSS pixels.
+    pub fn size(mut self, width: f64, height: f64) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// The pressure, from 0 to 1.
+    pub fn pressure(mut self, pressure: f64) -> Self {
+        self.pressure = Some(pressure);
+        self
+    }
+
+    /// The pressure of the barrel button of a pen, from -1 to 1.
+    pub fn tangential_pressure(mut self, pressure: f64) -> Self {
+        self.tangential_pressure = Some(pressure);
+        self
+    }
+
+    /// The angles between the pen and the screen, from -90 to 90 degrees on each axis.
+    pub fn tilt(mut self, x: i64, y: i64) -> Self {
+        self.tilt = Some((x, y));
+        self
+    }
+
+    /// The rotation of the pen around its axis, from 0 to 359 degrees.
+    pub fn twist(mut self, twist: u16) -> Self {
+        self.twist = Some(twist);
+        self
+    }
+
+    /// -> take a pointer action
+    /// add the properties which are set
+    fn apply(&self, mut action: JsonValue) -> JsonValue {
+        if let Some((width, height)) = self.size {
+            action["width"] = width.into();
+            action["height"] = height.into();
+        }
+        if let Some(pressure) = self.pressure {
+            action["pressure"] = pressure.into();
+        }
+        if let Some(pressure) = self.tangential_pressure {
+            action["tangentialPressure"] = pressure.into();
+        }
+        if let Some((x, y)) = self.tilt {
+            action["tiltX"] = x.into();
+            action["tiltY"] = y.into();
+        }
+        if let Some(twist) = self.twist {
+            action["twist"] = twist.into();
+        }
+        action
+    }
+}
+
+/// An input source of an action chain.
+struct Source {
+    id: String,
+    /// the source without its actions (its type and its parameters)
+    json: JsonValue
+}
+
+/// A list of actions performed one after the other.
+/// Perform it with [Tab::perform()](../tab/struct.Tab.html#method.perform).
+///
+/// Keys and buttons still pressed at the end of the chain stay pressed
+/// until they are released by another chain or by [Tab::release_actions()](../tab/struct.Tab.html#method.release_actions).
+#[derive(Default)]
+pub struct ActionChain {
+    sources: Vec<Source>,
+    /// the actions of each tick, with the index of their source, and the duration of the tick
+    ticks: Vec<(Vec<(usize, JsonValue)>, u64)>
+}
+
+impl ActionChain {
+    pub fn new() -> Self {
+        ActionChain::default()
+    }
+
+    /// -> take the id of an input source and the source
+    /// -> return the index of the source, adding it if it is not known
+    fn source(&mut self, id: &str, json: JsonValue) -> usize {
+        match self.sources.iter().position(|source| source.id == id) {
+            Some(index) => index,
+            None => {
+                self.sources.push(Source { id: id.to_string(), json });
+                self.sources.len() - 1
+            }
+        }
+    }
+
+    /// -> take an action of an input source
+    /// add a tick performing the action
+    fn push(mut self, id: &str, source: JsonValue, action: JsonValue) -> Self {
+        let index = self.source(id, source);
+        self.ticks.push((vec![(index, action)], 0));
+        self
+    }
+
+    fn mouse(self, action: JsonValue) -> Self {
+        let mouse = Pointer::new("mouse", PointerType::Mouse);
+        self.push(&mouse.id, mouse.to_json(), action)
+    }
+
+    fn keyboard(self, action: JsonValue) -> Self {
+        self.push("keyboard", object!{"type" => "key"}, action)
+    }
+
+    fn wheel(self, action: JsonValue) -> Self {
+        self.push("wheel", object!{"type" => "wheel"}, action)
+    }
+
+    /// Wait before the next action.
+    pub fn pause(mut self, duration: Duration) -> Self {
+        self.ticks.push((Vec::new(), duration.as_millis() as u64));
+        self
+    }
+
+    /// Move the mouse to a position of the viewport, in CSS pixels.
+    pub fn move_to(self, x: i64, y: i64) -> Self {
+        self.mouse(object!{"type" => "pointerMove", "duration" => 0, "origin" => "viewport", "x" => x, "y" => y})
+    }
+
+    /// Move the mouse relatively to its current position.
+    pub fn move_by(self, x: i64, y: i64) -> Self {
+        self.mouse(object!{"type" => "pointerMove", "duration" => 0, "origin" => "pointer", "x" => x, "y" => y})
+    }
+
+    /// Move the mouse to the center of the visible part of an element.
+    /// The element must be in the viewport.
+    pub fn move_to_element(self, element: &Element) -> Self {
+        self.move_to_element_with_offset(element, 0, 0)
+    }
+
+    /// Move the mouse to a position relative to the center of the visible part of an element.
+    pub fn move_to_element_with_offset(self, element: &Element, x: i64, y: i64) -> Self {
+        self.mouse(object!{"type" => "pointerMove", "duration" => 0, "origin" => element.as_json_object(), "x" => x, "y" => y})
+    }
+
+    /// Press a button of the mouse.
+    pub fn press(self, button: MouseButton) -> Self {
+        self.mouse(object!{"type" => "pointerDown", "button" => button.to_u8()})
+    }
+
+    /// Release a button of the mouse.
+    pub fn release(self, button: MouseButton) -> Self {
+        self.mouse(object!{"type" => "pointerUp", "button" => button.to_u8()})
+    }
+
+    /// Press and release a button of the mouse.
+    pub fn click(self, button: MouseButton) -> Self {
+        self.press(button).release(button)
+    }
+
+    /// Click twice with the left button.
+    pub fn double_click(self) -> Self {
+        self.click(MouseButton::Left).click(MouseButton::Left)
+    }
+
+    /// Press a key.
+    pub fn key_down(self, key: Keys) -> Self {
+        self.keyboard(object!{"type" => "keyDown", "value" => key.to_char().to_string()})
+    }
+
+    /// Release a key.
+    pub fn key_up(self, key: Keys) -> Self {
+        self.keyboard(object!{"type" => "keyUp", "value" => key.to_char().to_string()})
+    }
+
+    /// Press keys one after the other, then release them in the reverse order, like a keyboard shortcut.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::actions::{ActionChain, Keys};
+    /// // select all
+    /// let actions = ActionChain::new().key_chord(&[Keys::Control, Keys::from('a')]);
+    /// ```
+    pub fn key_chord(mut self, keys: &[Keys]) -> Self {
+        for key in keys {
+            self = self.key_down(*key);
+        }
+        for key in keys.iter().rev() {
+            self = self.key_up(*key);
+        }
+        self
+    }
+
+    /// Press and release the keys of a text, one character after the other.
+    pub fn send_keys(mut self, text: &str) -> Self {
+        for character in text.chars() {
+            self = self.key_down(Keys::Char(character)).key_up(Keys::Char(character));
+        }
+        self
+    }
+
+    /// Scroll with the mouse wheel, the mouse being at a position of the viewport.
+    /// The deltas are in CSS pixels, positive deltas scrolling to the right and to the bottom.
+    pub fn scroll(self, x: i64, y: i64, delta_x: i64, delta_y: i64) -> Self {
+        self.wheel(object!{"type" => "scroll", "duration" => 0, "origin" => "viewport", "x" => x, "y" => y, "deltaX" => delta_x, "deltaY" => delta_y})
+    }
+
+    /// Scroll with the mouse wheel, the mouse being at the center of an element.
+    /// The element is scrolled into view first if it is out of the viewport.
+    pub fn scroll_from_element(self, element: &Element, delta_x: i64, delta_y: i64) -> Self {
+        self.wheel(object!{"type" => "scroll", "duration" => 0, "origin" => element.as_json_object(), "x" => 0, "y" => 0, "deltaX" => delta_x, "deltaY" => delta_y})
+    }
+
+    /// Move a pointer to a position of the viewport in a duration, the pointer being down or not.
+    pub fn pointer_move(self, pointer: &Pointer, x: i64, y: i64, duration: Duration, properties: &PointerProperties) -> Self {
+        let action = properties.apply(object!{"type" => "pointerMove", "duration" => duration.as_millis() as u64, "origin" => "viewport", "x" => x, "y" => y});
+        self.push(&pointer.id, pointer.to_json(), action)
+    }
+
+    /// Put a pointer down (press the main button of a pen, or touch the screen).
+    pub fn pointer_down(self, pointer: &Pointer, properties: &PointerProperties) -> Self {
+        let action = properties.apply(object!{"type" => "pointerDown", "button" => 0});
+        self.push(&pointer.id, pointer.to_json(), action)
+    }
+
+    /// Lift a pointer up.
+    pub fn pointer_up(self, pointer: &Pointer) -> Self {
+        self.push(&pointer.id, pointer.to_json(), object!{"type" => "pointerUp", "button" => 0})
+    }
+
+    /// Perform chains at the same time, their first actions being performed together, then their second actions...
+    /// The chains must use different input sources, like different fingers.
+    pub fn simultaneously(mut self, chains: Vec<ActionChain>) -> Self {
+        let start = self.ticks.len();
+        for chain in chains {
+            let indexes: Vec<usize> = chain.sources.into_iter().map(|source| self.source(&source.id, source.json)).collect();
+            for (offset, (actions, duration)) in chain.ticks.into_iter().enumerate() {
+                if self.ticks.len() <= start + offset {
+                    self.ticks.push((Vec::new(), 0));
+                }
+                let tick = &mut self.ticks[start + offset];
+                tick.0.extend(actions.into_iter().map(|(index, action)| (indexes[index], action)));
+                tick.1 = tick.1.max(duration);
+            }
+        }
+        self
+    }
+
+    /// Pinch with two fingers around a position of the viewport, the distance between the fingers going from `from` to `to`.
+    /// The page zooms in if `to` is greater than `from`, and zooms out otherwise.
+    pub fn pinch(self, x: i64, y: i64, from: i64, to: i64, duration: Duration) -> Self {
+        let properties = PointerProperties::new();
+        let finger = |id: &str, direction: i64| {
+            let pointer = Pointer::touch(id);
+            ActionChain::new()
+                .pointer_move(&pointer, x + direction * from / 2, y, Duration::from_millis(0), &properties)
+                .pointer_down(&pointer, &properties)
+                .pointer_move(&pointer, x + direction * to / 2, y, duration, &properties)
+                .pointer_up(&pointer)
+        };
+        self.simultaneously(vec![finger("finger1", -1), finger("finger2", 1)])
+    }
+
+    /// Return the [action sequences](https://www.w3.org/TR/webdriver/#actions) sent to the driver,
+    /// the sources which do not act at a tick pausing.
+    pub fn to_json(&self) -> JsonValue {
+        let sequences: Vec<JsonValue> = self.sources.iter().enumerate().map(|(index, source)| {
+            let actions: Vec<JsonValue> = self.ticks.iter().map(|(tick, duration)| match tick.iter().find(|(source, _)| *source == index) {
+                Some((_, action)) => action.clone(),
+                None => object!{"type" => "pause", "duration" => *duration},
+            }).collect();
+
+            let mut sequence = source.json.clone();
+            sequence["id"] = source.id.as_str().into();
+            sequence["actions"] = actions.into();
+            sequence
+        }).collect();
+        sequences.into()
+    }
+}
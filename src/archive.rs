@@ -0,0 +1,22 @@
+//! Zip archives, used to send files to remote drivers
+
+use std::io::{Cursor, Write};
+use zip::{ZipWriter, CompressionMethod, write::FileOptions};
+use crate::error::WebdriverError;
+
+/// -> take the name of a file and its content
+/// -> return a zip archive containing the file, without compression
+pub(crate) fn zip_file(name: &str, content: &[u8]) -> Result<Vec<u8>, WebdriverError> {
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        // files of 4GiB and more need zip64 extensions
+        .large_file(content.len() as u64 >= u64::from(u32::MAX));
+    let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+    let result = archive.start_file(name, options)
+        .and_then(|()| archive.write_all(content).map_err(Into::into))
+        .and_then(|()| archive.finish());
+    match result {
+        Ok(archive) => Ok(archive.into_inner()),
+        Err(error) => Err(WebdriverError::custom(format!("failed to zip the file to upload: {}", error))),
+    }
+}
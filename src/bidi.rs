@@ -0,0 +1,60 @@
+//! A minimal WebDriver BiDi client, for the features of Firefox which classic webdriver does not provide
+//!
+//! The driver opens a WebSocket server when the session is created with [Capabilities::bidi()](../capabilities/struct.Capabilities.html#method.bidi).
+//! Only unencrypted (`ws://`) urls are supported, and the events sent by the browser are ignored.
+
+use std::sync::Mutex;
+use std::time::Duration;
+use json::{JsonValue, object};
+use log::debug;
+use crate::error::{WebdriverError, ErrorKind};
+use crate::websocket::WebSocket;
+
+/// A WebSocket connection to the BiDi server of a session.
+pub(crate) struct BidiConnection {
+    /// the connection and the id of the last command
+    socket: Mutex<(WebSocket, u64)>
+}
+
+impl BidiConnection {
+    /// -> take the `webSocketUrl` returned by the driver and how long to wait for a message (forever if None)
+    pub(crate) fn connect(url: &str, timeout: Option<Duration>) -> Result<Self, WebdriverError> {
+        Ok(BidiConnection {
+            socket: Mutex::new((WebSocket::connect(url, timeout)?, 0))
+        })
+    }
+
+    /// -> take the name of a command and its parameters
+    /// -> return the result of the command (the events received meanwhile are ignored)
+    pub(crate) fn command(&self, method: &str, params: JsonValue) -> Result<JsonValue, WebdriverError> {
+        let failed = |error: std::io::Error| WebdriverError::from(ErrorKind::FailedRequest).with_message(format!("BiDi command {} failed: {}", method, error));
+        let mut socket = self.socket.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (socket, last_id) = &mut *socket;
+        *last_id += 1;
+        let id = *last_id;
+
+        let message = object!{
+            "id" => id,
+            "method" => method,
+            "params" => params
+        }.dump();
+        debug!("BiDi: {}", message);
+        socket.send(&message).map_err(failed)?;
+
+        loop {
+            let text = socket.receive().map_err(failed)?;
+            let message = match json::parse(&text) {
+                Ok(message) => message,
+                Err(_) => return Err(WebdriverError::from(ErrorKind::InvalidResponse).with_message(format!("the BiDi server sent invalid json: {}", text))),
+            };
+            if message["id"].as_u64() != Some(id) {
+                continue;
+            }
+            return match message["type"].as_str() {
+                Some("success") => Ok(message["result"].clone()),
+                Some("error") => Err(WebdriverError::from(ErrorKind::from(message["error"].to_string())).with_message(message["message"].to_string())),
+                _ => Err(WebdriverError::from(ErrorKind::InvalidResponse).with_message(format!("unexpected BiDi message: {}", text))),
+            };
+        }
+    }
+}
@@ -0,0 +1,648 @@
+//! Capabilities allow you to configure the browser before creating a session
+
+use json::*;
+use std::path::{Path, PathBuf};
+use log::warn;
+use crate::enums::*;
+
+/// A Firefox profile used by a session.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum FirefoxProfile {
+    /// An existing profile directory, used in place (modifications are kept).
+    Directory(PathBuf),
+    /// A base64-encoded zip of a profile directory. The driver extracts it in a temporary directory.
+    Encoded(String)
+}
+
+/// A Chrome extension loaded by a session.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum ChromeExtension {
+    /// A base64-encoded packed extension (`.crx` file).
+    Encoded(String),
+    /// The directory of an unpacked extension.
+    Unpacked(PathBuf)
+}
+
+impl ChromeExtension {
+    /// Read and encode a packed extension (`.crx` file).
+    pub fn from_crx_file<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Ok(ChromeExtension::Encoded(base64::encode(&std::fs::read(path)?)))
+    }
+}
+
+/// The headless implementation used by Chrome and Edge.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum ChromeHeadlessMode {
+    /// The historical headless browser (`--headless`).
+    Classic,
+    /// The regular browser running without window (`--headless=new`). Renders like the headful browser and supports extensions.
+    New
+}
+
+impl ChromeHeadlessMode {
+    pub fn to_string(self) -> &'static str {
+        match self {
+            ChromeHeadlessMode::Classic => "--headless",
+            ChromeHeadlessMode::New => "--headless=new"
+        }
+    }
+}
+
+/// A mobile device emulated by Chrome and Edge, with [Capabilities::mobile_emulation()](struct.Capabilities.html#method.mobile_emulation).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum MobileEmulation {
+    /// A device of the devtools (like `Pixel 7` or `iPhone 14 Pro Max`), with its screen and user agent.
+    Device(String),
+    /// A custom device.
+    Metrics {
+        /// Width of the viewport in css pixels.
+        width: usize,
+        /// Height of the viewport in css pixels.
+        height: usize,
+        pixel_ratio: f64,
+        /// Whether to emit touch events instead of mouse events.
+        touch: bool,
+        /// The user agent of the device (the user agent of the browser is kept if None).
+        user_agent: Option<String>
+    }
+}
+
+impl MobileEmulation {
+    fn to_json(&self) -> JsonValue {
+        match self {
+            MobileEmulation::Device(name) => object!{"deviceName" => name.as_str()},
+            MobileEmulation::Metrics { width, height, pixel_ratio, touch, user_agent } => {
+                let mut emulation = object!{
+                    "deviceMetrics" => object!{
+                        "width" => *width,
+                        "height" => *height,
+                        "pixelRatio" => *pixel_ratio,
+                        "touch" => *touch
+                    }
+                };
+                if let Some(user_agent) = user_agent {
+                    emulation["userAgent"] = user_agent.as_str().into();
+                }
+                emulation
+            }
+        }
+    }
+}
+
+/// What the browser does with a user prompt (alert, confirm or prompt dialog) open when a command is received,
+/// set with [Capabilities::unhandled_prompt_behavior()](struct.Capabilities.html#method.unhandled_prompt_behavior).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum PromptBehavior {
+    /// Accept the prompt and run the command.
+    Accept,
+    /// Dismiss the prompt and run the command.
+    Dismiss,
+    /// Accept the prompt and fail with [UnexpectedAlertOpen](../error/enum.ErrorKind.html#variant.UnexpectedAlertOpen).
+    AcceptAndNotify,
+    /// Dismiss the prompt and fail with [UnexpectedAlertOpen](../error/enum.ErrorKind.html#variant.UnexpectedAlertOpen) (the default).
+    DismissAndNotify,
+    /// Leave the prompt open and fail with [UnexpectedAlertOpen](../error/enum.ErrorKind.html#variant.UnexpectedAlertOpen).
+    Ignore
+}
+
+impl PromptBehavior {
+    pub fn to_string(self) -> &'static str {
+        match self {
+            PromptBehavior::Accept => "accept",
+            PromptBehavior::Dismiss => "dismiss",
+            PromptBehavior::AcceptAndNotify => "accept and notify",
+            PromptBehavior::DismissAndNotify => "dismiss and notify",
+            PromptBehavior::Ignore => "ignore"
+        }
+    }
+}
+
+/// A type of resource which can be blocked to speed up page loads.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum Resource {
+    Images,
+    Fonts,
+    Stylesheets
+}
+
+/// Types of files Firefox saves without asking when a download directory is set.
+const DOWNLOADED_MIME_TYPES: [&str; 12] = [
+    "application/octet-stream", "application/pdf", "application/zip", "application/json", "application/xml", "text/csv", "text/plain",
+    "application/vnd.ms-excel", "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "application/msword", "application/vnd.openxmlformats-officedocument.wordprocessingml.document", "image/png"
+];
+
+/// Options used to create a session.
+///
+/// # Example
+///
+/// ```rust
+/// use lw_webdriver::{session::Session, capabilities::{Capabilities, FirefoxProfile}, enums::Browser};
+///
+/// let capabilities = Capabilities::new(Browser::Firefox)
+///     .headless(true)
+///     .firefox_profile(FirefoxProfile::Directory("/home/user/.mozilla/firefox/test.default".into()));
+///
+/// let mut session = Session::new_with_capabilities(capabilities).unwrap();
+/// ```
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Capabilities {
+    pub(crate) browser: Browser,
+    pub(crate) headless: bool,
+    chrome_headless_mode: ChromeHeadlessMode,
+    window_size: Option<(usize, usize)>,
+    kiosk: bool,
+    app: Option<String>,
+    args: Vec<String>,
+    blocked_resources: Vec<Resource>,
+    host_rules: Vec<(String, String)>,
+    notifications: bool,
+    network_log: bool,
+    bidi: bool,
+    pub(crate) download_directory: Option<PathBuf>,
+    firefox_prefs: Vec<(String, JsonValue)>,
+    chrome_prefs: Vec<(String, JsonValue)>,
+    firefox_profile: Option<FirefoxProfile>,
+    chrome_extensions: Vec<ChromeExtension>,
+    mobile_emulation: Option<MobileEmulation>,
+    locale: Option<String>,
+    accept_insecure_certs: bool,
+    unhandled_prompt_behavior: Option<PromptBehavior>,
+    pub(crate) timezone: Option<String>,
+    extra_capabilities: Vec<(String, JsonValue)>
+}
+
+impl Capabilities {
+    /// Default capabilities of a browser (not headless).
+    pub fn new(browser: Browser) -> Self {
+        Capabilities {
+            browser,
+            headless: false,
+            chrome_headless_mode: ChromeHeadlessMode::Classic,
+            window_size: None,
+            kiosk: false,
+            app: None,
+            args: Vec::new(),
+            blocked_resources: Vec::new(),
+            host_rules: Vec::new(),
+            notifications: false,
+            network_log: false,
+            bidi: false,
+            download_directory: None,
+            firefox_prefs: Vec::new(),
+            chrome_prefs: Vec::new(),
+            firefox_profile: None,
+            chrome_extensions: Vec::new(),
+            mobile_emulation: None,
+            locale: None,
+            accept_insecure_certs: false,
+            unhandled_prompt_behavior: None,
+            timezone: None,
+            extra_capabilities: Vec::new()
+        }
+    }
+
+    /// Headless mean that the browser will be opened but not displayed (useful for servers).
+    pub fn headless(mut self, headless: bool) -> Self {
+        self.headless = headless;
+        self
+    }
+
+    /// Choose the headless implementation of Chrome and Edge (classic by default).
+    /// Only used if the session is headless.
+    pub fn chrome_headless_mode(mut self, mode: ChromeHeadlessMode) -> Self {
+        self.chrome_headless_mode = mode;
+        self
+    }
+
+    /// Set the size of the browser window (width, height) in pixels.
+    /// Headless browsers use a small window by default, which can break responsive layouts.
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, capabilities::Capabilities, enums::Browser};
+    /// let capabilities = Capabilities::new(Browser::Chrome).headless(true).window_size(1920, 1080);
+    /// ```
+    pub fn window_size(mut self, width: usize, height: usize) -> Self {
+        self.window_size = Some((width, height));
+        self
+    }
+
+    /// Launch the browser in kiosk mode (fullscreen, without browser interface).
+    pub fn kiosk(mut self, kiosk: bool) -> Self {
+        self.kiosk = kiosk;
+        self
+    }
+
+    /// Launch the browser as an installed application window displaying an url (no tab bar, no address bar).
+    /// Firefox has no application mode: the url is opened in kiosk mode instead.
+    pub fn app(mut self, url: &str) -> Self {
+        self.app = Some(url.to_string());
+        self
+    }
+
+    /// Use a Firefox profile to reuse saved logins, certificates and extensions.
+    /// Ignored by other browsers.
+    pub fn firefox_profile(mut self, profile: FirefoxProfile) -> Self {
+        self.firefox_profile = Some(profile);
+        self
+    }
+
+    /// Load a Chrome extension (Edge loads Chrome extensions too).
+    /// Ignored by Firefox.
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, capabilities::{Capabilities, ChromeExtension}, enums::Browser};
+    /// let capabilities = Capabilities::new(Browser::Chrome)
+    ///     .chrome_extension(ChromeExtension::from_crx_file("extension.crx").unwrap())
+    ///     .chrome_extension(ChromeExtension::Unpacked("extensions/unpacked".into()));
+    /// ```
+    pub fn chrome_extension(mut self, extension: ChromeExtension) -> Self {
+        self.chrome_extensions.push(extension);
+        self
+    }
+
+    /// Emulate a mobile device (screen size, pixel ratio, touch events and user agent), to test responsive layouts.
+    /// Ignored by Firefox.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, capabilities::{Capabilities, MobileEmulation}, enums::Browser};
+    /// let capabilities = Capabilities::new(Browser::Chrome).mobile_emulation(MobileEmulation::Device("Pixel 7".to_string()));
+    ///
+    /// let capabilities = Capabilities::new(Browser::Chrome).mobile_emulation(MobileEmulation::Metrics {
+    ///     width: 390,
+    ///     height: 844,
+    ///     pixel_ratio: 3.0,
+    ///     touch: true,
+    ///     user_agent: Some("Mozilla/5.0 (iPhone; CPU iPhone OS 16_0 like Mac OS X)".to_string())
+    /// });
+    /// ```
+    pub fn mobile_emulation(mut self, emulation: MobileEmulation) -> Self {
+        self.mobile_emulation = Some(emulation);
+        self
+    }
+
+    /// Accept invalid TLS certificates (self-signed, expired or for another host), to drive staging environments.
+    /// Otherwise, navigating to such a site fails with [InsecureCertificate](../error/enum.ErrorKind.html#variant.InsecureCertificate).
+    pub fn accept_insecure_certs(mut self, accept: bool) -> Self {
+        self.accept_insecure_certs = accept;
+        self
+    }
+
+    /// Handle the alerts opened unexpectedly by pages, so that they do not make the next command fail.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, capabilities::{Capabilities, PromptBehavior}, enums::Browser};
+    /// let capabilities = Capabilities::new(Browser::Firefox).unhandled_prompt_behavior(PromptBehavior::Accept);
+    /// ```
+    pub fn unhandled_prompt_behavior(mut self, behavior: PromptBehavior) -> Self {
+        self.unhandled_prompt_behavior = Some(behavior);
+        self
+    }
+
+    /// Set the preferred languages of the browser (`fr-FR` or `fr-FR,fr,en`), sent in the `Accept-Language` header
+    /// and returned by `navigator.languages`. Chrome and Edge also use the first language for their interface and `Intl`.
+    pub fn locale(mut self, languages: &str) -> Self {
+        self.locale = Some(languages.to_string());
+        self
+    }
+
+    /// Set the timezone of the browser, as an IANA name (`Europe/Paris`, `America/New_York`...), to test how dates are formatted.
+    ///
+    /// Chrome and Edge override the timezone of every tab of the session with the Chrome DevTools Protocol.
+    /// Firefox uses the timezone of its process, so the `TZ` environment variable is set when the driver is launched by the session
+    /// (the timezone is ignored when the driver is already running or remote).
+    pub fn timezone(mut self, timezone: &str) -> Self {
+        self.timezone = Some(timezone.to_string());
+        self
+    }
+
+    /// Set a capability this crate has no method for (`se:recordVideo`, `webSocketUrl`...).
+    /// The value replaces the one generated by the other methods, if any (setting `moz:firefoxOptions` discards the Firefox arguments and preferences).
+    pub fn capability<T: Into<JsonValue>>(mut self, name: &str, value: T) -> Self {
+        self.extra_capabilities.push((name.to_string(), value.into()));
+        self
+    }
+
+    /// Add a command line argument of the browser.
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    /// Set a preference of Firefox (as in `about:config`).
+    /// Ignored by other browsers.
+    pub fn firefox_pref<T: Into<JsonValue>>(mut self, name: &str, value: T) -> Self {
+        self.firefox_prefs.push((name.to_string(), value.into()));
+        self
+    }
+
+    /// Set a preference of Chrome or Edge (as in the `Preferences` file of a profile).
+    /// Ignored by Firefox.
+    pub fn chrome_pref<T: Into<JsonValue>>(mut self, name: &str, value: T) -> Self {
+        self.chrome_prefs.push((name.to_string(), value.into()));
+        self
+    }
+
+    /// Prevent the browser from loading a type of resource.
+    /// Useful to speed up large crawls when only the DOM matters.
+    /// 
+    /// Firefox uses preferences. Chrome and Edge use preferences for images and block font and stylesheet urls on every tab of the session.
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, capabilities::{Capabilities, Resource}, enums::Browser};
+    /// let capabilities = Capabilities::new(Browser::Firefox)
+    ///     .block_resource(Resource::Images)
+    ///     .block_resource(Resource::Fonts)
+    ///     .block_resource(Resource::Stylesheets);
+    /// ```
+    pub fn block_resource(mut self, resource: Resource) -> Self {
+        if !self.blocked_resources.contains(&resource) {
+            self.blocked_resources.push(resource);
+        }
+        self
+    }
+
+    /// Resolve a host to another address (`ip` or `ip:port`) without editing `/etc/hosts`.
+    /// 
+    /// Chrome and Edge use host resolver rules. Firefox uses a generated proxy auto-config script sending the requests to the address,
+    /// which only works for plain http (the server must accept absolute urls in requests, which most servers do).
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, capabilities::Capabilities, enums::Browser};
+    /// let capabilities = Capabilities::new(Browser::Chrome)
+    ///     .host_resolver_rule("myapp.test", "127.0.0.1:8080");
+    /// ```
+    pub fn host_resolver_rule(mut self, host: &str, address: &str) -> Self {
+        self.host_rules.push((host.to_string(), address.to_string()));
+        self
+    }
+
+    /// Grant the permission to display notifications to every site, without asking the user.
+    /// Notifications can then be captured with [Tab::capture_notifications()](../tab/struct.Tab.html#method.capture_notifications).
+    pub fn allow_notifications(mut self, allow: bool) -> Self {
+        self.notifications = allow;
+        self
+    }
+
+    /// Make Chrome and Edge log the requests of every tab, so that [Tab::stop_har_recording()](../tab/struct.Tab.html#method.stop_har_recording)
+    /// can export their headers and detailed timings. Ignored by Firefox.
+    pub fn network_log(mut self, enabled: bool) -> Self {
+        self.network_log = enabled;
+        self
+    }
+
+    /// Ask the driver for a WebDriver BiDi server, used by [Tab::add_init_script()](../tab/struct.Tab.html#method.add_init_script)
+    /// on browsers without the Chrome DevTools Protocol (Firefox).
+    pub fn bidi(mut self, enabled: bool) -> Self {
+        self.bidi = enabled;
+        self
+    }
+
+    /// Save downloaded files in a directory without asking the user (PDF files are downloaded instead of being displayed).
+    /// A relative path is resolved from the current directory, and the directory is created if needed.
+    /// Downloads can then be awaited with [Tab::wait_for_download()](../tab/struct.Tab.html#method.wait_for_download).
+    pub fn download_directory<P: AsRef<Path>>(mut self, directory: P) -> Self {
+        let directory = directory.as_ref();
+        self.download_directory = Some(match std::env::current_dir() {
+            Ok(current) if directory.is_relative() => current.join(directory),
+            _ => directory.to_path_buf(),
+        });
+        self
+    }
+
+    /// The url patterns blocked with the Chrome DevTools Protocol on every tab.
+    pub(crate) fn blocked_url_patterns(&self) -> Vec<String> {
+        let mut patterns = Vec::new();
+        if self.browser.cdp_vendor().is_some() {
+            for resource in &self.blocked_resources {
+                match resource {
+                    Resource::Images => (),
+                    Resource::Fonts => patterns.extend(["*.woff", "*.woff2", "*.ttf", "*.otf", "*.eot"].iter().map(|pattern| pattern.to_string())),
+                    Resource::Stylesheets => patterns.push("*.css".to_string()),
+                }
+            }
+        }
+        patterns
+    }
+
+    pub fn get_browser(&self) -> Browser {
+        self.browser.clone()
+    }
+
+    /// Generate the body of a session creation request.
+    pub fn to_json(&self) -> JsonValue {
+        let mut always_match = object!{
+            "platformName" => Platform::current().to_string(),
+            "browserName" => self.browser.to_string()
+        };
+        if self.accept_insecure_certs {
+            always_match["acceptInsecureCerts"] = true.into();
+        }
+        if self.bidi {
+            always_match["webSocketUrl"] = true.into();
+        }
+        if let Some(behavior) = self.unhandled_prompt_behavior {
+            always_match["unhandledPromptBehavior"] = behavior.to_string().into();
+        }
+        let mut options = JsonValue::new_object();
+        let mut args: Vec<String> = Vec::new();
+        let mut prefs = JsonValue::new_object();
+
+        match self.browser {
+            Browser::Firefox => {
+                if self.headless {
+                    args.push("-headless".to_string());
+                }
+                if let Some((width, height)) = self.window_size {
+                    args.push(format!("--width={}", width));
+                    args.push(format!("--height={}", height));
+                }
+                for resource in &self.blocked_resources {
+                    match resource {
+                        Resource::Images => prefs["permissions.default.image"] = 2.into(),
+                        Resource::Fonts => prefs["browser.display.use_document_fonts"] = 0.into(),
+                        Resource::Stylesheets => prefs["permissions.default.stylesheet"] = 2.into(),
+                    }
+                }
+                if self.notifications {
+                    prefs["permissions.default.desktop-notification"] = 1.into();
+                }
+                if let Some(directory) = &self.download_directory {
+                    prefs["browser.download.folderList"] = 2.into();
+                    prefs["browser.download.dir"] = directory.to_string_lossy().to_string().into();
+                    prefs["browser.download.useDownloadDir"] = true.into();
+                    prefs["browser.download.always_ask_before_handling_new_types"] = false.into();
+                    prefs["browser.download.manager.showWhenStarting"] = false.into();
+                    prefs["browser.helperApps.neverAsk.saveToDisk"] = DOWNLOADED_MIME_TYPES.join(",").into();
+                    prefs["pdfjs.disabled"] = true.into();
+                }
+                if let Some(languages) = &self.locale {
+                    prefs["intl.accept_languages"] = languages.as_str().into();
+                }
+                for (name, value) in &self.firefox_prefs {
+                    prefs[name.as_str()] = value.clone();
+                }
+                if self.kiosk || self.app.is_some() {
+                    args.push("--kiosk".to_string());
+                }
+                if let Some(url) = &self.app {
+                    args.push(url.clone());
+                }
+                match &self.firefox_profile {
+                    Some(FirefoxProfile::Directory(path)) => {
+                        args.push("-profile".to_string());
+                        args.push(path.to_string_lossy().to_string());
+                    },
+                    Some(FirefoxProfile::Encoded(profile)) => {
+                        options["profile"] = profile.as_str().into();
+                    },
+                    None => (),
+                }
+            },
+            Browser::Chrome | Browser::Edge | Browser::ChromiumBased { .. } => {
+                if let Browser::ChromiumBased { binary } = &self.browser {
+                    options["binary"] = binary.to_string_lossy().to_string().into();
+                }
+                if self.headless {
+                    args.push(self.chrome_headless_mode.to_string().to_string());
+                }
+                if let Some((width, height)) = self.window_size {
+                    args.push(format!("--window-size={},{}", width, height));
+                }
+                if self.blocked_resources.contains(&Resource::Images) {
+                    prefs["profile.managed_default_content_settings.images"] = 2.into();
+                    args.push("--blink-settings=imagesEnabled=false".to_string());
+                }
+                if self.notifications {
+                    prefs["profile.default_content_setting_values.notifications"] = 1.into();
+                }
+                if let Some(directory) = &self.download_directory {
+                    prefs["download.default_directory"] = directory.to_string_lossy().to_string().into();
+                    prefs["download.prompt_for_download"] = false.into();
+                    prefs["download.directory_upgrade"] = true.into();
+                    prefs["plugins.always_open_pdf_externally"] = true.into();
+                }
+                if let Some(languages) = &self.locale {
+                    prefs["intl.accept_languages"] = languages.as_str().into();
+                    args.push(format!("--lang={}", languages.split(',').next().unwrap_or("").trim()));
+                }
+                for (name, value) in &self.chrome_prefs {
+                    prefs[name.as_str()] = value.clone();
+                }
+                if self.kiosk {
+                    args.push("--kiosk".to_string());
+                }
+                if let Some(url) = &self.app {
+                    args.push(format!("--app={}", url));
+                }
+
+                let mut encoded_extensions: Vec<&str> = Vec::new();
+                let mut unpacked_extensions: Vec<String> = Vec::new();
+                for extension in &self.chrome_extensions {
+                    match extension {
+                        ChromeExtension::Encoded(extension) => encoded_extensions.push(extension),
+                        ChromeExtension::Unpacked(path) => unpacked_extensions.push(path.to_string_lossy().to_string()),
+                    }
+                }
+                if !encoded_extensions.is_empty() {
+                    options["extensions"] = encoded_extensions.into();
+                }
+                if !unpacked_extensions.is_empty() {
+                    args.push(format!("--load-extension={}", unpacked_extensions.join(",")));
+                }
+                if let Some(emulation) = &self.mobile_emulation {
+                    options["mobileEmulation"] = emulation.to_json();
+                }
+                // needed by Tab::get_console_logs() and Tab::stop_har_recording()
+                if let Some(vendor) = self.browser.cdp_vendor() {
+                    let mut logging_prefs = object!{"browser" => "ALL"};
+                    if self.network_log {
+                        logging_prefs["performance"] = "ALL".into();
+                    }
+                    always_match[format!("{}:loggingPrefs", vendor)] = logging_prefs;
+                }
+            },
+            Browser::Safari => {
+                // safaridriver only knows the standard capabilities
+                let unsupported = [
+                    ("headless mode", self.headless),
+                    ("window size", self.window_size.is_some()),
+                    ("kiosk mode", self.kiosk || self.app.is_some()),
+                    ("arguments", !self.args.is_empty()),
+                    ("blocked resources", !self.blocked_resources.is_empty()),
+                    ("mobile emulation", self.mobile_emulation.is_some()),
+                    ("locale", self.locale.is_some()),
+                    ("timezone", self.timezone.is_some()),
+                    ("host resolver rules", !self.host_rules.is_empty()),
+                ];
+                for (option, _) in unsupported.iter().filter(|(_, set)| *set) {
+                    warn!("Safari does not support the {} capability, it is ignored", option);
+                }
+            }
+        }
+        args.extend(self.args.iter().cloned());
+
+        if !self.host_rules.is_empty() {
+            match self.browser {
+                Browser::Firefox => {
+                    let mut script = String::from("function FindProxyForURL(url, host) {");
+                    for (host, address) in &self.host_rules {
+                        let address = if address.contains(':') { address.clone() } else { format!("{}:80", address) };
+                        script.push_str(&format!("if (host == '{}') return 'PROXY {}';", host, address));
+                    }
+                    script.push_str("return 'DIRECT';}");
+
+                    let mut url = String::from("data:application/x-ns-proxy-autoconfig,");
+                    for byte in script.bytes() {
+                        match byte {
+                            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => url.push(byte as char),
+                            _ => url.push_str(&format!("%{:02X}", byte)),
+                        }
+                    }
+                    always_match["proxy"] = object!{
+                        "proxyType" => "pac",
+                        "proxyAutoconfigUrl" => url
+                    };
+                },
+                Browser::Chrome | Browser::Edge | Browser::ChromiumBased { .. } => {
+                    let rules: Vec<String> = self.host_rules.iter().map(|(host, address)| format!("MAP {} {}", host, address)).collect();
+                    args.push(format!("--host-resolver-rules={}", rules.join(", ")));
+                },
+                Browser::Safari => ()
+            }
+        }
+
+        if !args.is_empty() {
+            options["args"] = args.into();
+        }
+        if !prefs.is_empty() {
+            options["prefs"] = prefs;
+        }
+        if let (false, Some(options_key)) = (options.is_empty(), self.browser.options_key()) {
+            always_match[options_key] = options;
+        }
+        for (name, value) in &self.extra_capabilities {
+            always_match[name.as_str()] = value.clone();
+        }
+
+        object!{
+            "capabilities" => object!{
+                "alwaysMatch" => always_match
+            }
+        }
+    }
+}
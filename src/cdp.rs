@@ -0,0 +1,310 @@
+//! Chrome DevTools Protocol, for features of Chromium-based browsers which webdriver does not provide
+//!
+//! Every method of this module returns [UnknowCommand](../error/enum.ErrorKind.html#variant.UnknowCommand) on Firefox,
+//! except [add_init_script()](../tab/struct.Tab.html#method.add_init_script) which uses WebDriver BiDi there.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::Browser, cdp::DeviceMetrics};
+//! use json::object;
+//!
+//! let mut session = Session::new(Browser::Chrome, false).unwrap();
+//! println!("devtools are listening on {:?}", session.debugger_address());
+//!
+//! // a typed helper
+//! session.tabs[0].emulate_device(DeviceMetrics::mobile(390, 844)).unwrap();
+//!
+//! // any command of the protocol
+//! session.tabs[0].cdp_command("Emulation.setTimezoneOverride", object!{"timezoneId" => "Europe/Paris"}).unwrap();
+//! ```
+
+use std::time::Duration;
+use json::{JsonValue, object};
+use crate::tab::Tab;
+use crate::error::{WebdriverError, ErrorKind};
+
+/// The screen emulated by [Tab::emulate_device()](../tab/struct.Tab.html#method.emulate_device).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct DeviceMetrics {
+    /// Width of the viewport in css pixels.
+    pub width: usize,
+    /// Height of the viewport in css pixels.
+    pub height: usize,
+    pub device_scale_factor: f64,
+    /// Whether to emulate a mobile device (meta viewport, overlay scrollbars, text autosizing...).
+    pub mobile: bool
+}
+
+impl DeviceMetrics {
+    /// A desktop screen.
+    pub fn new(width: usize, height: usize) -> Self {
+        DeviceMetrics {
+            width,
+            height,
+            device_scale_factor: 1.0,
+            mobile: false
+        }
+    }
+
+    /// A phone screen with a device pixel ratio of 3.
+    pub fn mobile(width: usize, height: usize) -> Self {
+        DeviceMetrics {
+            width,
+            height,
+            device_scale_factor: 3.0,
+            mobile: true
+        }
+    }
+}
+
+/// The network emulated by [Tab::emulate_network()](../tab/struct.Tab.html#method.emulate_network).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct NetworkConditions {
+    pub offline: bool,
+    /// Additional latency of every request.
+    pub latency: Duration,
+    /// Maximal download speed in bytes per second (None for no limit).
+    pub download_throughput: Option<usize>,
+    /// Maximal upload speed in bytes per second (None for no limit).
+    pub upload_throughput: Option<usize>
+}
+
+impl NetworkConditions {
+    /// No network at all.
+    pub fn offline() -> Self {
+        NetworkConditions {
+            offline: true,
+            latency: Duration::from_millis(0),
+            download_throughput: None,
+            upload_throughput: None
+        }
+    }
+
+    /// The "Slow 3G" preset of the devtools.
+    pub fn slow_3g() -> Self {
+        NetworkConditions {
+            offline: false,
+            latency: Duration::from_millis(2000),
+            download_throughput: Some(50_000),
+            upload_throughput: Some(50_000)
+        }
+    }
+
+    /// The "Fast 3G" preset of the devtools.
+    pub fn fast_3g() -> Self {
+        NetworkConditions {
+            offline: false,
+            latency: Duration::from_millis(563),
+            download_throughput: Some(180_000),
+            upload_throughput: Some(84_375)
+        }
+    }
+}
+
+/// A script added by [Tab::add_init_script()](../tab/struct.Tab.html#method.add_init_script), to remove it with
+/// [Tab::remove_init_script()](../tab/struct.Tab.html#method.remove_init_script).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct InitScript(String);
+
+impl InitScript {
+    /// The identifier given by the browser.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The code of a script executed since [Tab::start_js_coverage()](../tab/struct.Tab.html#method.start_js_coverage).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct ScriptCoverage {
+    pub url: String,
+    /// Ranges of the script (in characters) with the number of times they were executed.
+    /// Nested ranges override the count of the ranges containing them, and code outside of every range was not executed.
+    pub ranges: Vec<CoveredRange>
+}
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct CoveredRange {
+    pub start: usize,
+    pub end: usize,
+    pub count: usize
+}
+
+impl ScriptCoverage {
+    fn from_json(value: &JsonValue) -> Self {
+        ScriptCoverage {
+            url: value["url"].as_str().unwrap_or("").to_string(),
+            ranges: value["functions"].members()
+                .flat_map(|function| function["ranges"].members())
+                .map(|range| CoveredRange {
+                    start: range["startOffset"].as_usize().unwrap_or(0),
+                    end: range["endOffset"].as_usize().unwrap_or(0),
+                    count: range["count"].as_usize().unwrap_or(0)
+                })
+                .collect()
+        }
+    }
+}
+
+impl Tab {
+    /// Execute a command of the [Chrome DevTools Protocol](https://chromedevtools.github.io/devtools-protocol/) on this tab
+    /// and return its result.
+    pub fn cdp_command(&self, method: &str, params: JsonValue) -> Result<JsonValue, WebdriverError> {
+        self.select()?;
+        self.execute_cdp_command(method, params)
+    }
+
+    /// Emulate the screen of another device. The emulation persists across navigations.
+    pub fn emulate_device(&self, metrics: DeviceMetrics) -> Result<(), WebdriverError> {
+        self.cdp_command("Emulation.setDeviceMetricsOverride", object!{
+            "width" => metrics.width,
+            "height" => metrics.height,
+            "deviceScaleFactor" => metrics.device_scale_factor,
+            "mobile" => metrics.mobile
+        })?;
+        Ok(())
+    }
+
+    /// Stop emulating a device.
+    pub fn clear_device_emulation(&self) -> Result<(), WebdriverError> {
+        self.cdp_command("Emulation.clearDeviceMetricsOverride", object!{})?;
+        Ok(())
+    }
+
+    /// Send another user agent in requests and `navigator.userAgent`.
+    pub fn set_user_agent(&self, user_agent: &str) -> Result<(), WebdriverError> {
+        self.cdp_command("Network.setUserAgentOverride", object!{
+            "userAgent" => user_agent
+        })?;
+        Ok(())
+    }
+
+    /// Use another timezone, as an IANA name (`Europe/Paris`). The override persists across navigations.
+    pub fn set_timezone(&self, timezone: &str) -> Result<(), WebdriverError> {
+        self.cdp_command("Emulation.setTimezoneOverride", object!{
+            "timezoneId" => timezone
+        })?;
+        Ok(())
+    }
+
+    /// Answer the geolocation requests of the page with a position (the page must be allowed to access it).
+    pub fn set_geolocation(&self, latitude: f64, longitude: f64, accuracy: f64) -> Result<(), WebdriverError> {
+        if latitude.abs() > 90.0 || longitude.abs() > 180.0 {
+            return Err(ErrorKind::InvalidArgument.into());
+        }
+        self.cdp_command("Emulation.setGeolocationOverride", object!{
+            "latitude" => latitude,
+            "longitude" => longitude,
+            "accuracy" => accuracy
+        })?;
+        Ok(())
+    }
+
+    /// Emulate a slow or unavailable network.
+    pub fn emulate_network(&self, conditions: NetworkConditions) -> Result<(), WebdriverError> {
+        self.cdp_command("Network.enable", object!{})?;
+        self.cdp_command("Network.emulateNetworkConditions", object!{
+            "offline" => conditions.offline,
+            "latency" => conditions.latency.as_millis() as f64,
+            // -1 disables throttling
+            "downloadThroughput" => conditions.download_throughput.map(|throughput| throughput as f64).unwrap_or(-1.0),
+            "uploadThroughput" => conditions.upload_throughput.map(|throughput| throughput as f64).unwrap_or(-1.0)
+        })?;
+        Ok(())
+    }
+
+    /// Run a script in every document loaded by this tab (including frames), before the scripts of the page.
+    /// Useful to stub APIs, to freeze the clock or to install hooks. The script persists across navigations.
+    ///
+    /// Documents which are already loaded are not affected. Other browsers than Chrome and Edge use a WebDriver BiDi preload script,
+    /// which requires [Capabilities::bidi()](../capabilities/struct.Capabilities.html#method.bidi)
+    /// ([UnknowCommand](../error/enum.ErrorKind.html#variant.UnknowCommand) is returned otherwise).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Chrome, false).unwrap();
+    /// let script = session.tabs[0].add_init_script("Date.now = () => 0;").unwrap();
+    /// session.tabs[0].navigate("https://example.com/").unwrap();
+    /// assert_eq!(session.tabs[0].execute_script("return Date.now();", Vec::new()).unwrap(), 0);
+    ///
+    /// session.tabs[0].remove_init_script(script).unwrap();
+    /// ```
+    pub fn add_init_script(&self, script: &str) -> Result<InitScript, WebdriverError> {
+        if self.browser.cdp_vendor().is_none() {
+            let result = self.connection.bidi_command("script.addPreloadScript", object!{
+                "functionDeclaration" => format!("() => {{\n{}\n}}", script),
+                "contexts" => vec![self.id.as_str()]
+            })?;
+            return match result["script"].as_str() {
+                Some(identifier) => Ok(InitScript(identifier.to_string())),
+                None => Err(WebdriverError::from(ErrorKind::InvalidResponse).with_message(format!("no script in {}", result.dump()))),
+            };
+        }
+        let result = self.cdp_command("Page.addScriptToEvaluateOnNewDocument", object!{
+            "source" => script
+        })?;
+        match result["identifier"].as_str() {
+            Some(identifier) => Ok(InitScript(identifier.to_string())),
+            None => Err(WebdriverError::from(ErrorKind::InvalidResponse).with_message(format!("no identifier in {}", result.dump()))),
+        }
+    }
+
+    /// Stop running a script added by [add_init_script()](#method.add_init_script) in the next documents.
+    pub fn remove_init_script(&self, script: InitScript) -> Result<(), WebdriverError> {
+        if self.browser.cdp_vendor().is_none() {
+            self.connection.bidi_command("script.removePreloadScript", object!{
+                "script" => script.0
+            })?;
+            return Ok(());
+        }
+        self.cdp_command("Page.removeScriptToEvaluateOnNewDocument", object!{
+            "identifier" => script.0
+        })?;
+        Ok(())
+    }
+
+    /// Start recording which javascript code is executed.
+    /// Call [take_js_coverage()](#method.take_js_coverage) to get the results.
+    pub fn start_js_coverage(&self) -> Result<(), WebdriverError> {
+        self.cdp_command("Profiler.enable", object!{})?;
+        self.cdp_command("Profiler.startPreciseCoverage", object!{
+            "callCount" => true,
+            "detailed" => true
+        })?;
+        Ok(())
+    }
+
+    /// Stop recording the execution of javascript code and return the coverage of every script.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Chrome, false).unwrap();
+    /// session.tabs[0].start_js_coverage().unwrap();
+    /// session.tabs[0].navigate("https://www.mozilla.org/en-US/").unwrap();
+    ///
+    /// for script in session.tabs[0].take_js_coverage().unwrap() {
+    ///     let executed = script.ranges.iter().filter(|range| range.count > 0).map(|range| range.end - range.start).sum::<usize>();
+    ///     println!("{}: {} characters executed", script.url, executed);
+    /// }
+    /// ```
+    pub fn take_js_coverage(&self) -> Result<Vec<ScriptCoverage>, WebdriverError> {
+        let coverage = self.cdp_command("Profiler.takePreciseCoverage", object!{})?;
+        self.cdp_command("Profiler.stopPreciseCoverage", object!{})?;
+        self.cdp_command("Profiler.disable", object!{})?;
+        Ok(coverage["result"].members().map(ScriptCoverage::from_json).collect())
+    }
+}
@@ -0,0 +1,200 @@
+//! TLS certificates of loaded pages
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A X.509 certificate sent by a server.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Certificate {
+    /// Distinguished name of the owner of the certificate (`CN=example.com, O=...`).
+    pub subject: String,
+    /// Distinguished name of the authority which signed the certificate.
+    pub issuer: String,
+    pub not_before: SystemTime,
+    pub not_after: SystemTime,
+    /// DNS names and IP addresses covered by the certificate.
+    pub subject_alt_names: Vec<String>,
+    /// The raw DER-encoded certificate.
+    pub der: Vec<u8>
+}
+
+impl Certificate {
+    /// Parse a DER-encoded certificate.
+    /// Return None if the certificate is malformed.
+    pub fn from_der(der: Vec<u8>) -> Option<Self> {
+        let (_, certificate, _) = read_tlv(&der, 0x30)?;
+        let (_, mut tbs, _) = read_tlv(certificate, 0x30)?;
+
+        // optional version, then serial number and signature algorithm
+        if tbs.first() == Some(&0xa0) {
+            tbs = read_tlv(tbs, 0xa0)?.2;
+        }
+        tbs = read_tlv(tbs, 0x02)?.2;
+        tbs = read_tlv(tbs, 0x30)?.2;
+
+        let (_, issuer, rest) = read_tlv(tbs, 0x30)?;
+        let (_, validity, rest) = read_tlv(rest, 0x30)?;
+        let (_, subject, rest) = read_tlv(rest, 0x30)?;
+        let (_, _, mut rest) = read_tlv(rest, 0x30)?;
+
+        let (not_before_tag, not_before, validity) = read_any_tlv(validity)?;
+        let (not_after_tag, not_after, _) = read_any_tlv(validity)?;
+
+        // optional issuer and subject unique ids, then extensions
+        let mut subject_alt_names = Vec::new();
+        while let Some((tag, content, next)) = read_any_tlv(rest) {
+            if tag == 0xa3 {
+                subject_alt_names = read_subject_alt_names(content)?;
+            }
+            rest = next;
+        }
+
+        Some(Certificate {
+            subject: read_name(subject)?,
+            issuer: read_name(issuer)?,
+            not_before: read_time(not_before_tag, not_before)?,
+            not_after: read_time(not_after_tag, not_after)?,
+            subject_alt_names,
+            der
+        })
+    }
+
+    /// Return true if the current time is in the validity period of the certificate.
+    pub fn is_valid_now(&self) -> bool {
+        let now = SystemTime::now();
+        self.not_before <= now && now <= self.not_after
+    }
+
+    /// Return the time left before the expiration of the certificate, or None if it is already expired.
+    pub fn expires_in(&self) -> Option<Duration> {
+        self.not_after.duration_since(SystemTime::now()).ok()
+    }
+}
+
+/// Read a DER element with a specific tag.
+/// -> return (tag, content, remaining data)
+fn read_tlv(data: &[u8], expected_tag: u8) -> Option<(u8, &[u8], &[u8])> {
+    let (tag, content, rest) = read_any_tlv(data)?;
+    if tag == expected_tag {
+        Some((tag, content, rest))
+    } else {
+        None
+    }
+}
+
+/// Read a DER element.
+/// -> return (tag, content, remaining data)
+fn read_any_tlv(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let first_length_byte = *data.get(1)? as usize;
+    let (length, header_length) = if first_length_byte < 0x80 {
+        (first_length_byte, 2)
+    } else {
+        let length_bytes = first_length_byte & 0x7f;
+        if length_bytes == 0 || length_bytes > 4 {
+            return None;
+        }
+        let mut length = 0;
+        for byte in data.get(2..2 + length_bytes)? {
+            length = (length << 8) | *byte as usize;
+        }
+        (length, 2 + length_bytes)
+    };
+
+    let content = data.get(header_length..header_length + length)?;
+    Some((tag, content, &data[header_length + length..]))
+}
+
+/// Format a X.501 name as a distinguished name string.
+fn read_name(mut name: &[u8]) -> Option<String> {
+    let mut parts = Vec::new();
+    while !name.is_empty() {
+        let (_, set, rest) = read_tlv(name, 0x31)?;
+        let (_, attribute, _) = read_tlv(set, 0x30)?;
+        let (_, oid, attribute) = read_tlv(attribute, 0x06)?;
+        let (_, value, _) = read_any_tlv(attribute)?;
+
+        let key = match oid {
+            [0x55, 0x04, 0x03] => "CN",
+            [0x55, 0x04, 0x06] => "C",
+            [0x55, 0x04, 0x07] => "L",
+            [0x55, 0x04, 0x08] => "ST",
+            [0x55, 0x04, 0x0a] => "O",
+            [0x55, 0x04, 0x0b] => "OU",
+            _ => {
+                name = rest;
+                continue;
+            }
+        };
+        parts.push(format!("{}={}", key, String::from_utf8_lossy(value)));
+        name = rest;
+    }
+    Some(parts.join(", "))
+}
+
+/// Read an UTCTime or a GeneralizedTime.
+fn read_time(tag: u8, time: &[u8]) -> Option<SystemTime> {
+    let time = std::str::from_utf8(time).ok()?;
+    let number = |range: std::ops::Range<usize>| time.get(range)?.parse::<u64>().ok();
+
+    let (year, time) = match tag {
+        0x17 => {
+            let year = number(0..2)?;
+            (if year >= 50 { 1900 + year } else { 2000 + year }, &time[2..])
+        },
+        0x18 => (number(0..4)?, &time[4..]),
+        _ => return None,
+    };
+    let number = |range: std::ops::Range<usize>| time.get(range)?.parse::<u64>().ok();
+    let (month, day, hour, minute, second) = (number(0..2)?, number(2..4)?, number(4..6)?, number(6..8)?, number(8..10)?);
+
+    // days since epoch of a date of the proleptic gregorian calendar
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = (era * 146_097 + day_of_era).checked_sub(719_468)?;
+
+    Some(UNIX_EPOCH + Duration::from_secs(days * 86400 + hour * 3600 + minute * 60 + second))
+}
+
+/// Read the subject alternative names in the extensions of a certificate.
+fn read_subject_alt_names(extensions: &[u8]) -> Option<Vec<String>> {
+    let (_, mut extensions, _) = read_tlv(extensions, 0x30)?;
+    let mut names = Vec::new();
+
+    while !extensions.is_empty() {
+        let (_, extension, rest) = read_tlv(extensions, 0x30)?;
+        extensions = rest;
+
+        let (_, oid, mut extension) = read_tlv(extension, 0x06)?;
+        if oid != [0x55, 0x1d, 0x11] {
+            continue;
+        }
+        if extension.first() == Some(&0x01) {
+            extension = read_tlv(extension, 0x01)?.2;
+        }
+        let (_, value, _) = read_tlv(extension, 0x04)?;
+        let (_, mut general_names, _) = read_tlv(value, 0x30)?;
+
+        while let Some((tag, name, rest)) = read_any_tlv(general_names) {
+            match (tag, name.len()) {
+                (0x82, _) => names.push(String::from_utf8_lossy(name).to_string()),
+                (0x87, 4) => names.push(format!("{}.{}.{}.{}", name[0], name[1], name[2], name[3])),
+                (0x87, 16) => {
+                    let mut groups = [0u16; 8];
+                    for (i, group) in groups.iter_mut().enumerate() {
+                        *group = u16::from(name[2 * i]) << 8 | u16::from(name[2 * i + 1]);
+                    }
+                    names.push(std::net::Ipv6Addr::from(groups).to_string())
+                },
+                _ => (),
+            }
+            general_names = rest;
+        }
+    }
+
+    Some(names)
+}
@@ -0,0 +1,249 @@
+//! Commands of the webdriver protocol
+
+use json::{JsonValue, object};
+use crate::enums::{Selector, WindowType};
+use crate::timeouts::Timeouts;
+use crate::print::PrintOptions;
+use crate::permissions::{Permission, PermissionState};
+use crate::transport::Method;
+
+/// A command sent to a session.
+/// Every method of this crate sends one of these commands, and commands which are not wrapped yet can be sent with [Session::execute()](../session/struct.Session.html#method.execute).
+///
+/// Commands related to a tab apply to the selected tab of the session.
+///
+/// # Example
+///
+/// ```rust
+/// use lw_webdriver::{session::Session, command::Command, enums::Browser};
+/// use lw_webdriver::transport::Method;
+///
+/// let mut session = Session::new(Browser::Firefox, false).unwrap();
+/// session.execute(Command::Navigate { url: "https://www.mozilla.org/en-US/".to_string() }).unwrap();
+/// let title = session.execute(Command::GetTitle).unwrap();
+///
+/// // an endpoint of the specification not covered by this enum
+/// let rect = session.execute(Command::Custom {
+///     method: Method::Get,
+///     path: "/window/rect".to_string(),
+///     body: None
+/// }).unwrap();
+/// ```
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum Command {
+    /// The type is a hint, the driver can open the other type.
+    NewWindow { window_type: WindowType },
+    GetWindowHandles,
+    GetWindowHandle,
+    SwitchToWindow { handle: String },
+    CloseWindow,
+    DeleteSession,
+    GetTimeouts,
+    SetTimeouts(Timeouts),
+    Navigate { url: String },
+    GetCurrentUrl,
+    GetTitle,
+    Back,
+    Forward,
+    Refresh,
+    GetPageSource,
+    ExecuteScript { script: String, args: Vec<JsonValue> },
+    FindElement { selector: Selector, value: String },
+    FindElementFromElement { element_id: String, selector: Selector, value: String },
+    ElementClick { element_id: String },
+    ElementSendKeys { element_id: String, text: String },
+    /// Not in the specification, supported by Selenium servers and drivers implementing the legacy protocol.
+    ElementSubmit { element_id: String },
+    GetElementText { element_id: String },
+    GetElementAttribute { element_id: String, name: String },
+    GetElementProperty { element_id: String, name: String },
+    GetElementCssValue { element_id: String, property: String },
+    GetElementTagName { element_id: String },
+    GetComputedRole { element_id: String },
+    GetComputedLabel { element_id: String },
+    GetElementRect { element_id: String },
+    IsElementEnabled { element_id: String },
+    IsElementSelected { element_id: String },
+    /// Not in the specification but supported by most drivers.
+    IsElementDisplayed { element_id: String },
+    /// The actions are [action sequences](https://www.w3.org/TR/webdriver/#actions), one for each input source.
+    PerformActions { actions: JsonValue },
+    ReleaseActions,
+    GetAllCookies,
+    /// The cookie is a [cookie object](https://www.w3.org/TR/webdriver/#dfn-table-for-cookie-conversion).
+    AddCookie { cookie: JsonValue },
+    DeleteAllCookies,
+    TakeScreenshot,
+    /// geckodriver only
+    TakeFullPageScreenshot,
+    PrintPage(PrintOptions),
+    /// The permission is set for the origin of the selected tab.
+    SetPermission { permission: Permission, state: PermissionState },
+    /// Chromium-based browsers only, the type being `browser` or `driver`.
+    GetLog { log_type: String },
+    /// Selenium servers and grids only, the file being a base64-encoded zip archive containing one file.
+    /// The value of the response is the path of the extracted file on the remote node.
+    UploadFile { file: String },
+    /// Chromium-based browsers only, the vendor being `goog` or `ms`.
+    ExecuteCdpCommand { vendor: String, method: String, params: JsonValue },
+    /// Any command, the path being relative to the session (`/window/rect`).
+    Custom { method: Method, path: String, body: Option<JsonValue> }
+}
+
+impl Command {
+    /// The name of the command (`Navigate`, `FindElement`...), as used in traces and exported transcripts.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::NewWindow { .. } => "NewWindow",
+            Command::GetWindowHandles => "GetWindowHandles",
+            Command::GetWindowHandle => "GetWindowHandle",
+            Command::SwitchToWindow { .. } => "SwitchToWindow",
+            Command::CloseWindow => "CloseWindow",
+            Command::DeleteSession => "DeleteSession",
+            Command::GetTimeouts => "GetTimeouts",
+            Command::SetTimeouts(_) => "SetTimeouts",
+            Command::Navigate { .. } => "Navigate",
+            Command::GetCurrentUrl => "GetCurrentUrl",
+            Command::GetTitle => "GetTitle",
+            Command::Back => "Back",
+            Command::Forward => "Forward",
+            Command::Refresh => "Refresh",
+            Command::GetPageSource => "GetPageSource",
+            Command::ExecuteScript { .. } => "ExecuteScript",
+            Command::FindElement { .. } => "FindElement",
+            Command::FindElementFromElement { .. } => "FindElementFromElement",
+            Command::ElementClick { .. } => "ElementClick",
+            Command::ElementSendKeys { .. } => "ElementSendKeys",
+            Command::ElementSubmit { .. } => "ElementSubmit",
+            Command::GetElementText { .. } => "GetElementText",
+            Command::GetElementAttribute { .. } => "GetElementAttribute",
+            Command::GetElementProperty { .. } => "GetElementProperty",
+            Command::GetElementCssValue { .. } => "GetElementCssValue",
+            Command::GetElementTagName { .. } => "GetElementTagName",
+            Command::GetComputedRole { .. } => "GetComputedRole",
+            Command::GetComputedLabel { .. } => "GetComputedLabel",
+            Command::GetElementRect { .. } => "GetElementRect",
+            Command::IsElementEnabled { .. } => "IsElementEnabled",
+            Command::IsElementSelected { .. } => "IsElementSelected",
+            Command::IsElementDisplayed { .. } => "IsElementDisplayed",
+            Command::PerformActions { .. } => "PerformActions",
+            Command::ReleaseActions => "ReleaseActions",
+            Command::GetAllCookies => "GetAllCookies",
+            Command::AddCookie { .. } => "AddCookie",
+            Command::DeleteAllCookies => "DeleteAllCookies",
+            Command::TakeScreenshot => "TakeScreenshot",
+            Command::TakeFullPageScreenshot => "TakeFullPageScreenshot",
+            Command::PrintPage(_) => "PrintPage",
+            Command::SetPermission { .. } => "SetPermission",
+            Command::GetLog { .. } => "GetLog",
+            Command::UploadFile { .. } => "UploadFile",
+            Command::ExecuteCdpCommand { .. } => "ExecuteCdpCommand",
+            Command::Custom { .. } => "Custom",
+        }
+    }
+
+    /// Whether the command can be sent again after a transient failure, according to the [retry policy](../retry/struct.RetryPolicy.html).
+    /// The driver may have run the other commands (a click, a navigation, a script...) before failing, so sending them again could run them twice.
+    pub(crate) fn is_resendable(&self) -> bool {
+        match self {
+            Command::FindElement { .. } | Command::FindElementFromElement { .. }
+                | Command::GetWindowHandles | Command::GetWindowHandle | Command::GetTimeouts | Command::SetTimeouts(_)
+                | Command::GetCurrentUrl | Command::GetTitle | Command::GetPageSource
+                | Command::GetElementText { .. } | Command::GetElementAttribute { .. } | Command::GetElementProperty { .. }
+                | Command::GetElementCssValue { .. } | Command::GetElementTagName { .. } | Command::GetComputedRole { .. }
+                | Command::GetComputedLabel { .. } | Command::GetElementRect { .. } | Command::GetAllCookies
+                | Command::IsElementEnabled { .. } | Command::IsElementSelected { .. } | Command::IsElementDisplayed { .. }
+                | Command::TakeScreenshot | Command::TakeFullPageScreenshot => true,
+            // reading the logs empties them
+            Command::GetLog { .. } => false,
+            Command::NewWindow { .. } | Command::SwitchToWindow { .. } | Command::CloseWindow | Command::DeleteSession
+                | Command::Navigate { .. } | Command::Back | Command::Forward | Command::Refresh | Command::ExecuteScript { .. }
+                | Command::ElementClick { .. } | Command::ElementSendKeys { .. } | Command::ElementSubmit { .. }
+                | Command::PerformActions { .. } | Command::ReleaseActions | Command::AddCookie { .. } | Command::DeleteAllCookies
+                | Command::PrintPage(_) | Command::SetPermission { .. } | Command::UploadFile { .. }
+                | Command::ExecuteCdpCommand { .. } | Command::Custom { .. } => false,
+        }
+    }
+
+    /// -> take the session id
+    /// -> return the method, the path and the body of the request
+    pub(crate) fn to_request(&self, session_id: &str) -> (Method, String, Option<String>) {
+        let (method, path, body) = self.to_relative_request();
+        (method, format!("/session/{}{}", session_id, path), body)
+    }
+
+    /// -> return the method, the path relative to the session (`/url`) and the body of the request
+    pub(crate) fn to_relative_request(&self) -> (Method, String, Option<String>) {
+        let (method, path, body) = match self {
+            Command::NewWindow { window_type } => (Method::Post, "/window/new".to_string(), Some(object!{"type" => window_type.to_string()})),
+            Command::GetWindowHandles => (Method::Get, "/window/handles".to_string(), None),
+            Command::GetWindowHandle => (Method::Get, "/window".to_string(), None),
+            Command::SwitchToWindow { handle } => (Method::Post, "/window".to_string(), Some(object!{"handle" => handle.as_str()})),
+            Command::CloseWindow => (Method::Delete, "/window".to_string(), None),
+            Command::DeleteSession => (Method::Delete, String::new(), None),
+            Command::GetTimeouts => (Method::Get, "/timeouts".to_string(), None),
+            Command::SetTimeouts(timeouts) => (Method::Post, "/timeouts".to_string(), Some(timeouts.to_json())),
+            Command::Navigate { url } => (Method::Post, "/url".to_string(), Some(object!{"url" => url.as_str()})),
+            Command::GetCurrentUrl => (Method::Get, "/url".to_string(), None),
+            Command::GetTitle => (Method::Get, "/title".to_string(), None),
+            Command::Back => (Method::Post, "/back".to_string(), Some(object!{})),
+            Command::Forward => (Method::Post, "/forward".to_string(), Some(object!{})),
+            Command::Refresh => (Method::Post, "/refresh".to_string(), Some(object!{})),
+            Command::GetPageSource => (Method::Get, "/source".to_string(), None),
+            Command::ExecuteScript { script, args } => (Method::Post, "/execute/sync".to_string(), Some(object!{
+                "script" => script.as_str(),
+                "args" => args.clone()
+            })),
+            Command::FindElement { selector, value } => {
+                let (strategy, value) = selector.to_strategy(value);
+                (Method::Post, "/element".to_string(), Some(object!{
+                    "using" => strategy,
+                    "value" => value
+                }))
+            },
+            Command::FindElementFromElement { element_id, selector, value } => {
+                let (strategy, value) = selector.to_strategy(value);
+                (Method::Post, format!("/element/{}/element", element_id), Some(object!{
+                    "using" => strategy,
+                    "value" => value
+                }))
+            },
+            Command::ElementClick { element_id } => (Method::Post, format!("/element/{}/click", element_id), Some(object!{})),
+            Command::ElementSubmit { element_id } => (Method::Post, format!("/element/{}/submit", element_id), Some(object!{})),
+            Command::ElementSendKeys { element_id, text } => (Method::Post, format!("/element/{}/value", element_id), Some(object!{"text" => text.as_str()})),
+            Command::GetElementText { element_id } => (Method::Get, format!("/element/{}/text", element_id), None),
+            Command::GetElementAttribute { element_id, name } => (Method::Get, format!("/element/{}/attribute/{}", element_id, name), None),
+            Command::GetElementProperty { element_id, name } => (Method::Get, format!("/element/{}/property/{}", element_id, name), None),
+            Command::GetElementCssValue { element_id, property } => (Method::Get, format!("/element/{}/css/{}", element_id, property), None),
+            Command::GetElementTagName { element_id } => (Method::Get, format!("/element/{}/name", element_id), None),
+            Command::GetComputedRole { element_id } => (Method::Get, format!("/element/{}/computedrole", element_id), None),
+            Command::GetComputedLabel { element_id } => (Method::Get, format!("/element/{}/computedlabel", element_id), None),
+            Command::GetElementRect { element_id } => (Method::Get, format!("/element/{}/rect", element_id), None),
+            Command::IsElementEnabled { element_id } => (Method::Get, format!("/element/{}/enabled", element_id), None),
+            Command::IsElementSelected { element_id } => (Method::Get, format!("/element/{}/selected", element_id), None),
+            Command::IsElementDisplayed { element_id } => (Method::Get, format!("/element/{}/displayed", element_id), None),
+            Command::PerformActions { actions } => (Method::Post, "/actions".to_string(), Some(object!{"actions" => actions.clone()})),
+            Command::ReleaseActions => (Method::Delete, "/actions".to_string(), None),
+            Command::GetAllCookies => (Method::Get, "/cookie".to_string(), None),
+            Command::AddCookie { cookie } => (Method::Post, "/cookie".to_string(), Some(object!{"cookie" => cookie.clone()})),
+            Command::DeleteAllCookies => (Method::Delete, "/cookie".to_string(), None),
+            Command::TakeScreenshot => (Method::Get, "/screenshot".to_string(), None),
+            Command::TakeFullPageScreenshot => (Method::Get, "/moz/screenshot/full".to_string(), None),
+            Command::PrintPage(options) => (Method::Post, "/print".to_string(), Some(options.to_json())),
+            Command::SetPermission { permission, state } => (Method::Post, "/permissions".to_string(), Some(object!{
+                "descriptor" => permission.to_json(),
+                "state" => state.to_string()
+            })),
+            Command::GetLog { log_type } => (Method::Post, "/se/log".to_string(), Some(object!{"type" => log_type.as_str()})),
+            Command::UploadFile { file } => (Method::Post, "/se/file".to_string(), Some(object!{"file" => file.as_str()})),
+            Command::ExecuteCdpCommand { vendor, method, params } => (Method::Post, format!("/{}/cdp/execute", vendor), Some(object!{
+                "cmd" => method.as_str(),
+                "params" => params.clone()
+            })),
+            Command::Custom { method, path, body } => (*method, path.clone(), body.clone()),
+        };
+        (method, path, body.map(|body| body.dump()))
+    }
+}
@@ -0,0 +1,126 @@
+//! Compatibility of drivers and browsers
+
+use json::{JsonValue, object};
+use crate::enums::Browser;
+
+/// What to do when a session is created with a driver which does not support the version of the browser.
+/// These pairs often fail later with cryptic errors.
+///
+/// # Example
+///
+/// ```rust
+/// use lw_webdriver::{session::Session, config::SessionConfig, capabilities::Capabilities, enums::Browser};
+/// use lw_webdriver::compatibility::VersionCheck;
+///
+/// let config = SessionConfig::new(Capabilities::new(Browser::Chrome)).version_check(VersionCheck::Error);
+/// match Session::new_with_config(config) {
+///     Ok(session) => (),
+///     Err(error) => eprintln!("{}", error), // "session not created: chromedriver 114.0.5735.90 supports Chrome 114, but Chrome 120.0.6099.109 is used"
+/// }
+/// ```
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Default)]
+pub enum VersionCheck {
+    /// Do not check the versions.
+    Off,
+    /// Log a warning (default).
+    #[default]
+    Warn,
+    /// Close the session and return a [SessionNotCreated](../error/enum.ErrorKind.html#variant.SessionNotCreated) error.
+    /// When the driver fails to create the session, the error explains the incompatibility if the versions are known.
+    Error
+}
+
+/// The oldest Firefox supported by each geckodriver release
+/// (see [supported platforms](https://firefox-source-docs.mozilla.org/testing/geckodriver/Support.html)).
+const GECKODRIVER_MIN_FIREFOX: [((u32, u32), u32); 14] = [
+    ((0, 36), 128),
+    ((0, 34), 115),
+    ((0, 32), 102),
+    ((0, 31), 91),
+    ((0, 30), 78),
+    ((0, 26), 60),
+    ((0, 21), 57),
+    ((0, 20), 55),
+    ((0, 19), 55),
+    ((0, 18), 53),
+    ((0, 17), 52),
+    ((0, 16), 52),
+    ((0, 15), 48),
+    ((0, 14), 47),
+];
+
+/// -> take the numbers of a version ("114.0.5735.90 (386bc09e8f4f2e025eddae123f36f6263096ae49-refs/branch-heads/5735@{#1052})")
+fn parse_version(version: &str) -> Vec<u32> {
+    version.split_whitespace().next().unwrap_or("")
+        .split('.')
+        .map_while(|number| number.parse().ok())
+        .collect()
+}
+
+/// -> take the capabilities returned by the driver on session creation
+/// -> return an explanation if the driver does not support the browser
+pub(crate) fn check_versions(capabilities: &JsonValue) -> Result<(), String> {
+    let browser_version = match capabilities["browserVersion"].as_str() {
+        Some(version) => version,
+        None => return Ok(()),
+    };
+    let browser = parse_version(browser_version);
+
+    if let Some(driver_version) = capabilities["moz:geckodriverVersion"].as_str() {
+        let driver = parse_version(driver_version);
+        if let (Some(&firefox_major), [major, minor, ..]) = (browser.first(), driver.as_slice()) {
+            let minimum = GECKODRIVER_MIN_FIREFOX.iter().find(|(version, _)| *version <= (*major, *minor));
+            if let Some((_, minimum)) = minimum {
+                if firefox_major < *minimum {
+                    return Err(format!("geckodriver {} requires Firefox {} or later, but Firefox {} is used", driver_version, minimum, browser_version));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let chromium_driver = match (capabilities["chrome"]["chromedriverVersion"].as_str(), capabilities["msedge"]["msedgedriverVersion"].as_str()) {
+        (Some(version), _) => Some(("chromedriver", "Chrome", version)),
+        (None, Some(version)) => Some(("msedgedriver", "Edge", version)),
+        (None, None) => None,
+    };
+    if let Some((driver_name, browser_name, driver_version)) = chromium_driver {
+        let driver = parse_version(driver_version);
+        // since Chrome 73, a driver only supports the browser with the same major version
+        if let (Some(browser_major), Some(driver_major)) = (browser.first(), driver.first()) {
+            if browser_major != driver_major && *driver_major >= 73 {
+                let driver_version = driver_version.split_whitespace().next().unwrap_or(driver_version);
+                return Err(format!("{} {} supports {} {}, but {} {} is used", driver_name, driver_version, browser_name, driver_major, browser_name, browser_version));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// -> take the browser, the version of the driver given by its status, the version of the browser requested in the capabilities (if any)
+///    and the message of the session creation error, which often gives the version of the browser found by the driver
+/// -> return an explanation if the driver does not support the browser
+pub(crate) fn check_failed_versions(browser: &Browser, driver_version: &str, requested_version: Option<&str>, error_message: &str) -> Result<(), String> {
+    let browser_version = match requested_version.or_else(|| reported_browser_version(error_message)) {
+        Some(version) => version,
+        None => return Ok(()),
+    };
+    let mut capabilities = object!{"browserVersion" => browser_version};
+    match browser {
+        Browser::Firefox => capabilities["moz:geckodriverVersion"] = driver_version.into(),
+        Browser::Chrome | Browser::ChromiumBased { .. } => capabilities["chrome"] = object!{"chromedriverVersion" => driver_version},
+        Browser::Edge => capabilities["msedge"] = object!{"msedgedriverVersion" => driver_version},
+        Browser::Safari => return Ok(()),
+    }
+    check_versions(&capabilities)
+}
+
+/// -> take the message of a session creation error ("... Current browser version is 120.0.6099.109 with binary path ...")
+/// -> return the version of the browser
+fn reported_browser_version(message: &str) -> Option<&str> {
+    let start = message.find("browser version is ")? + "browser version is ".len();
+    message[start..].split_whitespace().next()
+}
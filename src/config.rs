@@ -0,0 +1,376 @@
+//! Configuration of the webdriver server used by a session
+
+use std::path::PathBuf;
+use std::time::Duration;
+use std::sync::Arc;
+#[cfg(feature = "toml")]
+use serde::Deserialize;
+use crate::capabilities::Capabilities;
+use crate::timeouts::Timeouts;
+use crate::transport::Transport;
+use crate::compatibility::VersionCheck;
+use crate::enums::Browser;
+use crate::error::{WebdriverError, ErrorKind};
+
+/// How long a launched driver is given to exit by itself once the session is ended, unless another period is set.
+pub const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_millis(500);
+
+/// Everything needed to create a [session](../session/struct.Session.html): the [capabilities](../capabilities/struct.Capabilities.html) of the browser
+/// and the webdriver server to use (or how to launch it).
+///
+/// # Example
+///
+/// ```rust
+/// use lw_webdriver::{session::Session, config::SessionConfig, capabilities::Capabilities, enums::Browser};
+///
+/// // connect to a Selenium Grid instead of a local driver
+/// let config = SessionConfig::new(Capabilities::new(Browser::Firefox).headless(true))
+///     .remote("http://grid.example.com:4444/wd/hub")
+///     .credentials("user", "password");
+///
+/// let mut session = Session::new_with_config(config).unwrap();
+/// ```
+///
+/// ```rust
+/// use lw_webdriver::{session::Session, config::SessionConfig, capabilities::Capabilities, enums::Browser};
+///
+/// // launch a driver installed somewhere else, with verbose logs
+/// let config = SessionConfig::new(Capabilities::new(Browser::Firefox))
+///     .driver_path("/usr/local/bin/geckodriver")
+///     .driver_arg("--log")
+///     .driver_arg("trace")
+///     .port(4445);
+///
+/// let mut session = Session::new_with_config(config).unwrap();
+/// ```
+
+#[derive(Clone)]
+pub struct SessionConfig {
+    pub(crate) capabilities: Capabilities,
+    pub(crate) remote_url: Option<String>,
+    pub(crate) credentials: Option<(String, String)>,
+    pub(crate) port: Option<u16>,
+    pub(crate) driver_path: Option<PathBuf>,
+    pub(crate) driver_args: Vec<String>,
+    pub(crate) max_in_flight: usize,
+    pub(crate) transport: Option<Arc<dyn Transport>>,
+    pub(crate) dry_run: bool,
+    pub(crate) version_check: VersionCheck,
+    pub(crate) shutdown_grace_period: Duration,
+    pub(crate) request_timeout: Option<Duration>,
+    pub(crate) keep_alive: Option<Duration>,
+    pub(crate) crash_recovery: bool,
+    pub(crate) driver_log_file: Option<PathBuf>,
+    pub(crate) driver_log_lines: usize,
+    pub(crate) timeouts: Option<Timeouts>
+}
+
+impl SessionConfig {
+    /// Use a local driver (launched if needed) with some capabilities.
+    pub fn new(capabilities: Capabilities) -> Self {
+        SessionConfig {
+            capabilities,
+            remote_url: None,
+            credentials: None,
+            port: None,
+            driver_path: None,
+            driver_args: Vec::new(),
+            max_in_flight: 1,
+            transport: None,
+            dry_run: false,
+            version_check: VersionCheck::Warn,
+            shutdown_grace_period: DEFAULT_SHUTDOWN_GRACE_PERIOD,
+            request_timeout: None,
+            keep_alive: None,
+            crash_recovery: false,
+            driver_log_file: None,
+            driver_log_lines: 1000,
+            timeouts: None
+        }
+    }
+
+    /// Read the configuration from environment variables, so that continuous integration can switch browsers and servers without recompiling the tests:
+    ///
+    /// - `LW_WEBDRIVER_BROWSER`: `firefox` (default), `chrome`, `edge`, `safari` or the path of a Chromium-based browser
+    /// - `LW_WEBDRIVER_HEADLESS`: `true` or `false` (default)
+    /// - `LW_WEBDRIVER_URL`: the url of a [remote](#method.remote) server (a local driver is used by default)
+    /// - `LW_WEBDRIVER_DRIVER_PATH`: the [driver](#method.driver_path) to launch
+    ///
+    /// Unset and empty variables are ignored. Return [InvalidArgument](../error/enum.ErrorKind.html#variant.InvalidArgument) if a variable has an invalid value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, config::SessionConfig};
+    /// // LW_WEBDRIVER_BROWSER=chrome LW_WEBDRIVER_HEADLESS=true cargo test
+    /// let mut session = Session::new_with_config(SessionConfig::from_env().unwrap()).unwrap();
+    /// ```
+    pub fn from_env() -> Result<Self, WebdriverError> {
+        SessionConfig::new(Capabilities::new(Browser::Firefox)).with_env()
+    }
+
+    /// Read the configuration from a [TOML](https://toml.io) file (with the `toml` feature), so that the defaults of a project are written once:
+    ///
+    /// ```toml
+    /// browser = "chrome"          # firefox (default), chrome, edge, safari or the path of a Chromium-based browser
+    /// headless = true
+    /// driver_path = "/usr/local/bin/chromedriver"
+    /// url = "http://grid.example.com:4444/wd/hub"  # a remote server, instead of a local driver
+    ///
+    /// [timeouts]                  # in milliseconds
+    /// script = 10000
+    /// page_load = 60000
+    /// implicit = 0
+    /// request = 120000            # see request_timeout()
+    ///
+    /// [capabilities]              # see Capabilities::capability()
+    /// acceptInsecureCerts = true
+    /// ```
+    ///
+    /// Every setting is optional. The settings are merged in this order, each one overriding the previous ones:
+    /// the defaults, the file, the [environment variables](#method.from_env), and the methods called on the returned configuration.
+    ///
+    /// Return [InvalidArgument](../error/enum.ErrorKind.html#variant.InvalidArgument) if the file is not valid (unknown settings and unknown browsers included),
+    /// the browser being read like `LW_WEBDRIVER_BROWSER`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, config::SessionConfig};
+    /// # use std::time::Duration;
+    /// let config = SessionConfig::from_file("webdriver.toml").unwrap()
+    ///     .keep_alive(Duration::from_secs(60));
+    /// let mut session = Session::new_with_config(config).unwrap();
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self, WebdriverError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|error| WebdriverError::custom(format!("failed to read {}: {}", path.display(), error)))?;
+        let file: ConfigFile = toml::from_str(&content)
+            .map_err(|error| WebdriverError::from(ErrorKind::InvalidArgument).with_message(format!("invalid configuration file {}: {}", path.display(), error)))?;
+
+        let browser = file.browser.as_deref().map(Browser::from_name).transpose()?.unwrap_or(Browser::Firefox);
+        let mut capabilities = Capabilities::new(browser).headless(file.headless.unwrap_or(false));
+        for (name, value) in &file.capabilities {
+            // toml values have the same shape as json values (dates aside, written as strings)
+            let value = serde_json::to_string(value).ok().and_then(|value| json::parse(&value).ok()).unwrap_or(json::JsonValue::Null);
+            capabilities = capabilities.capability(name, value);
+        }
+
+        let mut config = SessionConfig::new(capabilities);
+        config.remote_url = file.url;
+        config.driver_path = file.driver_path;
+        let timeouts = file.timeouts;
+        if timeouts.script.is_some() || timeouts.page_load.is_some() || timeouts.implicit.is_some() {
+            let mut session_timeouts = Timeouts::builder();
+            if let Some(script) = timeouts.script {
+                session_timeouts = session_timeouts.script(Duration::from_millis(script));
+            }
+            if let Some(page_load) = timeouts.page_load {
+                session_timeouts = session_timeouts.page_load(Duration::from_millis(page_load));
+            }
+            if let Some(implicit) = timeouts.implicit {
+                session_timeouts = session_timeouts.implicit(Duration::from_millis(implicit));
+            }
+            config.timeouts = Some(session_timeouts);
+        }
+        config.request_timeout = timeouts.request.map(Duration::from_millis);
+        config.with_env()
+    }
+
+    /// -> take a configuration
+    /// -> return it with the settings of the environment variables
+    pub(crate) fn with_env(mut self) -> Result<Self, WebdriverError> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        if let Some(browser) = var("LW_WEBDRIVER_BROWSER") {
+            self.capabilities.browser = Browser::from_name(&browser)?;
+        }
+        if let Some(headless) = var("LW_WEBDRIVER_HEADLESS") {
+            self.capabilities.headless = match headless.as_str() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => return Err(WebdriverError::from(ErrorKind::InvalidArgument)
+                    .with_message(format!("LW_WEBDRIVER_HEADLESS must be true or false, not {:?}", headless))),
+            };
+        }
+        if let Some(url) = var("LW_WEBDRIVER_URL") {
+            self.remote_url = Some(url);
+        }
+        if let Some(path) = var("LW_WEBDRIVER_DRIVER_PATH") {
+            self.driver_path = Some(path.into());
+        }
+        Ok(self)
+    }
+
+    /// Use a remote webdriver server (Selenium Grid, Selenium standalone, or a driver running on another machine).
+    /// No driver will be launched if the server is unreachable.
+    pub fn remote(mut self, url: &str) -> Self {
+        self.remote_url = Some(url.to_string());
+        self
+    }
+
+    /// Basic authentication credentials sent with every request.
+    pub fn credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// The port of the local driver.
+    /// By default, the driver listening on 4444 is used, and if there is none, a driver is launched on a free port.
+    /// With a port, the launched driver listens on it.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// The driver program to launch if no driver is listening.
+    /// By default, `geckodriver` or `chromedriver` is expected in the directory of the running program.
+    pub fn driver_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.driver_path = Some(path.into());
+        self
+    }
+
+    /// Add a command line argument passed to the launched driver (`--log`, `trace`, `--verbose`...).
+    /// The port is already passed by this crate, use [port()](#method.port) to change it.
+    pub fn driver_arg(mut self, arg: &str) -> Self {
+        self.driver_args.push(arg.to_string());
+        self
+    }
+
+    /// Append the output of the launched driver to a file (created if needed), in addition to the lines kept by [Session::driver_log()](../session/struct.Session.html#method.driver_log).
+    pub fn driver_log_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.driver_log_file = Some(path.into());
+        self
+    }
+
+    /// The number of lines of the driver output kept by [Session::driver_log()](../session/struct.Session.html#method.driver_log) (1000 by default).
+    pub fn driver_log_lines(mut self, lines: usize) -> Self {
+        self.driver_log_lines = lines;
+        self
+    }
+
+    /// The number of commands of the session which can wait for a response at the same time (1 by default).
+    /// Commands are always sent in the order they were issued. Drivers process the commands of a session one by one,
+    /// so raising this limit is only useful with servers handling concurrent commands.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// Send the commands with a custom [transport](../transport/trait.Transport.html).
+    /// The server reached by the transport is used like a remote server: no driver is launched, and the url and credentials of the configuration are ignored.
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Validate and log the commands without sending them: no browser is launched.
+    /// Selectors are syntax-checked and urls are parsed, so that scripts can be linted quickly.
+    /// Commands return placeholder values (empty strings, elements and tabs which do not exist).
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// What to do if the driver does not support the version of the browser (a warning is logged by default).
+    pub fn version_check(mut self, version_check: VersionCheck) -> Self {
+        self.version_check = version_check;
+        self
+    }
+
+    /// How long the launched driver is given to exit by itself once the session is ended, before it is killed
+    /// ([DEFAULT_SHUTDOWN_GRACE_PERIOD](constant.DEFAULT_SHUTDOWN_GRACE_PERIOD.html) by default).
+    /// Killing the driver while the browser is closing can leave the browser running.
+    pub fn shutdown_grace_period(mut self, grace_period: Duration) -> Self {
+        self.shutdown_grace_period = grace_period;
+        self
+    }
+
+    /// Give up the commands (HTTP requests) of the session if the server does not respond within `timeout`,
+    /// instead of waiting forever for a hung driver. The command then fails with [ErrorKind::FailedRequest](../error/enum.ErrorKind.html#variant.FailedRequest).
+    /// The creation of the session is not limited, as launching a browser can be slow.
+    ///
+    /// The timeout must be longer than the [timeouts](../timeouts/struct.Timeouts.html) of the session,
+    /// otherwise slow page loads and scripts are interrupted.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the [timeouts](../timeouts/struct.Timeouts.html) of the session once it is created (the defaults of the driver are kept otherwise).
+    pub fn timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = Some(timeouts);
+        self
+    }
+
+    /// Keep the session alive by sending a cheap command whenever it has been idle for `interval` (disabled by default).
+    /// See [Session::set_keep_alive()](../session/struct.Session.html#method.set_keep_alive).
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+
+    /// Create a new session when the browser crashes or when the launched driver exits, instead of failing every command (disabled by default).
+    /// The tabs are opened again on the last url they navigated to. See the [recovery](../recovery/index.html) module.
+    pub fn crash_recovery(mut self, crash_recovery: bool) -> Self {
+        self.crash_recovery = crash_recovery;
+        self
+    }
+
+    pub fn get_capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+}
+
+impl std::fmt::Debug for SessionConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionConfig")
+            .field("capabilities", &self.capabilities)
+            .field("remote_url", &self.remote_url)
+            // the password is not printed, as configurations end up in logs
+            .field("credentials", &self.credentials.as_ref().map(|(username, _)| (username, "<redacted>")))
+            .field("port", &self.port)
+            .field("driver_path", &self.driver_path)
+            .field("driver_args", &self.driver_args)
+            .field("max_in_flight", &self.max_in_flight)
+            .field("transport", &self.transport.as_ref().map(|_| "custom"))
+            .field("dry_run", &self.dry_run)
+            .field("version_check", &self.version_check)
+            .field("shutdown_grace_period", &self.shutdown_grace_period)
+            .field("request_timeout", &self.request_timeout)
+            .field("keep_alive", &self.keep_alive)
+            .field("crash_recovery", &self.crash_recovery)
+            .field("driver_log_file", &self.driver_log_file)
+            .field("driver_log_lines", &self.driver_log_lines)
+            .field("timeouts", &self.timeouts)
+            .finish()
+    }
+}
+
+/// The content of a configuration file, see [SessionConfig::from_file()](struct.SessionConfig.html#method.from_file)
+#[cfg(feature = "toml")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    browser: Option<String>,
+    headless: Option<bool>,
+    driver_path: Option<PathBuf>,
+    url: Option<String>,
+    #[serde(default)]
+    timeouts: TimeoutsFile,
+    #[serde(default)]
+    capabilities: toml::Table
+}
+
+/// The `[timeouts]` table of a configuration file, in milliseconds
+#[cfg(feature = "toml")]
+#[derive(Deserialize)]
+#[derive(Default)]
+#[serde(deny_unknown_fields)]
+struct TimeoutsFile {
+    script: Option<u64>,
+    page_load: Option<u64>,
+    implicit: Option<u64>,
+    request: Option<u64>
+}
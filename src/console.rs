@@ -0,0 +1,102 @@
+//! Capture of the messages logged in the console of web pages
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use json::JsonValue;
+use crate::responses::LogEntryValue;
+
+/// Replaces the methods of `console` and listens to uncaught errors to record every message in `window.__lwConsole`.
+/// Installing it twice has no effect.
+pub(crate) const CONSOLE_SHIM_SCRIPT: &str = "
+    (() => {
+        if (window.__lwConsole) {
+            return;
+        }
+        window.__lwConsole = [];
+        const record = (level, source, text) => window.__lwConsole.push({
+            level,
+            message: text,
+            source,
+            timestamp: Date.now()
+        });
+        const format = value => {
+            if (typeof value === 'string') {
+                return value;
+            }
+            try {
+                return JSON.stringify(value);
+            } catch (error) {
+                return String(value);
+            }
+        };
+
+        for (const [method, level] of [['debug', 'DEBUG'], ['log', 'INFO'], ['info', 'INFO'], ['warn', 'WARNING'], ['error', 'SEVERE']]) {
+            const original = console[method];
+            console[method] = function(...args) {
+                record(level, 'console-api', args.map(format).join(' '));
+                return original.apply(this, args);
+            };
+        }
+        window.addEventListener('error', event => record('SEVERE', 'javascript', event.message));
+        window.addEventListener('unhandledrejection', event => record('SEVERE', 'javascript', 'Uncaught (in promise) ' + format(event.reason)));
+    })();
+";
+
+/// The severity of a console message.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warning,
+    /// Also used for uncaught exceptions and failed requests.
+    Error
+}
+
+impl LogLevel {
+    fn from_name(name: &str) -> LogLevel {
+        match name {
+            "SEVERE" => LogLevel::Error,
+            "WARNING" => LogLevel::Warning,
+            "INFO" => LogLevel::Info,
+            _ => LogLevel::Debug,
+        }
+    }
+}
+
+/// A message of the console, returned by [Tab::get_console_logs()](../tab/struct.Tab.html#method.get_console_logs).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct ConsoleEntry {
+    pub level: LogLevel,
+    /// The message as formatted by the browser.
+    pub text: String,
+    /// What produced the message: `console-api` for calls to `console`, `javascript` for uncaught errors,
+    /// and other values like `network` on Chrome and Edge.
+    pub source: String,
+    pub timestamp: SystemTime
+}
+
+impl ConsoleEntry {
+    /// -> take an entry recorded by the console shim
+    pub(crate) fn from_json(json: &JsonValue) -> Option<Self> {
+        Some(LogEntryValue {
+            level: json["level"].as_str()?.to_string(),
+            message: json["message"].as_str()?.to_string(),
+            source: json["source"].as_str()?.to_string(),
+            timestamp: json["timestamp"].as_f64()?
+        }.into())
+    }
+}
+
+impl From<LogEntryValue> for ConsoleEntry {
+    fn from(entry: LogEntryValue) -> Self {
+        ConsoleEntry {
+            level: LogLevel::from_name(&entry.level),
+            text: entry.message,
+            source: entry.source,
+            timestamp: UNIX_EPOCH + Duration::from_millis(entry.timestamp as u64)
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Files downloaded by the browser
+//!
+//! Set a download directory with [Capabilities::download_directory()](../capabilities/struct.Capabilities.html#method.download_directory)
+//! to make the browser save files there without asking the user. Downloads are then followed by watching the directory.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, capabilities::Capabilities, enums::{Browser, Selector}};
+//! use std::time::Duration;
+//!
+//! let mut session = Session::new_with_capabilities(Capabilities::new(Browser::Firefox).download_directory("reports")).unwrap();
+//! session.tabs[0].navigate("https://example.com/reports").unwrap();
+//! session.tabs[0].find(Selector::Css, "a.export").unwrap().unwrap().click().unwrap();
+//!
+//! let report = session.tabs[0].wait_for_download(Duration::from_secs(30)).unwrap();
+//! println!("report saved at {}", report.display());
+//! ```
+
+use std::sync::Mutex;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use log::error;
+use crate::error::*;
+use crate::wait::Wait;
+
+/// Extensions of the files written while a download is in progress (Firefox and Chromium).
+const PARTIAL_EXTENSIONS: [&str; 2] = ["part", "crdownload"];
+
+/// The download directory of a session, shared by its tabs.
+#[derive(Debug)]
+pub struct Downloads {
+    directory: PathBuf,
+    /// completed files which were already in the directory or already returned
+    known: Mutex<HashSet<PathBuf>>
+}
+
+impl Downloads {
+    /// -> take the download directory, created if it does not exist
+    /// files already in the directory are not considered as downloads
+    pub(crate) fn new(directory: PathBuf) -> Result<Downloads, WebdriverError> {
+        if let Err(error) = std::fs::create_dir_all(&directory) {
+            error!("failed to create the download directory {}: {}", directory.display(), error);
+            return Err(WebdriverError::custom(format!("failed to create the download directory: {}", error)));
+        }
+        let downloads = Downloads {
+            directory,
+            known: Mutex::new(HashSet::new())
+        };
+        let completed = downloads.completed()?;
+        downloads.known.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).extend(completed);
+        Ok(downloads)
+    }
+
+    /// The directory where files are downloaded.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// -> return the files of the directory
+    fn files(&self) -> Result<Vec<PathBuf>, WebdriverError> {
+        let entries = std::fs::read_dir(&self.directory).map_err(|error| {
+            WebdriverError::custom(format!("failed to read the download directory: {}", error))
+        })?;
+        Ok(entries.filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect())
+    }
+
+    fn is_partial(path: &Path) -> bool {
+        path.extension().and_then(|extension| extension.to_str()).map(|extension| PARTIAL_EXTENSIONS.contains(&extension)).unwrap_or(false)
+    }
+
+    /// List the files being downloaded (the temporary files written by the browser).
+    pub fn in_progress(&self) -> Result<Vec<PathBuf>, WebdriverError> {
+        Ok(self.files()?.into_iter().filter(|path| Downloads::is_partial(path)).collect())
+    }
+
+    /// List the downloaded files, including the files which were already in the directory.
+    pub fn completed(&self) -> Result<Vec<PathBuf>, WebdriverError> {
+        let files = self.files()?;
+        Ok(files.iter().filter(|path| {
+            let hidden = path.file_name().and_then(|name| name.to_str()).map(|name| name.starts_with('.')).unwrap_or(true);
+            // Firefox creates the final file before the download ends, next to a .part file
+            let partial = PARTIAL_EXTENSIONS.iter().any(|extension| {
+                let mut partial = path.as_os_str().to_os_string();
+                partial.push(format!(".{}", extension));
+                files.contains(&PathBuf::from(partial))
+            });
+            !hidden && !partial && !Downloads::is_partial(path)
+        }).cloned().collect())
+    }
+
+    /// Wait for a download to complete and return the path of the file.
+    /// Every file is returned once, so that successive calls return successive downloads.
+    /// Return [Timeout](../error/enum.ErrorKind.html#variant.Timeout) if no download completed in time.
+    pub fn wait(&self, timeout: Duration) -> Result<PathBuf, WebdriverError> {
+        Wait::new(timeout).until(|| {
+            let mut completed = self.completed()?;
+            completed.sort();
+            let path = completed.into_iter().find(|path| !self.known.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).contains(path));
+            if let Some(path) = &path {
+                self.known.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(path.clone());
+            }
+            Ok(path)
+        }).map_err(|error| if error == ErrorKind::Timeout {
+            error.with_message(format!("no download completed in {:?}", timeout))
+        } else {
+            error
+        })
+    }
+}
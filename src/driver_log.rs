@@ -0,0 +1,81 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use log::warn;
+
+/// The last lines written by a launched driver, and the file they are copied to
+pub(crate) struct DriverLog {
+    lines: Mutex<VecDeque<String>>,
+    capacity: usize,
+    path: Option<PathBuf>,
+    file: Mutex<Option<File>>
+}
+
+impl DriverLog {
+    /// -> take the number of lines to keep and the file the lines are appended to
+    pub(crate) fn new(capacity: usize, path: Option<PathBuf>) -> Self {
+        DriverLog {
+            lines: Mutex::new(VecDeque::new()),
+            capacity,
+            path,
+            file: Mutex::new(None)
+        }
+    }
+
+    /// read the stdout and the stderr of a driver until it exits
+    pub(crate) fn capture(self: &Arc<Self>, process: &mut Child) {
+        if let Some(path) = &self.path {
+            match OpenOptions::new().create(true).append(true).open(path) {
+                Ok(file) => *self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(file),
+                Err(error) => warn!("failed to open the driver log {}: {}", path.display(), error),
+            }
+        }
+        if let Some(stdout) = process.stdout.take() {
+            self.read(stdout);
+        }
+        if let Some(stderr) = process.stderr.take() {
+            self.read(stderr);
+        }
+    }
+
+    fn read<R: Read + Send + 'static>(self: &Arc<Self>, output: R) {
+        let log = Arc::clone(self);
+        thread::spawn(move || {
+            // the pipe is drained until the driver exits, or the driver would block once the pipe is full
+            let mut output = BufReader::new(output);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                match output.read_until(b'\n', &mut line) {
+                    Ok(0) | Err(_) => break,
+                    // drivers can print invalid utf-8 (from web pages for example)
+                    Ok(_) => log.push(String::from_utf8_lossy(&line).trim_end_matches(&['\r', '\n'][..]).to_string()),
+                }
+            }
+        });
+    }
+
+    fn push(&self, line: String) {
+        if let Some(file) = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_mut() {
+            if let Err(error) = writeln!(file, "{}", line) {
+                warn!("failed to write the driver log: {}", error);
+            }
+        }
+        let mut lines = self.lines.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        if self.capacity > 0 {
+            lines.push_back(line);
+        }
+    }
+
+    /// -> return the lines kept, the oldest first
+    pub(crate) fn lines(&self) -> Vec<String> {
+        self.lines.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).iter().cloned().collect()
+    }
+}
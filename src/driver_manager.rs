@@ -0,0 +1,235 @@
+//! Download drivers matching the installed browsers (requires the `driver-manager` feature)
+//!
+//! When no driver is found next to the program, [Session::new()](../session/struct.Session.html#method.new) detects the version of the
+//! installed browser, downloads the matching driver in a cache directory and launches it.
+
+use std::ffi::OsStr;
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+use std::process::Command;
+use log::{debug, info, warn};
+use crate::enums::Browser;
+use crate::error::{WebdriverError, ErrorKind};
+
+/// The geckodriver release used when the latest one cannot be found.
+const FALLBACK_GECKODRIVER_VERSION: &str = "0.34.0";
+
+/// Return the directory where drivers are downloaded.
+/// This is `lw-webdriver` in the cache directory of the user (`$XDG_CACHE_HOME`, `~/.cache`, `~/Library/Caches` or `%LOCALAPPDATA%`).
+pub fn cache_directory() -> PathBuf {
+    let base = if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Caches"))
+    } else {
+        std::env::var_os("XDG_CACHE_HOME").map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+    };
+    base.unwrap_or_else(std::env::temp_dir).join("lw-webdriver")
+}
+
+/// Return the version of the installed browser (`115.0.2`), or None if the browser was not found.
+pub fn browser_version(browser: &Browser) -> Option<String> {
+    let candidates: &[&str] = match (browser, cfg!(windows), cfg!(target_os = "macos")) {
+        (Browser::Firefox, true, _) => &["C:\\Program Files\\Mozilla Firefox\\firefox.exe", "C:\\Program Files (x86)\\Mozilla Firefox\\firefox.exe"],
+        (Browser::Firefox, _, true) => &["/Applications/Firefox.app/Contents/MacOS/firefox"],
+        (Browser::Firefox, _, _) => &["firefox"],
+        (Browser::Chrome, true, _) => &[],
+        (Browser::Chrome, _, true) => &["/Applications/Google Chrome.app/Contents/MacOS/Google Chrome"],
+        (Browser::Chrome, _, _) => &["google-chrome", "google-chrome-stable", "chromium", "chromium-browser"],
+        (Browser::Edge, true, _) => &[],
+        (Browser::Edge, _, true) => &["/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge"],
+        (Browser::Edge, _, _) => &["microsoft-edge", "microsoft-edge-stable"],
+        (Browser::Safari, _, _) => &[],
+        (Browser::ChromiumBased { .. }, _, _) => &[],
+    };
+    let custom_binary = match browser {
+        Browser::ChromiumBased { binary } => Some(binary.as_os_str()),
+        _ => None,
+    };
+
+    let mut outputs: Vec<String> = candidates.iter().map(OsStr::new).chain(custom_binary)
+        .filter_map(|binary| Command::new(binary).arg("--version").output().ok())
+        .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        .collect();
+    let registry_key = match browser {
+        Browser::Firefox | Browser::Safari | Browser::ChromiumBased { .. } => None,
+        Browser::Chrome => Some("HKEY_CURRENT_USER\\Software\\Google\\Chrome\\BLBeacon"),
+        Browser::Edge => Some("HKEY_CURRENT_USER\\Software\\Microsoft\\Edge\\BLBeacon"),
+    };
+    if let (Some(registry_key), true) = (registry_key, cfg!(windows)) {
+        if let Ok(output) = Command::new("reg").args(&["query", registry_key, "/v", "version"]).output() {
+            outputs.push(String::from_utf8_lossy(&output.stdout).to_string());
+        }
+    }
+    if let (Browser::Safari, true) = (browser, cfg!(target_os = "macos")) {
+        if let Ok(output) = Command::new("defaults").args(["read", "/Applications/Safari.app/Contents/Info", "CFBundleShortVersionString"]).output() {
+            outputs.push(String::from_utf8_lossy(&output.stdout).to_string());
+        }
+    }
+
+    for output in outputs {
+        let version = output.split_whitespace().find(|word| word.starts_with(|c: char| c.is_ascii_digit()) && word.contains('.'));
+        if let Some(version) = version {
+            debug!("{:?} version is {}", browser, version);
+            return Some(version.to_string());
+        }
+    }
+    None
+}
+
+/// Return the path of a driver matching the installed browser, downloading it if it is not in the cache yet.
+/// The drivers installed with the system (safaridriver) are returned as they are.
+pub fn ensure_driver(browser: &Browser) -> Result<PathBuf, WebdriverError> {
+    if let Some(path) = browser.system_driver() {
+        return Ok(PathBuf::from(path));
+    }
+    let version = browser_version(browser)
+        .ok_or_else(|| WebdriverError::custom(format!("{:?} is not installed", browser)))?;
+    let major: usize = version.split('.').next().and_then(|major| major.parse().ok())
+        .ok_or_else(|| WebdriverError::custom(format!("invalid browser version: {}", version)))?;
+
+    let binary_name = if cfg!(windows) { format!("{}.exe", browser.driver_name()) } else { browser.driver_name().to_string() };
+    let directory = cache_directory().join(format!("{}-for-{}{}", browser.driver_name(), browser.to_string(), major));
+    let path = directory.join(&binary_name);
+    if path.exists() {
+        debug!("using cached driver {}", path.display());
+        return Ok(path);
+    }
+
+    let url = match browser {
+        Browser::Firefox => geckodriver_url(major)?,
+        Browser::Chrome | Browser::ChromiumBased { .. } => chromedriver_url(major)?,
+        Browser::Edge => edgedriver_url(&version)?,
+        Browser::Safari => unreachable!("safaridriver is installed with the system"),
+    };
+    info!("Downloading {} from {}...", browser.driver_name(), url);
+    let archive = download(&url)?;
+
+    let binary = if url.ends_with(".zip") {
+        extract_zip(archive, &binary_name)
+    } else {
+        extract_tar_gz(archive, &binary_name)
+    }.ok_or_else(|| WebdriverError::custom(format!("{} not found in {}", binary_name, url)))?;
+
+    let io_error = |error: std::io::Error| WebdriverError::custom(format!("failed to save driver: {}", error));
+    std::fs::create_dir_all(&directory).map_err(io_error)?;
+    std::fs::write(&path, binary).map_err(io_error)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).map_err(io_error)?;
+    }
+
+    info!("Driver saved to {}", path.display());
+    Ok(path)
+}
+
+fn download(url: &str) -> Result<Vec<u8>, WebdriverError> {
+    match minreq::get(url).with_header("User-Agent", "lw-webdriver").send() {
+        Ok(response) if response.status_code == 200 => Ok(response.into_bytes()),
+        Ok(response) => Err(WebdriverError::custom(format!("failed to download {}: {} {}", url, response.status_code, response.reason_phrase))),
+        Err(error) => Err(WebdriverError::custom(format!("failed to download {}: {}", url, error))),
+    }
+}
+
+fn geckodriver_url(firefox_major: usize) -> Result<String, WebdriverError> {
+    // minimal Firefox version supported by each geckodriver release
+    let version = if firefox_major >= 115 {
+        download("https://api.github.com/repos/mozilla/geckodriver/releases/latest").ok()
+            .and_then(|json| json::parse(&String::from_utf8_lossy(&json)).ok())
+            .and_then(|json| json["tag_name"].as_str().map(|tag| tag.trim_start_matches('v').to_string()))
+            .unwrap_or_else(|| {
+                warn!("latest geckodriver release not found, using {}", FALLBACK_GECKODRIVER_VERSION);
+                FALLBACK_GECKODRIVER_VERSION.to_string()
+            })
+    } else if firefox_major >= 102 {
+        "0.33.0".to_string()
+    } else if firefox_major >= 91 {
+        "0.31.0".to_string()
+    } else if firefox_major >= 78 {
+        "0.30.0".to_string()
+    } else {
+        "0.26.0".to_string()
+    };
+
+    let platform = match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "aarch64") => "linux-aarch64.tar.gz",
+        ("linux", "x86") => "linux32.tar.gz",
+        ("linux", _) => "linux64.tar.gz",
+        ("macos", "aarch64") => "macos-aarch64.tar.gz",
+        ("macos", _) => "macos.tar.gz",
+        ("windows", "x86") => "win32.zip",
+        ("windows", _) => "win64.zip",
+        _ => return Err(ErrorKind::UnsupportedPlatform.into()),
+    };
+
+    Ok(format!("https://github.com/mozilla/geckodriver/releases/download/v{0}/geckodriver-v{0}-{1}", version, platform))
+}
+
+fn chromedriver_url(chrome_major: usize) -> Result<String, WebdriverError> {
+    let (platform, legacy_platform) = match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", _) => ("linux64", "linux64"),
+        ("macos", "aarch64") => ("mac-arm64", "mac_arm64"),
+        ("macos", _) => ("mac-x64", "mac64"),
+        ("windows", "x86") => ("win32", "win32"),
+        ("windows", _) => ("win64", "win32"),
+        _ => return Err(ErrorKind::UnsupportedPlatform.into()),
+    };
+
+    if chrome_major >= 115 {
+        // Chrome for Testing
+        let json = download("https://googlechromelabs.github.io/chrome-for-testing/latest-versions-per-milestone-with-downloads.json")?;
+        let json = json::parse(&String::from_utf8_lossy(&json))
+            .map_err(|_| WebdriverError::custom("invalid Chrome for Testing index".to_string()))?;
+        json["milestones"][chrome_major.to_string().as_str()]["downloads"]["chromedriver"].members()
+            .find(|download| download["platform"] == platform)
+            .and_then(|download| download["url"].as_str().map(|url| url.to_string()))
+            .ok_or_else(|| WebdriverError::custom(format!("no chromedriver available for Chrome {} on {}", chrome_major, platform)))
+    } else {
+        let version = download(&format!("https://chromedriver.storage.googleapis.com/LATEST_RELEASE_{}", chrome_major))?;
+        let version = String::from_utf8_lossy(&version).trim().to_string();
+        Ok(format!("https://chromedriver.storage.googleapis.com/{}/chromedriver_{}.zip", version, legacy_platform))
+    }
+}
+
+fn edgedriver_url(edge_version: &str) -> Result<String, WebdriverError> {
+    // msedgedriver is released for every version of Edge
+    let platform = match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", _) => "linux64",
+        ("macos", "aarch64") => "mac64_m1",
+        ("macos", _) => "mac64",
+        ("windows", "x86") => "win32",
+        ("windows", "aarch64") => "arm64",
+        ("windows", _) => "win64",
+        _ => return Err(ErrorKind::UnsupportedPlatform.into()),
+    };
+
+    Ok(format!("https://msedgedriver.microsoft.com/{}/edgedriver_{}.zip", edge_version, platform))
+}
+
+fn extract_zip(archive: Vec<u8>, binary_name: &str) -> Option<Vec<u8>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(archive)).ok()?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).ok()?;
+        if file.name().rsplit('/').next() == Some(binary_name) {
+            let mut binary = Vec::new();
+            file.read_to_end(&mut binary).ok()?;
+            return Some(binary);
+        }
+    }
+    None
+}
+
+fn extract_tar_gz(archive: Vec<u8>, binary_name: &str) -> Option<Vec<u8>> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(Cursor::new(archive)));
+    for file in archive.entries().ok()? {
+        let mut file = file.ok()?;
+        if file.path().ok()?.file_name().and_then(|name| name.to_str()) == Some(binary_name) {
+            let mut binary = Vec::new();
+            file.read_to_end(&mut binary).ok()?;
+            return Some(binary);
+        }
+    }
+    None
+}
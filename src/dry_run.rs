@@ -0,0 +1,231 @@
+//! Validation of commands for sessions which do not send them
+
+use std::sync::Mutex;
+use json::{JsonValue, object, array};
+use crate::command::Command;
+use crate::enums::Selector;
+use crate::error::{WebdriverError, ErrorKind};
+use crate::transport::{Transport, Method, Response};
+
+/// Check the arguments of a command without sending it.
+pub(crate) fn validate(command: &Command) -> Result<(), WebdriverError> {
+    let invalid = |kind: ErrorKind, message: String| Err(WebdriverError::from(kind).with_message(message));
+
+    match command {
+        Command::Navigate { url } => {
+            if let Err(message) = check_url(url) {
+                return invalid(ErrorKind::InvalidArgument, format!("invalid url {:?}: {}", url, message));
+            }
+        },
+        Command::FindElement { selector, value } | Command::FindElementFromElement { selector, value, .. } => {
+            if let Err(message) = check_selector(*selector, value) {
+                return invalid(ErrorKind::InvalidSelector, format!("invalid {:?} {:?}: {}", selector, value, message));
+            }
+        },
+        Command::SwitchToWindow { handle } if handle.is_empty() => {
+            return invalid(ErrorKind::InvalidArgument, "empty tab id".to_string());
+        },
+        Command::GetElementAttribute { name, .. } | Command::GetElementProperty { name, .. } | Command::GetElementCssValue { property: name, .. } if name.is_empty() => {
+            return invalid(ErrorKind::InvalidArgument, "empty name".to_string());
+        },
+        Command::AddCookie { cookie } if !cookie["name"].is_string() || !cookie["value"].is_string() => {
+            return invalid(ErrorKind::InvalidArgument, "a cookie must have a name and a value".to_string());
+        },
+        Command::PrintPage(options) => {
+            if let Err(message) = options.check() {
+                return invalid(ErrorKind::InvalidArgument, message);
+            }
+        },
+        Command::SetPermission { permission, .. } if !permission.to_json()["name"].is_string() => {
+            return invalid(ErrorKind::InvalidArgument, "a permission descriptor must have a name".to_string());
+        },
+        Command::PerformActions { actions } if !actions.is_array() || !actions.members().all(|source| source["id"].is_string() && source["actions"].is_array()) => {
+            return invalid(ErrorKind::InvalidArgument, "an action sequence must have an id and actions".to_string());
+        },
+        Command::Custom { path, .. } if !path.starts_with('/') => {
+            return invalid(ErrorKind::InvalidArgument, format!("path {:?} must start with a slash", path));
+        },
+        _ => (),
+    }
+    Ok(())
+}
+
+fn check_url(url: &str) -> Result<(), &'static str> {
+    let index = url.find(':').ok_or("no scheme")?;
+    let (scheme, rest) = (&url[..index], &url[index + 1..]);
+    if !scheme.starts_with(|c: char| c.is_ascii_alphabetic()) || !scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.') {
+        return Err("invalid scheme");
+    }
+    match scheme.to_ascii_lowercase().as_str() {
+        "http" | "https" | "ftp" | "ws" | "wss" => {
+            let authority = rest.strip_prefix("//").ok_or("no host")?;
+            let host = authority.split(&['/', '?', '#'][..]).next().unwrap_or("");
+            let host = host.rsplit('@').next().unwrap_or("");
+            if host.is_empty() || host.starts_with(':') {
+                return Err("no host");
+            }
+            if url.contains(char::is_whitespace) {
+                return Err("contains whitespace");
+            }
+        },
+        "file" if !rest.starts_with("//") => return Err("no path"),
+        _ => (),
+    }
+    Ok(())
+}
+
+fn check_selector(selector: Selector, value: &str) -> Result<(), &'static str> {
+    match selector {
+        Selector::Css => {
+            let value = value.trim();
+            if value.is_empty() {
+                return Err("empty selector");
+            }
+            check_balanced(value)?;
+            let combinators = &['>', '+', '~', ','][..];
+            if value.starts_with(combinators) || value.ends_with(combinators) {
+                return Err("dangling combinator");
+            }
+            if value.split(',').any(|part| part.trim().is_empty()) {
+                return Err("empty selector in list");
+            }
+        },
+        Selector::XPath => {
+            if value.trim().is_empty() {
+                return Err("empty expression");
+            }
+            check_balanced(value)?;
+            if value.contains("///") || (value.len() > 1 && value.ends_with('/')) {
+                return Err("missing step");
+            }
+        },
+        Selector::TagName => {
+            if value.is_empty() || !value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ':') {
+                return Err("invalid tag name");
+            }
+        },
+        Selector::LinkText | Selector::PartialLinkText => (),
+        Selector::Text | Selector::ContainsText => {
+            if value.trim().is_empty() {
+                return Err("empty text");
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Check that brackets and quotes are closed.
+fn check_balanced(value: &str) -> Result<(), &'static str> {
+    let mut stack = Vec::new();
+    let mut quote = None;
+    let mut escaped = false;
+    for c in value.chars() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' => quote = Some(c),
+            '(' | '[' => stack.push(c),
+            ')' if stack.pop() != Some('(') => return Err("unbalanced parenthesis"),
+            ']' if stack.pop() != Some('[') => return Err("unbalanced bracket"),
+            _ => (),
+        }
+    }
+    if quote.is_some() {
+        return Err("unclosed quote");
+    }
+    if !stack.is_empty() {
+        return Err("unclosed bracket");
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct State {
+    tabs: Vec<String>,
+    selected: String,
+    url: String,
+    elements: usize
+}
+
+/// Answers every command with a placeholder value.
+pub(crate) struct DryRunTransport {
+    state: Mutex<State>
+}
+
+impl DryRunTransport {
+    pub(crate) fn new() -> Self {
+        DryRunTransport {
+            state: Mutex::new(State {
+                tabs: vec!["tab-0".to_string()],
+                selected: "tab-0".to_string(),
+                url: "about:blank".to_string(),
+                elements: 0
+            })
+        }
+    }
+}
+
+impl Transport for DryRunTransport {
+    fn send(&self, method: Method, path: &str, body: Option<&str>) -> Result<Response, String> {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let body = body.and_then(|body| json::parse(body).ok()).unwrap_or(JsonValue::Null);
+        // the path without the session
+        let command = match path.strip_prefix("/session/dry-run") {
+            Some(command) => command,
+            None => path,
+        };
+
+        let value = match (method, command) {
+            (Method::Get, "/status") => object!{"ready" => true, "message" => "dry run"},
+            (Method::Post, "/session") => object!{"sessionId" => "dry-run", "capabilities" => object!{}},
+            (Method::Get, "/window/handles") => state.tabs.clone().into(),
+            (Method::Get, "/window") => state.selected.clone().into(),
+            (Method::Post, "/window") => {
+                state.selected = body["handle"].to_string();
+                JsonValue::Null
+            },
+            (Method::Post, "/window/new") => {
+                let handle = format!("tab-{}", state.tabs.len());
+                state.tabs.push(handle.clone());
+                let window_type = if body["type"] == "window" { "window" } else { "tab" };
+                object!{"handle" => handle, "type" => window_type}
+            },
+            (Method::Delete, "/window") => {
+                let selected = state.selected.clone();
+                state.tabs.retain(|tab| *tab != selected);
+                state.tabs.clone().into()
+            },
+            (Method::Post, "/url") => {
+                state.url = body["url"].to_string();
+                JsonValue::Null
+            },
+            (Method::Get, "/url") => state.url.clone().into(),
+            (Method::Get, "/timeouts") => object!{"script" => 30_000, "pageLoad" => 300_000, "implicit" => 0},
+            (Method::Get, "/cookie") => array![],
+            (Method::Post, "/se/log") => array![],
+            (Method::Post, "/print") => "".into(),
+            (Method::Post, command) if command == "/element" || (command.starts_with("/element/") && command.ends_with("/element")) => {
+                state.elements += 1;
+                object!{"element-6066-11e4-a52e-4f735466cecf" => format!("element-{}", state.elements)}
+            },
+            (Method::Get, command) if command.starts_with("/element/") && command.ends_with("/rect") => object!{"x" => 0, "y" => 0, "width" => 0, "height" => 0},
+            (Method::Get, command) if command.starts_with("/element/") && (command.ends_with("/enabled") || command.ends_with("/displayed")) => true.into(),
+            (Method::Get, command) if command.starts_with("/element/") && command.ends_with("/selected") => false.into(),
+            (Method::Get, command) if command.starts_with("/element/") || command == "/title" || command == "/source" || command.ends_with("/screenshot") || command.ends_with("/screenshot/full") => "".into(),
+            _ => JsonValue::Null,
+        };
+
+        Ok(Response {
+            status_code: 200,
+            body: object!{"value" => value}.dump().into_bytes()
+        })
+    }
+}
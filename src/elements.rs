@@ -4,75 +4,369 @@ use crate::enums::{Selector, WebdriverObject};
 use json::*;
 use std::result::Result;
 use log::{debug, info, warn, error};
-use std::rc::Rc;
-use crate::http_requests::{execute_script_sync, click_on_element, get_element_text, send_text_to_element, get_selected_tab, select_tab,
-    get_element_attribute, get_element_css_value, get_element_property, get_element_tag_name, is_element_enabled, get_element_rect};
+use std::sync::{Arc, Mutex};
+use crate::http_requests::{Connection, execute_script_sync, click_on_element, get_element_text, send_text_to_element, select_tab,
+    get_element_attribute, get_element_css_value, get_element_property, get_element_tag_name, is_element_enabled, get_element_rect, upload_file,
+    is_element_selected, is_element_displayed, submit_element, perform_actions, find_element, find_element_from_element, get_computed_role,
+    get_computed_label};
+use crate::locator::{Locator, Location};
+use crate::form::SUBMIT_SCRIPT;
+use crate::actions::{ActionChain, MouseButton};
+use crate::archive::zip_file;
+use std::path::Path;
+
+/// Returns true if an element is rendered and visible, like the displayedness atom of Selenium.
+const IS_DISPLAYED_SCRIPT: &str = "
+    const element = arguments[0];
+    const displayed = element => {
+        if (!element.isConnected) {
+            return false;
+        }
+        const tag = element.tagName.toLowerCase();
+        // options are displayed with their select
+        if (tag === 'option' || tag === 'optgroup') {
+            const select = element.closest('select, datalist');
+            return select ? displayed(select) : true;
+        }
+        if (tag === 'input' && element.type.toLowerCase() === 'hidden') {
+            return false;
+        }
+        for (let ancestor = element; ancestor; ancestor = ancestor.parentElement) {
+            const style = getComputedStyle(ancestor);
+            if (style.display === 'none' || style.opacity === '0') {
+                return false;
+            }
+        }
+        const style = getComputedStyle(element);
+        if (style.visibility === 'hidden' || style.visibility === 'collapse') {
+            return false;
+        }
+        const hasSize = element => {
+            const rect = element.getBoundingClientRect();
+            return (rect.width > 0 && rect.height > 0) || Array.from(element.children).some(hasSize);
+        };
+        return hasSize(element);
+    };
+    return displayed(element);
+";
+
+/// The position and the size of an element, in CSS pixels.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64
+}
+
+impl Rect {
+    /// (x, y)
+    pub fn location(&self) -> (f64, f64) {
+        (self.x, self.y)
+    }
+
+    /// (width, height)
+    pub fn size(&self) -> (f64, f64) {
+        (self.width, self.height)
+    }
+
+    /// The center of the rectangle (x, y), where a click lands by default.
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    /// Return true if a point (x, y) is in the rectangle.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
+    }
+}
 
 pub struct Element {
+    /// the id the element was found with
     id: String,
-    session_id: Rc<String>,
-    tab_id: Rc<String>
+    /// the id of the element found again after being stale (None if it was not stale)
+    recovered_id: Mutex<Option<String>>,
+    /// how the element was found (None if it was returned by a script)
+    location: Option<Location>,
+    pub(crate) session_id: Arc<String>,
+    pub(crate) tab_id: Arc<WindowHandle>,
+    pub(crate) connection: Arc<Connection>
 }
 
 impl Element {
-    pub fn new(id: String, session_id: Rc<String>, tab_id: Rc<String>) -> Self {
+    /// Create an element from the id given by the driver (in the result of a [command](../command/enum.Command.html) for instance) and the tab containing it.
+    pub fn new(id: String, tab: &Tab) -> Self {
+        Element::with_connection(id, Arc::clone(&tab.session_id), Arc::clone(&tab.id), Arc::clone(&tab.connection))
+    }
+
+    pub(crate) fn with_connection(id: String, session_id: Arc<String>, tab_id: Arc<WindowHandle>, connection: Arc<Connection>) -> Self {
         Element{
             id,
+            recovered_id: Mutex::new(None),
+            location: None,
             session_id,
-            tab_id
+            tab_id,
+            connection
+        }
+    }
+
+    /// -> take how the element was found, to find it again if it becomes stale
+    pub(crate) fn located(mut self, location: Location) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    /// -> return the id to send the commands with
+    fn current_id(&self) -> String {
+        self.recovered_id.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone().unwrap_or_else(|| self.id.clone())
+    }
+
+    /// -> take a command sent with the id of the element
+    /// -> return its result, the element being found again and the command retried once if the element was stale
+    /// and the [recovery](../session/struct.Session.html#method.set_stale_element_recovery) is enabled or the [retry policy](../retry/struct.RetryPolicy.html) allows retries
+    fn recover<T, F: Fn(&str) -> Result<T, WebdriverError>>(&self, command: F) -> Result<T, WebdriverError> {
+        // sending the stale id again would fail again
+        let recovery = self.connection.get_stale_element_recovery() || self.connection.get_retry_policy().get_max_attempts() > 1;
+        match command(&self.current_id()) {
+            Err(error) if error == ErrorKind::StaleElementReference && recovery => {
+                let id = match self.find_again()? {
+                    Some(id) => id,
+                    None => return Err(error),
+                };
+                info!("element {} was stale and has been found again ({})", self.id, id);
+                *self.recovered_id.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(id.clone());
+                command(&id)
+            },
+            result => result,
+        }
+    }
+
+    /// -> return the id of the element found again with its locator (None if there is no locator or no element)
+    fn find_again(&self) -> Result<Option<String>, WebdriverError> {
+        match &self.location {
+            Some(Location::Selector { selector, value }) => match find_element(&self.connection, &self.session_id, *selector, value) {
+                Ok(id) => Ok(Some(id)),
+                Err(error) if error == ErrorKind::NoSuchElement => Ok(None),
+                Err(error) => Err(error),
+            },
+            Some(Location::Script { script, args }) => {
+                let json = execute_script_sync(&self.connection, &self.session_id, script, args.clone())?;
+                Ok(json[ELEMENT_KEY].as_str().map(|id| id.to_string()))
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn select_tab(&self) -> Result<(), WebdriverError> {
+        select_tab(&self.connection, &self.session_id, self.tab_id.as_str())
+    }
+
+    /// Find an element in this element, selected by a [Selector](../enums/enum.Selector.html).
+    /// Xpath expressions must start with `.` to be relative to this element.
+    ///
+    /// Elements found in another element are not [found again](../session/struct.Session.html#method.set_stale_element_recovery) when they are stale.
+    pub fn find(&self, selector: Selector, tofind: &str) -> Result<Option<Element>, WebdriverError> {
+        self.select_tab()?;
+        match self.recover(|id| find_element_from_element(&self.connection, &self.session_id, id, selector, tofind)) {
+            Ok(id) => Ok(Some(Element::with_connection(id, Arc::clone(&self.session_id), Arc::clone(&self.tab_id), Arc::clone(&self.connection)))),
+            Err(error) if error == ErrorKind::NoSuchElement => Ok(None),
+            Err(error) => Err(error),
         }
     }
 
-    fn select_tab(&self) -> Result<(), WebdriverError> {
-        // check if it is needed to select the tab
-        if let Ok(id) = get_selected_tab(&self.session_id) {
-            if id == *self.tab_id {
-                return Ok(());
+    /// Find an element in this element with any [Locator](../locator/trait.Locator.html).
+    /// Locator scripts receive this element as an additional last argument, and the scripts of this crate only search in it.
+    pub fn find_by<L: Locator>(&self, locator: L) -> Result<Option<Element>, WebdriverError> {
+        match locator.locate() {
+            Location::Selector { selector, value } => self.find(selector, &value),
+            Location::Script { script, args } => {
+                self.select_tab()?;
+                let json = self.recover(|id| {
+                    let args = args.iter().cloned().chain(std::iter::once(reference(id))).collect();
+                    execute_script_sync(&self.connection, &self.session_id, &script, args)
+                })?;
+
+                if let Some(id) = json[ELEMENT_KEY].as_str() {
+                    Ok(Some(Element::with_connection(id.to_string(), Arc::clone(&self.session_id), Arc::clone(&self.tab_id), Arc::clone(&self.connection))))
+                } else if json.is_null() {
+                    Ok(None)
+                } else {
+                    error!("locator script did not return an element: {}", json);
+                    Err(WebdriverError::from(ErrorKind::InvalidResponse).with_message(format!("locator script did not return an element or null: {}", json)))
+                }
             }
         }
+    }
+
+    /// Find an element in this element with a css selector.
+    pub fn find_by_css(&self, selector: &str) -> Result<Option<Element>, WebdriverError> {
+        self.find(Selector::Css, selector)
+    }
+
+    /// Find an element in this element with an xpath expression, which must start with `.` (`.//span`).
+    pub fn find_by_xpath(&self, xpath: &str) -> Result<Option<Element>, WebdriverError> {
+        self.find(Selector::XPath, xpath)
+    }
 
-        // select tab
-        select_tab(&self.session_id, &self.tab_id)
+    /// Find a link in this element by its visible text.
+    pub fn find_by_link_text(&self, text: &str) -> Result<Option<Element>, WebdriverError> {
+        self.find(Selector::LinkText, text)
+    }
+
+    /// Find a link in this element whose visible text contains `text`.
+    pub fn find_by_partial_link_text(&self, text: &str) -> Result<Option<Element>, WebdriverError> {
+        self.find(Selector::PartialLinkText, text)
     }
 
     pub fn type_text(&mut self, text: &str) -> Result<(), WebdriverError> {
         self.select_tab()?;
-        send_text_to_element(&self.session_id, &self.id, text)
+        self.recover(|id| send_text_to_element(&self.connection, &self.session_id, id, text))
+    }
+
+    /// Select a local file in a file input (`<input type="file">`).
+    /// 
+    /// Local drivers receive the absolute path of the file. When the session runs on a remote server,
+    /// the file is first sent to the node with the `/se/file` endpoint of Selenium servers and grids.
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::{Browser, Selector}};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://example.com/upload").unwrap();
+    /// let mut input = session.tabs[0].find(Selector::Css, "input[type=file]").unwrap().unwrap();
+    /// input.upload_file("report.pdf").unwrap();
+    /// ```
+    pub fn upload_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), WebdriverError> {
+        let path = path.as_ref().canonicalize().map_err(|error| {
+            error!("file to upload {} was not found: {}", path.as_ref().display(), error);
+            WebdriverError::from(ErrorKind::InvalidArgument).with_message(format!("file to upload was not found: {}", error))
+        })?;
+        self.select_tab()?;
+
+        let path = if self.connection.remote {
+            let content = std::fs::read(&path).map_err(|error| WebdriverError::custom(format!("failed to read the file to upload: {}", error)))?;
+            let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            upload_file(&self.connection, &self.session_id, base64::encode(&zip_file(&name, &content)?))?
+        } else {
+            path.to_string_lossy().to_string()
+        };
+        self.recover(|id| send_text_to_element(&self.connection, &self.session_id, id, &path))
     }
 
     pub fn get_text(&self) -> Result<String, WebdriverError> {
         self.select_tab()?;
-        get_element_text(&self.session_id, &self.id)
+        self.recover(|id| get_element_text(&self.connection, &self.session_id, id))
     }
 
     pub fn get_attribute(&self, attribute_name: &str) -> Result<String, WebdriverError> {
         self.select_tab()?;
-        get_element_attribute(&self.session_id, &self.id, attribute_name)
+        self.recover(|id| get_element_attribute(&self.connection, &self.session_id, id, attribute_name))
     }
 
     pub fn get_tag_name(&self) -> Result<String, WebdriverError> {
         self.select_tab()?;
-        get_element_tag_name(&self.session_id, &self.id)
+        self.recover(|id| get_element_tag_name(&self.connection, &self.session_id, id))
     }
 
     pub fn get_css_value(&self, property_name: &str) -> Result<String, WebdriverError> {
         self.select_tab()?;
-        get_element_css_value(&self.session_id, &self.id, property_name)
+        self.recover(|id| get_element_css_value(&self.connection, &self.session_id, id, property_name))
     }
 
     pub fn get_property(&self, property_name: &str) -> Result<String, WebdriverError> {
         self.select_tab()?;
-        get_element_property(&self.session_id, &self.id, property_name)
+        self.recover(|id| get_element_property(&self.connection, &self.session_id, id, property_name))
+    }
+
+    /// Return the [ARIA role](https://www.w3.org/TR/wai-aria/#role_definitions) of the element computed by the browser,
+    /// explicit or implied by its tag (`button`, `link`, `heading`...). Elements without role return an empty string or `generic`.
+    pub fn computed_role(&self) -> Result<String, WebdriverError> {
+        self.select_tab()?;
+        self.recover(|id| get_computed_role(&self.connection, &self.session_id, id))
+    }
+
+    /// Return the accessible name of the element computed by the browser, the name read by screen readers
+    /// (from `aria-label`, `aria-labelledby`, a label, `alt`, the text...).
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::{Browser, Selector}};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://example.com/").unwrap();
+    ///
+    /// let link = session.tabs[0].find(Selector::TagName, "a").unwrap().unwrap();
+    /// assert_eq!(link.computed_role().unwrap(), "link");
+    /// assert!(!link.accessible_name().unwrap().is_empty());
+    /// ```
+    pub fn accessible_name(&self) -> Result<String, WebdriverError> {
+        self.select_tab()?;
+        self.recover(|id| get_computed_label(&self.connection, &self.session_id, id))
+    }
+
+    /// Return the html of the element, its own tag included (`<a href="/">Home</a>`).
+    pub fn outer_html(&self) -> Result<String, WebdriverError> {
+        self.get_property("outerHTML")
     }
 
+    /// Return the html of the content of the element, without its own tag (`Home` for `<a href="/">Home</a>`).
+    pub fn inner_html(&self) -> Result<String, WebdriverError> {
+        self.get_property("innerHTML")
+    }
+
+    /// Return ((x, y), (width, height)), rounded down. Negative positions become 0, see [rect()](#method.rect) for exact values.
     pub fn get_rect(&self) -> Result<((usize, usize), (usize, usize)), WebdriverError> {
+        let rect = self.rect()?;
+        Ok(((rect.x as usize, rect.y as usize), (rect.width as usize, rect.height as usize)))
+    }
+
+    /// Return the position and the size of the element in CSS pixels, relative to the document.
+    /// Positions are fractional with zoom and transforms.
+    pub fn rect(&self) -> Result<Rect, WebdriverError> {
         self.select_tab()?;
-        get_element_rect(&self.session_id, &self.id)
+        self.recover(|id| get_element_rect(&self.connection, &self.session_id, id))
+    }
+
+    /// Return the position (x, y) of the top left corner of the element, relative to the document.
+    pub fn location(&self) -> Result<(f64, f64), WebdriverError> {
+        Ok(self.rect()?.location())
+    }
+
+    /// Return the size (width, height) of the element.
+    pub fn size(&self) -> Result<(f64, f64), WebdriverError> {
+        Ok(self.rect()?.size())
     }
 
     pub fn is_enabled(&self) -> Result<bool, WebdriverError> {
         self.select_tab()?;
-        is_element_enabled(&self.session_id, &self.id)
+        self.recover(|id| is_element_enabled(&self.connection, &self.session_id, id))
+    }
+
+    /// Return true if the element is a checked checkbox or radio button, or a selected option.
+    pub fn is_selected(&self) -> Result<bool, WebdriverError> {
+        self.select_tab()?;
+        self.recover(|id| is_element_selected(&self.connection, &self.session_id, id))
+    }
+
+    /// Return true if the element is visible to the user: rendered, not hidden by CSS and not empty.
+    /// The element can be out of the viewport.
+    ///
+    /// A script is used for drivers which do not support the displayed endpoint.
+    pub fn is_displayed(&self) -> Result<bool, WebdriverError> {
+        self.select_tab()?;
+        match self.recover(|id| is_element_displayed(&self.connection, &self.session_id, id)) {
+            Err(error) if error == ErrorKind::UnknowCommand || error == ErrorKind::UnknowMethod => {
+                debug!("displayed endpoint is not supported, using a script");
+                let displayed = self.recover(|id| execute_script_sync(&self.connection, &self.session_id, IS_DISPLAYED_SCRIPT, vec![reference(id)]))?;
+                match displayed.as_bool() {
+                    Some(displayed) => Ok(displayed),
+                    None => {
+                        error!("displayedness of element was not understood: {}", displayed);
+                        Err(ErrorKind::InvalidResponse.into())
+                    }
+                }
+            },
+            result => result,
+        }
     }
 
     pub fn click(&mut self) -> Result<(), WebdriverError> {
@@ -80,17 +374,19 @@ impl Element {
         
         // TODO watch the bug
         warn!("Using javascript click because of a bug in geckodriver where and error hapen but is not reported to us.");
-        if let Ok(()) = execute_script_sync(&self.session_id, "arguments[0].click();", vec![self.as_json_object()]) {
+        if self.recover(|id| execute_script_sync(&self.connection, &self.session_id, "arguments[0].click();", vec![reference(id)])).is_ok() {
             return Ok(());
         } else {
             error!("Failed to click with javascript. Using normal method.");
         }
 
-        match click_on_element(&self.session_id, &self.id) {
+        // chromedriver does not scroll to elements below the fold
+        self.scroll_into_view()?;
+        match self.recover(|id| click_on_element(&self.connection, &self.session_id, id)) {
             Ok(()) => {
                 Ok(())
             }
-            Err(error) if error == WebdriverError::ElementNotInteractable || error == WebdriverError::ElementClickIntercepted => {
+            Err(error) if error == ErrorKind::ElementNotInteractable || error == ErrorKind::ElementClickIntercepted => {
                 Ok(())
             },
             Err(error) => {
@@ -99,15 +395,79 @@ impl Element {
         }
     }
 
+    /// Submit the form containing the element (or the element itself if it is a form).
+    /// This is more reliable than typing Enter, which pages can intercept.
+    ///
+    /// A script is used for drivers which do not support the submit endpoint.
+    /// Return [NoSuchElement](../error/enum.ErrorKind.html#variant.NoSuchElement) if the element is not in a form.
+    pub fn submit(&self) -> Result<(), WebdriverError> {
+        self.select_tab()?;
+        match self.recover(|id| submit_element(&self.connection, &self.session_id, id)) {
+            Err(error) if error == ErrorKind::UnknowCommand || error == ErrorKind::UnknowMethod => {
+                debug!("submit endpoint is not supported, using a script");
+                let submitted = self.recover(|id| execute_script_sync(&self.connection, &self.session_id, SUBMIT_SCRIPT, vec![reference(id)]))?;
+                match submitted.as_bool() {
+                    Some(true) => Ok(()),
+                    Some(false) => Err(WebdriverError::from(ErrorKind::NoSuchElement).with_message("the element is not in a form".to_string())),
+                    None => {
+                        error!("result of form submission was not understood: {}", submitted);
+                        Err(ErrorKind::InvalidResponse.into())
+                    }
+                }
+            },
+            result => result,
+        }
+    }
+
+    /// Move the mouse over the element, at the center of its visible part, like the user would to open a menu.
+    /// The element is scrolled into view first.
+    pub fn hover(&self) -> Result<(), WebdriverError> {
+        self.mouse_gesture(|actions| actions.move_to_element(self))
+    }
+
+    /// Click with the right button, opening the context menu.
+    pub fn context_click(&self) -> Result<(), WebdriverError> {
+        self.mouse_gesture(|actions| actions.move_to_element(self).click(MouseButton::Right))
+    }
+
+    /// Click twice with the left button.
+    pub fn double_click(&self) -> Result<(), WebdriverError> {
+        self.mouse_gesture(|actions| actions.move_to_element(self).double_click())
+    }
+
+    /// Click with the middle button, opening links in a new tab.
+    pub fn middle_click(&self) -> Result<(), WebdriverError> {
+        self.mouse_gesture(|actions| actions.move_to_element(self).click(MouseButton::Middle))
+    }
+
+    /// -> take a function adding actions starting by moving the mouse to the element
+    /// scroll the element into view and perform them
+    fn mouse_gesture<F: Fn(ActionChain) -> ActionChain>(&self, gesture: F) -> Result<(), WebdriverError> {
+        self.scroll_into_view()?;
+        // the actions are built again if the element is found again
+        self.recover(|_| perform_actions(&self.connection, &self.session_id, gesture(ActionChain::new()).to_json()))
+    }
+
     pub fn as_json_object(&self) -> JsonValue {
-        object!{ "element-6066-11e4-a52e-4f735466cecf" => self.id.as_str() }
+        reference(&self.current_id())
     }
 
+    /// Scroll the page so that the element is at the center of the viewport (if the page can be scrolled enough).
     pub fn scroll_into_view(&self) -> Result<(), WebdriverError> {
-        execute_script_sync(&self.session_id, "arguments[0].scrollIntoView();", vec![self.as_json_object()])
+        self.select_tab()?;
+        self.recover(|id| execute_script_sync(&self.connection, &self.session_id, "arguments[0].scrollIntoView({block: 'center', inline: 'center'});", vec![reference(id)]))?;
+        Ok(())
     }
 }
 
+const ELEMENT_KEY: &str = "element-6066-11e4-a52e-4f735466cecf";
+
+/// -> take the id of an element
+/// -> return the reference of the element passed to scripts
+fn reference(id: &str) -> JsonValue {
+    object!{ ELEMENT_KEY => id }
+}
+
 impl PartialEq for Element {
     fn eq(&self, other: &Self) -> bool {
         self.get_id() == other.get_id()
@@ -115,6 +475,8 @@ impl PartialEq for Element {
 }
 
 impl WebdriverObject for Element {
+    type Id = String;
+
     fn get_id(&self) -> &String {
         &self.id
     }
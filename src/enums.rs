@@ -1,3 +1,6 @@
+use std::path::{Path, PathBuf};
+use crate::error::{WebdriverError, ErrorKind};
+
 #[derive(PartialEq)]
 #[derive(Debug)]
 #[derive(Copy, Clone)]
@@ -6,34 +9,146 @@ pub enum Selector {
     XPath,
     TagName,
     LinkText,
-    PartialLinkText
+    PartialLinkText,
+    /// An element whose text, with whitespace normalized, is the value (like `Sign in` for `<button> Sign  in </button>`).
+    /// The innermost matching element is found. Translated to an xpath expression.
+    Text,
+    /// An element whose text, with whitespace normalized, contains the value. The innermost matching element is found.
+    ContainsText
 }
 
 impl Selector {
+    /// The location strategy of the webdriver protocol (text selectors are sent as xpath expressions).
     pub fn to_string(self) -> &'static str {
         match self {
             Selector::Css => "css selector",
             Selector::XPath => "xpath",
             Selector::TagName => "tag name",
             Selector::LinkText => "link text",
-            Selector::PartialLinkText => "partial link text"
+            Selector::PartialLinkText => "partial link text",
+            Selector::Text | Selector::ContainsText => "xpath"
         }
     }
+
+    /// -> take the value of the selector
+    /// -> return the strategy of the webdriver protocol and its value, text selectors being translated to xpath expressions
+    pub(crate) fn to_strategy(self, value: &str) -> (&'static str, String) {
+        let text = xpath_literal(&value.split_whitespace().collect::<Vec<&str>>().join(" "));
+        let condition = match self {
+            Selector::Text => format!("normalize-space()={}", text),
+            Selector::ContainsText => format!("contains(normalize-space(), {})", text),
+            selector => return (selector.to_string(), value.to_string()),
+        };
+        // the ancestors of the element contain its text too
+        ("xpath", format!(".//*[{0}][not(.//*[{0}])]", condition))
+    }
+}
+
+/// -> take a string
+/// -> return an xpath string literal (xpath 1.0 has no escape sequences)
+fn xpath_literal(value: &str) -> String {
+    if !value.contains('\'') {
+        format!("'{}'", value)
+    } else if !value.contains('"') {
+        format!("\"{}\"", value)
+    } else {
+        let parts: Vec<String> = value.split('\'').map(|part| format!("'{}'", part)).collect();
+        format!("concat({})", parts.join(", \"'\", "))
+    }
 }
 
 #[derive(PartialEq)]
 #[derive(Debug)]
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub enum Browser {
     Firefox,
-    Chrome
+    Chrome,
+    Edge,
+    /// Safari on macOS, driven by the `safaridriver` shipped with the system.
+    /// Remote automation must be enabled once with `safaridriver --enable`. Safari cannot run headless.
+    Safari,
+    /// A browser based on Chromium (Brave, Opera, Chromium...) driven by chromedriver.
+    /// The binary is the executable of the browser (`/usr/bin/brave-browser`), sessions cannot be created without it.
+    ChromiumBased { binary: PathBuf }
 }
 
 impl Browser {
-    pub fn to_string(self) -> &'static str {
+    /// The `browserName` of the capabilities. Chromium-based browsers are driven by chromedriver as `chrome`.
+    pub fn to_string(&self) -> &'static str {
         match self {
             Browser::Firefox => "firefox",
-            Browser::Chrome => "chrome"
+            Browser::Chrome | Browser::ChromiumBased { .. } => "chrome",
+            Browser::Edge => "MicrosoftEdge",
+            Browser::Safari => "safari"
+        }
+    }
+
+    /// -> take `firefox`, `chrome`, `edge`, `safari` (in any case) or the path of a Chromium-based browser
+    /// -> return the browser, or InvalidArgument if the name is unknown and is not a path (a misspelled name is not a binary)
+    pub(crate) fn from_name(name: &str) -> Result<Browser, WebdriverError> {
+        match name.to_lowercase().as_str() {
+            "firefox" => Ok(Browser::Firefox),
+            "chrome" => Ok(Browser::Chrome),
+            "edge" => Ok(Browser::Edge),
+            "safari" => Ok(Browser::Safari),
+            _ if name.contains('/') || name.contains(std::path::MAIN_SEPARATOR) || Path::new(name).exists() => Ok(Browser::ChromiumBased { binary: name.into() }),
+            _ => Err(WebdriverError::from(ErrorKind::InvalidArgument)
+                .with_message(format!("the browser must be firefox, chrome, edge, safari or the path of a Chromium-based browser, not {:?}", name))),
+        }
+    }
+
+    /// The name of the driver program.
+    pub(crate) fn driver_name(&self) -> &'static str {
+        match self {
+            Browser::Firefox => "geckodriver",
+            Browser::Chrome | Browser::ChromiumBased { .. } => "chromedriver",
+            Browser::Edge => "msedgedriver",
+            Browser::Safari => "safaridriver"
+        }
+    }
+
+    /// The driver installed with the system, which is used instead of a driver next to the program.
+    pub(crate) fn system_driver(&self) -> Option<&'static str> {
+        match self {
+            Browser::Safari => Some("/usr/bin/safaridriver"),
+            Browser::Firefox | Browser::Chrome | Browser::Edge | Browser::ChromiumBased { .. } => None
+        }
+    }
+
+    /// The key of the vendor-specific options in the capabilities (None if the browser takes no arguments nor preferences).
+    pub(crate) fn options_key(&self) -> Option<&'static str> {
+        match self {
+            Browser::Firefox => Some("moz:firefoxOptions"),
+            Browser::Chrome | Browser::ChromiumBased { .. } => Some("goog:chromeOptions"),
+            Browser::Edge => Some("ms:edgeOptions"),
+            Browser::Safari => None
+        }
+    }
+
+    /// The vendor prefix of the Chrome DevTools Protocol endpoint of the driver, if the browser is based on Chromium.
+    pub(crate) fn cdp_vendor(&self) -> Option<&'static str> {
+        match self {
+            Browser::Firefox | Browser::Safari => None,
+            Browser::Chrome | Browser::ChromiumBased { .. } => Some("goog"),
+            Browser::Edge => Some("ms")
+        }
+    }
+}
+
+/// The kind of a top-level browsing context.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum WindowType {
+    Tab,
+    Window
+}
+
+impl WindowType {
+    pub fn to_string(self) -> &'static str {
+        match self {
+            WindowType::Tab => "tab",
+            WindowType::Window => "window"
         }
     }
 }
@@ -44,6 +159,7 @@ impl Browser {
 pub enum Platform {
     Linux,
     Windows,
+    Mac,
     Unknow
 }
 
@@ -52,12 +168,15 @@ impl Platform {
         match self {
             Platform::Linux => "linux",
             Platform::Windows => "windows",
+            Platform::Mac => "mac",
             Platform::Unknow => "unknow"
         }
     }
 
     pub fn current() -> Platform {
-        if cfg!(unix) {
+        if cfg!(target_os = "macos") {
+            Platform::Mac
+        } else if cfg!(unix) {
             Platform::Linux
         } else if cfg!(windows) {
             Platform::Windows
@@ -68,5 +187,8 @@ impl Platform {
 }
 
 pub trait WebdriverObject: PartialEq {
-    fn get_id(&self) -> &String;
+    /// The id of the object ([WindowHandle](../tab/struct.WindowHandle.html) for tabs).
+    type Id: PartialEq + ?Sized;
+
+    fn get_id(&self) -> &Self::Id;
 }
\ No newline at end of file
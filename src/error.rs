@@ -1,10 +1,152 @@
+//! Errors returned by sessions
+
+use std::fmt;
+use json::JsonValue;
+use crate::responses::{ErrorValue, to_json_value};
+use crate::recovery::SessionRestarted;
+
+/// An error of a command, with the details given by the driver.
+///
+/// Compare the [kind](#structfield.kind) of an error to handle it:
+///
+/// ```rust
+/// use lw_webdriver::{session::Session, enums::{Browser, Selector}, error::ErrorKind};
+///
+/// let mut session = Session::new(Browser::Firefox, false).unwrap();
+/// session.tabs[0].navigate("https://www.mozilla.org/en-US/").unwrap();
+///
+/// match session.tabs[0].find(Selector::Css, "#does-not-exist") {
+///     Err(error) if error == ErrorKind::NoSuchElement => println!("not found: {}", error),
+///     Err(error) => panic!("{}", error),
+///     Ok(_) => (),
+/// }
+/// ```
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct WebdriverError {
+    pub kind: ErrorKind,
+    /// boxed to keep results small
+    details: Box<Details>
+}
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+struct Details {
+    message: Option<String>,
+    data: JsonValue,
+    stacktrace: Option<String>,
+    status: Option<u16>,
+    command: Option<String>
+}
+
+impl WebdriverError {
+    /// -> take the value of an error response and its HTTP status code
+    pub(crate) fn from_response(value: ErrorValue, status: u16) -> Self {
+        let non_empty = |value: String| if value.is_empty() { None } else { Some(value) };
+        let mut error = WebdriverError::from(ErrorKind::from(value.error));
+        error.details.message = non_empty(value.message);
+        error.details.data = to_json_value(value.data);
+        error.details.stacktrace = non_empty(value.stacktrace);
+        error.details.status = Some(status);
+        error
+    }
+
+    pub(crate) fn custom(message: String) -> Self {
+        WebdriverError::from(ErrorKind::Custom(message))
+    }
+
+    pub(crate) fn with_message(mut self, message: String) -> Self {
+        self.details.message = Some(message);
+        self
+    }
+
+    pub(crate) fn with_status(mut self, status: u16) -> Self {
+        self.details.status = Some(status);
+        self
+    }
+
+    pub(crate) fn with_command(mut self, command: String) -> Self {
+        self.details.command = Some(command);
+        self
+    }
+
+    /// Explanation of the error, given by the driver.
+    pub fn message(&self) -> Option<&str> {
+        self.details.message.as_deref()
+    }
+
+    /// Additional data given by the driver (JsonValue::Null if none).
+    pub fn data(&self) -> &JsonValue {
+        &self.details.data
+    }
+
+    /// Stacktrace of the driver, if provided.
+    pub fn stacktrace(&self) -> Option<&str> {
+        self.details.stacktrace.as_deref()
+    }
+
+    /// Status code of the HTTP response, if the server responded.
+    pub fn status(&self) -> Option<u16> {
+        self.details.status
+    }
+
+    /// The command which failed (`POST /session/{session id}/element`), if the error comes from a command.
+    pub fn command(&self) -> Option<&str> {
+        self.details.command.as_deref()
+    }
+}
+
+impl From<ErrorKind> for WebdriverError {
+    fn from(kind: ErrorKind) -> Self {
+        WebdriverError {
+            kind,
+            details: Box::new(Details {
+                message: None,
+                data: JsonValue::Null,
+                stacktrace: None,
+                status: None,
+                command: None
+            })
+        }
+    }
+}
+
+impl PartialEq<ErrorKind> for WebdriverError {
+    fn eq(&self, kind: &ErrorKind) -> bool {
+        self.kind == *kind
+    }
+}
+
+impl fmt::Display for WebdriverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        match (self.command(), self.status()) {
+            (Some(command), Some(status)) => write!(f, " ({} on {})", status, command)?,
+            (Some(command), None) => write!(f, " (on {})", command)?,
+            (None, Some(status)) => write!(f, " ({})", status)?,
+            (None, None) => (),
+        }
+        if let Some(message) = self.message() {
+            write!(f, ": {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for WebdriverError {}
+
+/// The kinds of errors, mostly the [error codes](https://www.w3.org/TR/webdriver/#errors) of the webdriver protocol.
 #[derive(PartialEq)]
 #[derive(Debug)]
 #[derive(Clone)]
-pub enum WebdriverError {
+pub enum ErrorKind {
     UnsupportedPlatform,
     FailedRequest,
     InvalidResponse,
+    /// The tab (or the tab of the element) was closed, by a web page for example.
+    TabClosed,
     Unknow,
     ElementClickIntercepted,
     ElementNotInteractable,
@@ -32,39 +174,82 @@ pub enum WebdriverError {
     UnknowError,
     UnknowMethod,
     UnsupportedOperation,
+    /// The session crashed and was restarted (see [SessionConfig::crash_recovery()](../config/struct.SessionConfig.html#method.crash_recovery)),
+    /// but the command was not sent again because it could have been executed before the crash.
+    SessionRestarted(Box<SessionRestarted>),
     Custom(String)
 }
 
-impl WebdriverError {
+impl ErrorKind {
     pub fn from(error: String) -> Self {
         match error.as_str() {
-            "element click intercepted" => WebdriverError::ElementClickIntercepted,
-            "element not interactable" => WebdriverError::ElementNotInteractable,
-            "insecure certificate" => WebdriverError::InsecureCertificate,
-            "invalid argument" => WebdriverError::InvalidArgument,
-            "invalid cookie domain" => WebdriverError::InvalidCookieDomain,
-            "invalid element state" => WebdriverError::InvalidElementState,
-            "invalid selector" => WebdriverError::InvalidSelector,
-            "invalid session id " => WebdriverError::InvalidSessionId,
-            "javascript error" => WebdriverError::JavascriptError,
-            "move target out of bounds" => WebdriverError::MoveTargetOutOfBounds,
-            "no such alert" => WebdriverError::NoSuchAlert,
-            "no such cookie" => WebdriverError::NoSuchCookie,
-            "no such element" => WebdriverError::NoSuchElement,
-            "no such frame" => WebdriverError::NoSuchFrame,
-            "no such window" => WebdriverError::NoSuchWindow,
-            "script timeout error" => WebdriverError::ScriptTimeoutError,
-            "session not created" => WebdriverError::SessionNotCreated,
-            "stale element reference" => WebdriverError::StaleElementReference,
-            "timeout" => WebdriverError::Timeout,
-            "unable to set cookie" => WebdriverError::UnnableToSetCookie,
-            "unable to capture screen" => WebdriverError::UnableToCaptureScreen,
-            "unexpected alert open" => WebdriverError::UnexpectedAlertOpen,
-            "unknown command" => WebdriverError::UnknowCommand,
-            "unknown error" => WebdriverError::Unknow,
-            "unknown method" => WebdriverError::UnknowMethod,
-            "unsupported operation" => WebdriverError::UnsupportedOperation,
-            _ => WebdriverError::Custom(error),
+            "element click intercepted" => ErrorKind::ElementClickIntercepted,
+            "element not interactable" => ErrorKind::ElementNotInteractable,
+            "insecure certificate" => ErrorKind::InsecureCertificate,
+            "invalid argument" => ErrorKind::InvalidArgument,
+            "invalid cookie domain" => ErrorKind::InvalidCookieDomain,
+            "invalid element state" => ErrorKind::InvalidElementState,
+            "invalid selector" => ErrorKind::InvalidSelector,
+            "invalid session id" => ErrorKind::InvalidSessionId,
+            "javascript error" => ErrorKind::JavascriptError,
+            "move target out of bounds" => ErrorKind::MoveTargetOutOfBounds,
+            "no such alert" => ErrorKind::NoSuchAlert,
+            "no such cookie" => ErrorKind::NoSuchCookie,
+            "no such element" => ErrorKind::NoSuchElement,
+            "no such frame" => ErrorKind::NoSuchFrame,
+            "no such window" => ErrorKind::NoSuchWindow,
+            "script timeout error" => ErrorKind::ScriptTimeoutError,
+            "session not created" => ErrorKind::SessionNotCreated,
+            "stale element reference" => ErrorKind::StaleElementReference,
+            "timeout" => ErrorKind::Timeout,
+            "unable to set cookie" => ErrorKind::UnnableToSetCookie,
+            "unable to capture screen" => ErrorKind::UnableToCaptureScreen,
+            "unexpected alert open" => ErrorKind::UnexpectedAlertOpen,
+            "unknown command" => ErrorKind::UnknowCommand,
+            "unknown error" => ErrorKind::Unknow,
+            "unknown method" => ErrorKind::UnknowMethod,
+            "unsupported operation" => ErrorKind::UnsupportedOperation,
+            _ => ErrorKind::Custom(error),
         }
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            ErrorKind::UnsupportedPlatform => "unsupported platform",
+            ErrorKind::FailedRequest => "failed to reach the webdriver server",
+            ErrorKind::InvalidResponse => "invalid response of the webdriver server",
+            ErrorKind::TabClosed => "the tab was closed",
+            ErrorKind::Unknow | ErrorKind::UnknowError => "unknown error",
+            ErrorKind::ElementClickIntercepted => "element click intercepted",
+            ErrorKind::ElementNotInteractable => "element not interactable",
+            ErrorKind::InsecureCertificate => "insecure certificate",
+            ErrorKind::InvalidArgument => "invalid argument",
+            ErrorKind::InvalidCookieDomain => "invalid cookie domain",
+            ErrorKind::InvalidElementState => "invalid element state",
+            ErrorKind::InvalidSelector => "invalid selector",
+            ErrorKind::InvalidSessionId => "invalid session id",
+            ErrorKind::JavascriptError => "javascript error",
+            ErrorKind::MoveTargetOutOfBounds => "move target out of bounds",
+            ErrorKind::NoSuchAlert => "no such alert",
+            ErrorKind::NoSuchCookie => "no such cookie",
+            ErrorKind::NoSuchElement => "no such element",
+            ErrorKind::NoSuchFrame => "no such frame",
+            ErrorKind::NoSuchWindow => "no such window",
+            ErrorKind::ScriptTimeoutError => "script timeout",
+            ErrorKind::SessionNotCreated => "session not created",
+            ErrorKind::StaleElementReference => "stale element reference",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::UnnableToSetCookie => "unable to set cookie",
+            ErrorKind::UnableToCaptureScreen => "unable to capture screen",
+            ErrorKind::UnexpectedAlertOpen => "unexpected alert open",
+            ErrorKind::UnknowCommand => "unknown command",
+            ErrorKind::UnknowMethod => "unknown method",
+            ErrorKind::UnsupportedOperation => "unsupported operation",
+            ErrorKind::SessionRestarted(_) => "the session was restarted after a crash",
+            ErrorKind::Custom(error) => error,
+        };
+        write!(f, "{}", description)
+    }
+}
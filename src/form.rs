@@ -0,0 +1,175 @@
+//! Filling of forms
+//!
+//! Fields are found with css selectors and filled according to their type:
+//! - text fields (inputs, textareas, editable elements) receive the value,
+//! - checkboxes are checked if the value is `true`, `on`, `yes` or `1`, and unchecked otherwise,
+//! - radio buttons are chosen by value (the selector can match the whole group),
+//! - selects choose the option with the value, or with the visible text,
+//! - file inputs receive the local file at the path (see [Element::upload_file()](../elements/struct.Element.html#method.upload_file)).
+//!
+//! Events are dispatched like when the user fills the field, so that frameworks notice the changes.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::{Browser, Selector}, form::Form};
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.tabs[0].navigate("https://example.com/signup").unwrap();
+//!
+//! let form = Form::new(session.tabs[0].find(Selector::Css, "form#signup").unwrap().unwrap()).unwrap();
+//! form.fill(&[
+//!     ("[name=email]", "user@example.com"),
+//!     ("[name=password]", "correct horse battery staple"),
+//!     ("[name=plan]", "premium"),
+//!     ("#terms", "true"),
+//! ]).unwrap();
+//! form.submit().unwrap();
+//! ```
+
+use std::sync::Arc;
+use json::JsonValue;
+use log::{debug, error};
+use crate::elements::Element;
+use crate::tab::WindowHandle;
+use crate::error::*;
+use crate::http_requests::{Connection, execute_script_sync};
+
+/// Fills a field (arguments: the element containing the field or null for the document, the selector, the value).
+/// Returns `{status}`, the status being `filled`, `missing`, `no option` or `file` (with the element to upload a file to).
+const FILL_SCRIPT: &str = "
+    const [root, selector, value] = arguments;
+    const fields = Array.from((root || document).querySelectorAll(selector));
+    const field = fields[0];
+    if (!field) {
+        return { status: 'missing' };
+    }
+    const dispatch = element => {
+        element.dispatchEvent(new Event('input', { bubbles: true }));
+        element.dispatchEvent(new Event('change', { bubbles: true }));
+    };
+    const type = field.tagName === 'INPUT' ? (field.type || 'text').toLowerCase() : field.tagName.toLowerCase();
+
+    if (type === 'file') {
+        return { status: 'file', element: field };
+    } else if (type === 'checkbox') {
+        if (field.checked !== ['true', 'on', 'yes', '1'].includes(value.toLowerCase())) {
+            field.click();
+        }
+    } else if (type === 'radio') {
+        const radio = fields.concat(field.form && field.name ? Array.from(field.form.elements).filter(element => element.name === field.name) : [])
+            .find(radio => radio.value === value);
+        if (!radio) {
+            return { status: 'no option' };
+        }
+        if (!radio.checked) {
+            radio.click();
+        }
+    } else if (type === 'select') {
+        const options = Array.from(field.options);
+        const option = options.find(option => option.value === value) || options.find(option => option.text.trim() === value.trim());
+        if (!option) {
+            return { status: 'no option' };
+        }
+        option.selected = true;
+        dispatch(field);
+    } else if (field.isContentEditable && !('value' in field)) {
+        field.focus();
+        field.textContent = value;
+        dispatch(field);
+    } else {
+        // the setter of the prototype notifies frameworks wrapping the value property
+        const prototype = type === 'textarea' ? HTMLTextAreaElement.prototype : HTMLInputElement.prototype;
+        const setter = Object.getOwnPropertyDescriptor(prototype, 'value').set;
+        field.focus();
+        setter.call(field, value);
+        dispatch(field);
+    }
+    return { status: 'filled' };
+";
+
+/// Submits a form, or the form containing an element, like the user would, running validation and submit listeners.
+/// Returns false if there is no form.
+pub(crate) const SUBMIT_SCRIPT: &str = "
+    const element = arguments[0];
+    const form = element.tagName === 'FORM' ? element : element.form || element.closest('form');
+    if (!form) {
+        return false;
+    }
+    if (form.requestSubmit) {
+        form.requestSubmit();
+    } else {
+        form.submit();
+    }
+    return true;
+";
+
+/// -> take the element containing the fields (null for the whole document) and the fields
+/// fill the fields one by one
+pub(crate) fn fill_fields(connection: &Arc<Connection>, session_id: &Arc<String>, tab_id: &Arc<WindowHandle>, root: JsonValue, fields: &[(&str, &str)]) -> Result<(), WebdriverError> {
+    for (selector, value) in fields {
+        let result = execute_script_sync(connection, session_id, FILL_SCRIPT, vec![root.clone(), (*selector).into(), (*value).into()])?;
+        match result["status"].as_str() {
+            Some("filled") => debug!("field {:?} filled", selector),
+            Some("missing") => {
+                return Err(WebdriverError::from(ErrorKind::NoSuchElement).with_message(format!("no field matched {:?}", selector)));
+            },
+            Some("no option") => {
+                return Err(WebdriverError::from(ErrorKind::InvalidArgument).with_message(format!("field {:?} has no option {:?}", selector, value)));
+            },
+            Some("file") => match result["element"]["element-6066-11e4-a52e-4f735466cecf"].as_str() {
+                Some(id) => {
+                    let mut input = Element::with_connection(id.to_string(), Arc::clone(session_id), Arc::clone(tab_id), Arc::clone(connection));
+                    input.upload_file(value)?;
+                },
+                None => {
+                    error!("file input was not understood: {}", result);
+                    return Err(ErrorKind::InvalidResponse.into());
+                }
+            },
+            _ => {
+                error!("result of form filling was not understood: {}", result);
+                return Err(ErrorKind::InvalidResponse.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A `<form>` element.
+pub struct Form {
+    element: Element
+}
+
+impl Form {
+    /// Wrap a form element.
+    /// Return [InvalidArgument](../error/enum.ErrorKind.html#variant.InvalidArgument) if the element is not a form.
+    pub fn new(element: Element) -> Result<Form, WebdriverError> {
+        let tag_name = element.get_tag_name()?;
+        if !tag_name.eq_ignore_ascii_case("form") {
+            error!("a {} element is not a form", tag_name);
+            return Err(WebdriverError::from(ErrorKind::InvalidArgument).with_message(format!("expected a form element, found a {} element", tag_name)));
+        }
+        Ok(Form { element })
+    }
+
+    /// The wrapped element.
+    pub fn element(&self) -> &Element {
+        &self.element
+    }
+
+    /// Fill fields of the form, found with css selectors relative to the form (see the [module](index.html) for the supported fields).
+    /// Return [NoSuchElement](../error/enum.ErrorKind.html#variant.NoSuchElement) if a field does not exist,
+    /// and [InvalidArgument](../error/enum.ErrorKind.html#variant.InvalidArgument) if a select or a radio group has no such option.
+    pub fn fill(&self, fields: &[(&str, &str)]) -> Result<(), WebdriverError> {
+        self.element.select_tab()?;
+        fill_fields(&self.element.connection, &self.element.session_id, &self.element.tab_id, self.element.as_json_object(), fields)
+    }
+
+    /// Submit the form like the user would (validation and submit listeners are run).
+    pub fn submit(&self) -> Result<(), WebdriverError> {
+        self.element.select_tab()?;
+        execute_script_sync(&self.element.connection, &self.element.session_id, SUBMIT_SCRIPT, vec![self.element.as_json_object()])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,325 @@
+//! Recording of the traffic of a tab in the [HAR format](http://www.softwareishard.com/blog/har-12-spec/)
+//!
+//! On Chrome and Edge, requests are read from the performance log of the driver, which must be enabled with
+//! [Capabilities::network_log()](../capabilities/struct.Capabilities.html#method.network_log). Entries then contain
+//! headers, statuses and detailed timings of every request of the tab.
+//!
+//! Otherwise, and always on Firefox, entries are built from the [Resource Timing API](https://developer.mozilla.org/en-US/docs/Web/API/PerformanceResourceTiming)
+//! of the loaded page: only requests of the current page are recorded, without headers.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, capabilities::Capabilities, enums::Browser};
+//!
+//! let mut session = Session::new_with_capabilities(Capabilities::new(Browser::Chrome).network_log(true)).unwrap();
+//! session.tabs[0].start_har_recording().unwrap();
+//! session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+//! let har = session.tabs[0].stop_har_recording().unwrap();
+//!
+//! std::fs::write("mubelotix.har", har.pretty(2)).unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use json::{JsonValue, object, array};
+use crate::responses::LogEntryValue;
+
+/// Lists every request of the page with its timings, relative to `performance.timeOrigin`.
+pub(crate) const RESOURCE_TIMINGS_SCRIPT: &str = "return {
+    timeOrigin: performance.timeOrigin,
+    entries: performance.getEntriesByType('navigation').concat(performance.getEntriesByType('resource')).map(entry => entry.toJSON())
+};";
+
+/// -> take milliseconds since the unix epoch
+/// -> return a date like "2020-01-31T18:04:05.123Z"
+fn iso_8601(milliseconds: f64) -> String {
+    let milliseconds = milliseconds.max(0.0) as u64;
+    let (days, day_milliseconds) = ((milliseconds / 86_400_000) as i64, milliseconds % 86_400_000);
+
+    // conversion of days to a civil date (http://howardhinnant.github.io/date_algorithms.html#civil_from_days)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z", year, month, day,
+        day_milliseconds / 3_600_000, day_milliseconds / 60_000 % 60, day_milliseconds / 1000 % 60, day_milliseconds % 1000)
+}
+
+pub(crate) fn unix_milliseconds(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH).map(|duration| duration.as_secs_f64() * 1000.0).unwrap_or(0.0)
+}
+
+fn query_string(url: &str) -> JsonValue {
+    let query = url.split('#').next().unwrap_or("").split_once('?').map(|(_, query)| query).unwrap_or("");
+    query.split('&').filter(|parameter| !parameter.is_empty()).map(|parameter| {
+        let mut parts = parameter.splitn(2, '=');
+        object!{
+            "name" => parts.next().unwrap_or(""),
+            "value" => parts.next().unwrap_or("")
+        }
+    }).collect::<Vec<JsonValue>>().into()
+}
+
+/// -> take headers as an object ({"name": "value"})
+/// -> return headers as a list of name/value pairs
+fn headers(headers: &JsonValue) -> JsonValue {
+    headers.entries().flat_map(|(name, value)| {
+        // several values of a header are separated by new lines
+        value.as_str().unwrap_or("").split('\n').map(move |value| object!{
+            "name" => name,
+            "value" => value
+        }).collect::<Vec<JsonValue>>()
+    }).collect::<Vec<JsonValue>>().into()
+}
+
+fn http_version(protocol: &str) -> String {
+    match protocol.to_lowercase().as_str() {
+        "h2" | "http/2.0" => "HTTP/2".to_string(),
+        "h3" | "http/3" => "HTTP/3".to_string(),
+        "" => "HTTP/1.1".to_string(),
+        protocol => protocol.to_uppercase(),
+    }
+}
+
+/// -> take the start of a phase and its end, -1 meaning the phase did not happen
+fn phase(start: f64, end: f64) -> f64 {
+    if start < 0.0 || end < 0.0 { -1.0 } else { (end - start).max(0.0) }
+}
+
+/// -> take the start of the recording, the title of the page and the entries
+/// -> return a HAR document
+pub(crate) fn document(started: SystemTime, title: &str, entries: Vec<JsonValue>) -> JsonValue {
+    object!{
+        "log" => object!{
+            "version" => "1.2",
+            "creator" => object!{
+                "name" => "lw-webdriver",
+                "version" => env!("CARGO_PKG_VERSION")
+            },
+            "pages" => array![object!{
+                "startedDateTime" => iso_8601(unix_milliseconds(started)),
+                "id" => "page_1",
+                "title" => title,
+                "pageTimings" => object!{}
+            }],
+            "entries" => entries
+        }
+    }
+}
+
+/// A request followed in the performance log
+struct Request {
+    /// seconds since the unix epoch
+    wall_time: f64,
+    /// seconds on the monotonic clock of the browser
+    timestamp: f64,
+    method: String,
+    url: String,
+    headers: JsonValue,
+    post_data: Option<String>,
+    response: Option<JsonValue>,
+    end: Option<f64>,
+    encoded_length: Option<f64>,
+    error: Option<String>
+}
+
+impl Request {
+    fn to_entry(&self) -> JsonValue {
+        let null = JsonValue::Null;
+        let response = self.response.as_ref().unwrap_or(&null);
+        let http_version = http_version(response["protocol"].as_str().unwrap_or(""));
+        let time = self.end.map(|end| (end - self.timestamp) * 1000.0).unwrap_or(0.0).max(0.0);
+
+        // timings are in milliseconds relative to timing.requestTime
+        let timing = &response["timing"];
+        let at = |name: &str| timing[name].as_f64().unwrap_or(-1.0);
+        let headers_received = at("receiveHeadersEnd");
+        let (send, wait, receive) = if timing.is_object() {
+            let request_start = (timing["requestTime"].as_f64().unwrap_or(self.timestamp) - self.timestamp) * 1000.0;
+            (phase(at("sendStart"), at("sendEnd")).max(0.0), phase(at("sendEnd"), headers_received).max(0.0), (time - request_start - headers_received).max(0.0))
+        } else {
+            (0.0, time, 0.0)
+        };
+
+        let mut request = object!{
+            "method" => self.method.as_str(),
+            "url" => self.url.as_str(),
+            "httpVersion" => http_version.as_str(),
+            "cookies" => array![],
+            "headers" => headers(&self.headers),
+            "queryString" => query_string(&self.url),
+            "headersSize" => -1,
+            "bodySize" => self.post_data.as_ref().map(|data| data.len()).unwrap_or(0)
+        };
+        if let Some(data) = &self.post_data {
+            request["postData"] = object!{
+                "mimeType" => self.headers["Content-Type"].as_str().unwrap_or(""),
+                "text" => data.as_str()
+            };
+        }
+
+        object!{
+            "pageref" => "page_1",
+            "startedDateTime" => iso_8601(self.wall_time * 1000.0),
+            "time" => time,
+            "request" => request,
+            "response" => object!{
+                "status" => response["status"].as_u64().unwrap_or(0),
+                "statusText" => self.error.as_deref().unwrap_or_else(|| response["statusText"].as_str().unwrap_or("")),
+                "httpVersion" => http_version.as_str(),
+                "cookies" => array![],
+                "headers" => headers(&response["headers"]),
+                "content" => object!{
+                    "size" => self.encoded_length.unwrap_or(0.0),
+                    "mimeType" => response["mimeType"].as_str().unwrap_or("")
+                },
+                "redirectURL" => response["headers"]["location"].as_str().or_else(|| response["headers"]["Location"].as_str()).unwrap_or(""),
+                "headersSize" => -1,
+                "bodySize" => self.encoded_length.map(|length| length as i64).unwrap_or(-1)
+            },
+            "cache" => object!{},
+            "timings" => object!{
+                "blocked" => -1,
+                "dns" => phase(at("dnsStart"), at("dnsEnd")),
+                "connect" => phase(at("connectStart"), at("connectEnd")),
+                "ssl" => phase(at("sslStart"), at("sslEnd")),
+                "send" => send,
+                "wait" => wait,
+                "receive" => receive
+            }
+        }
+    }
+}
+
+/// -> take the entries of the performance log of chromedriver and the id of the tab (webview) to keep
+/// -> return the HAR entries of the requests, None if the log has no network event
+pub(crate) fn entries_from_performance_log(log: &[LogEntryValue], webview: &str) -> Option<Vec<JsonValue>> {
+    let mut requests: Vec<Request> = Vec::new();
+    let mut pending: HashMap<String, usize> = HashMap::new();
+    let mut network_events = false;
+
+    for entry in log {
+        let message = match json::parse(&entry.message) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+        let method = message["message"]["method"].as_str().unwrap_or("");
+        if !method.starts_with("Network.") {
+            continue;
+        }
+        network_events = true;
+        if message["webview"].as_str().map(|id| id != webview).unwrap_or(false) {
+            continue;
+        }
+        let params = &message["message"]["params"];
+        let id = params["requestId"].as_str().unwrap_or("").to_string();
+        let timestamp = params["timestamp"].as_f64().unwrap_or(0.0);
+
+        match method {
+            "Network.requestWillBeSent" => {
+                // a redirection ends the previous request with the same id
+                if let Some(index) = pending.remove(&id) {
+                    requests[index].response = Some(params["redirectResponse"].clone());
+                    requests[index].end = Some(timestamp);
+                }
+                pending.insert(id, requests.len());
+                requests.push(Request {
+                    wall_time: params["wallTime"].as_f64().unwrap_or(0.0),
+                    timestamp,
+                    method: params["request"]["method"].as_str().unwrap_or("GET").to_string(),
+                    url: params["request"]["url"].as_str().unwrap_or("").to_string(),
+                    headers: params["request"]["headers"].clone(),
+                    post_data: params["request"]["postData"].as_str().map(|data| data.to_string()),
+                    response: None,
+                    end: None,
+                    encoded_length: None,
+                    error: None
+                });
+            },
+            "Network.responseReceived" => if let Some(&index) = pending.get(&id) {
+                requests[index].response = Some(params["response"].clone());
+            },
+            "Network.loadingFinished" => if let Some(index) = pending.remove(&id) {
+                requests[index].end = Some(timestamp);
+                requests[index].encoded_length = params["encodedDataLength"].as_f64();
+            },
+            "Network.loadingFailed" => if let Some(index) = pending.remove(&id) {
+                requests[index].end = Some(timestamp);
+                requests[index].error = params["errorText"].as_str().map(|error| error.to_string());
+            },
+            _ => (),
+        }
+    }
+
+    if network_events {
+        Some(requests.iter().map(Request::to_entry).collect())
+    } else {
+        None
+    }
+}
+
+/// -> take the value returned by RESOURCE_TIMINGS_SCRIPT and the start of the recording
+/// -> return the HAR entries of the requests started after the start of the recording
+pub(crate) fn entries_from_resource_timings(timings: &JsonValue, started: SystemTime) -> Vec<JsonValue> {
+    let origin = timings["timeOrigin"].as_f64().unwrap_or(0.0);
+    let started = unix_milliseconds(started);
+
+    timings["entries"].members().filter(|timing| origin + timing["startTime"].as_f64().unwrap_or(0.0) >= started).map(|timing| {
+        let at = |name: &str| match timing[name].as_f64() {
+            // zero means that the phase did not happen or that the timing is hidden
+            Some(time) if time > 0.0 => time,
+            _ => -1.0,
+        };
+        let url = timing["name"].as_str().unwrap_or("");
+        let http_version = http_version(timing["nextHopProtocol"].as_str().unwrap_or(""));
+        let ssl = if at("secureConnectionStart") > 0.0 { phase(at("secureConnectionStart"), at("connectEnd")) } else { -1.0 };
+
+        object!{
+            "pageref" => "page_1",
+            "startedDateTime" => iso_8601(origin + timing["startTime"].as_f64().unwrap_or(0.0)),
+            "time" => timing["duration"].as_f64().unwrap_or(0.0),
+            "request" => object!{
+                "method" => "GET",
+                "url" => url,
+                "httpVersion" => http_version.as_str(),
+                "cookies" => array![],
+                "headers" => array![],
+                "queryString" => query_string(url),
+                "headersSize" => -1,
+                "bodySize" => 0
+            },
+            "response" => object!{
+                "status" => timing["responseStatus"].as_u64().unwrap_or(0),
+                "statusText" => "",
+                "httpVersion" => http_version.as_str(),
+                "cookies" => array![],
+                "headers" => array![],
+                "content" => object!{
+                    "size" => timing["decodedBodySize"].as_u64().unwrap_or(0),
+                    "mimeType" => timing["contentType"].as_str().unwrap_or("")
+                },
+                "redirectURL" => "",
+                "headersSize" => -1,
+                "bodySize" => timing["encodedBodySize"].as_u64().unwrap_or(0)
+            },
+            "cache" => object!{},
+            "timings" => object!{
+                "blocked" => -1,
+                "dns" => phase(at("domainLookupStart"), at("domainLookupEnd")),
+                "connect" => phase(at("connectStart"), at("connectEnd")),
+                "ssl" => ssl,
+                "send" => 0,
+                "wait" => phase(at("requestStart"), at("responseStart")).max(0.0),
+                "receive" => phase(at("responseStart"), at("responseEnd")).max(0.0)
+            },
+            "_initiator" => timing["initiatorType"].as_str().unwrap_or("")
+        }
+    }).collect()
+}
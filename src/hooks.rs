@@ -0,0 +1,61 @@
+//! Hooks observe and rewrite the requests of a session
+//!
+//! A [Hook](trait.Hook.html) added with [Session::add_hook()](../session/struct.Session.html#method.add_hook) is called before and after
+//! every request sent for the session, its tabs and its elements. Hooks can log the commands, measure them or rewrite them.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::Browser};
+//! use lw_webdriver::hooks::{Hook, Request};
+//! use lw_webdriver::transport::Response;
+//! use std::time::Duration;
+//!
+//! /// Warn about slow commands.
+//! struct SlowCommands(Duration);
+//!
+//! impl Hook for SlowCommands {
+//!     fn after_command(&self, request: &Request, _response: Result<&Response, &str>, latency: Duration) {
+//!         if latency > self.0 {
+//!             println!("{} {} took {:?}", request.method.to_string(), request.path, latency);
+//!         }
+//!     }
+//! }
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.add_hook(SlowCommands(Duration::from_secs(1)));
+//! session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+//! ```
+
+use std::time::Duration;
+use crate::transport::{Method, Response};
+
+/// A request about to be sent to the webdriver server.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Request {
+    pub method: Method,
+    /// The path of the command, starting with a slash (`/session/{session id}/url`).
+    pub path: String,
+    /// The json body of the request.
+    pub body: Option<String>
+}
+
+/// Callbacks called around the requests of a session.
+/// Both methods do nothing by default, so that a hook only implements what it needs.
+///
+/// Hooks are called from the thread sending the command. A hook can send commands itself (with an element or a tab it owns),
+/// but those commands call the hooks too.
+pub trait Hook: Send + Sync {
+    /// Called before a request is sent. The request can be modified (to add a header to the body, to rewrite an url...).
+    fn before_command(&self, request: &mut Request) {
+        let _ = request;
+    }
+
+    /// Called when a request is answered, with the response (or the error message if the server could not be reached)
+    /// and the time it took. Retried requests call this method once, with the last response and the time taken by all the attempts.
+    fn after_command(&self, request: &Request, response: Result<&Response, &str>, latency: Duration) {
+        let _ = (request, response, latency);
+    }
+}
@@ -0,0 +1,262 @@
+//! Minimal HTTP/1.1 client keeping its connection to the webdriver server alive
+//!
+//! minreq closes its connection after every request and has no way to keep it open, and the clients that do
+//! (ureq, hyper) would more than double the dependencies of the crate. Local drivers only speak plain http,
+//! so this client handles that case and minreq is still used for https servers.
+
+use std::io::{BufRead, BufReader, Read, Write, Error, ErrorKind};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use std::sync::{Mutex, TryLockError};
+use log::debug;
+use crate::transport::Response;
+
+/// The largest response body accepted, far above the full page screenshots of long documents.
+/// Larger bodies are refused before being read, instead of exhausting the memory.
+const MAX_BODY_SIZE: usize = 256 * 1024 * 1024;
+/// The largest status line or header line accepted.
+const MAX_LINE_SIZE: u64 = 64 * 1024;
+
+/// Sends requests to plain http servers over a single persistent connection.
+/// Opening a connection for every command was the main cost of long sessions.
+///
+/// The connection is opened again if the server closed it.
+/// Requests sent while the connection is busy use a temporary connection.
+///
+/// When the connection breaks before the response is read, the server may have run the request.
+/// Only `GET` and `DELETE` requests are sent again in that case, `POST` requests return the error.
+pub(crate) struct HttpClient {
+    stream: Mutex<Option<(String, BufReader<TcpStream>)>>
+}
+
+impl HttpClient {
+    pub(crate) fn new() -> Self {
+        HttpClient {
+            stream: Mutex::new(None)
+        }
+    }
+
+    /// -> take a method, an url starting with `http://`, additional headers, an optional json body
+    /// and how long to wait for the server (forever if None)
+    /// -> return the response
+    pub(crate) fn send(&self, method: &str, url: &str, headers: &[(&str, &str)], body: Option<&str>, timeout: Option<Duration>) -> Result<Response, Error> {
+        // sockets do not accept null timeouts
+        let timeout = timeout.map(|timeout| timeout.max(Duration::from_millis(1)));
+        let address_and_path = url.strip_prefix("http://").ok_or_else(|| Error::new(ErrorKind::InvalidInput, "only http urls are supported"))?;
+        let (address, path) = match address_and_path.find('/') {
+            Some(index) => address_and_path.split_at(index),
+            None => (address_and_path, "/"),
+        };
+
+        let mut request = format!("{} {} HTTP/1.1\r\nHost: {}\r\nConnection: keep-alive\r\n", method, path, address);
+        for (name, value) in headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        let body = match (method, body) {
+            ("GET", None) => None,
+            (_, body) => Some(body.unwrap_or("")),
+        };
+        if let Some(body) = body {
+            request.push_str(&format!("Content-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\n", body.len()));
+        }
+        request.push_str("\r\n");
+        request.push_str(body.unwrap_or(""));
+
+        // sending a POST twice could run its command twice
+        let resendable = method != "POST";
+
+        let mut stream = match self.stream.try_lock() {
+            Ok(stream) => stream,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                // the kept connection is busy with another command in flight
+                let mut reader = BufReader::new(connect(address, timeout)?);
+                return match exchange(&mut reader, &request) {
+                    Ok((response, _)) => Ok(response),
+                    Err(Exchange::Closed(error)) | Err(Exchange::Failed(error)) => Err(error),
+                };
+            }
+        };
+
+        // a kept connection may have been closed by the server since the last request
+        let reused = match &*stream {
+            Some((connected_address, reader)) => connected_address == address && !is_closed(reader.get_ref()),
+            None => false,
+        };
+        if reused {
+            let (_, reader) = stream.as_mut().unwrap();
+            reader.get_ref().set_read_timeout(timeout)?;
+            reader.get_ref().set_write_timeout(timeout)?;
+            match exchange(reader, &request) {
+                Ok((response, keep_alive)) => {
+                    if !keep_alive {
+                        *stream = None;
+                    }
+                    return Ok(response);
+                },
+                Err(Exchange::Closed(error)) if resendable => {
+                    debug!("kept connection to {} is closed ({}), reconnecting", address, error);
+                },
+                Err(Exchange::Closed(error)) | Err(Exchange::Failed(error)) => {
+                    *stream = None;
+                    return Err(error);
+                },
+            }
+        }
+
+        *stream = None;
+        let mut reader = BufReader::new(connect(address, timeout)?);
+        match exchange(&mut reader, &request) {
+            Ok((response, keep_alive)) => {
+                if keep_alive {
+                    *stream = Some((address.to_string(), reader));
+                }
+                Ok(response)
+            },
+            Err(Exchange::Closed(error)) | Err(Exchange::Failed(error)) => Err(error),
+        }
+    }
+}
+
+/// -> take a kept connection
+/// -> return whether the server closed it while it was idle (or sent unexpected data)
+fn is_closed(stream: &TcpStream) -> bool {
+    if stream.set_nonblocking(true).is_err() {
+        return true;
+    }
+    let closed = match stream.peek(&mut [0; 1]) {
+        Err(error) if error.kind() == ErrorKind::WouldBlock => false,
+        Ok(_) | Err(_) => true,
+    };
+    stream.set_nonblocking(false).is_err() || closed
+}
+
+/// -> take an address and how long to wait for the server (forever if None)
+/// -> return a connection to the server, reads and writes timing out
+fn connect(address: &str, timeout: Option<Duration>) -> Result<TcpStream, Error> {
+    let stream = match timeout {
+        Some(timeout) => {
+            let mut last_error = Error::new(ErrorKind::InvalidInput, "no address");
+            let mut connected = None;
+            for socket_address in address.to_socket_addrs()? {
+                match TcpStream::connect_timeout(&socket_address, timeout) {
+                    Ok(stream) => {
+                        connected = Some(stream);
+                        break;
+                    },
+                    Err(error) => last_error = error,
+                }
+            }
+            connected.ok_or(last_error)?
+        },
+        None => TcpStream::connect(address)?,
+    };
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+    Ok(stream)
+}
+
+enum Exchange {
+    /// The connection was closed before a response was read.
+    /// The server may have received and run the request.
+    Closed(Error),
+    Failed(Error)
+}
+
+/// Write a request and read the response.
+/// -> return the response and whether the connection can be kept
+fn exchange(reader: &mut BufReader<TcpStream>, request: &str) -> Result<(Response, bool), Exchange> {
+    if let Err(error) = reader.get_mut().write_all(request.as_bytes()).and_then(|()| reader.get_mut().flush()) {
+        return Err(Exchange::Closed(error));
+    }
+
+    let mut status_line = String::new();
+    match reader.by_ref().take(MAX_LINE_SIZE).read_line(&mut status_line) {
+        Ok(0) => return Err(Exchange::Closed(Error::new(ErrorKind::UnexpectedEof, "connection closed"))),
+        Ok(_) => (),
+        Err(error) if error.kind() == ErrorKind::ConnectionReset || error.kind() == ErrorKind::ConnectionAborted => return Err(Exchange::Closed(error)),
+        Err(error) => return Err(Exchange::Failed(error)),
+    }
+    read_response(reader, &status_line).map_err(Exchange::Failed)
+}
+
+/// -> take a connection and the status line of the response
+/// -> return the response and whether the connection can be kept
+fn read_response(reader: &mut BufReader<TcpStream>, status_line: &str) -> Result<(Response, bool), Error> {
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+    let too_large = || invalid("response body too large");
+    let status_code: u16 = status_line.split_whitespace().nth(1).and_then(|code| code.parse().ok())
+        .ok_or_else(|| invalid("invalid status line"))?;
+    let mut keep_alive = status_line.starts_with("HTTP/1.1");
+
+    let mut content_length = None;
+    let mut chunked = false;
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(index) = line.find(':') {
+            let (name, value) = (line[..index].trim().to_ascii_lowercase(), line[index + 1..].trim());
+            match name.as_str() {
+                "content-length" => content_length = Some(value.parse::<usize>().map_err(|_| invalid("invalid content length"))?),
+                "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+                "connection" => keep_alive = value.eq_ignore_ascii_case("keep-alive") || (keep_alive && !value.eq_ignore_ascii_case("close")),
+                _ => (),
+            }
+        }
+    }
+
+    let mut body = Vec::new();
+    if status_code == 204 || status_code == 304 {
+        // no body
+    } else if chunked {
+        loop {
+            let size = read_line(reader)?;
+            let size = usize::from_str_radix(size.split(';').next().unwrap_or("").trim(), 16).map_err(|_| invalid("invalid chunk size"))?;
+            if size == 0 {
+                // the trailer headers end with an empty line
+                while !read_line(reader)?.is_empty() {}
+                break;
+            }
+            if size > MAX_BODY_SIZE - body.len() {
+                return Err(too_large());
+            }
+            let start = body.len();
+            body.resize(start + size, 0);
+            reader.read_exact(&mut body[start..])?;
+            if !read_line(reader)?.is_empty() {
+                return Err(invalid("invalid chunk"));
+            }
+        }
+    } else if let Some(length) = content_length {
+        if length > MAX_BODY_SIZE {
+            return Err(too_large());
+        }
+        body.resize(length, 0);
+        reader.read_exact(&mut body)?;
+    } else {
+        // the body ends with the connection
+        reader.by_ref().take(MAX_BODY_SIZE as u64 + 1).read_to_end(&mut body)?;
+        if body.len() > MAX_BODY_SIZE {
+            return Err(too_large());
+        }
+        keep_alive = false;
+    }
+
+    Ok((Response { status_code, body }, keep_alive))
+}
+
+/// -> take a connection
+/// -> return the next line of the response, without its line break
+fn read_line(reader: &mut BufReader<TcpStream>) -> Result<String, Error> {
+    let mut line = String::new();
+    reader.by_ref().take(MAX_LINE_SIZE).read_line(&mut line)?;
+    if !line.ends_with('\n') {
+        return Err(match line.len() as u64 {
+            MAX_LINE_SIZE => Error::new(ErrorKind::InvalidData, "header line too long"),
+            _ => Error::new(ErrorKind::UnexpectedEof, "connection closed before the end of the response"),
+        });
+    }
+    Ok(line.trim_end().to_string())
+}
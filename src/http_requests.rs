@@ -1,576 +1,1092 @@
 use crate::timeouts::Timeouts;
-use crate::error::WebdriverError;
-use crate::enums::Selector;
+use crate::print::PrintOptions;
+use crate::permissions::{Permission, PermissionState};
+use crate::status::DriverStatus;
+use crate::error::{WebdriverError, ErrorKind};
+use crate::transport::{Transport, HttpTransport, Method};
+use crate::queue::CommandQueue;
+use crate::retry::RetryPolicy;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::enums::{Selector, WindowType};
+use crate::command::Command;
+use crate::elements::Rect;
+use crate::dry_run::{DryRunTransport, validate};
+use crate::transcript::TranscriptEntry;
+use crate::hooks::{Hook, Request};
+use crate::recovery::SessionRestarted;
+use crate::bidi::BidiConnection;
+use crate::responses::*;
+use serde::de::DeserializeOwned;
 use json::{JsonValue, object};
 use log::{debug, info, warn, error};
 
-/// used by requests sending data
-fn post(url: &str, body: &str) -> Result<JsonValue, WebdriverError> {
-    let res = minreq::post(url)
-        .with_body(body.to_string())
-        .send();
-
-    if let Ok(res) = res {
-        if let Ok(text) = res.as_str() {
-            if let Ok(json) = json::parse(text) {
-                if !json["value"]["error"].is_string() {
-                    Ok(json)
-                } else {
-                    error!("{:?}, response: {}", WebdriverError::from(json["value"]["error"].to_string()), json);
-                    Err(WebdriverError::from(json["value"]["error"].to_string()))
+/// creates a session replacing a crashed one, relaunching the driver if needed
+/// -> take the connection
+/// -> return the id of the new session
+pub(crate) type Restarter = Arc<dyn Fn(&Connection) -> Result<String, WebdriverError> + Send + Sync>;
+/// -> return true if the driver launched by the session exited
+pub(crate) type ExitCheck = Arc<dyn Fn() -> bool + Send + Sync>;
+/// called once a session is restarted
+pub(crate) type RestartCallback = Arc<dyn Fn(&SessionRestarted) + Send + Sync>;
+
+/// a tab opened again when the session is restarted
+struct KnownTab {
+    /// the handle the tab is known by (its handle in the first session)
+    handle: String,
+    /// the handle of the tab in the current session
+    current: String,
+    /// the last url navigated to (None if unknown)
+    url: Option<String>
+}
+
+/// The webdriver server receiving the requests
+pub(crate) struct Connection {
+    transport: Arc<dyn Transport>,
+    /// None if the server is reached with a custom transport
+    pub(crate) url: Option<String>,
+    /// the basic authentication credentials of the remote server
+    pub(crate) credentials: Option<(String, String)>,
+    pub(crate) remote: bool,
+    queue: CommandQueue,
+    retry_policy: Mutex<RetryPolicy>,
+    /// how long to wait for a response (forever if None)
+    request_timeout: Mutex<Option<Duration>>,
+    /// true if stale elements are found again with their locator
+    stale_element_recovery: Mutex<bool>,
+    /// true if the commands are validated instead of being sent
+    dry_run: bool,
+    /// when the recording started and the commands sent since (None if not recording)
+    transcript: Mutex<Option<(Instant, Vec<TranscriptEntry>)>>,
+    /// the handle of the selected tab (None if unknown), to avoid selecting it again
+    selected_tab: Mutex<Option<String>>,
+    /// called around every request
+    hooks: Mutex<Vec<Arc<dyn Hook>>>,
+    /// when the last request was sent or answered
+    last_activity: Mutex<Instant>,
+    /// recreates the session when it crashed (None if crash recovery is disabled)
+    restarter: Mutex<Option<Restarter>>,
+    /// tells whether the driver launched by the session exited (None if the session did not launch it)
+    driver_exited: Mutex<Option<ExitCheck>>,
+    /// called when the session is restarted
+    restart_callbacks: Mutex<Vec<RestartCallback>>,
+    /// the id of the session replacing the crashed one (None if the session was never restarted)
+    restarted_session: Mutex<Option<String>>,
+    /// the tabs selected since the session started, opened again when it is restarted
+    known_tabs: Mutex<Vec<KnownTab>>,
+    /// the commands applying the settings of the session to a tab, sent to the tabs opened again
+    tab_setup: Mutex<Vec<Command>>,
+    /// the url of the WebDriver BiDi server of the session (None if not requested in the capabilities)
+    pub(crate) web_socket_url: Option<String>,
+    /// the connection to the BiDi server, opened by the first BiDi command
+    bidi: Mutex<Option<Arc<BidiConnection>>>,
+    /// the address of the DevTools server of Chrome and Edge (None for other browsers)
+    pub(crate) debugger_address: Option<String>
+}
+
+impl Connection {
+    /// -> take the url of a remote server and optional basic authentication credentials
+    pub(crate) fn new(url: &str, credentials: Option<(&str, &str)>) -> Self {
+        let transport = match credentials {
+            Some((username, password)) => HttpTransport::new(url).credentials(username, password),
+            None => HttpTransport::new(url),
+        };
+        let mut connection = Connection::with_transport(Arc::new(transport), true);
+        connection.url = Some(url.trim_end_matches('/').to_string());
+        connection.credentials = credentials.map(|(username, password)| (username.to_string(), password.to_string()));
+        connection
+    }
+
+    /// the driver launched or expected by this crate
+    pub(crate) fn local(port: u16) -> Self {
+        let url = format!("http://localhost:{}", port);
+        let mut connection = Connection::with_transport(Arc::new(HttpTransport::new(&url)), false);
+        connection.url = Some(url);
+        connection
+    }
+
+    /// -> take the transport reaching the server and whether the server is remote
+    pub(crate) fn with_transport(transport: Arc<dyn Transport>, remote: bool) -> Self {
+        Connection {
+            transport,
+            url: None,
+            credentials: None,
+            remote,
+            queue: CommandQueue::new(1),
+            retry_policy: Mutex::new(RetryPolicy::none()),
+            request_timeout: Mutex::new(None),
+            stale_element_recovery: Mutex::new(false),
+            dry_run: false,
+            transcript: Mutex::new(None),
+            selected_tab: Mutex::new(None),
+            hooks: Mutex::new(Vec::new()),
+            last_activity: Mutex::new(Instant::now()),
+            restarter: Mutex::new(None),
+            driver_exited: Mutex::new(None),
+            restart_callbacks: Mutex::new(Vec::new()),
+            restarted_session: Mutex::new(None),
+            known_tabs: Mutex::new(Vec::new()),
+            tab_setup: Mutex::new(Vec::new()),
+            web_socket_url: None,
+            bidi: Mutex::new(None),
+            debugger_address: None
+        }
+    }
+
+    /// a server validating the commands and answering with placeholder values
+    pub(crate) fn dry_run() -> Self {
+        let mut connection = Connection::with_transport(Arc::new(DryRunTransport::new()), false);
+        connection.dry_run = true;
+        connection
+    }
+
+    /// -> take the number of commands which can wait for a response at the same time
+    pub(crate) fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.queue = CommandQueue::new(max_in_flight);
+        self
+    }
+
+    pub(crate) fn set_retry_policy(&self, policy: RetryPolicy) {
+        *self.retry_policy.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = policy;
+    }
+
+    pub(crate) fn get_retry_policy(&self) -> RetryPolicy {
+        *self.retry_policy.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn set_request_timeout(&self, timeout: Option<Duration>) {
+        *self.request_timeout.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = timeout;
+    }
+
+    pub(crate) fn get_request_timeout(&self) -> Option<Duration> {
+        *self.request_timeout.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn set_stale_element_recovery(&self, recovery: bool) {
+        *self.stale_element_recovery.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = recovery;
+    }
+
+    pub(crate) fn get_stale_element_recovery(&self) -> bool {
+        *self.stale_element_recovery.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// start recording the commands sent to sessions, forgetting the commands recorded before
+    pub(crate) fn start_recording(&self) {
+        *self.transcript.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some((Instant::now(), Vec::new()));
+    }
+
+    /// -> return the commands recorded since start_recording() (empty if the recording was not started)
+    pub(crate) fn stop_recording(&self) -> Vec<TranscriptEntry> {
+        self.transcript.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take().map(|(_, entries)| entries).unwrap_or_default()
+    }
+
+    /// -> return the time elapsed since the last request was sent or answered
+    pub(crate) fn idle_time(&self) -> Duration {
+        self.last_activity.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).elapsed()
+    }
+
+    fn touch(&self) {
+        *self.last_activity.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Instant::now();
+    }
+
+    fn is_recording(&self) -> bool {
+        self.transcript.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).is_some()
+    }
+
+    /// send a command to a session
+    /// -> return the value of the response
+    pub(crate) fn command<T: DeserializeOwned>(&self, session_id: &str, command: &Command) -> Result<T, WebdriverError> {
+        self.command_with_timeout(session_id, command, self.get_request_timeout())
+    }
+
+    pub(crate) fn add_hook(&self, hook: Arc<dyn Hook>) {
+        self.hooks.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(hook);
+    }
+
+    pub(crate) fn set_restarter(&self, restarter: Option<Restarter>) {
+        *self.restarter.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = restarter;
+    }
+
+    pub(crate) fn set_exit_check(&self, driver_exited: Option<ExitCheck>) {
+        *self.driver_exited.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = driver_exited;
+    }
+
+    /// -> take the error of a command
+    /// -> return true if the error reveals a crash: the session does not exist anymore, or the request failed because the driver exited
+    fn is_crash(&self, error: &WebdriverError) -> bool {
+        match error.kind {
+            ErrorKind::InvalidSessionId => true,
+            ErrorKind::FailedRequest => self.driver_exited.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_ref().is_some_and(|driver_exited| driver_exited()),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn set_tab_setup(&self, commands: Vec<Command>) {
+        *self.tab_setup.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = commands;
+    }
+
+    pub(crate) fn add_restart_callback(&self, callback: RestartCallback) {
+        self.restart_callbacks.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(callback);
+    }
+
+    /// -> take the id of a session, as known by its tabs and elements
+    /// -> return the id of the session replacing it if it was restarted
+    pub(crate) fn current_session_id(&self, session_id: &str) -> String {
+        self.restarted_session.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone().unwrap_or_else(|| session_id.to_string())
+    }
+
+    /// -> take the handle of a tab, as known by the tab
+    /// -> return its handle in the current session
+    fn current_handle(&self, handle: &str) -> String {
+        let known_tabs = self.known_tabs.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        known_tabs.iter().find(|tab| tab.handle == handle).map(|tab| tab.current.clone()).unwrap_or_else(|| handle.to_string())
+    }
+
+    /// -> take the handle of a tab in the current session
+    /// -> return the handle the tab is known by
+    fn original_handle(&self, current: &str) -> String {
+        let known_tabs = self.known_tabs.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        known_tabs.iter().find(|tab| tab.current == current).map(|tab| tab.handle.clone()).unwrap_or_else(|| current.to_string())
+    }
+
+    /// -> take the value of a command returning handles
+    /// -> return it with the handles the tabs are known by, remembering the listed tabs
+    fn original_handles(&self, command: &Command, value: serde_json::Value) -> serde_json::Value {
+        match (command, value) {
+            (Command::GetWindowHandle, value) => match value.as_str() {
+                Some(handle) => self.original_handle(handle).into(),
+                None => value,
+            },
+            (Command::GetWindowHandles, value) => {
+                let handles: Vec<String> = value.as_array().into_iter().flatten().filter_map(|handle| handle.as_str()).map(|handle| self.original_handle(handle)).collect();
+                for handle in &handles {
+                    self.remember_tab(handle, None);
+                }
+                handles.into()
+            },
+            (_, value) => value,
+        }
+    }
+
+    /// remember a tab, and the url it navigated to if any
+    fn remember_tab(&self, handle: &str, url: Option<&str>) {
+        let mut known_tabs = self.known_tabs.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match known_tabs.iter_mut().find(|tab| tab.handle == handle) {
+            Some(tab) => if let Some(url) = url {
+                tab.url = Some(url.to_string());
+            },
+            None => known_tabs.push(KnownTab {
+                handle: handle.to_string(),
+                current: handle.to_string(),
+                url: url.map(|url| url.to_string())
+            }),
+        }
+    }
+
+    fn forget_tab(&self, handle: &str) {
+        self.known_tabs.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).retain(|tab| tab.handle != handle);
+    }
+
+    /// -> take the id of a session whose command failed, and the error
+    /// create a session replacing it and open the known tabs again
+    /// -> return the notification sent to the restart callbacks
+    fn recover(&self, session_id: &str, error: &WebdriverError, timeout: Option<Duration>) -> Result<SessionRestarted, WebdriverError> {
+        let restarter = match self.restarter.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone() {
+            Some(restarter) => restarter,
+            None => return Err(error.clone()),
+        };
+        let previous_id = self.current_session_id(session_id);
+        warn!("session {} failed ({}), restarting it", previous_id, error);
+        let id = restarter(self)?;
+        *self.restarted_session.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(id.clone());
+        self.set_selected_tab(None);
+
+        // the tabs are opened again, the first one being the tab of the new session
+        let mut known_tabs = std::mem::take(&mut *self.known_tabs.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        let handles: Vec<String> = self.send_command(&id, &Command::GetWindowHandles, timeout).unwrap_or_default();
+        let setup = self.tab_setup.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        let mut reopened_urls = Vec::new();
+        for (index, tab) in known_tabs.iter_mut().enumerate() {
+            let handle = match (index, handles.first()) {
+                (0, Some(handle)) => Ok(handle.clone()),
+                _ => self.send_command(&id, &Command::NewWindow { window_type: WindowType::Tab }, timeout)
+                    .map(|value: serde_json::Value| value["handle"].as_str().unwrap_or_default().to_string()),
+            };
+            let reopened = handle.and_then(|handle| {
+                tab.current = handle;
+                if tab.url.is_none() && setup.is_empty() {
+                    return Ok(());
+                }
+                let _: serde_json::Value = self.send_command(&id, &Command::SwitchToWindow { handle: tab.current.clone() }, timeout)?;
+                // the blocked urls and the timezone are settings of the tab, lost with the browser
+                for command in &setup {
+                    let _: serde_json::Value = self.send_command(&id, command, timeout)?;
+                }
+                if let Some(url) = &tab.url {
+                    let _: serde_json::Value = self.send_command(&id, &Command::Navigate { url: url.clone() }, timeout)?;
+                    reopened_urls.push(url.clone());
                 }
-            } else {
-                error!("WebdriverError::InvalidResponse (not json), text: {}, error: {:?}", text, json::parse(text));
-                Err(WebdriverError::InvalidResponse)
+                Ok(())
+            });
+            if let Err(error) = reopened {
+                warn!("failed to open tab {} again: {}", tab.handle, error);
             }
-        } else {
-            error!("WebdriverError::InvalidResponse (not utf8), error: {:?}", res.as_str());
-            Err(WebdriverError::InvalidResponse)
         }
-    } else {
-        error!("WebdriverError::FailedRequest, error: {:?}", res);
-        Err(WebdriverError::FailedRequest)
+        *self.known_tabs.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = known_tabs;
+        info!("session {} restarted as session {}", previous_id, id);
+
+        let restarted = SessionRestarted {
+            previous_id,
+            id,
+            error: error.clone(),
+            reopened_urls
+        };
+        let callbacks = self.restart_callbacks.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        for callback in callbacks {
+            callback(&restarted);
+        }
+        Ok(restarted)
     }
-}
 
-/// use by requests getting data
-fn get(url: &str) -> Result<JsonValue, WebdriverError> {
-    let res = minreq::get(url)
-        .send();
-
-    if let Ok(res) = res {
-        if let Ok(text) = res.as_str() {
-            if let Ok(json) = json::parse(text) {
-                if !json["value"]["error"].is_string() {
-                    Ok(json)
-                } else {
-                    error!("{:?}, response: {}", WebdriverError::from(json["value"]["error"].to_string()), json);
-                    Err(WebdriverError::from(json["value"]["error"].to_string()))
+    /// -> take the name of a WebDriver BiDi command and its parameters
+    /// -> return the result of the command
+    pub(crate) fn bidi_command(&self, method: &str, params: JsonValue) -> Result<JsonValue, WebdriverError> {
+        let bidi = {
+            let mut bidi = self.bidi.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            match (&*bidi, &self.web_socket_url) {
+                (Some(bidi), _) => Arc::clone(bidi),
+                (None, Some(url)) => {
+                    let connection = Arc::new(BidiConnection::connect(url, self.get_request_timeout())?);
+                    *bidi = Some(Arc::clone(&connection));
+                    connection
+                },
+                (None, None) => return Err(WebdriverError::from(ErrorKind::UnknowCommand).with_message(format!("{} requires WebDriver BiDi, enable it with Capabilities::bidi()", method))),
+            }
+        };
+        bidi.command(method, params)
+    }
+
+    fn get_selected_tab(&self) -> Option<String> {
+        self.selected_tab.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    fn set_selected_tab(&self, handle: Option<String>) {
+        *self.selected_tab.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = handle;
+    }
+
+    /// send a command to a session, waiting at most `timeout` for the response (forever if None)
+    /// -> return the value of the response
+    pub(crate) fn command_with_timeout<T: DeserializeOwned>(&self, session_id: &str, command: &Command, timeout: Option<Duration>) -> Result<T, WebdriverError> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("webdriver_command",
+            command = command.name(),
+            session_id,
+            duration_ms = tracing::field::Empty,
+            outcome = tracing::field::Empty
+        );
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        #[cfg(feature = "tracing")]
+        let start = Instant::now();
+
+        let mut result = self.send_command(session_id, command, timeout);
+        if let Err(error) = &result {
+            // the driver or the browser crashed
+            if self.is_crash(error) && *command != Command::DeleteSession {
+                if let Ok(restarted) = self.recover(session_id, error, timeout) {
+                    result = if command.is_resendable() {
+                        self.send_command(session_id, command, timeout)
+                    } else {
+                        // the command may have been executed before the crash (a click, a submitted form...)
+                        Err(WebdriverError::from(ErrorKind::SessionRestarted(Box::new(restarted))))
+                    };
                 }
-            } else {
-                error!("WebdriverError::InvalidResponse (not json), text: {}, error: {:?}", text, json::parse(text));
-                Err(WebdriverError::InvalidResponse)
             }
-        } else {
-            error!("WebdriverError::InvalidResponse (not utf8), error: {:?}", res.as_str());
-            Err(WebdriverError::InvalidResponse)
         }
-    } else {
-        error!("WebdriverError::FailedRequest, error: {:?}", res);
-        Err(WebdriverError::FailedRequest)
+
+        #[cfg(feature = "tracing")]
+        {
+            span.record("duration_ms", start.elapsed().as_millis() as u64);
+            match &result {
+                Ok(_) => span.record("outcome", "ok"),
+                Err(error) => span.record("outcome", tracing::field::display(&error.kind)),
+            };
+        }
+
+        // keep track of the selected tab
+        match (command, &result) {
+            (Command::SwitchToWindow { handle }, Ok(_)) => {
+                self.set_selected_tab(Some(handle.clone()));
+                self.remember_tab(handle, None);
+            },
+            (Command::Navigate { url }, Ok(_)) => if let Some(handle) = self.get_selected_tab() {
+                self.remember_tab(&handle, Some(url));
+            },
+            (Command::CloseWindow, _) => {
+                if let Some(handle) = self.get_selected_tab() {
+                    self.forget_tab(&handle);
+                }
+                self.set_selected_tab(None);
+            },
+            (Command::SwitchToWindow { .. }, Err(_)) => self.set_selected_tab(None),
+            (_, Err(error)) if *error == ErrorKind::NoSuchWindow => {
+                // the selected tab was closed by someone else (a web page for example)
+                if let Some(handle) = self.selected_tab.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take() {
+                    return Err(WebdriverError::from(ErrorKind::TabClosed).with_message(format!("tab {} was closed", handle)));
+                }
+            },
+            _ => (),
+        }
+        result
     }
-}
 
-/// use by requests using delete http requests
-fn delete(url: &str) -> Result<JsonValue, WebdriverError> {
-    let res = minreq::delete(url)
-        .send();
+    /// -> take a command, validated and recorded if needed
+    /// -> return the value of the response
+    fn send_command<T: DeserializeOwned>(&self, session_id: &str, command: &Command, timeout: Option<Duration>) -> Result<T, WebdriverError> {
+        // the session and its tabs can have been replaced after a crash
+        let session_id = self.current_session_id(session_id);
+        let switch;
+        let command = match command {
+            Command::SwitchToWindow { handle } => {
+                switch = Command::SwitchToWindow { handle: self.current_handle(handle) };
+                &switch
+            },
+            command => command,
+        };
+        if self.dry_run {
+            validate(command).map_err(|error| {
+                warn!("dry run: {:?} is invalid: {}", command, error);
+                error
+            })?;
+            info!("dry run: {:?}", command);
+        }
+        let (method, path, body) = command.to_request(&session_id);
+        // the handles of the tabs opened again after a crash are replaced by the handles the tabs are known by
+        let lists_handles = matches!(command, Command::GetWindowHandle | Command::GetWindowHandles);
+        let policy = if command.is_resendable() { self.get_retry_policy() } else { RetryPolicy::none() };
+        if !self.is_recording() && !lists_handles {
+            return self.execute(method, &path, body.as_deref(), timeout, policy);
+        }
+
+        // the raw value is recorded before being decoded, and failed commands are recorded too
+        let sent = Instant::now();
+        let result: Result<serde_json::Value, WebdriverError> = self.execute(method, &path, body.as_deref(), timeout, policy);
+        if let Some((start, transcript)) = self.transcript.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_mut() {
+            transcript.push(TranscriptEntry {
+                command: command.clone(),
+                time: sent.saturating_duration_since(*start),
+                value: result.as_ref().map(|value| to_json_value(value.clone())).unwrap_or(JsonValue::Null),
+                error: result.as_ref().err().map(|error| error.to_string())
+            });
+        }
+        let value = self.original_handles(command, result?);
+        T::deserialize(&value).map_err(|decoding_error| {
+            error!("ErrorKind::InvalidResponse ({}), value: {}", decoding_error, value);
+            WebdriverError::from(ErrorKind::InvalidResponse).with_command(format!("{} {}", method.to_string(), path))
+        })
+    }
 
-    if let Ok(res) = res {
-        if let Ok(text) = res.as_str() {
-            if let Ok(json) = json::parse(text) {
-                if !json["value"]["error"].is_string() {
-                    Ok(json)
-                } else {
-                    error!("{:?}, response: {}", WebdriverError::from(json["value"]["error"].to_string()), json);
-                    Err(WebdriverError::from(json["value"]["error"].to_string()))
+    /// send a request when its turn comes, retrying it according to a retry policy
+    /// and waiting at most `timeout` for every attempt (forever if None)
+    /// -> return the value of the response
+    fn execute<T: DeserializeOwned>(&self, method: Method, path: &str, body: Option<&str>, timeout: Option<Duration>, policy: RetryPolicy) -> Result<T, WebdriverError> {
+        // the hooks are not locked while the request is sent
+        let hooks = self.hooks.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        let mut request = Request {
+            method,
+            path: path.to_string(),
+            body: body.map(|body| body.to_string())
+        };
+        for hook in &hooks {
+            hook.before_command(&mut request);
+        }
+        let (method, path, body) = (request.method, request.path.as_str(), request.body.as_deref());
+
+        let start = Instant::now();
+        let (response, result) = self.queue.run(|| {
+            let mut attempt = 1;
+            loop {
+                self.touch();
+                let response = self.transport.send_with_timeout(method, path, body, timeout);
+                self.touch();
+                let (result, transient) = decode(response.as_ref().map_err(|message| message.as_str()));
+                let result = result.map_err(|error| error.with_command(format!("{} {}", method.to_string(), path)));
+                match result {
+                    Err(error) if transient && attempt < policy.get_max_attempts() => {
+                        let backoff = policy.backoff(attempt);
+                        warn!("{} {} failed ({}), retrying in {:?}", method.to_string(), path, error, backoff);
+                        thread::sleep(backoff);
+                        attempt += 1;
+                    },
+                    result => return (response, result),
                 }
-            } else {
-                error!("WebdriverError::InvalidResponse (not json), text: {}, error: {:?}", text, json::parse(text));
-                Err(WebdriverError::InvalidResponse)
             }
-        } else {
-            error!("WebdriverError::InvalidResponse (not utf8), error: {:?}", res.as_str());
-            Err(WebdriverError::InvalidResponse)
+        });
+
+        // the hooks are called once the turn of the request is over, so that they can send commands too
+        for hook in &hooks {
+            hook.after_command(&request, response.as_ref().map_err(|message| message.as_str()), start.elapsed());
         }
-    } else {
-        error!("WebdriverError::FailedRequest, error: {:?}", res);
-        Err(WebdriverError::FailedRequest)
+        result
     }
 }
 
+/// send a cheap request keeping the session alive, without recording it
+/// -> take the session id and how long to wait for the response at most (less if the request timeout is shorter)
+pub(crate) fn ping(connection: &Connection, session_id: &str, timeout: Duration) -> Result<(), WebdriverError> {
+    debug!("pinging session with id {}", session_id);
+    let session_id = connection.current_session_id(session_id);
+    let timeout = connection.get_request_timeout().map_or(timeout, |request_timeout| request_timeout.min(timeout));
+    let _: serde_json::Value = connection.execute(Method::Get, &format!("/session/{}/timeouts", session_id), None, Some(timeout), connection.get_retry_policy())?;
+    Ok(())
+}
+
+/// used by requests sending data, which are not sent again (creating a session again would leave the first one open)
+fn post<T: DeserializeOwned>(connection: &Connection, path: &str, body: &str) -> Result<T, WebdriverError> {
+    connection.execute(Method::Post, path, Some(body), connection.get_request_timeout(), RetryPolicy::none())
+}
+
+/// use by requests getting data
+fn get<T: DeserializeOwned>(connection: &Connection, path: &str) -> Result<T, WebdriverError> {
+    connection.execute(Method::Get, path, None, connection.get_request_timeout(), connection.get_retry_policy())
+}
+
+/// -> return the status of the server
+pub(crate) fn get_status(connection: &Connection) -> Result<DriverStatus, WebdriverError> {
+    debug!("getting status of the webdriver server");
+
+    let value: StatusValue = get(connection, "/status")?;
+
+    let status = DriverStatus {
+        ready: value.ready,
+        message: value.message,
+        build: value.build.and_then(|build| build.version),
+        os: value.os.map(|os| match (os.version, os.arch) {
+            (Some(version), Some(arch)) => format!("{} {} ({})", os.name, version, arch),
+            (Some(version), None) => format!("{} {}", os.name, version),
+            _ => os.name,
+        })
+    };
+    debug!("status is {:?}", status);
+    Ok(status)
+}
+
 /// -> take capabilities (options)
 /// create a session
-/// -> return created session id
-pub(crate) fn new_session(capabilities: &str) -> Result<String, WebdriverError> {
+/// -> return created session id and the capabilities of the session
+pub(crate) fn new_session(connection: &Connection, capabilities: &str) -> Result<(String, JsonValue), WebdriverError> {
     debug!("session creation request with capabilities {}", capabilities);
 
-    let json = post("http://localhost:4444/session", capabilities)?;
+    let value: NewSessionValue = post(connection, "/session", capabilities)?;
 
-    if json["value"]["sessionId"].is_string() {
-        let session_id = json["value"]["sessionId"].to_string();
-        debug!("session created (id: {})", session_id);
-        Ok(session_id)
-    } else {
-        error!("response to session creation request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("session created (id: {}, capabilities: {})", value.session_id, value.capabilities);
+    Ok((value.session_id, to_json_value(value.capabilities)))
 }
 
 /// -> take session id
 /// create a tab on this session
 /// -> return created tab id
-pub(crate) fn new_tab(session_id: &str) -> Result<String, WebdriverError> {
-    debug!("tab creation request on session with id {}", session_id);
-
-    let json = post(&format!("http://localhost:4444/session/{}/window/new", session_id), "{}")?;
-
-    if json["value"]["handle"].is_string() {
-        let session_id = json["value"]["handle"].to_string();
-        debug!("tab created (id: {})", session_id);
-        Ok(session_id)
-    } else {
-        error!("response to session creation request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+/// -> take session id and the type of window to open
+/// -> return the id of the new tab and its type
+pub(crate) fn new_window(connection: &Connection, session_id: &str, window_type: WindowType) -> Result<(String, WindowType), WebdriverError> {
+    debug!("{} creation request on session with id {}", window_type.to_string(), session_id);
+
+    let value: NewWindowValue = connection.command(session_id, &Command::NewWindow { window_type })?;
+    // old drivers do not return the type
+    let window_type = match value.window_type.as_deref() {
+        Some("window") => WindowType::Window,
+        Some("tab") => WindowType::Tab,
+        Some(other) => {
+            warn!("unknown window type {:?}", other);
+            window_type
+        },
+        None => window_type,
+    };
+
+    debug!("{} created (id: {})", window_type.to_string(), value.handle);
+    Ok((value.handle, window_type))
 }
 
 /// -> take session id
 /// -> return every open tab ids
-pub(crate) fn get_open_tabs(session_id: &str) -> Result<Vec<String>, WebdriverError> {
+pub(crate) fn get_open_tabs(connection: &Connection, session_id: &str) -> Result<Vec<String>, WebdriverError> {
     debug!("getting ids of open tabs on session with id {}", session_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/window/handles", session_id))?;
-
-    if !json["value"].is_null() {
-        let mut tabs: Vec<String> = Vec::new();
-        let mut i = 0;
-        while !json["value"][i].is_null() {
-            tabs.push(json["value"][i].to_string());
-            i += 1;
-        }
-        debug!("ids of open tabs: {:?}", session_id);
-        Ok(tabs)
-    } else {
-        error!("response to open tab ids request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
-}
-
-/// -> take session id
-/// -> return selected tab id
-pub(crate) fn get_selected_tab(session_id: &str) -> Result<String, WebdriverError> {
-    debug!("getting id of the selected tab on session with id {}", session_id);
-
-    let json = get(&format!("http://localhost:4444/session/{}/window", session_id))?;
+    let tabs: Vec<String> = connection.command(session_id, &Command::GetWindowHandles)?;
 
-    if json["value"].is_string() {
-        let id = json["value"].to_string();
-        debug!("the selected tab id is {}", id);
-        Ok(id)
-    } else {
-        error!("response to selected tab id request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("ids of open tabs: {:?}", tabs);
+    Ok(tabs)
 }
 
 /// -> take session id
 /// -> return timeouts
-pub(crate) fn get_timeouts(session_id: &str) -> Result<Timeouts, WebdriverError> {
+pub(crate) fn get_timeouts(connection: &Connection, session_id: &str) -> Result<Timeouts, WebdriverError> {
     debug!("getting timeouts on session with id {}", session_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/timeouts", session_id))?;
+    let value: TimeoutsValue = connection.command(session_id, &Command::GetTimeouts)?;
 
-    if json["value"]["pageLoad"].is_number() && json["value"]["implicit"].is_number() {
-        let timeouts = Timeouts{
-            script: json["value"]["script"].as_usize(),
-            page_load: json["value"]["pageLoad"].as_usize().unwrap(),
-            implicit: json["value"]["implicit"].as_usize().unwrap(),
-        };
-        debug!("timeouts are {:?}", timeouts);
-        Ok(timeouts)
-    } else {
-        error!("response to timeouts request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    let timeouts = Timeouts {
+        script: value.script.map(Duration::from_millis),
+        page_load: Duration::from_millis(value.page_load),
+        implicit: Duration::from_millis(value.implicit),
+    };
+    debug!("timeouts are {:?}", timeouts);
+    Ok(timeouts)
 }
 
 /// -> take session id and timeouts
 /// set timeouts
-pub(crate) fn set_timeouts(session_id: &str, timeouts: Timeouts) -> Result<(), WebdriverError> {
+pub(crate) fn set_timeouts(connection: &Connection, session_id: &str, timeouts: Timeouts) -> Result<(), WebdriverError> {
     debug!("setting timeouts to {:?} on session with id {}", timeouts, session_id);
 
-    let json = post(&format!("http://localhost:4444/session/{}/timeouts", session_id), &timeouts.to_json().to_string())?;
+    connection.command::<()>(session_id, &Command::SetTimeouts(timeouts))?;
 
-    if json["value"].is_null() {
-        debug!("setting timeouts succeed");
-        Ok(())
-    } else {
-        error!("response to timeouts change request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("setting timeouts succeed");
+    Ok(())
 }
 
 /// -> take session id and tab id
-/// select tab
-pub(crate) fn select_tab(session_id: &str, tab_id: &str) -> Result<(), WebdriverError> {
+/// select tab, unless it is already selected
+pub(crate) fn select_tab(connection: &Connection, session_id: &str, tab_id: &str) -> Result<(), WebdriverError> {
+    if connection.get_selected_tab().as_deref() == Some(tab_id) {
+        return Ok(());
+    }
     debug!("selecting tab with id {} on session with id {}", tab_id, session_id);
 
-    let json = post(&format!("http://localhost:4444/session/{}/window", session_id), &object! {
-        "handle" => tab_id,
-    }.to_string())?;
-
-    if json["value"].is_null() {
-        debug!("selecting tab succeed");
-        Ok(())
-    } else {
-        error!("response to tab selection request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
+    match connection.command::<()>(session_id, &Command::SwitchToWindow { handle: tab_id.to_string() }) {
+        Err(error) if error == ErrorKind::NoSuchWindow => {
+            return Err(WebdriverError::from(ErrorKind::TabClosed).with_message(format!("tab {} was closed", tab_id)));
+        },
+        result => result?,
     }
+
+    debug!("selecting tab succeed");
+    Ok(())
 }
 
 /// -> take session id and a valid url
 /// load a website in the selected tab
-pub(crate) fn navigate(session_id: &str, url: &str) -> Result<(), WebdriverError> {
+pub(crate) fn navigate(connection: &Connection, session_id: &str, url: &str, timeout: Option<Duration>) -> Result<(), WebdriverError> {
     debug!("navigating to {} on session with id {}", url, session_id);
 
-    let json = post(&format!("http://localhost:4444/session/{}/url", session_id), &object! {
-        "url" => url,
-    }.to_string())?;
+    connection.command_with_timeout::<()>(session_id, &Command::Navigate { url: url.to_string() }, timeout)?;
 
-    if json["value"].is_null() {
-        debug!("navigation succeed");
-        Ok(())
-    } else {
-        error!("response to navigation request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("navigation succeed");
+    Ok(())
 }
 
 /// -> take session id
 /// close active tab
-pub(crate) fn close_active_tab(session_id: &str) -> Result<(), WebdriverError> {
+pub(crate) fn close_active_tab(connection: &Connection, session_id: &str) -> Result<(), WebdriverError> {
     debug!("closing active tab on session with id {}", session_id);
 
-    let json = delete(&format!("http://localhost:4444/session/{}/window", session_id))?;
+    // the driver returns the handles of the remaining tabs, or null
+    let _: Option<Vec<String>> = connection.command(session_id, &Command::CloseWindow)?;
 
-    if json["value"].is_array() || json["value"].is_null() {
-        debug!("tab closed successfully");
-        Ok(())
-    } else {
-        error!("response to close request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("tab closed successfully");
+    Ok(())
+}
+
+/// -> take session id
+/// end the session, closing the remaining tabs of the browser
+pub(crate) fn delete_session(connection: &Connection, session_id: &str) -> Result<(), WebdriverError> {
+    debug!("deleting session with id {}", session_id);
+
+    connection.command::<()>(session_id, &Command::DeleteSession)?;
+
+    debug!("session deleted successfully");
+    Ok(())
 }
 
 /// -> take session id, a selector and a value
 /// search for elements
 /// -> return id of the first element found
-pub(crate) fn find_element(session_id: &str, selector: Selector, value: &str) -> Result<String, WebdriverError> {
-    debug!("selecting element by {} with value {} on session with id {}", selector.to_string(), value, session_id);
+pub(crate) fn find_element(connection: &Connection, session_id: &str, selector: Selector, value: &str) -> Result<String, WebdriverError> {
+    debug!("selecting element by {:?} with value {} on session with id {}", selector, value, session_id);
 
-    let json = post(&format!("http://localhost:4444/session/{}/element", session_id), &object! {
-        "using" => selector.to_string(),
-        "value" => value
-    }.to_string())?;
+    let element: ElementValue = connection.command(session_id, &Command::FindElement { selector, value: value.to_string() })?;
 
-    if !json["value"]["element-6066-11e4-a52e-4f735466cecf"].is_null() {
-        debug!("element found");
-        Ok(json["value"]["element-6066-11e4-a52e-4f735466cecf"].to_string())
-    } else {
-        error!("response to element search request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("element found");
+    Ok(element.id)
+}
+
+/// -> take session id, the id of an element, a selector and its value
+/// -> return the id of the first element found in the element
+pub(crate) fn find_element_from_element(connection: &Connection, session_id: &str, element_id: &str, selector: Selector, value: &str) -> Result<String, WebdriverError> {
+    debug!("selecting element by {:?} with value {} in element {} on session with id {}", selector, value, element_id, session_id);
+
+    let element: ElementValue = connection.command(session_id, &Command::FindElementFromElement { element_id: element_id.to_string(), selector, value: value.to_string() })?;
+
+    debug!("element found");
+    Ok(element.id)
 }
 
 /// -> take session id
 /// -> return url of the active tab
-pub(crate) fn get_active_tab_url(session_id: &str) -> Result<String, WebdriverError> {
+pub(crate) fn get_active_tab_url(connection: &Connection, session_id: &str) -> Result<String, WebdriverError> {
     debug!("getting url of active tab on session with id {}", session_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/url", session_id))?;
+    let url: String = connection.command(session_id, &Command::GetCurrentUrl)?;
 
-    if json["value"].is_string() {
-        let url = json["value"].to_string();
-        debug!("active tab url is {}", url);
-        Ok(url)
-    } else {
-        error!("response to url request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("active tab url is {}", url);
+    Ok(url)
 }
 
 /// -> take session id
 /// -> return title of the active tab
-pub(crate) fn get_active_tab_title(session_id: &str) -> Result<String, WebdriverError> {
+pub(crate) fn get_active_tab_title(connection: &Connection, session_id: &str) -> Result<String, WebdriverError> {
     debug!("getting title of active tab on session with id {}", session_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/title", session_id))?;
+    let title: String = connection.command(session_id, &Command::GetTitle)?;
 
-    if json["value"].is_string() {
-        let url = json["value"].to_string();
-        debug!("active tab title is {}", url);
-        Ok(url)
-    } else {
-        error!("response to title request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("active tab title is {}", title);
+    Ok(title)
 }
 
 /// -> take session id
 /// navigate backward on the selected tab
-pub(crate) fn back(session_id: &str) -> Result<(), WebdriverError> {
+pub(crate) fn back(connection: &Connection, session_id: &str) -> Result<(), WebdriverError> {
     debug!("navigating backward on active tab on session with id {}", session_id);
 
-    let json = post(&format!("http://localhost:4444/session/{}/back", session_id), "{}")?;
+    connection.command::<()>(session_id, &Command::Back)?;
 
-    if json["value"].is_null() {
-        debug!("successfully navigated backward");
-        Ok(())
-    } else {
-        error!("response to back request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("successfully navigated backward");
+    Ok(())
 }
 
 /// -> take session id
 /// navigate forward on the selected tab
-pub(crate) fn forward(session_id: &str) -> Result<(), WebdriverError> {
+pub(crate) fn forward(connection: &Connection, session_id: &str) -> Result<(), WebdriverError> {
     debug!("navigating forward on active tab on session with id {}", session_id);
 
-    let json = post(&format!("http://localhost:4444/session/{}/forward", session_id), "{}")?;
+    connection.command::<()>(session_id, &Command::Forward)?;
 
-    if json["value"].is_null() {
-        debug!("successfully navigated forward");
-        Ok(())
-    } else {
-        error!("response to forward request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("successfully navigated forward");
+    Ok(())
 }
 
 /// -> take session id
 /// refresh the selected tab
-pub(crate) fn refresh(session_id: &str) -> Result<(), WebdriverError> {
+pub(crate) fn refresh(connection: &Connection, session_id: &str) -> Result<(), WebdriverError> {
     debug!("refreshing the active tab on session with id {}", session_id);
 
-    let json = post(&format!("http://localhost:4444/session/{}/refresh", session_id), "{}")?;
+    connection.command::<()>(session_id, &Command::Refresh)?;
 
-    if json["value"].is_null() {
-        debug!("tab successfully refreshed");
-        Ok(())
-    } else {
-        error!("response to refresh request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("tab successfully refreshed");
+    Ok(())
 }
 
 /// -> take session id, script and args
 /// execute the script on the active tab
-pub(crate) fn execute_script_sync(session_id: &str, script: &str, args: Vec<JsonValue>) -> Result<(), WebdriverError> {
+/// -> return the value returned by the script
+pub(crate) fn execute_script_sync(connection: &Connection, session_id: &str, script: &str, args: Vec<JsonValue>) -> Result<JsonValue, WebdriverError> {
     debug!("executing script on selected tab on session with id {}", session_id);
 
-    let json = post(&format!("http://localhost:4444/session/{}/execute/sync", session_id), &object!{
-        "script" => script,
-        "args" => args
-    }.to_string())?;
+    let value = connection.command(session_id, &Command::ExecuteScript { script: script.to_string(), args })?;
 
-    if json["value"].is_null() {
-        debug!("script successfully executed");
-        Ok(())
-    } else {
-        error!("response to refresh request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("script successfully executed");
+    Ok(to_json_value(value))
 }
 
-pub(crate) fn click_on_element(session_id: &str, element_id: &str) -> Result<(), WebdriverError> {
+pub(crate) fn click_on_element(connection: &Connection, session_id: &str, element_id: &str) -> Result<(), WebdriverError> {
     debug!("clicking on element with id {} on session with id {}", session_id, element_id);
     warn!("click_on_element function may fail silently in firefox");
 
-    let json = post(&format!("http://localhost:4444/session/{}/element/{}/click", session_id, element_id), "{}")?;
+    connection.command::<()>(session_id, &Command::ElementClick { element_id: element_id.to_string() })?;
 
-    if json["value"].is_null() {
-        debug!("clicked successfully");
-        Ok(())
-    } else {
-        error!("response to click request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("clicked successfully");
+    Ok(())
+}
+
+pub(crate) fn submit_element(connection: &Connection, session_id: &str, element_id: &str) -> Result<(), WebdriverError> {
+    debug!("submitting the form of element with id {} on session with id {}", element_id, session_id);
+
+    connection.command::<()>(session_id, &Command::ElementSubmit { element_id: element_id.to_string() })?;
+
+    debug!("submitted successfully");
+    Ok(())
 }
 
-pub(crate) fn get_element_text(session_id: &str, element_id: &str) -> Result<String, WebdriverError> {
+pub(crate) fn perform_actions(connection: &Connection, session_id: &str, actions: JsonValue) -> Result<(), WebdriverError> {
+    debug!("performing actions {} on session with id {}", actions, session_id);
+
+    connection.command::<()>(session_id, &Command::PerformActions { actions })
+}
+
+pub(crate) fn release_actions(connection: &Connection, session_id: &str) -> Result<(), WebdriverError> {
+    debug!("releasing pressed keys and buttons on session with id {}", session_id);
+
+    connection.command::<()>(session_id, &Command::ReleaseActions)
+}
+
+pub(crate) fn get_element_text(connection: &Connection, session_id: &str, element_id: &str) -> Result<String, WebdriverError> {
     debug!("getting text of element with id {} on session with id {}", session_id, element_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/element/{}/text", session_id, element_id))?;
+    let text: String = connection.command(session_id, &Command::GetElementText { element_id: element_id.to_string() })?;
 
-    if json["value"].is_string() {
-        let text = json["value"].to_string();
-        debug!("text of element with id {} is {}", element_id, text);
-        Ok(text)
-    } else {
-        error!("response to text request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("text of element with id {} is {}", element_id, text);
+    Ok(text)
 }
 
-pub(crate) fn send_text_to_element(session_id: &str, element_id: &str, text: &str) -> Result<(), WebdriverError> {
+pub(crate) fn send_text_to_element(connection: &Connection, session_id: &str, element_id: &str, text: &str) -> Result<(), WebdriverError> {
     debug!("sending text ({}) to element with id {} on session with id {}", text, session_id, element_id);
 
-    let json = post(&format!("http://localhost:4444/session/{}/element/{}/value", session_id, element_id), &object!{
-        "text" => text,
-    }.to_string())?;
+    connection.command::<()>(session_id, &Command::ElementSendKeys { element_id: element_id.to_string(), text: text.to_string() })?;
 
-    if json["value"].is_null() {
-        debug!("success");
-        Ok(())
-    } else {
-        error!("response to send text request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("success");
+    Ok(())
 }
 
-pub(crate) fn get_element_attribute(session_id: &str, element_id: &str, attribute_name: &str) -> Result<String, WebdriverError> {
+/// -> take session id and a zip archive of the file, encoded in base64
+/// send the file to the node running the session
+/// -> return the path of the file on the node
+pub(crate) fn upload_file(connection: &Connection, session_id: &str, file: String) -> Result<String, WebdriverError> {
+    debug!("uploading a file on session with id {}", session_id);
+
+    let path: String = connection.command(session_id, &Command::UploadFile { file })?;
+
+    debug!("file uploaded at {}", path);
+    Ok(path)
+}
+
+pub(crate) fn get_element_attribute(connection: &Connection, session_id: &str, element_id: &str, attribute_name: &str) -> Result<String, WebdriverError> {
     debug!("getting attribute {} of element with id {} on session with id {}", attribute_name, session_id, element_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/element/{}/attribute/{}", session_id, element_id, attribute_name))?;
+    let value: String = connection.command(session_id, &Command::GetElementAttribute { element_id: element_id.to_string(), name: attribute_name.to_string() })?;
 
-    if json["value"].is_string() {
-        let value = json["value"].to_string();
-        debug!("attribute {} is {}", attribute_name, value);
-        Ok(value)
-    } else {
-        error!("response to get element attribute request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("attribute {} is {}", attribute_name, value);
+    Ok(value)
 }
 
-pub(crate) fn get_element_property(session_id: &str, element_id: &str, property_name: &str) -> Result<String, WebdriverError> {
+pub(crate) fn get_element_property(connection: &Connection, session_id: &str, element_id: &str, property_name: &str) -> Result<String, WebdriverError> {
     debug!("getting property {} of element with id {} on session with id {}", property_name, session_id, element_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/element/{}/property/{}", session_id, element_id, property_name))?;
+    let value = to_json_value(connection.command(session_id, &Command::GetElementProperty { element_id: element_id.to_string(), name: property_name.to_string() })?);
 
-    if !json["value"].is_null() {
-        let value = json["value"].to_string();
+    if !value.is_null() {
+        let value = value.to_string();
         debug!("property {} is {}", property_name, value);
         Ok(value)
     } else {
-        error!("response to get element property request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
+        error!("element has no property {}", property_name);
+        Err(ErrorKind::InvalidResponse.into())
     }
 }
 
-pub(crate) fn get_element_css_value(session_id: &str, element_id: &str, property_name: &str) -> Result<String, WebdriverError> {
+pub(crate) fn get_element_css_value(connection: &Connection, session_id: &str, element_id: &str, property_name: &str) -> Result<String, WebdriverError> {
     debug!("getting css value of property {} of element with id {} on session with id {}", property_name, session_id, element_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/element/{}/css/{}", session_id, element_id, property_name))?;
+    let value: String = connection.command(session_id, &Command::GetElementCssValue { element_id: element_id.to_string(), property: property_name.to_string() })?;
 
-    if json["value"].is_string() {
-        let value = json["value"].to_string();
-        debug!("css value for {} is {}", property_name, value);
-        Ok(value)
-    } else {
-        error!("response to get element css value request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("css value for {} is {}", property_name, value);
+    Ok(value)
 }
 
-pub(crate) fn get_element_tag_name(session_id: &str, element_id: &str) -> Result<String, WebdriverError> {
+pub(crate) fn get_element_tag_name(connection: &Connection, session_id: &str, element_id: &str) -> Result<String, WebdriverError> {
     debug!("getting tag name of element with id {} on session with id {}", session_id, element_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/element/{}/name", session_id, element_id))?;
+    let value: String = connection.command(session_id, &Command::GetElementTagName { element_id: element_id.to_string() })?;
 
-    if json["value"].is_string() {
-        let value = json["value"].to_string();
-        debug!("tag name is {}", value);
-        Ok(value)
-    } else {
-        error!("response to get element tag name request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    debug!("tag name is {}", value);
+    Ok(value)
+}
+
+/// -> take session id and element id
+/// -> return the WAI-ARIA role of the element, as computed by the browser
+pub(crate) fn get_computed_role(connection: &Connection, session_id: &str, element_id: &str) -> Result<String, WebdriverError> {
+    debug!("getting computed role of element with id {} on session with id {}", element_id, session_id);
+
+    let value: String = connection.command(session_id, &Command::GetComputedRole { element_id: element_id.to_string() })?;
+
+    debug!("computed role is {}", value);
+    Ok(value)
+}
+
+/// -> take session id and element id
+/// -> return the accessible name of the element, as computed by the browser
+pub(crate) fn get_computed_label(connection: &Connection, session_id: &str, element_id: &str) -> Result<String, WebdriverError> {
+    debug!("getting computed label of element with id {} on session with id {}", element_id, session_id);
+
+    let value: String = connection.command(session_id, &Command::GetComputedLabel { element_id: element_id.to_string() })?;
+
+    debug!("computed label is {}", value);
+    Ok(value)
 }
 
-pub(crate) fn get_element_rect(session_id: &str, element_id: &str) -> Result<((usize, usize), (usize, usize)), WebdriverError> {
+pub(crate) fn get_element_rect(connection: &Connection, session_id: &str, element_id: &str) -> Result<Rect, WebdriverError> {
     debug!("getting rect of element with id {} on session with id {}", session_id, element_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/element/{}/rect", session_id, element_id))?;
+    let rect: RectValue = connection.command(session_id, &Command::GetElementRect { element_id: element_id.to_string() })?;
 
-    if json["value"]["x"].is_number() && json["value"]["y"].is_number() && json["value"]["width"].is_number() && json["value"]["height"].is_number() {
-        let value = ((json["value"]["x"].as_usize().unwrap(), json["value"]["y"].as_usize().unwrap()), (json["value"]["width"].as_usize().unwrap(), json["value"]["height"].as_usize().unwrap()));
-        debug!("rect is {:?}", value);
-        Ok(value)
-    } else {
-        error!("response to get element rect request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+    let rect = Rect {
+        x: rect.x,
+        y: rect.y,
+        width: rect.width,
+        height: rect.height
+    };
+    debug!("rect is {:?}", rect);
+    Ok(rect)
 }
 
-pub(crate) fn is_element_enabled(session_id: &str, element_id: &str) -> Result<bool, WebdriverError> {
+pub(crate) fn is_element_enabled(connection: &Connection, session_id: &str, element_id: &str) -> Result<bool, WebdriverError> {
     debug!("checking if element with id {} on session with id {} is enabled", element_id, session_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/element/{}/enabled", session_id, element_id))?;
+    connection.command(session_id, &Command::IsElementEnabled { element_id: element_id.to_string() })
+}
 
-    if json["value"].is_boolean() {
-        let value = json["value"].as_bool().unwrap();
-        Ok(value)
-    } else {
-        error!("response to is element enabled request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+pub(crate) fn is_element_selected(connection: &Connection, session_id: &str, element_id: &str) -> Result<bool, WebdriverError> {
+    debug!("checking if element with id {} on session with id {} is selected", element_id, session_id);
+
+    connection.command(session_id, &Command::IsElementSelected { element_id: element_id.to_string() })
+}
+
+pub(crate) fn is_element_displayed(connection: &Connection, session_id: &str, element_id: &str) -> Result<bool, WebdriverError> {
+    debug!("checking if element with id {} on session with id {} is displayed", element_id, session_id);
+
+    connection.command(session_id, &Command::IsElementDisplayed { element_id: element_id.to_string() })
 }
 
-pub(crate) fn get_all_cookies(session_id: &str) -> Result<Vec<(String, usize, bool, String, String, bool, String)>, WebdriverError> {
+pub(crate) fn get_all_cookies(connection: &Connection, session_id: &str) -> Result<Vec<(String, usize, bool, String, String, bool, String)>, WebdriverError> {
     debug!("getting cookies on session with id {}", session_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/cookie", session_id))?;
-
-    if json["value"].is_array() {
-        let mut i = 0;
-        let mut cookies = Vec::new();
-        while json["value"][i].is_object() {
-            if let (domain, Some(expiry), Some(http_only), name, path, Some(secure), value) =
-            (json["value"][i]["domain"].to_string(), json["value"][i]["expiry"].as_usize(), json["value"][i]["httpOnly"].as_bool(), json["value"][i]["name"].to_string(), json["value"][i]["path"].to_string(), json["value"][i]["secure"].as_bool(), json["value"][i]["value"].to_string()) {
-                cookies.push((domain, expiry, http_only, name, path, secure, value))
-            } else {
-                warn!("a cookie was invalid; result: {:?}", (json["value"][i]["domain"].to_string(), json["value"][i]["expiry"].as_usize(), json["value"][i]["httpOnly"].as_bool(), json["value"][i]["name"].to_string(), json["value"][i]["path"].to_string(), json["value"][i]["secure"].as_bool(), json["value"][i]["value"].to_string()))
-            }
-            i += 1;
-        }
-        
-        debug!("cookies: {:?}", cookies);
+    let values: Vec<CookieValue> = connection.command(session_id, &Command::GetAllCookies)?;
 
-        Ok(cookies)
-    } else {
-        error!("response to cookies request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
+    let mut cookies = Vec::new();
+    for cookie in values {
+        if let (Some(expiry), Some(http_only), Some(secure)) = (cookie.expiry, cookie.http_only, cookie.secure) {
+            cookies.push((cookie.domain, expiry, http_only, cookie.name, cookie.path, secure, cookie.value))
+        } else {
+            warn!("a cookie was invalid; result: {:?}", cookie)
+        }
     }
+    debug!("cookies: {:?}", cookies);
+    Ok(cookies)
 }
 
-pub(crate) fn set_cookie(session_id: &str, cookie: (String, usize, bool, String, String, bool, String)) -> Result<(), WebdriverError> {
+pub(crate) fn set_cookie(connection: &Connection, session_id: &str, cookie: (String, usize, bool, String, String, bool, String)) -> Result<(), WebdriverError> {
     debug!("setting cookie {} to {} on session with id {}", cookie.3, cookie.6, session_id);
 
-    let json = post(&format!("http://localhost:4444/session/{}/cookie", session_id), &object!{
-        "cookie" => object!{
-            "domain" => cookie.0,
-            "expiry" => cookie.1,
-            "httpOnly" => cookie.2,
-            "name" => cookie.3,
-            "path" => cookie.4,
-            "secure" => cookie.5,
-            "value" => cookie.6
-        }
-    }.to_string())?;
+    connection.command::<()>(session_id, &Command::AddCookie { cookie: object!{
+        "domain" => cookie.0,
+        "expiry" => cookie.1,
+        "httpOnly" => cookie.2,
+        "name" => cookie.3,
+        "path" => cookie.4,
+        "secure" => cookie.5,
+        "value" => cookie.6
+    }})?;
+
+    debug!("success");
+    Ok(())
+}
 
-    if json["value"].is_null() {
-        debug!("success");
-        Ok(())
-    } else {
-        error!("response to add cookie request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
-    }
+pub(crate) fn delete_all_cookies(connection: &Connection, session_id: &str) -> Result<(), WebdriverError> {
+    debug!("deleting cookies of active tab on session with id {}", session_id);
+
+    connection.command::<()>(session_id, &Command::DeleteAllCookies)?;
+
+    debug!("success");
+    Ok(())
 }
 
-pub(crate) fn get_page_source(session_id: &str) -> Result<String, WebdriverError> {
+pub(crate) fn get_page_source(connection: &Connection, session_id: &str) -> Result<String, WebdriverError> {
     debug!("getting page source of active tab on session with id {}", session_id);
 
-    let json = get(&format!("http://localhost:4444/session/{}/source", session_id))?;
+    let source: String = connection.command(session_id, &Command::GetPageSource)?;
 
-    if json["value"].is_string() {
-        let source = json["value"].to_string();
-        debug!("page source is {}", source);
-        return Ok(source)
-    } else {
-        error!("response to page source request was not understood: {}", json);
-        Err(WebdriverError::InvalidResponse)
+    debug!("page source is {}", source);
+    Ok(source)
+}
+
+/// -> take session id and the type of log ("browser")
+/// -> return the entries logged since the last call (chromedriver and msedgedriver only)
+pub(crate) fn get_log(connection: &Connection, session_id: &str, log_type: &str) -> Result<Vec<LogEntryValue>, WebdriverError> {
+    debug!("getting {} log on session with id {}", log_type, session_id);
+
+    let entries: Vec<LogEntryValue> = connection.command(session_id, &Command::GetLog { log_type: log_type.to_string() })?;
+
+    debug!("{} log entries received", entries.len());
+    Ok(entries)
+}
+
+/// -> take session id, the vendor prefix of the driver ("goog" or "ms"), a Chrome DevTools Protocol method and its parameters
+/// execute the command on the selected tab (chromedriver and msedgedriver only)
+/// -> return the result of the command
+pub(crate) fn execute_cdp_command(connection: &Connection, session_id: &str, vendor: &str, method: &str, params: JsonValue) -> Result<JsonValue, WebdriverError> {
+    debug!("executing devtools command {} with params {} on session with id {}", method, params, session_id);
+
+    let value = connection.command(session_id, &Command::ExecuteCdpCommand { vendor: vendor.to_string(), method: method.to_string(), params })?;
+
+    debug!("devtools command successfully executed");
+    Ok(to_json_value(value))
+}
+
+/// -> take session id
+/// capture the viewport of the selected tab
+/// -> return png bytes
+pub(crate) fn take_screenshot(connection: &Connection, session_id: &str) -> Result<Vec<u8>, WebdriverError> {
+    debug!("taking screenshot of active tab on session with id {}", session_id);
+
+    let encoded: String = connection.command(session_id, &Command::TakeScreenshot)?;
+
+    match base64::decode(&encoded) {
+        Ok(png) => {
+            debug!("screenshot taken ({} bytes)", png.len());
+            Ok(png)
+        },
+        Err(error) => {
+            error!("screenshot is not valid base64: {}", error);
+            Err(ErrorKind::InvalidResponse.into())
+        }
+    }
+}
+
+/// -> take session id
+/// capture the whole document of the selected tab (geckodriver only)
+/// -> return png bytes
+pub(crate) fn take_full_page_screenshot(connection: &Connection, session_id: &str) -> Result<Vec<u8>, WebdriverError> {
+    debug!("taking full page screenshot of active tab on session with id {}", session_id);
+
+    let encoded: String = connection.command(session_id, &Command::TakeFullPageScreenshot)?;
+
+    match base64::decode(&encoded) {
+        Ok(png) => {
+            debug!("full page screenshot taken ({} bytes)", png.len());
+            Ok(png)
+        },
+        Err(error) => {
+            error!("full page screenshot is not valid base64: {}", error);
+            Err(ErrorKind::InvalidResponse.into())
+        }
     }
-}
\ No newline at end of file
+}
+
+/// -> take session id and print options
+/// print the selected tab
+/// -> return pdf bytes
+pub(crate) fn print_page(connection: &Connection, session_id: &str, options: PrintOptions) -> Result<Vec<u8>, WebdriverError> {
+    debug!("printing active tab on session with id {}", session_id);
+
+    let encoded: String = connection.command(session_id, &Command::PrintPage(options))?;
+
+    match base64::decode(&encoded) {
+        Ok(pdf) => {
+            debug!("page printed ({} bytes)", pdf.len());
+            Ok(pdf)
+        },
+        Err(error) => {
+            error!("printed page is not valid base64: {}", error);
+            Err(ErrorKind::InvalidResponse.into())
+        }
+    }
+}
+
+pub(crate) fn set_permission(connection: &Connection, session_id: &str, permission: Permission, state: PermissionState) -> Result<(), WebdriverError> {
+    debug!("setting permission {} to {} on session with id {}", permission.to_json(), state.to_string(), session_id);
+
+    connection.command::<()>(session_id, &Command::SetPermission { permission, state })?;
+
+    debug!("success");
+    Ok(())
+}
@@ -0,0 +1,411 @@
+//! Interception and mocking of the requests made by web pages
+//!
+//! On Chrome and Edge, the requests of the tab are paused with the `Fetch` domain of the Chrome DevTools Protocol,
+//! on a connection of the crate to the DevTools server of the browser. Every request is intercepted (navigations,
+//! images, stylesheets, scripts, frames...) and the rules apply to every page loaded by the tab.
+//!
+//! Other browsers do not expose the requests to webdriver, so `fetch()` and `XMLHttpRequest` are replaced in the page:
+//! only the requests made by scripts can be intercepted, and the rules apply to the current page.
+//! Install them after navigating, or they are lost, unless the session was created with
+//! [Capabilities::bidi()](../capabilities/struct.Capabilities.html#method.bidi), which installs them in the next pages too.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::Browser, interception::NetworkInterceptor};
+//!
+//! let mut session = Session::new(Browser::Chrome, false).unwrap();
+//! let interceptor = NetworkInterceptor::new()
+//!     .block("*://www.google-analytics.com/*")
+//!     .set_header("https://api.example.com/*", "Authorization", "Bearer test")
+//!     .respond("https://api.example.com/user", 200, "application/json", r#"{"name": "test"}"#);
+//! session.tabs[0].intercept_network(&interceptor).unwrap();
+//! session.tabs[0].navigate("https://example.com/").unwrap();
+//!
+//! for request in session.tabs[0].intercepted_requests().unwrap() {
+//!     println!("{:?} {} {}", request.interception, request.method, request.url);
+//! }
+//! ```
+//!
+//! ```rust
+//! # use lw_webdriver::{session::Session, enums::Browser, interception::NetworkInterceptor};
+//! // on Firefox, the rules are installed in the loaded page
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.tabs[0].navigate("https://example.com/").unwrap();
+//! session.tabs[0].intercept_network(&NetworkInterceptor::new().block("*://www.google-analytics.com/*")).unwrap();
+//! ```
+
+use std::sync::{Arc, Mutex};
+use std::net::{Shutdown, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use json::{JsonValue, object};
+use log::{debug, warn};
+use crate::error::{WebdriverError, ErrorKind};
+use crate::websocket::WebSocket;
+
+/// Replaces `fetch()` and `XMLHttpRequest` by functions applying the rules of `window.__lwInterception`
+/// and recording the intercepted requests. Installing it again only replaces the rules.
+const INTERCEPTION_SHIM_SCRIPT: &str = "
+    (rules => {
+        const wildcard = pattern => new RegExp('^' + pattern.split('*').map(part => part.replace(/[.+?^${}()|[\\]\\\\]/g, '\\\\$&')).join('.*') + '$');
+        const state = window.__lwInterception = window.__lwInterception || { requests: [] };
+        state.rules = rules.map(rule => Object.assign({ regex: wildcard(rule.pattern) }, rule));
+        if (state.installed) {
+            return;
+        }
+        state.installed = true;
+
+        const resolve = url => {
+            try {
+                return new URL(url, location.href).href;
+            } catch (error) {
+                return String(url);
+            }
+        };
+        // the first blocking or stubbing rule, and the headers of every matching rule
+        const match = (method, url) => {
+            const rules = state.rules.filter(rule => rule.regex.test(url));
+            const final = rules.find(rule => rule.action !== 'headers');
+            const headers = rules.filter(rule => rule.action === 'headers').map(rule => rule.headers);
+            const interception = final ? final.action : (headers.length > 0 ? 'headers' : null);
+            if (interception) {
+                state.requests.push({ url, method, interception });
+            }
+            return { final, headers };
+        };
+
+        if (window.fetch) {
+            const fetch = window.fetch;
+            window.fetch = function(input, init) {
+                let request;
+                try {
+                    request = new Request(input, init);
+                } catch (error) {
+                    return fetch.call(this, input, init);
+                }
+                const { final, headers } = match(request.method, resolve(request.url));
+                if (final && final.action === 'block') {
+                    return Promise.reject(new TypeError('Failed to fetch'));
+                }
+                if (final && final.action === 'respond') {
+                    return Promise.resolve(new Response(final.body, { status: final.status, headers: final.headers }));
+                }
+                if (headers.length > 0) {
+                    headers.forEach(headers => Object.entries(headers).forEach(([name, value]) => request.headers.set(name, value)));
+                    return fetch.call(this, request);
+                }
+                return fetch.call(this, input, init);
+            };
+        }
+
+        const open = XMLHttpRequest.prototype.open;
+        const send = XMLHttpRequest.prototype.send;
+        XMLHttpRequest.prototype.open = function(method, url) {
+            this.__lwRequest = { method: String(method).toUpperCase(), url: resolve(url) };
+            return open.apply(this, arguments);
+        };
+        XMLHttpRequest.prototype.send = function() {
+            if (!this.__lwRequest) {
+                return send.apply(this, arguments);
+            }
+            const { final, headers } = match(this.__lwRequest.method, this.__lwRequest.url);
+            const dispatch = types => setTimeout(() => types.forEach(type => this.dispatchEvent(type === 'readystatechange' ? new Event(type) : new ProgressEvent(type))));
+            if (final && final.action === 'block') {
+                Object.defineProperty(this, 'readyState', { value: 4 });
+                dispatch(['readystatechange', 'error', 'loadend']);
+                return;
+            }
+            if (final && final.action === 'respond') {
+                const responseHeaders = Object.entries(final.headers).map(([name, value]) => name.toLowerCase() + ': ' + value + '\\r\\n').join('');
+                Object.defineProperties(this, {
+                    readyState: { value: 4 },
+                    status: { value: final.status },
+                    statusText: { value: '' },
+                    responseURL: { value: this.__lwRequest.url },
+                    responseText: { value: final.body },
+                    response: { value: this.responseType === 'json' ? JSON.parse(final.body) : final.body },
+                    getResponseHeader: { value: name => {
+                        const header = Object.entries(final.headers).find(([key]) => key.toLowerCase() === String(name).toLowerCase());
+                        return header ? header[1] : null;
+                    } },
+                    getAllResponseHeaders: { value: () => responseHeaders }
+                });
+                dispatch(['readystatechange', 'load', 'loadend']);
+                return;
+            }
+            headers.forEach(headers => Object.entries(headers).forEach(([name, value]) => this.setRequestHeader(name, value)));
+            return send.apply(this, arguments);
+        };
+    })
+";
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+enum Action {
+    Block,
+    SetHeader { name: String, value: String },
+    Respond { status: u16, content_type: String, body: String }
+}
+
+/// A list of rules applied to the requests whose url matches a pattern (`*` is a wildcard).
+/// Rules are installed with [Tab::intercept_network()](../tab/struct.Tab.html#method.intercept_network).
+///
+/// If several rules match a request, the first blocking or stubbing rule is applied and the headers of every matching rule are set.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Default)]
+pub struct NetworkInterceptor {
+    rules: Vec<(String, Action)>
+}
+
+impl NetworkInterceptor {
+    pub fn new() -> Self {
+        NetworkInterceptor::default()
+    }
+
+    /// Make the matching requests fail.
+    pub fn block(mut self, pattern: &str) -> Self {
+        self.rules.push((pattern.to_string(), Action::Block));
+        self
+    }
+
+    /// Add or replace a header in the matching requests.
+    pub fn set_header(mut self, pattern: &str, name: &str, value: &str) -> Self {
+        self.rules.push((pattern.to_string(), Action::SetHeader {
+            name: name.to_string(),
+            value: value.to_string()
+        }));
+        self
+    }
+
+    /// Answer the matching requests without sending them.
+    pub fn respond(mut self, pattern: &str, status: u16, content_type: &str, body: &str) -> Self {
+        self.rules.push((pattern.to_string(), Action::Respond {
+            status,
+            content_type: content_type.to_string(),
+            body: body.to_string()
+        }));
+        self
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// -> take a request paused by the `Fetch` domain of the Chrome DevTools Protocol
+    /// -> return the command handling it, its parameters and what happened to the request (None if no rule matched)
+    pub(crate) fn fetch_reply(&self, paused: &JsonValue) -> (&'static str, JsonValue, Option<Interception>) {
+        let request_id = paused["requestId"].clone();
+        let url = paused["request"]["url"].as_str().unwrap_or_default();
+        let actions: Vec<&Action> = self.rules.iter().filter(|(pattern, _)| matches(pattern, url)).map(|(_, action)| action).collect();
+
+        match actions.iter().find(|action| !matches!(action, Action::SetHeader { .. })) {
+            Some(Action::Block) => ("Fetch.failRequest", object!{
+                "requestId" => request_id,
+                "errorReason" => "BlockedByClient"
+            }, Some(Interception::Blocked)),
+            Some(Action::Respond { status, content_type, body }) => ("Fetch.fulfillRequest", object!{
+                "requestId" => request_id,
+                "responseCode" => *status,
+                "responseHeaders" => vec![object!{"name" => "Content-Type", "value" => content_type.as_str()}],
+                "body" => base64::encode(body)
+            }, Some(Interception::Stubbed)),
+            _ => {
+                let mut headers: Vec<(String, String)> = paused["request"]["headers"].entries()
+                    .map(|(name, value)| (name.to_string(), value.as_str().unwrap_or_default().to_string()))
+                    .collect();
+                let mut modified = false;
+                for action in &actions {
+                    if let Action::SetHeader { name, value } = action {
+                        headers.retain(|(header, _)| !header.eq_ignore_ascii_case(name));
+                        headers.push((name.clone(), value.clone()));
+                        modified = true;
+                    }
+                }
+                if !modified {
+                    return ("Fetch.continueRequest", object!{"requestId" => request_id}, None);
+                }
+                let headers: Vec<JsonValue> = headers.into_iter().map(|(name, value)| object!{"name" => name, "value" => value}).collect();
+                ("Fetch.continueRequest", object!{
+                    "requestId" => request_id,
+                    "headers" => headers
+                }, Some(Interception::Modified))
+            },
+        }
+    }
+
+    /// -> return a script installing the rules in a page
+    pub(crate) fn script(&self) -> String {
+        let rules: Vec<JsonValue> = self.rules.iter().map(|(pattern, action)| match action {
+            Action::Block => object!{
+                "pattern" => pattern.as_str(),
+                "action" => "block"
+            },
+            Action::SetHeader { name, value } => {
+                let mut headers = object!{};
+                headers[name.as_str()] = value.as_str().into();
+                object!{
+                    "pattern" => pattern.as_str(),
+                    "action" => "headers",
+                    "headers" => headers
+                }
+            },
+            Action::Respond { status, content_type, body } => object!{
+                "pattern" => pattern.as_str(),
+                "action" => "respond",
+                "status" => *status,
+                "headers" => object!{"Content-Type" => content_type.as_str()},
+                "body" => body.as_str()
+            },
+        }).collect();
+
+        format!("{}({});", INTERCEPTION_SHIM_SCRIPT, JsonValue::from(rules).dump())
+    }
+}
+
+/// What happened to an intercepted request.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum Interception {
+    Blocked,
+    /// Headers were set before sending the request.
+    Modified,
+    /// A stubbed response was returned without sending the request.
+    Stubbed
+}
+
+/// A request to which a rule was applied, returned by [Tab::intercepted_requests()](../tab/struct.Tab.html#method.intercepted_requests).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct InterceptedRequest {
+    pub url: String,
+    /// The http method in uppercase.
+    pub method: String,
+    pub interception: Interception
+}
+
+impl InterceptedRequest {
+    pub(crate) fn from_json(json: &JsonValue) -> Option<Self> {
+        Some(InterceptedRequest {
+            url: json["url"].as_str()?.to_string(),
+            method: json["method"].as_str()?.to_string(),
+            interception: match json["interception"].as_str()? {
+                "block" => Interception::Blocked,
+                "headers" => Interception::Modified,
+                "respond" => Interception::Stubbed,
+                _ => return None,
+            }
+        })
+    }
+}
+
+/// -> take a url pattern (`*` is a wildcard) and a url
+/// -> return whether the url matches the pattern
+fn matches(pattern: &str, url: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if parts.len() == 1 {
+        return url == pattern;
+    }
+    if url.len() < first.len() + last.len() || !url.starts_with(first) || !url.ends_with(last) {
+        return false;
+    }
+    let mut rest = &url[first.len()..url.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// The requests of a tab paused with the `Fetch` domain of the Chrome DevTools Protocol,
+/// and handled with the rules of an interceptor by a background thread.
+pub(crate) struct FetchInterception {
+    requests: Arc<Mutex<Vec<InterceptedRequest>>>,
+    /// the connection to the DevTools server, shut down to stop the thread
+    stream: TcpStream,
+    thread: Option<JoinHandle<()>>
+}
+
+impl FetchInterception {
+    /// -> take the address of the DevTools server, the target id of the tab, the rules and how long to wait for the server (forever if None)
+    pub(crate) fn start(debugger_address: &str, target_id: &str, interceptor: NetworkInterceptor, timeout: Option<Duration>) -> Result<Self, WebdriverError> {
+        let failed = |error: std::io::Error| WebdriverError::from(ErrorKind::FailedRequest).with_message(format!("failed to intercept the requests: {}", error));
+        let mut socket = WebSocket::connect(&format!("ws://{}/devtools/page/{}", debugger_address, target_id), timeout)?;
+        let patterns: Vec<JsonValue> = interceptor.rules.iter().map(|(pattern, _)| object!{"urlPattern" => pattern.as_str()}).collect();
+        socket.send(&object!{
+            "id" => 1,
+            "method" => "Fetch.enable",
+            "params" => object!{"patterns" => patterns}
+        }.dump()).map_err(failed)?;
+        loop {
+            let message = json::parse(&socket.receive().map_err(failed)?).unwrap_or(JsonValue::Null);
+            if message["id"].as_u64() == Some(1) {
+                if message["error"].is_object() {
+                    return Err(WebdriverError::custom(format!("Fetch.enable failed: {}", message["error"]["message"])));
+                }
+                break;
+            }
+        }
+        // the pages can stay idle for a long time
+        socket.set_read_timeout(None).map_err(failed)?;
+        let stream = socket.try_clone_stream().map_err(failed)?;
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        let target_id = target_id.to_string();
+        let thread = thread::spawn(move || {
+            let mut last_id = 1;
+            // until the interception is dropped or the tab is closed
+            while let Ok(text) = socket.receive() {
+                let message = json::parse(&text).unwrap_or(JsonValue::Null);
+                if message["method"].as_str() != Some("Fetch.requestPaused") {
+                    continue;
+                }
+                let paused = &message["params"];
+                let (method, params, interception) = interceptor.fetch_reply(paused);
+                if let Some(interception) = interception {
+                    recorded.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(InterceptedRequest {
+                        url: paused["request"]["url"].to_string(),
+                        method: paused["request"]["method"].to_string(),
+                        interception
+                    });
+                }
+                last_id += 1;
+                if let Err(error) = socket.send(&object!{"id" => last_id, "method" => method, "params" => params}.dump()) {
+                    warn!("failed to resume an intercepted request: {}", error);
+                    break;
+                }
+            }
+            debug!("request interception of target {} stopped", target_id);
+        });
+
+        Ok(FetchInterception {
+            requests,
+            stream,
+            thread: Some(thread)
+        })
+    }
+
+    /// -> return the requests to which a rule was applied
+    pub(crate) fn requests(&self) -> Vec<InterceptedRequest> {
+        self.requests.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+}
+
+impl Drop for FetchInterception {
+    /// close the connection, so that the browser stops pausing the requests, and wait for the thread
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Pings of idle sessions, so that the driver or the grid does not expire them
+//!
+//! A ping waits for its response at most for the keep-alive interval (or the request timeout if it is shorter),
+//! so that ending the session, which waits for the thread, is never blocked by a hung server.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use log::{debug, warn};
+use crate::error::ErrorKind;
+use crate::http_requests::{Connection, ping};
+
+/// A background thread sending a request when the session is idle, so that the driver does not expire it
+pub(crate) struct KeepAlive {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>
+}
+
+impl KeepAlive {
+    /// -> take the connection of a session and the idle time after which it is pinged
+    pub(crate) fn start(connection: Arc<Connection>, session_id: Arc<String>, interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let thread = thread::spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                let idle_time = connection.idle_time();
+                if idle_time < interval {
+                    thread::sleep((interval - idle_time).min(Duration::from_millis(50)));
+                    continue;
+                }
+                match ping(&connection, &session_id, interval) {
+                    Ok(()) => (),
+                    Err(error) if error == ErrorKind::InvalidSessionId => {
+                        warn!("session {} expired, keep-alive stopped", session_id);
+                        return;
+                    },
+                    Err(error) => warn!("failed to keep session {} alive: {}", session_id, error),
+                }
+            }
+            debug!("keep-alive of session {} stopped", session_id);
+        });
+
+        KeepAlive {
+            stop,
+            thread: Some(thread)
+        }
+    }
+}
+
+impl Drop for KeepAlive {
+    /// wait for the ping being sent (bounded by its timeout), so that no request is sent once the session is ended
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -1,7 +1,13 @@
-//! This crate allows you to control a web browser (Firefox or chrome) easily. 
+//! This crate allows you to control a web browser (Firefox, Chrome, Edge, Safari or another Chromium-based browser) easily. 
 //! It does not use selenium, which is much more lightweight.
-//! It only uses geckodriver or chromedriver (you have to download the one you want to use depending on your browser and place it in your program's directory).
+//! It only uses geckodriver, chromedriver or msedgedriver (you have to download the one you want to use depending on your browser and place it in your program's directory).
+//! With the `driver-manager` feature, a driver matching your browser is downloaded automatically if none is found in your program's directory.
+//! With the `ocr` feature, text can be located in screenshots with [Tesseract](https://github.com/tesseract-ocr/tesseract), for interfaces the DOM cannot reach.
+//! With the `derive` feature, page objects can be declared with `#[derive(PageObject)]` and browser tests with `#[webdriver_test]`.
+//! With the `toml` feature, sessions can be configured with a TOML file (`SessionConfig::from_file()`).
+//! With the `tracing` feature, every command is traced in a [tracing](https://docs.rs/tracing) span with its name, session id, duration and outcome.
 //! This crate can launch the driver and kill his process after, but if one is already running, it will be used.  
+//! Launched drivers listen on a free port, so several programs can run their sessions at the same time.
 //! A lot of improvements can be done. Feel free to contribute.
 //! 
 //! # Example
@@ -32,9 +38,57 @@
 //! ```
 
 pub mod session;
+pub mod capabilities;
+pub mod config;
+pub mod status;
 pub mod enums;
 pub mod tab;
 pub mod elements;
 pub mod timeouts;
 pub mod error;
-mod http_requests;
\ No newline at end of file
+pub mod pool;
+pub mod security;
+pub mod certificate;
+pub mod notifications;
+pub mod performance;
+pub mod transport;
+pub mod retry;
+pub mod command;
+pub mod compatibility;
+pub mod cdp;
+pub mod locator;
+pub mod interception;
+pub mod console;
+pub mod pipeline;
+pub mod transcript;
+pub mod replay;
+pub mod testing;
+pub mod recovery;
+pub mod har;
+pub mod downloads;
+pub mod print;
+pub mod permissions;
+pub mod wait;
+pub mod select;
+pub mod form;
+pub mod actions;
+pub mod recorder;
+pub mod hooks;
+#[cfg(feature = "driver-manager")]
+pub mod driver_manager;
+#[cfg(feature = "ocr")]
+pub mod ocr;
+#[cfg(feature = "image")]
+pub mod screenshot;
+#[cfg(feature = "derive")]
+pub mod page;
+mod http_requests;
+mod responses;
+mod dry_run;
+mod http_client;
+mod queue;
+mod keep_alive;
+mod driver_log;
+mod bidi;
+mod websocket;
+mod archive;
@@ -0,0 +1,306 @@
+//! Strategies locating elements
+//!
+//! Webdriver only locates elements with css selectors, xpath expressions, tag names and link texts.
+//! Other strategies are implemented with scripts, by implementing the [Locator](trait.Locator.html) trait.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::{Browser, Selector}};
+//! use lw_webdriver::locator::{Locator, Location, TestId, Role};
+//!
+//! /// Locate an input by the text of its label.
+//! struct Label(&'static str);
+//!
+//! impl Locator for Label {
+//!     fn locate(&self) -> Location {
+//!         Location::Script {
+//!             script: "return Array.from(document.querySelectorAll('label'))
+//!                 .filter(label => label.textContent.trim() === arguments[0])
+//!                 .map(label => label.control)[0] || null;".to_string(),
+//!             args: vec![self.0.into()]
+//!         }
+//!     }
+//! }
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.tabs[0].navigate("https://www.mozilla.org/en-US/").unwrap();
+//!
+//! let email = session.tabs[0].find_by(Label("Your email address")).unwrap();
+//! let download = session.tabs[0].find_by(Role::new("link").name("Download Firefox")).unwrap();
+//! let logo = session.tabs[0].find_by(TestId("logo")).unwrap();
+//! let footer = session.tabs[0].find_by((Selector::Css, "footer")).unwrap();
+//! ```
+//!
+//! Pages without stable ids or classes can be navigated with [relative locators](struct.Relative.html):
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::{Browser, Selector}, locator::Relative};
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.tabs[0].navigate("https://example.com/login").unwrap();
+//!
+//! let label = session.tabs[0].find(Selector::LinkText, "Forgot your password?").unwrap().unwrap();
+//! let button = session.tabs[0].find_by(Relative::new(Selector::TagName, "button").above(&label)).unwrap().unwrap();
+//! ```
+
+use json::{JsonValue, object};
+use crate::enums::Selector;
+use crate::elements::Element;
+
+/// How an element is located.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum Location {
+    /// A strategy of the webdriver protocol.
+    Selector { selector: Selector, value: String },
+    /// A script returning the element, or null if there is none.
+    Script { script: String, args: Vec<JsonValue> }
+}
+
+/// A strategy locating elements, accepted by [Tab::find_by()](../tab/struct.Tab.html#method.find_by),
+/// [Tab::wait_for_element_by()](../tab/struct.Tab.html#method.wait_for_element_by) and [Element::find_by()](../elements/struct.Element.html#method.find_by).
+///
+/// Scripts run by [Element::find_by()](../elements/struct.Element.html#method.find_by) receive the parent element after their own arguments.
+pub trait Locator {
+    fn locate(&self) -> Location;
+}
+
+impl Locator for (Selector, &str) {
+    fn locate(&self) -> Location {
+        Location::Selector {
+            selector: self.0,
+            value: self.1.to_string()
+        }
+    }
+}
+
+impl Locator for Location {
+    fn locate(&self) -> Location {
+        self.clone()
+    }
+}
+
+/// Locate an element by its `data-testid` attribute.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct TestId<'a>(pub &'a str);
+
+impl<'a> Locator for TestId<'a> {
+    fn locate(&self) -> Location {
+        Location::Selector {
+            selector: Selector::Css,
+            value: format!("[data-testid=\"{}\"]", self.0.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+    }
+}
+
+/// Locate an element by its [ARIA role](https://www.w3.org/TR/wai-aria/#role_definitions), explicit or implied by its tag,
+/// and optionally by its accessible name (`aria-label`, label, `alt`, `title` or text).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Role {
+    role: String,
+    name: Option<String>
+}
+
+impl Role {
+    pub fn new(role: &str) -> Self {
+        Role {
+            role: role.to_string(),
+            name: None
+        }
+    }
+
+    /// Only match elements with this accessible name.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+}
+
+const ROLE_SCRIPT: &str = r#"
+const [role, name, root = document] = arguments;
+const implicit = {
+    button: "button, input[type=button], input[type=submit], input[type=reset]",
+    link: "a[href], area[href]",
+    heading: "h1, h2, h3, h4, h5, h6",
+    textbox: "input:not([type]), input[type=text], input[type=email], input[type=tel], input[type=url], textarea",
+    searchbox: "input[type=search]",
+    checkbox: "input[type=checkbox]",
+    radio: "input[type=radio]",
+    combobox: "select",
+    list: "ul, ol",
+    listitem: "li",
+    img: "img[alt]",
+    navigation: "nav",
+    main: "main",
+    form: "form"
+};
+const selector = `[role="${CSS.escape(role)}"]` + (implicit[role] ? ", " + implicit[role] : "");
+const accessibleName = element => (element.getAttribute("aria-label")
+    || (element.labels && element.labels.length > 0 ? element.labels[0].textContent : "")
+    || element.getAttribute("alt")
+    || element.getAttribute("title")
+    || element.textContent
+    || element.value
+    || "").trim();
+return Array.from(root.querySelectorAll(selector))
+    .filter(element => !element.hasAttribute("role") || element.getAttribute("role") === role)
+    .find(element => name === null || accessibleName(element) === name) || null;
+"#;
+
+impl Locator for Role {
+    fn locate(&self) -> Location {
+        Location::Script {
+            script: ROLE_SCRIPT.to_string(),
+            args: vec![self.role.as_str().into(), self.name.as_deref().map(JsonValue::from).unwrap_or(JsonValue::Null)]
+        }
+    }
+}
+
+/// The distance used by [Relative::near()](struct.Relative.html#method.near), in CSS pixels.
+pub const DEFAULT_NEAR_DISTANCE: f64 = 50.0;
+
+/// Locate an element by its position on the screen relative to other elements, like the relative locators of Selenium.
+/// The elements matching a selector are filtered by their position, and the closest one to the first anchor element is found.
+/// Elements which are not rendered are ignored.
+///
+/// # Example
+///
+/// ```rust
+/// use lw_webdriver::{session::Session, enums::{Browser, Selector}, locator::Relative};
+///
+/// let mut session = Session::new(Browser::Firefox, false).unwrap();
+/// session.tabs[0].navigate("https://example.com/").unwrap();
+///
+/// let label = session.tabs[0].find(Selector::XPath, "//label[text()='Email']").unwrap().unwrap();
+/// let mut email = session.tabs[0].find_by(Relative::new(Selector::TagName, "input").below(&label)).unwrap().unwrap();
+/// email.type_text("user@example.com").unwrap();
+///
+/// let password = Relative::new(Selector::TagName, "input").below(&email).near(&email);
+/// ```
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Relative {
+    selector: Selector,
+    value: String,
+    /// the positions the element must have, relative to an anchor element
+    conditions: Vec<JsonValue>
+}
+
+impl Relative {
+    /// Locate an element matching a selector, to be filtered by its position.
+    pub fn new(selector: Selector, value: &str) -> Self {
+        Relative {
+            selector,
+            value: value.to_string(),
+            conditions: Vec::new()
+        }
+    }
+
+    /// -> take a direction and an anchor element
+    fn condition(mut self, direction: &str, element: &Element, distance: f64) -> Self {
+        self.conditions.push(object!{
+            "direction" => direction,
+            "element" => element.as_json_object(),
+            "distance" => distance
+        });
+        self
+    }
+
+    /// The element is entirely above the anchor element.
+    pub fn above(self, element: &Element) -> Self {
+        self.condition("above", element, 0.0)
+    }
+
+    /// The element is entirely below the anchor element.
+    pub fn below(self, element: &Element) -> Self {
+        self.condition("below", element, 0.0)
+    }
+
+    /// The element is entirely on the left of the anchor element.
+    pub fn left_of(self, element: &Element) -> Self {
+        self.condition("left", element, 0.0)
+    }
+
+    /// The element is entirely on the right of the anchor element.
+    pub fn right_of(self, element: &Element) -> Self {
+        self.condition("right", element, 0.0)
+    }
+
+    /// The element is at most [DEFAULT_NEAR_DISTANCE](constant.DEFAULT_NEAR_DISTANCE.html) pixels away from the anchor element.
+    pub fn near(self, element: &Element) -> Self {
+        self.condition("near", element, DEFAULT_NEAR_DISTANCE)
+    }
+
+    /// The element is at most `distance` pixels away from the anchor element (between their closest edges).
+    pub fn near_within(self, element: &Element, distance: f64) -> Self {
+        self.condition("near", element, distance)
+    }
+}
+
+const RELATIVE_SCRIPT: &str = r#"
+const [strategy, value, conditions, root = document] = arguments;
+const candidates = (() => {
+    switch (strategy) {
+        case "css selector": return Array.from(root.querySelectorAll(value));
+        case "tag name": return Array.from(root.getElementsByTagName(value));
+        case "link text": return Array.from(root.querySelectorAll("a")).filter(link => link.innerText.trim() === value);
+        case "partial link text": return Array.from(root.querySelectorAll("a")).filter(link => link.innerText.includes(value));
+        case "xpath": {
+            const result = document.evaluate(value, root, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null);
+            return Array.from({ length: result.snapshotLength }, (_, index) => result.snapshotItem(index))
+                .filter(node => node.nodeType === Node.ELEMENT_NODE);
+        }
+    }
+})();
+const matches = (candidate, condition) => {
+    if (candidate === condition.element) {
+        return false;
+    }
+    const rect = candidate.getBoundingClientRect();
+    const anchor = condition.element.getBoundingClientRect();
+    switch (condition.direction) {
+        case "above": return rect.bottom <= anchor.top;
+        case "below": return rect.top >= anchor.bottom;
+        case "left": return rect.right <= anchor.left;
+        case "right": return rect.left >= anchor.right;
+        case "near": {
+            const dx = Math.max(anchor.left - rect.right, rect.left - anchor.right, 0);
+            const dy = Math.max(anchor.top - rect.bottom, rect.top - anchor.bottom, 0);
+            return Math.hypot(dx, dy) <= condition.distance;
+        }
+    }
+};
+const center = element => {
+    const rect = element.getBoundingClientRect();
+    return [rect.left + rect.width / 2, rect.top + rect.height / 2];
+};
+const distance = element => {
+    const [x, y] = center(element);
+    const [anchorX, anchorY] = center(conditions[0].element);
+    return Math.hypot(x - anchorX, y - anchorY);
+};
+const found = candidates
+    .filter(candidate => candidate.getClientRects().length > 0)
+    .filter(candidate => conditions.every(condition => matches(candidate, condition)));
+if (conditions.length > 0) {
+    found.sort((a, b) => distance(a) - distance(b));
+}
+return found[0] || null;
+"#;
+
+impl Locator for Relative {
+    fn locate(&self) -> Location {
+        let (strategy, value) = self.selector.to_strategy(&self.value);
+        Location::Script {
+            script: RELATIVE_SCRIPT.to_string(),
+            args: vec![strategy.into(), value.into(), self.conditions.clone().into()]
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Capture of the notifications displayed by web pages
+
+use json::JsonValue;
+
+/// Replaces the `Notification` constructor and `ServiceWorkerRegistration.showNotification()` by functions recording
+/// every notification in `window.__lwNotifications` before displaying it. Installing it twice has no effect.
+pub(crate) const NOTIFICATION_SHIM_SCRIPT: &str = "
+    (() => {
+        if (window.__lwNotifications) {
+            return;
+        }
+        window.__lwNotifications = [];
+        const record = (title, options) => window.__lwNotifications.push({
+            title: String(title),
+            body: (options && options.body) || '',
+            tag: (options && options.tag) || ''
+        });
+
+        if (window.Notification) {
+            const Original = window.Notification;
+            const Shim = function(title, options) {
+                record(title, options);
+                return new Original(title, options);
+            };
+            Shim.prototype = Original.prototype;
+            Object.defineProperty(Shim, 'permission', { get: () => Original.permission });
+            Shim.requestPermission = Original.requestPermission.bind(Original);
+            window.Notification = Shim;
+        }
+        if (window.ServiceWorkerRegistration) {
+            const showNotification = ServiceWorkerRegistration.prototype.showNotification;
+            ServiceWorkerRegistration.prototype.showNotification = function(title, options) {
+                record(title, options);
+                return showNotification.call(this, title, options);
+            };
+        }
+    })();
+";
+
+/// A notification created by a page, captured with [Tab::capture_notifications()](../tab/struct.Tab.html#method.capture_notifications).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Notification {
+    pub title: String,
+    /// Empty if the notification has no body.
+    pub body: String,
+    /// Empty if the notification has no tag.
+    pub tag: String
+}
+
+impl Notification {
+    pub(crate) fn from_json(json: &JsonValue) -> Option<Self> {
+        Some(Notification {
+            title: json["title"].as_str()?.to_string(),
+            body: json["body"].as_str()?.to_string(),
+            tag: json["tag"].as_str()?.to_string()
+        })
+    }
+}
@@ -0,0 +1,169 @@
+//! Find text in screenshots (requires the `ocr` feature)
+//!
+//! Text is recognized by [Tesseract](https://github.com/tesseract-ocr/tesseract), which must be installed and in the `PATH`.
+//! This is a last resort for interfaces which cannot be reached through the DOM, like pages rendered in a canvas or
+//! frames from another origin. It is much slower and less reliable than [Tab::find()](../tab/struct.Tab.html#method.find).
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::Browser};
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.tabs[0].navigate("https://example.com/").unwrap();
+//!
+//! let location = session.tabs[0].find_text_visually("More information").unwrap().unwrap();
+//! let (x, y) = location.center();
+//! session.tabs[0].execute_script("document.elementFromPoint(arguments[0], arguments[1]).click();", vec![x.into(), y.into()]).unwrap();
+//! ```
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use log::{debug, error};
+use crate::tab::Tab;
+use crate::error::{WebdriverError, ErrorKind};
+
+/// Text found in the viewport by [Tab::find_text_visually()](../tab/struct.Tab.html#method.find_text_visually).
+/// Coordinates are in css pixels, relative to the top left corner of the viewport.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct TextLocation {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Mean confidence of the recognition of the words, from 0 to 100.
+    pub confidence: f64
+}
+
+impl TextLocation {
+    /// Return the point to click to reach the text.
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+}
+
+struct Word {
+    line: (u32, u32, u32, u32),
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+    confidence: f64,
+    text: String
+}
+
+/// Lowercase a word without the punctuation around it.
+fn normalize(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase()
+}
+
+/// -> take the words recognized by tesseract (tsv output) and the text to find
+/// -> return the location of the consecutive words of a line matching the text with the best confidence, in pixels of the image
+fn find_in_tsv(tsv: &str, text: &str) -> Option<TextLocation> {
+    let expected: Vec<String> = text.split_whitespace().map(normalize).filter(|word| !word.is_empty()).collect();
+    if expected.is_empty() {
+        return None;
+    }
+
+    // level, page, block, paragraph, line, word, left, top, width, height, confidence, text
+    let words: Vec<Word> = tsv.lines().skip(1).filter_map(|line| {
+        let columns: Vec<&str> = line.split('\t').collect();
+        if columns.len() < 12 || columns[0] != "5" {
+            return None;
+        }
+        let number = |index: usize| columns[index].parse::<f64>().ok();
+        let integer = |index: usize| columns[index].parse::<u32>().ok();
+        Some(Word {
+            line: (integer(1)?, integer(2)?, integer(3)?, integer(4)?),
+            left: number(6)?,
+            top: number(7)?,
+            width: number(8)?,
+            height: number(9)?,
+            confidence: number(10)?,
+            text: normalize(columns[11])
+        })
+    }).filter(|word| !word.text.is_empty()).collect();
+
+    let mut best: Option<TextLocation> = None;
+    for start in 0..words.len() {
+        let candidate = match words.get(start..start + expected.len()) {
+            Some(candidate) => candidate,
+            None => break,
+        };
+        if candidate.iter().any(|word| word.line != candidate[0].line) || candidate.iter().zip(&expected).any(|(word, expected)| word.text != *expected) {
+            continue;
+        }
+
+        let left = candidate.iter().map(|word| word.left).fold(f64::INFINITY, f64::min);
+        let top = candidate.iter().map(|word| word.top).fold(f64::INFINITY, f64::min);
+        let right = candidate.iter().map(|word| word.left + word.width).fold(0.0, f64::max);
+        let bottom = candidate.iter().map(|word| word.top + word.height).fold(0.0, f64::max);
+        let location = TextLocation {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+            confidence: candidate.iter().map(|word| word.confidence).sum::<f64>() / candidate.len() as f64
+        };
+        if best.map(|best| location.confidence > best.confidence).unwrap_or(true) {
+            best = Some(location);
+        }
+    }
+    best
+}
+
+/// -> take a png image
+/// -> return the words recognized by tesseract (tsv output)
+fn recognize(png: &[u8]) -> Result<String, WebdriverError> {
+    let mut child = Command::new("tesseract")
+        .args(["stdin", "stdout", "tsv"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| WebdriverError::custom(format!("failed to launch tesseract: {}", error)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(png).map_err(|error| WebdriverError::custom(format!("failed to send the screenshot to tesseract: {}", error)))?;
+    }
+    let output = child.wait_with_output().map_err(|error| WebdriverError::custom(format!("failed to run tesseract: {}", error)))?;
+    if !output.status.success() {
+        error!("tesseract failed: {}", String::from_utf8_lossy(&output.stderr));
+        return Err(WebdriverError::custom(format!("tesseract exited with {}", output.status)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+impl Tab {
+    /// Take a screenshot of the viewport, recognize the text it contains and return where a text is displayed.
+    /// Return None if the text was not found. The case and the punctuation around words are ignored.
+    pub fn find_text_visually(&self, text: &str) -> Result<Option<TextLocation>, WebdriverError> {
+        let png = self.screenshot()?;
+        let tsv = recognize(&png)?;
+        let location = match find_in_tsv(&tsv, text) {
+            Some(location) => location,
+            None => {
+                debug!("text {:?} was not recognized in the screenshot", text);
+                return Ok(None);
+            }
+        };
+
+        // screenshots are in device pixels
+        let ratio = match self.execute_script("return window.devicePixelRatio;", Vec::new())?.as_f64() {
+            Some(ratio) if ratio > 0.0 => ratio,
+            _ => {
+                error!("device pixel ratio was not understood");
+                return Err(ErrorKind::InvalidResponse.into());
+            }
+        };
+        Ok(Some(TextLocation {
+            x: location.x / ratio,
+            y: location.y / ratio,
+            width: location.width / ratio,
+            height: location.height / ratio,
+            confidence: location.confidence
+        }))
+    }
+}
@@ -0,0 +1,98 @@
+//! Page objects, describing the elements of a page once (requires the `derive` feature)
+//!
+//! A page object is a struct whose fields are the elements of a page, located when they are used.
+//! Tests use the fields instead of repeating selectors, so that a change of the page is fixed in one place.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::Browser};
+//! use lw_webdriver::page::{PageObject, LazyElement};
+//!
+//! #[derive(PageObject)]
+//! struct Header {
+//!     #[selector(css = "header nav")]
+//!     navigation: LazyElement,
+//! }
+//!
+//! #[derive(PageObject)]
+//! struct LoginPage {
+//!     #[selector(css = "#username")]
+//!     username: LazyElement,
+//!     #[selector(xpath = "//button[@type='submit']")]
+//!     submit: LazyElement,
+//!     header: Header,
+//! }
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.tabs[0].navigate("https://example.com/login").unwrap();
+//!
+//! let page = LoginPage::new(&session.tabs[0]);
+//! page.username.get().unwrap().type_text("user").unwrap();
+//! page.submit.get().unwrap().click().unwrap();
+//! assert!(page.header.navigation.exists().unwrap());
+//! ```
+
+use std::sync::Arc;
+use crate::tab::{Tab, WindowHandle};
+use crate::elements::Element;
+use crate::enums::Selector;
+use crate::locator::Location;
+use crate::error::{WebdriverError, ErrorKind};
+use crate::http_requests::{Connection, select_tab, find_element};
+
+pub use lw_webdriver_derive::PageObject;
+
+/// A page, or a part of a page, built from a tab.
+/// Implement it with `#[derive(PageObject)]`.
+pub trait PageObject {
+    fn new(tab: &Tab) -> Self;
+}
+
+/// An element of a tab, located every time it is used.
+/// The element can be missing when the page object is built, and a page reloaded since does not make it stale.
+#[derive(Clone)]
+pub struct LazyElement {
+    session_id: Arc<String>,
+    tab_id: Arc<WindowHandle>,
+    connection: Arc<Connection>,
+    selector: Selector,
+    value: String
+}
+
+impl LazyElement {
+    pub fn new(tab: &Tab, selector: Selector, value: &str) -> Self {
+        LazyElement {
+            session_id: Arc::clone(&tab.session_id),
+            tab_id: Arc::clone(&tab.id),
+            connection: Arc::clone(&tab.connection),
+            selector,
+            value: value.to_string()
+        }
+    }
+
+    /// Locate the element in its tab. Return None if there is no such element.
+    pub fn find(&self) -> Result<Option<Element>, WebdriverError> {
+        select_tab(&self.connection, &self.session_id, self.tab_id.as_str())?;
+        match find_element(&self.connection, &self.session_id, self.selector, &self.value) {
+            Ok(id) => {
+                let location = Location::Selector { selector: self.selector, value: self.value.clone() };
+                Ok(Some(Element::with_connection(id, Arc::clone(&self.session_id), Arc::clone(&self.tab_id), Arc::clone(&self.connection)).located(location)))
+            },
+            Err(error) if error == ErrorKind::NoSuchElement => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Locate the element in its tab.
+    /// Return [NoSuchElement](../error/enum.ErrorKind.html#variant.NoSuchElement) if there is no such element.
+    pub fn get(&self) -> Result<Element, WebdriverError> {
+        self.find()?.ok_or_else(|| WebdriverError::from(ErrorKind::NoSuchElement)
+            .with_message(format!("no element matches {:?} {:?}", self.selector, self.value)))
+    }
+
+    /// Return true if the element is in the page.
+    pub fn exists(&self) -> Result<bool, WebdriverError> {
+        Ok(self.find()?.is_some())
+    }
+}
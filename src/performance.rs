@@ -0,0 +1,113 @@
+//! Performance measurements of the loaded page
+
+use std::time::Duration;
+use json::JsonValue;
+
+/// A resource loaded by a page, read from the [Resource Timing API](https://developer.mozilla.org/en-US/docs/Web/API/PerformanceResourceTiming).
+/// Every time is relative to the start of the navigation.
+///
+/// Browsers hide the detailed timings and sizes of cross-origin resources unless their server sends a `Timing-Allow-Origin` header.
+/// The hidden values are zero.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct ResourceTiming {
+    /// The url of the resource.
+    pub name: String,
+    /// What requested the resource (`img`, `script`, `link`, `fetch`, `xmlhttprequest`...).
+    pub initiator: String,
+    /// Bytes received, headers included (zero if the resource was served from the cache).
+    pub transfer_size: u64,
+    /// Size of the body before decompression.
+    pub encoded_body_size: u64,
+    /// Size of the body after decompression.
+    pub decoded_body_size: u64,
+    pub start_time: Duration,
+    /// Total time spent loading the resource.
+    pub duration: Duration,
+    /// Time spent resolving the domain name.
+    pub dns: Duration,
+    /// Time spent opening the connection, TLS handshake included.
+    pub connection: Duration,
+    /// Time between the request and the first byte of the response.
+    pub waiting: Duration,
+    /// Time spent receiving the response.
+    pub download: Duration
+}
+
+impl ResourceTiming {
+    pub(crate) fn from_json(json: &JsonValue) -> Option<Self> {
+        let time = |name: &str| json[name].as_f64();
+        let elapsed = |start: &str, end: &str| -> Option<Duration> {
+            Some(milliseconds((time(end)? - time(start)?).max(0.0)))
+        };
+
+        Some(ResourceTiming {
+            name: json["name"].as_str()?.to_string(),
+            initiator: json["initiatorType"].as_str()?.to_string(),
+            transfer_size: json["transferSize"].as_u64().unwrap_or(0),
+            encoded_body_size: json["encodedBodySize"].as_u64().unwrap_or(0),
+            decoded_body_size: json["decodedBodySize"].as_u64().unwrap_or(0),
+            start_time: milliseconds(time("startTime")?),
+            duration: milliseconds(time("duration")?),
+            dns: elapsed("domainLookupStart", "domainLookupEnd")?,
+            connection: elapsed("connectStart", "connectEnd")?,
+            waiting: elapsed("requestStart", "responseStart")?,
+            download: elapsed("responseStart", "responseEnd")?
+        })
+    }
+}
+
+/// The load times of the page, read from the [Navigation Timing](https://developer.mozilla.org/en-US/docs/Web/API/PerformanceNavigationTiming)
+/// and [Paint Timing](https://developer.mozilla.org/en-US/docs/Web/API/PerformancePaintTiming) APIs.
+/// Every time is relative to the start of the navigation.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct PerformanceMetrics {
+    /// Time to first byte: when the first byte of the document was received.
+    pub time_to_first_byte: Duration,
+    /// When the `DOMContentLoaded` event was handled (None if the document is still being parsed).
+    pub dom_content_loaded: Option<Duration>,
+    /// When the `load` event was handled (None if the page is still loading).
+    pub load: Option<Duration>,
+    /// First paint (None if the page was not painted yet or if the browser does not report it).
+    pub first_paint: Option<Duration>,
+    /// First contentful paint, when text or an image was first painted (None if not painted yet).
+    pub first_contentful_paint: Option<Duration>
+}
+
+/// Returns the navigation entry and the paint timings, or null if there is no navigation entry
+pub(crate) const PERFORMANCE_METRICS_SCRIPT: &str = "
+    const navigation = performance.getEntriesByType('navigation')[0];
+    const paint = name => {
+        const entry = performance.getEntriesByName(name, 'paint')[0];
+        return entry ? entry.startTime : null;
+    };
+    return navigation ? {
+        navigation: navigation.toJSON(),
+        firstPaint: paint('first-paint'),
+        firstContentfulPaint: paint('first-contentful-paint')
+    } : null;
+";
+
+impl PerformanceMetrics {
+    pub(crate) fn from_json(json: &JsonValue) -> Option<Self> {
+        let navigation = &json["navigation"];
+        // events which did not happen yet are at zero
+        let event = |name: &str| navigation[name].as_f64().filter(|time| *time > 0.0).map(milliseconds);
+        let paint = |name: &str| json[name].as_f64().map(milliseconds);
+
+        Some(PerformanceMetrics {
+            time_to_first_byte: milliseconds(navigation["responseStart"].as_f64()? - navigation["startTime"].as_f64()?),
+            dom_content_loaded: event("domContentLoadedEventEnd"),
+            load: event("loadEventEnd"),
+            first_paint: paint("firstPaint"),
+            first_contentful_paint: paint("firstContentfulPaint")
+        })
+    }
+}
+
+fn milliseconds(milliseconds: f64) -> Duration {
+    Duration::from_micros((milliseconds.max(0.0) * 1000.0) as u64)
+}
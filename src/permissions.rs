@@ -0,0 +1,70 @@
+//! Control of the permissions granted to web pages
+//!
+//! Permissions are set with the [Permissions extension](https://w3c.github.io/permissions/#automation) of the webdriver protocol,
+//! so that pages never display permission prompts.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::Browser};
+//! use lw_webdriver::permissions::{Permission, PermissionState};
+//!
+//! let mut session = Session::new(Browser::Chrome, false).unwrap();
+//! session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+//!
+//! session.set_permission(Permission::Geolocation, PermissionState::Granted).unwrap();
+//! session.set_permission(Permission::Camera, PermissionState::Denied).unwrap();
+//! ```
+
+use json::{JsonValue, object};
+
+/// A [powerful feature](https://w3c.github.io/permissions/#powerful-features-registry) requiring a permission.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum Permission {
+    Notifications,
+    Geolocation,
+    ClipboardRead,
+    ClipboardWrite,
+    Camera,
+    Microphone,
+    /// Any [permission descriptor](https://w3c.github.io/permissions/#dom-permissiondescriptor), like `{"name": "midi", "sysex": true}`.
+    Descriptor(JsonValue)
+}
+
+impl Permission {
+    pub fn to_json(&self) -> JsonValue {
+        let name = match self {
+            Permission::Notifications => "notifications",
+            Permission::Geolocation => "geolocation",
+            Permission::ClipboardRead => "clipboard-read",
+            Permission::ClipboardWrite => "clipboard-write",
+            Permission::Camera => "camera",
+            Permission::Microphone => "microphone",
+            Permission::Descriptor(descriptor) => return descriptor.clone(),
+        };
+        object!{"name" => name}
+    }
+}
+
+/// The state of a permission.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum PermissionState {
+    Granted,
+    Denied,
+    /// The page has to ask the user.
+    Prompt
+}
+
+impl PermissionState {
+    pub fn to_string(self) -> &'static str {
+        match self {
+            PermissionState::Granted => "granted",
+            PermissionState::Denied => "denied",
+            PermissionState::Prompt => "prompt"
+        }
+    }
+}
@@ -0,0 +1,355 @@
+//! Declarative extraction of records from web pages
+//!
+//! A [Pipeline](struct.Pipeline.html) selects the items of a page, extracts a value for each field of an item
+//! and follows the link to the next page. Items are extracted in a single request per page, then
+//! post-processed, converted to their types and deserialized into your own structs.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::Browser};
+//! use lw_webdriver::pipeline::{Pipeline, Field};
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize, Debug)]
+//! struct Story {
+//!     title: String,
+//!     link: String,
+//!     points: Option<i64>
+//! }
+//!
+//! let pipeline = Pipeline::new("tr.athing")
+//!     .field(Field::text("title", ".titleline > a"))
+//!     .field(Field::attribute("link", ".titleline > a", "href"))
+//!     .field(Field::text("points", "+ tr .score").integer())
+//!     .next_page("a.morelink")
+//!     .max_pages(3);
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.tabs[0].navigate("https://news.ycombinator.com/").unwrap();
+//! let stories: Vec<Story> = pipeline.run(&mut session.tabs[0]).unwrap();
+//! ```
+
+use json::{JsonValue, object};
+use log::{debug, error};
+use serde::de::DeserializeOwned;
+use crate::tab::Tab;
+use crate::error::{WebdriverError, ErrorKind};
+
+/// Selects the items and extracts the raw value of every field.
+/// Relative selectors starting with a combinator (`+ tr .score`) are resolved from the item with `:scope`.
+const EXTRACTION_SCRIPT: &str = "
+    const [itemSelector, fields, nextSelector] = arguments;
+    const select = (item, selector, multiple) => {
+        if (selector === null) {
+            return [item];
+        }
+        let matches;
+        if (/^\\s*[+~]/.test(selector) && item.parentElement) {
+            const index = Array.from(item.parentElement.children).indexOf(item) + 1;
+            matches = item.parentElement.querySelectorAll(':scope > :nth-child(' + index + ') ' + selector);
+        } else {
+            matches = item.querySelectorAll(/^\\s*>/.test(selector) ? ':scope ' + selector : selector);
+        }
+        return multiple ? Array.from(matches) : Array.from(matches).slice(0, 1);
+    };
+    const read = (element, source) => {
+        switch (source.type) {
+            case 'text': return element.innerText !== undefined ? element.innerText : element.textContent;
+            case 'html': return element.innerHTML;
+            case 'attribute': return element.getAttribute(source.name);
+            case 'property': {
+                const value = element[source.name];
+                return value === undefined || value === null ? null : String(value);
+            }
+        }
+    };
+    const records = Array.from(document.querySelectorAll(itemSelector)).map(item => {
+        const record = {};
+        for (const field of fields) {
+            const values = select(item, field.selector, field.multiple).map(element => read(element, field.source)).filter(value => value !== null);
+            record[field.name] = field.multiple ? values : (values.length > 0 ? values[0] : null);
+        }
+        return record;
+    });
+    const next = nextSelector === null ? null : document.querySelector(nextSelector);
+    return { records, next: next && next.href ? next.href : null };
+";
+
+/// Where the raw value of a field is read.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum Source {
+    /// The rendered text of the element.
+    Text,
+    /// The inner html of the element.
+    Html,
+    /// An attribute, as written in the document (`href` may be relative).
+    Attribute(String),
+    /// A property of the element, converted to a string (`href` is absolute).
+    Property(String)
+}
+
+/// The type a field is converted to. Values which cannot be converted become null.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum FieldType {
+    String,
+    /// The first number of the value, ignoring thousands separators (`"1,234 points"` is 1234).
+    Integer,
+    /// The first decimal number of the value (`"$12.50"` is 12.5).
+    Float,
+    /// `true`, `yes`, `on` and `1` are true, other values are false.
+    Boolean
+}
+
+/// A transformation of the raw value of a field, applied before converting its type.
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum PostProcessor {
+    /// Remove the whitespaces around the value and collapse the others.
+    Trim,
+    Lowercase,
+    Uppercase,
+    Replace { from: String, to: String },
+    /// Keep the part of the value after a separator (the whole value if the separator is absent).
+    After(String),
+    /// Keep the part of the value before a separator (the whole value if the separator is absent).
+    Before(String),
+    Custom(fn(&str) -> String)
+}
+
+impl PostProcessor {
+    fn apply(&self, value: &str) -> String {
+        match self {
+            PostProcessor::Trim => value.split_whitespace().collect::<Vec<&str>>().join(" "),
+            PostProcessor::Lowercase => value.to_lowercase(),
+            PostProcessor::Uppercase => value.to_uppercase(),
+            PostProcessor::Replace { from, to } => value.replace(from.as_str(), to),
+            PostProcessor::After(separator) => match value.find(separator.as_str()) {
+                Some(index) => value[index + separator.len()..].to_string(),
+                None => value.to_string(),
+            },
+            PostProcessor::Before(separator) => match value.find(separator.as_str()) {
+                Some(index) => value[..index].to_string(),
+                None => value.to_string(),
+            },
+            PostProcessor::Custom(function) => function(value),
+        }
+    }
+}
+
+/// A field of the extracted records.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Field {
+    name: String,
+    selector: Option<String>,
+    source: Source,
+    field_type: FieldType,
+    multiple: bool,
+    post_processors: Vec<PostProcessor>
+}
+
+impl Field {
+    /// A field read in the first element matching a css selector, relative to the item
+    /// (None to read the item itself). The text is trimmed by default.
+    pub fn new(name: &str, selector: Option<&str>, source: Source) -> Self {
+        Field {
+            name: name.to_string(),
+            selector: selector.map(|selector| selector.to_string()),
+            source,
+            field_type: FieldType::String,
+            multiple: false,
+            post_processors: vec![PostProcessor::Trim]
+        }
+    }
+
+    /// The text of an element.
+    pub fn text(name: &str, selector: &str) -> Self {
+        Field::new(name, Some(selector), Source::Text)
+    }
+
+    /// An attribute of an element.
+    pub fn attribute(name: &str, selector: &str, attribute: &str) -> Self {
+        Field::new(name, Some(selector), Source::Attribute(attribute.to_string()))
+    }
+
+    pub fn field_type(mut self, field_type: FieldType) -> Self {
+        self.field_type = field_type;
+        self
+    }
+
+    /// Shortcut for `field_type(FieldType::Integer)`.
+    pub fn integer(self) -> Self {
+        self.field_type(FieldType::Integer)
+    }
+
+    /// Shortcut for `field_type(FieldType::Float)`.
+    pub fn float(self) -> Self {
+        self.field_type(FieldType::Float)
+    }
+
+    /// Read every matching element into an array, instead of the first one.
+    pub fn multiple(mut self, multiple: bool) -> Self {
+        self.multiple = multiple;
+        self
+    }
+
+    /// Add a post-processor, applied after the previous ones.
+    pub fn post_process(mut self, post_processor: PostProcessor) -> Self {
+        self.post_processors.push(post_processor);
+        self
+    }
+
+    /// Remove the post-processors, including the default trimming.
+    pub fn raw(mut self) -> Self {
+        self.post_processors.clear();
+        self
+    }
+
+    fn to_json(&self) -> JsonValue {
+        let source = match &self.source {
+            Source::Text => object!{"type" => "text"},
+            Source::Html => object!{"type" => "html"},
+            Source::Attribute(name) => object!{"type" => "attribute", "name" => name.as_str()},
+            Source::Property(name) => object!{"type" => "property", "name" => name.as_str()},
+        };
+        object!{
+            "name" => self.name.as_str(),
+            "selector" => self.selector.as_deref(),
+            "source" => source,
+            "multiple" => self.multiple
+        }
+    }
+
+    /// -> take a raw value extracted by the script
+    /// -> return the post-processed and converted value
+    fn convert(&self, raw: &str) -> serde_json::Value {
+        let value = self.post_processors.iter().fold(raw.to_string(), |value, post_processor| post_processor.apply(&value));
+        match self.field_type {
+            FieldType::String => value.into(),
+            FieldType::Integer => first_number(&value, false).and_then(|number| number.parse::<i64>().ok()).into(),
+            FieldType::Float => first_number(&value, true).and_then(|number| number.parse::<f64>().ok()).into(),
+            FieldType::Boolean => matches!(value.trim().to_lowercase().as_str(), "true" | "yes" | "on" | "1").into(),
+        }
+    }
+}
+
+/// Return the first number of a text, without thousands separators.
+fn first_number(text: &str, decimal: bool) -> Option<String> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let negative = text[..start].ends_with('-');
+    let mut number = if negative { String::from("-") } else { String::new() };
+    let mut chars = text[start..].chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '0'..='9' => number.push(c),
+            ',' if chars.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) => (),
+            '.' if decimal && !number.contains('.') && chars.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) => number.push(c),
+            _ => break,
+        }
+    }
+    Some(number)
+}
+
+/// Extraction rules producing records from the items of web pages.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Pipeline {
+    item_selector: String,
+    fields: Vec<Field>,
+    next_page: Option<String>,
+    max_pages: usize
+}
+
+impl Pipeline {
+    /// A pipeline producing a record from every element matching a css selector.
+    pub fn new(item_selector: &str) -> Self {
+        Pipeline {
+            item_selector: item_selector.to_string(),
+            fields: Vec::new(),
+            next_page: None,
+            max_pages: 1
+        }
+    }
+
+    pub fn field(mut self, field: Field) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Follow the link matching a css selector after extracting the items of a page, until the link is absent.
+    /// Unless [max_pages()](#method.max_pages) is called, at most 100 pages are loaded.
+    pub fn next_page(mut self, selector: &str) -> Self {
+        self.next_page = Some(selector.to_string());
+        if self.max_pages == 1 {
+            self.max_pages = 100;
+        }
+        self
+    }
+
+    /// The maximal number of pages to extract items from (1 by default, 100 if a next page link is set).
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    /// Extract the records of the loaded page and of the next pages, as json objects.
+    pub fn run_json(&self, tab: &mut Tab) -> Result<Vec<serde_json::Value>, WebdriverError> {
+        let fields: Vec<JsonValue> = self.fields.iter().map(Field::to_json).collect();
+        let mut records = Vec::new();
+
+        for page in 0..self.max_pages {
+            let result = tab.execute_script(EXTRACTION_SCRIPT, vec![
+                self.item_selector.as_str().into(),
+                fields.clone().into(),
+                self.next_page.as_deref().into()
+            ])?;
+            if !result["records"].is_array() {
+                error!("extraction script did not return records: {}", result);
+                return Err(ErrorKind::InvalidResponse.into());
+            }
+
+            for item in result["records"].members() {
+                let mut record = serde_json::Map::new();
+                for field in &self.fields {
+                    let raw = &item[field.name.as_str()];
+                    let value = if field.multiple {
+                        serde_json::Value::Array(raw.members().filter_map(|value| value.as_str()).map(|value| field.convert(value)).collect())
+                    } else {
+                        raw.as_str().map(|value| field.convert(value)).unwrap_or(serde_json::Value::Null)
+                    };
+                    record.insert(field.name.clone(), value);
+                }
+                records.push(serde_json::Value::Object(record));
+            }
+            debug!("{} records extracted from page {}", result["records"].len(), page + 1);
+
+            match result["next"].as_str() {
+                Some(url) if page + 1 < self.max_pages => tab.navigate(url)?,
+                _ => break,
+            }
+        }
+        Ok(records)
+    }
+
+    /// Extract the records of the loaded page and of the next pages, deserialized into `T`.
+    pub fn run<T: DeserializeOwned>(&self, tab: &mut Tab) -> Result<Vec<T>, WebdriverError> {
+        self.run_json(tab)?.into_iter().map(|record| {
+            serde_json::from_value(record.clone()).map_err(|error| WebdriverError::custom(format!("record {} could not be deserialized: {}", record, error)))
+        }).collect()
+    }
+
+    /// Load every url and extract the records of its pages, deserialized into `T`.
+    pub fn run_on<T: DeserializeOwned>(&self, tab: &mut Tab, urls: &[&str]) -> Result<Vec<T>, WebdriverError> {
+        let mut records = Vec::new();
+        for url in urls {
+            tab.navigate(url)?;
+            records.append(&mut self.run(tab)?);
+        }
+        Ok(records)
+    }
+}
@@ -0,0 +1,159 @@
+//! Pools keep sessions open to reuse them
+
+use std::sync::Mutex;
+use std::ops::{Deref, DerefMut};
+use json::object;
+use log::{debug, warn};
+use crate::config::SessionConfig;
+use crate::error::{WebdriverError, ErrorKind};
+use crate::session::Session;
+
+/// A set of warm sessions handed out to tasks.
+/// Creating a session launches a browser, which is far too slow to be done for every task of a crawler.
+///
+/// Sessions are lent with a [PooledSession](struct.PooledSession.html) guard.
+/// When the guard is dropped, the session is cleaned (extra tabs closed, cookies deleted, blank page loaded) and returned to the pool.
+/// Sessions which stopped responding (crashed browser or driver) are replaced by new ones when they are requested.
+///
+/// Note that geckodriver only handles one session at a time: pools of Firefox sessions require a Selenium server ([remote configuration](../config/struct.SessionConfig.html#method.remote)).
+///
+/// # Example
+///
+/// ```rust
+/// use lw_webdriver::{pool::SessionPool, config::SessionConfig, capabilities::Capabilities, enums::Browser};
+///
+/// let pool = SessionPool::new(SessionConfig::new(Capabilities::new(Browser::Chrome).headless(true)), 2).unwrap();
+///
+/// for url in &["https://mubelotix.dev/", "http://example.com/"] {
+///     let mut session = pool.get().unwrap();
+///     session.tabs[0].navigate(url).unwrap();
+///     println!("{}", session.tabs[0].get_title().unwrap());
+/// } // the session is returned to the pool here
+/// ```
+pub struct SessionPool {
+    config: SessionConfig,
+    size: usize,
+    idle_sessions: Mutex<Vec<Session>>
+}
+
+impl SessionPool {
+    /// Create a pool and open `size` sessions.
+    pub fn new(config: SessionConfig, size: usize) -> Result<Self, WebdriverError> {
+        let mut idle_sessions = Vec::new();
+        for _ in 0..size {
+            idle_sessions.push(Session::new_with_config(config.clone())?);
+        }
+
+        Ok(SessionPool {
+            config,
+            size,
+            idle_sessions: Mutex::new(idle_sessions)
+        })
+    }
+
+    /// Take a session from the pool.
+    /// If every session is in use, a new one is created (and kept in the pool if there is room when it is returned).
+    pub fn get(&self) -> Result<PooledSession<'_>, WebdriverError> {
+        loop {
+            let session = self.idle_sessions.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).pop();
+            match session {
+                Some(mut session) => {
+                    if session.update_tabs().is_ok() && !session.tabs.is_empty() {
+                        return Ok(PooledSession {
+                            pool: self,
+                            session: Some(session)
+                        });
+                    }
+                    warn!("a pooled session stopped responding and will be replaced");
+                    if session.owns_driver() {
+                        // the driver is stopped with the session, so the replacement launches its own
+                        drop(session);
+                        debug!("creating a session with its own driver for the pool");
+                        return Ok(PooledSession {
+                            pool: self,
+                            session: Some(Session::new_with_config(self.config.clone())?)
+                        });
+                    }
+                },
+                None => {
+                    debug!("creating a session for the pool");
+                    return Ok(PooledSession {
+                        pool: self,
+                        session: Some(Session::new_with_config(self.config.clone())?)
+                    });
+                }
+            }
+        }
+    }
+
+    /// Return the number of sessions waiting to be used.
+    pub fn idle_count(&self) -> usize {
+        self.idle_sessions.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).len()
+    }
+
+    /// Clean a session and store it, or drop it if it cannot be reused.
+    fn recycle(&self, mut session: Session) {
+        let clean = (|| -> Result<(), WebdriverError> {
+            session.update_tabs()?;
+            session.tabs.truncate(1);
+            let tab = session.tabs.get_mut(0).ok_or_else(|| WebdriverError::from(ErrorKind::NoSuchWindow))?;
+            tab.delete_cookies()?;
+            if tab.browser.cdp_vendor().is_some() {
+                tab.execute_cdp_command("Network.clearBrowserCookies", object!{})?;
+            }
+            tab.navigate("about:blank")
+        })();
+        if let Err(error) = clean {
+            warn!("a session could not be cleaned and was dropped: {:?}", error);
+            return;
+        }
+
+        let mut idle_sessions = self.idle_sessions.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if idle_sessions.len() < self.size {
+            idle_sessions.push(session);
+        } else if session.owns_driver() {
+            // keep the session owning the launched driver, or every other session would lose its driver
+            if let Some(index) = idle_sessions.iter().position(|idle_session| !idle_session.owns_driver()) {
+                let removed_session = std::mem::replace(&mut idle_sessions[index], session);
+                drop(idle_sessions);
+                drop(removed_session);
+            }
+        }
+    }
+}
+
+/// A session lent by a [SessionPool](struct.SessionPool.html).
+/// Dereferences to a [Session](../session/struct.Session.html) and returns it to the pool when dropped.
+pub struct PooledSession<'a> {
+    pool: &'a SessionPool,
+    session: Option<Session>
+}
+
+impl<'a> PooledSession<'a> {
+    /// Take the session out of the pool definitively.
+    pub fn detach(mut self) -> Session {
+        self.session.take().unwrap()
+    }
+}
+
+impl<'a> Deref for PooledSession<'a> {
+    type Target = Session;
+
+    fn deref(&self) -> &Session {
+        self.session.as_ref().unwrap()
+    }
+}
+
+impl<'a> DerefMut for PooledSession<'a> {
+    fn deref_mut(&mut self) -> &mut Session {
+        self.session.as_mut().unwrap()
+    }
+}
+
+impl<'a> Drop for PooledSession<'a> {
+    fn drop(&mut self) {
+        if let Some(session) = self.session.take() {
+            self.pool.recycle(session);
+        }
+    }
+}
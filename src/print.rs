@@ -0,0 +1,160 @@
+//! Options used to print pages as PDF documents
+
+use json::{JsonValue, object};
+
+/// The orientation of printed pages.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum Orientation {
+    Portrait,
+    Landscape
+}
+
+impl Orientation {
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Orientation::Portrait => "portrait",
+            Orientation::Landscape => "landscape"
+        }
+    }
+}
+
+/// Options of [Tab::print()](../tab/struct.Tab.html#method.print). Sizes are in centimeters.
+///
+/// The default options print every page in portrait on A4 paper (21 x 29.7), with 1cm margins and without backgrounds.
+///
+/// ```rust
+/// # use lw_webdriver::{session::Session, enums::Browser};
+/// use lw_webdriver::print::{PrintOptions, Orientation};
+///
+/// let mut session = Session::new(Browser::Firefox, false).unwrap();
+/// session.tabs[0].navigate("https://example.com/invoice").unwrap();
+///
+/// let options = PrintOptions::new()
+///     .orientation(Orientation::Landscape)
+///     .page_size(21.59, 27.94) // US letter
+///     .margins(0.5, 0.5, 0.5, 0.5)
+///     .page_range("1-2")
+///     .background(true);
+/// session.tabs[0].print_to_file("invoice.pdf", options).unwrap();
+/// ```
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct PrintOptions {
+    pub orientation: Orientation,
+    /// between 0.1 and 2
+    pub scale: f64,
+    pub background: bool,
+    /// (width, height)
+    pub page_size: (f64, f64),
+    /// (top, right, bottom, left)
+    pub margins: (f64, f64, f64, f64),
+    /// pages (`"3"`) and ranges of pages (`"1-5"`, `"-2"`, `"4-"`), every page if empty
+    pub page_ranges: Vec<String>,
+    /// resize the content to fit the width of the page
+    pub shrink_to_fit: bool
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions {
+            orientation: Orientation::Portrait,
+            scale: 1.0,
+            background: false,
+            page_size: (21.0, 29.7),
+            margins: (1.0, 1.0, 1.0, 1.0),
+            page_ranges: Vec::new(),
+            shrink_to_fit: true
+        }
+    }
+}
+
+impl PrintOptions {
+    /// Portrait A4 pages with 1cm margins.
+    pub fn new() -> Self {
+        PrintOptions::default()
+    }
+
+    pub fn orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Zoom the content (between 0.1 and 2).
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Print the background colors and images.
+    pub fn background(mut self, background: bool) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn page_size(mut self, width: f64, height: f64) -> Self {
+        self.page_size = (width, height);
+        self
+    }
+
+    pub fn margins(mut self, top: f64, right: f64, bottom: f64, left: f64) -> Self {
+        self.margins = (top, right, bottom, left);
+        self
+    }
+
+    /// Print only a page (`"3"`) or a range of pages (`"1-5"`). Can be called several times.
+    pub fn page_range(mut self, range: &str) -> Self {
+        self.page_ranges.push(range.to_string());
+        self
+    }
+
+    pub fn shrink_to_fit(mut self, shrink_to_fit: bool) -> Self {
+        self.shrink_to_fit = shrink_to_fit;
+        self
+    }
+
+    /// -> return the message of the error if the driver would reject the options
+    pub(crate) fn check(&self) -> Result<(), String> {
+        let (width, height) = self.page_size;
+        let (top, right, bottom, left) = self.margins;
+        if !(0.1..=2.0).contains(&self.scale) {
+            return Err(format!("scale {} is not between 0.1 and 2", self.scale));
+        }
+        if width < 0.0 || height < 0.0 || top < 0.0 || right < 0.0 || bottom < 0.0 || left < 0.0 {
+            return Err("sizes cannot be negative".to_string());
+        }
+        if left + right >= width || top + bottom >= height {
+            return Err("margins are larger than the page".to_string());
+        }
+        for range in &self.page_ranges {
+            let valid = range.split('-').count() <= 2 && !range.is_empty() && range != "-"
+                && range.split('-').all(|page| page.is_empty() || page.parse::<usize>().is_ok());
+            if !valid {
+                return Err(format!("invalid page range {:?}", range));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        object!{
+            "orientation" => self.orientation.to_string(),
+            "scale" => self.scale,
+            "background" => self.background,
+            "page" => object!{
+                "width" => self.page_size.0,
+                "height" => self.page_size.1
+            },
+            "margin" => object!{
+                "top" => self.margins.0,
+                "right" => self.margins.1,
+                "bottom" => self.margins.2,
+                "left" => self.margins.3
+            },
+            "shrinkToFit" => self.shrink_to_fit,
+            "pageRanges" => self.page_ranges.clone()
+        }
+    }
+}
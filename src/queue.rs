@@ -0,0 +1,66 @@
+//! Ordering of the commands sent to a session
+
+use std::sync::{Condvar, Mutex};
+
+#[derive(Debug)]
+struct QueueState {
+    /// ticket given to the next command
+    next_ticket: u64,
+    /// ticket of the next command allowed to be sent
+    next_admitted: u64,
+    in_flight: usize
+}
+
+/// Sends commands in the order they were issued, with a limited number of commands waiting for a response.
+/// Drivers reject or misbehave with interleaved commands, so only one command is in flight by default.
+#[derive(Debug)]
+pub(crate) struct CommandQueue {
+    max_in_flight: usize,
+    state: Mutex<QueueState>,
+    condvar: Condvar
+}
+
+impl CommandQueue {
+    pub(crate) fn new(max_in_flight: usize) -> Self {
+        CommandQueue {
+            max_in_flight: max_in_flight.max(1),
+            state: Mutex::new(QueueState {
+                next_ticket: 0,
+                next_admitted: 0,
+                in_flight: 0
+            }),
+            condvar: Condvar::new()
+        }
+    }
+
+    /// Wait for the turn of a command and run it.
+    pub(crate) fn run<T, F: FnOnce() -> T>(&self, command: F) -> T {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        while state.next_admitted != ticket || state.in_flight >= self.max_in_flight {
+            state = self.condvar.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        state.next_admitted += 1;
+        state.in_flight += 1;
+        drop(state);
+        self.condvar.notify_all();
+
+        // the slot is released even if the command panics
+        let _slot = Slot { queue: self };
+        command()
+    }
+}
+
+struct Slot<'a> {
+    queue: &'a CommandQueue
+}
+
+impl<'a> Drop for Slot<'a> {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.in_flight -= 1;
+        drop(state);
+        self.queue.condvar.notify_all();
+    }
+}
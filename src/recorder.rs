@@ -0,0 +1,182 @@
+//! Video recording of sessions, to replay failed runs visually
+//!
+//! A [Recorder](struct.Recorder.html) takes screenshots of the selected tab on an interval, in a background thread.
+//! The frames are saved as a directory of png images, or as an animated gif with the `image` feature.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::{Browser, Selector}, recorder::Recorder};
+//! use std::time::Duration;
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! let recorder = Recorder::new(Duration::from_millis(500)).max_frames(120).start(&session).unwrap();
+//!
+//! session.tabs[0].navigate("https://example.com/").unwrap();
+//! session.tabs[0].find(Selector::TagName, "a").unwrap().unwrap().click().unwrap();
+//!
+//! let recording = recorder.stop();
+//! recording.save_frames("target/replay").unwrap();
+//! ```
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use std::collections::VecDeque;
+use log::{debug, warn};
+use crate::session::Session;
+use crate::error::WebdriverError;
+use crate::http_requests::{Connection, take_screenshot};
+
+/// A screenshot taken by a [Recorder](struct.Recorder.html).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Frame {
+    /// When the screenshot was taken, relative to the start of the recording.
+    pub time: Duration,
+    /// The bytes of a png image.
+    pub png: Vec<u8>
+}
+
+/// The configuration of a recording, started with [start()](#method.start).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct Recorder {
+    interval: Duration,
+    max_frames: Option<usize>
+}
+
+impl Recorder {
+    /// Take a screenshot every `interval`. Taking a screenshot can take longer than the interval, which is then extended.
+    pub fn new(interval: Duration) -> Self {
+        Recorder {
+            interval,
+            max_frames: None
+        }
+    }
+
+    /// Only keep the last frames (every frame is kept by default).
+    /// The memory used by long recordings is limited, and the end of the run is usually what matters.
+    pub fn max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = Some(max_frames);
+        self
+    }
+
+    /// Start recording the selected tab of a session.
+    /// The screenshots are taken with another connection to the server, so the session can be used while recording.
+    /// Return an error for sessions using a custom transport or a dry run.
+    pub fn start(self, session: &Session) -> Result<RunningRecorder, WebdriverError> {
+        let url = match &session.connection.url {
+            Some(url) => url.clone(),
+            None => return Err(WebdriverError::custom("sessions with a custom transport cannot be recorded".to_string())),
+        };
+        let credentials = session.connection.credentials.clone();
+        let session_id = session.id.to_string();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let stopped = Arc::clone(&stop);
+        let thread = thread::spawn(move || {
+            let connection = Connection::new(&url, credentials.as_ref().map(|(username, password)| (username.as_str(), password.as_str())));
+            let start = Instant::now();
+            let mut frames = VecDeque::new();
+            while !stopped.load(Ordering::SeqCst) {
+                let next = Instant::now() + self.interval;
+                match take_screenshot(&connection, &session_id) {
+                    Ok(png) => {
+                        if self.max_frames.map(|max| frames.len() >= max).unwrap_or(false) {
+                            frames.pop_front();
+                        }
+                        frames.push_back(Frame { time: start.elapsed(), png });
+                    },
+                    // the page can be navigating
+                    Err(error) => warn!("recorder failed to take a screenshot: {}", error),
+                }
+                while !stopped.load(Ordering::SeqCst) && Instant::now() < next {
+                    thread::sleep((next - Instant::now()).min(Duration::from_millis(50)));
+                }
+            }
+            debug!("recording stopped after {} frames", frames.len());
+            frames.into_iter().collect()
+        });
+
+        Ok(RunningRecorder {
+            stop,
+            thread: Some(thread)
+        })
+    }
+}
+
+/// A recording in progress. Dropping it stops the recording and discards the frames.
+pub struct RunningRecorder {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Vec<Frame>>>
+}
+
+impl RunningRecorder {
+    /// Stop the recording and return the frames, waiting for the screenshot being taken.
+    pub fn stop(mut self) -> Recording {
+        self.stop.store(true, Ordering::SeqCst);
+        let frames = match self.thread.take().map(|thread| thread.join()) {
+            Some(Ok(frames)) => frames,
+            _ => {
+                warn!("recorder thread panicked, the frames are lost");
+                Vec::new()
+            }
+        };
+        Recording { frames }
+    }
+}
+
+impl Drop for RunningRecorder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// The frames of a stopped recording.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Recording {
+    pub frames: Vec<Frame>
+}
+
+impl Recording {
+    /// Save the frames as png images in a directory (created if needed), named in order (`frame-00001.png`...).
+    pub fn save_frames<P: AsRef<Path>>(&self, directory: P) -> Result<(), WebdriverError> {
+        let directory = directory.as_ref();
+        let io_error = |error: std::io::Error| WebdriverError::custom(format!("failed to save frames in {}: {}", directory.display(), error));
+        std::fs::create_dir_all(directory).map_err(io_error)?;
+        for (index, frame) in self.frames.iter().enumerate() {
+            std::fs::write(directory.join(format!("frame-{:05}.png", index + 1)), &frame.png).map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    /// Save the frames as an animated gif (requires the `image` feature), each frame lasting until the next one.
+    /// Gif images have 256 colors per frame, which is enough to follow a run.
+    #[cfg(feature = "image")]
+    pub fn save_gif<P: AsRef<Path>>(&self, path: P) -> Result<(), WebdriverError> {
+        use image::{Delay, ImageFormat, gif::GifEncoder};
+        use crate::error::ErrorKind;
+
+        let path = path.as_ref();
+        let file = std::fs::File::create(path).map_err(|error| WebdriverError::custom(format!("failed to create {}: {}", path.display(), error)))?;
+        let mut encoder = GifEncoder::new(file);
+        for (index, frame) in self.frames.iter().enumerate() {
+            let image = image::load_from_memory_with_format(&frame.png, ImageFormat::Png)
+                .map_err(|error| WebdriverError::from(ErrorKind::InvalidArgument).with_message(format!("frame {} is not a png image: {}", index + 1, error)))?;
+            let duration = match self.frames.get(index + 1) {
+                Some(next) => next.time - frame.time,
+                None => Duration::from_secs(1),
+            };
+            let frame = image::Frame::from_parts(image.into_rgba8(), 0, 0, Delay::from_saturating_duration(duration));
+            encoder.encode_frame(frame).map_err(|error| WebdriverError::custom(format!("failed to encode {}: {}", path.display(), error)))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+//! Recovery of sessions whose driver or browser crashed
+//!
+//! With [SessionConfig::crash_recovery()](../config/struct.SessionConfig.html#method.crash_recovery), a command failing because the session
+//! does not exist anymore ([InvalidSessionId](../error/enum.ErrorKind.html#variant.InvalidSessionId)) or because the launched driver exited
+//! ([FailedRequest](../error/enum.ErrorKind.html#variant.FailedRequest) while the driver process is not running) creates a new session, relaunching the driver if needed.
+//! A request failing while the driver is running is not a crash, and is reported as is.
+//! The tabs used by the session are opened again on the last url they navigated to, and the tabs of the session keep working.
+//!
+//! Only the commands reading the state of the browser (finding elements, getting the url, taking screenshots...) are sent again to the new session.
+//! The other ones (navigating, clicking, typing...) may have been executed before the crash, so they fail with
+//! [SessionRestarted](../error/enum.ErrorKind.html#variant.SessionRestarted) instead, and can be sent again if it is safe.
+//!
+//! The state of the pages is lost: the elements must be found again, and the cookies are cleared.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, config::SessionConfig, capabilities::Capabilities, enums::Browser};
+//!
+//! let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).crash_recovery(true);
+//! let mut session = Session::new_with_config(config).unwrap();
+//! session.on_restart(|restarted| eprintln!("session {} crashed ({}), reopened {:?}", restarted.previous_id, restarted.error, restarted.reopened_urls));
+//!
+//! session.tabs[0].navigate("https://example.com/").unwrap();
+//! // if the browser crashes here, the page is loaded again in a new session
+//! session.tabs[0].get_title().unwrap();
+//! ```
+
+use crate::error::WebdriverError;
+
+/// A notification sent to the callbacks registered with [Session::on_restart()](../session/struct.Session.html#method.on_restart).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct SessionRestarted {
+    /// The id of the crashed session.
+    pub previous_id: String,
+    /// The id of the session replacing it.
+    pub id: String,
+    /// The error of the command which detected the crash.
+    pub error: WebdriverError,
+    /// The urls loaded again in the reopened tabs.
+    pub reopened_urls: Vec<String>
+}
@@ -0,0 +1,214 @@
+//! Replay of recorded sessions, to reproduce a run without rewriting it
+//!
+//! A [Transcript](../transcript/struct.Transcript.html) recorded with [Session::start_recording()](../session/struct.Session.html#method.start_recording),
+//! or loaded from a json script, is sent again to another session. The elements and tabs of the recorded session are replaced by
+//! the ones found while replaying, urls can be substituted (to replay a production run on a staging server) and the commands can be slowed down.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::Browser, transcript::Transcript, replay::Replay};
+//! use std::time::Duration;
+//!
+//! let script = json::parse(&std::fs::read_to_string("target/example.json").unwrap()).unwrap();
+//! let transcript = Transcript::from_json(&script).unwrap();
+//!
+//! let mut session = Session::new(transcript.browser.clone(), false).unwrap();
+//! Replay::new()
+//!     .substitute_url("https://example.com/", "http://localhost:8080/")
+//!     .delay(Duration::from_millis(200))
+//!     .run(&mut session, &transcript)
+//!     .unwrap();
+//! ```
+
+use std::thread;
+use std::time::{Duration, Instant};
+use json::JsonValue;
+use log::{debug, warn, error};
+use crate::session::Session;
+use crate::command::Command;
+use crate::transcript::Transcript;
+use crate::error::WebdriverError;
+
+const ELEMENT_KEY: &str = "element-6066-11e4-a52e-4f735466cecf";
+
+/// The configuration of a replay, started with [run()](#method.run).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Replay {
+    urls: Vec<(String, String)>,
+    delay: Duration,
+    recorded_timing: bool
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Replay::new()
+    }
+}
+
+impl Replay {
+    /// Send the commands as fast as possible, to the recorded urls.
+    pub fn new() -> Self {
+        Replay {
+            urls: Vec::new(),
+            delay: Duration::from_secs(0),
+            recorded_timing: false
+        }
+    }
+
+    /// Replace the beginning of the urls navigated to (`https://example.com/` by `http://localhost:8080/`).
+    /// Substitutions are tried in the order they were added, and the first matching one is used.
+    pub fn substitute_url(mut self, from: &str, to: &str) -> Self {
+        self.urls.push((from.to_string(), to.to_string()));
+        self
+    }
+
+    /// Wait before sending every command, to follow the replay or to slow down a page reacting too fast.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Wait between the commands as long as during the recording, to reproduce timing issues.
+    /// The [delay](#method.delay) is added to the recorded time.
+    pub fn recorded_timing(mut self, recorded_timing: bool) -> Self {
+        self.recorded_timing = recorded_timing;
+        self
+    }
+
+    /// -> take a recorded url
+    /// -> return the url to navigate to
+    fn url(&self, url: &str) -> String {
+        match self.urls.iter().find(|(from, _)| url.starts_with(from.as_str())) {
+            Some((from, to)) => format!("{}{}", to, &url[from.len()..]),
+            None => url.to_string(),
+        }
+    }
+
+    /// Send the recorded commands to a session, in order, and update its tabs.
+    ///
+    /// Commands which failed during the recording can fail again. Any other failure stops the replay and is returned.
+    /// The values returned by the commands are not compared with the recorded ones (differences are logged).
+    /// Deleting the session is not replayed.
+    pub fn run(&self, session: &mut Session, transcript: &Transcript) -> Result<(), WebdriverError> {
+        let mut ids = Ids {
+            handles: Vec::new(),
+            elements: Vec::new(),
+            unmapped_tabs: session.tabs.iter().map(|tab| tab.id.to_string()).collect()
+        };
+        let start = Instant::now();
+
+        for (index, entry) in transcript.entries.iter().enumerate() {
+            if self.recorded_timing {
+                if let Some(wait) = entry.time.checked_sub(start.elapsed()) {
+                    thread::sleep(wait);
+                }
+            }
+            thread::sleep(self.delay);
+
+            let command = match &entry.command {
+                Command::DeleteSession => continue,
+                Command::Navigate { url } => Command::Navigate { url: self.url(url) },
+                Command::SwitchToWindow { handle } => Command::SwitchToWindow { handle: ids.handle(handle) },
+                command @ Command::GetWindowHandle | command @ Command::GetWindowHandles | command @ Command::CloseWindow
+                    | command @ Command::NewWindow { .. } => command.clone(),
+                command => {
+                    let (method, path, body) = command.to_relative_request();
+                    Command::Custom {
+                        method,
+                        path: ids.replace_elements(&path),
+                        body: body.and_then(|body| json::parse(&ids.replace_elements(&body)).ok())
+                    }
+                },
+            };
+
+            debug!("replaying command {} ({})", index, entry.command.name());
+            let value = match (session.execute(command), &entry.error) {
+                (Ok(value), _) => value,
+                (Err(error), Some(_)) => {
+                    debug!("command {} ({}) failed again: {}", index, entry.command.name(), error);
+                    continue;
+                },
+                (Err(error), None) => {
+                    error!("replay failed at command {} ({}): {}", index, entry.command.name(), error);
+                    return Err(error);
+                },
+            };
+
+            match &entry.command {
+                Command::GetWindowHandle => if let (Some(recorded), Some(handle)) = (entry.value.as_str(), value.as_str()) {
+                    ids.map_handle(recorded, handle);
+                },
+                Command::NewWindow { .. } => if let (Some(recorded), Some(handle)) = (entry.value["handle"].as_str(), value["handle"].as_str()) {
+                    ids.map_handle(recorded, handle);
+                },
+                _ => ids.map_elements(&entry.value, &value),
+            }
+            // the texts read from the page (titles, urls...) are expected to be the same
+            if (entry.value.is_string() || entry.value.is_boolean()) && entry.value != value {
+                warn!("command {} ({}) returned {} instead of {}", index, entry.command.name(), value, entry.value);
+            }
+        }
+
+        session.update_tabs()
+    }
+}
+
+/// The tabs and elements of the recorded session, with the ones of the replaying session
+struct Ids {
+    /// recorded handles and replaying handles
+    handles: Vec<(String, String)>,
+    /// recorded element ids and replaying element ids
+    elements: Vec<(String, String)>,
+    /// the tabs of the replaying session which were not matched with a recorded tab yet
+    unmapped_tabs: Vec<String>
+}
+
+impl Ids {
+    fn map_handle(&mut self, recorded: &str, handle: &str) {
+        if !self.handles.iter().any(|(known, _)| known == recorded) {
+            self.unmapped_tabs.retain(|tab| tab != handle);
+            self.handles.push((recorded.to_string(), handle.to_string()));
+        }
+    }
+
+    /// -> take a recorded handle
+    /// -> return the handle of the replaying tab, guessing it for the tabs which were open before the recording
+    fn handle(&mut self, recorded: &str) -> String {
+        if let Some((_, handle)) = self.handles.iter().find(|(known, _)| known == recorded) {
+            return handle.clone();
+        }
+        if self.unmapped_tabs.is_empty() {
+            return recorded.to_string();
+        }
+        let handle = self.unmapped_tabs.remove(0);
+        self.handles.push((recorded.to_string(), handle.clone()));
+        handle
+    }
+
+    /// -> take a recorded value and the value returned while replaying
+    /// remember the elements found in both
+    fn map_elements(&mut self, recorded: &JsonValue, value: &JsonValue) {
+        if let (Some(recorded), Some(id)) = (recorded[ELEMENT_KEY].as_str(), value[ELEMENT_KEY].as_str()) {
+            if recorded != id {
+                self.elements.push((recorded.to_string(), id.to_string()));
+            }
+        } else if recorded.is_array() {
+            for (recorded, value) in recorded.members().zip(value.members()) {
+                self.map_elements(recorded, value);
+            }
+        } else if recorded.is_object() {
+            for (key, recorded) in recorded.entries() {
+                self.map_elements(recorded, &value[key]);
+            }
+        }
+    }
+
+    /// -> take a path or a body
+    /// -> return it with the recorded element ids replaced
+    fn replace_elements(&self, text: &str) -> String {
+        self.elements.iter().fold(text.to_string(), |text, (recorded, id)| text.replace(recorded.as_str(), id))
+    }
+}
@@ -0,0 +1,171 @@
+//! Typed responses of the webdriver server
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use json::JsonValue;
+use log::error;
+use crate::error::{WebdriverError, ErrorKind};
+use crate::transport::Response;
+
+/// A successful response, the value depending on the command
+#[derive(Deserialize)]
+pub(crate) struct ValueResponse<T> {
+    pub(crate) value: T
+}
+
+/// The value of a response reporting an [error](https://www.w3.org/TR/webdriver/#errors)
+#[derive(Deserialize)]
+pub(crate) struct ErrorValue {
+    pub(crate) error: String,
+    #[serde(default)]
+    pub(crate) message: String,
+    #[serde(default)]
+    pub(crate) stacktrace: String,
+    #[serde(default)]
+    pub(crate) data: serde_json::Value
+}
+
+#[derive(Deserialize)]
+pub(crate) struct StatusValue {
+    pub(crate) ready: bool,
+    #[serde(default)]
+    pub(crate) message: String,
+    pub(crate) build: Option<BuildValue>,
+    pub(crate) os: Option<OsValue>
+}
+
+#[derive(Deserialize)]
+pub(crate) struct BuildValue {
+    pub(crate) version: Option<String>
+}
+
+#[derive(Deserialize)]
+pub(crate) struct OsValue {
+    pub(crate) name: String,
+    pub(crate) version: Option<String>,
+    pub(crate) arch: Option<String>
+}
+
+#[derive(Deserialize)]
+pub(crate) struct NewSessionValue {
+    #[serde(rename = "sessionId")]
+    pub(crate) session_id: String,
+    #[serde(default)]
+    pub(crate) capabilities: serde_json::Value
+}
+
+#[derive(Deserialize)]
+pub(crate) struct NewWindowValue {
+    pub(crate) handle: String,
+    #[serde(rename = "type")]
+    pub(crate) window_type: Option<String>
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TimeoutsValue {
+    /// null when scripts never time out
+    pub(crate) script: Option<u64>,
+    #[serde(rename = "pageLoad")]
+    pub(crate) page_load: u64,
+    pub(crate) implicit: u64
+}
+
+/// A [web element](https://www.w3.org/TR/webdriver/#elements)
+#[derive(Deserialize)]
+pub(crate) struct ElementValue {
+    #[serde(rename = "element-6066-11e4-a52e-4f735466cecf")]
+    pub(crate) id: String
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RectValue {
+    pub(crate) x: f64,
+    pub(crate) y: f64,
+    pub(crate) width: f64,
+    pub(crate) height: f64
+}
+
+#[derive(Deserialize)]
+#[derive(Debug)]
+pub(crate) struct CookieValue {
+    #[serde(default)]
+    pub(crate) domain: String,
+    pub(crate) expiry: Option<usize>,
+    #[serde(rename = "httpOnly")]
+    pub(crate) http_only: Option<bool>,
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) path: String,
+    pub(crate) secure: Option<bool>,
+    pub(crate) value: String
+}
+
+#[derive(Deserialize)]
+pub(crate) struct LogEntryValue {
+    pub(crate) level: String,
+    pub(crate) message: String,
+    #[serde(default)]
+    pub(crate) source: String,
+    pub(crate) timestamp: f64
+}
+
+/// -> take a json value decoded with serde, for the commands returning arbitrary data (scripts, devtools commands)
+/// -> return the same value as a JsonValue
+pub(crate) fn to_json_value(value: serde_json::Value) -> JsonValue {
+    match value {
+        serde_json::Value::Null => JsonValue::Null,
+        serde_json::Value::Bool(value) => value.into(),
+        serde_json::Value::Number(number) => match (number.as_u64(), number.as_i64()) {
+            (Some(value), _) => value.into(),
+            (None, Some(value)) => value.into(),
+            (None, None) => number.as_f64().map(JsonValue::from).unwrap_or(JsonValue::Null),
+        },
+        serde_json::Value::String(value) => value.into(),
+        serde_json::Value::Array(values) => JsonValue::Array(values.into_iter().map(to_json_value).collect()),
+        serde_json::Value::Object(map) => {
+            let mut object = JsonValue::new_object();
+            for (key, value) in map {
+                object[key.as_str()] = to_json_value(value);
+            }
+            object
+        },
+    }
+}
+
+/// Decode the response of a command.
+/// -> return the value or the error, and whether the error is transient
+pub(crate) fn decode<T: DeserializeOwned>(response: Result<&Response, &str>) -> (Result<T, WebdriverError>, bool) {
+    let response = match response {
+        Ok(response) => response,
+        Err(message) => {
+            error!("ErrorKind::FailedRequest, error: {:?}", message);
+            return (Err(WebdriverError::from(ErrorKind::FailedRequest).with_message(message.to_string())), true);
+        }
+    };
+
+    let invalid = |decoding_error: serde_json::Error| {
+        error!("ErrorKind::InvalidResponse ({}), text: {}", decoding_error, String::from_utf8_lossy(&response.body));
+        // proxies and grids may answer with an html page while the driver is unavailable
+        (Err(WebdriverError::from(ErrorKind::InvalidResponse).with_status(response.status_code)), response.status_code >= 500)
+    };
+    let value = match serde_json::from_slice::<ValueResponse<serde_json::Value>>(&response.body) {
+        Ok(ValueResponse { value }) => value,
+        Err(decoding_error) => return invalid(decoding_error),
+    };
+
+    if value.get("error").map(|error| error.is_string()).unwrap_or(false) {
+        return match serde_json::from_value::<ErrorValue>(value) {
+            Ok(value) => {
+                let error = WebdriverError::from_response(value, response.status_code);
+                error!("{:?}, response: {}", error.kind, String::from_utf8_lossy(&response.body));
+                (Err(error), false)
+            },
+            Err(decoding_error) => invalid(decoding_error),
+        };
+    }
+
+    match serde_json::from_value::<T>(value) {
+        Ok(value) => (Ok(value), false),
+        Err(decoding_error) => invalid(decoding_error),
+    }
+}
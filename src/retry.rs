@@ -0,0 +1,95 @@
+//! Retries of commands failing for transient reasons
+
+use std::time::Duration;
+
+/// How commands failing for a transient reason are retried.
+///
+/// A command is retried when the server could not be reached ([FailedRequest](../error/enum.ErrorKind.html#variant.FailedRequest))
+/// and when a proxy or a grid answers with a 5xx status code without a webdriver error. Other errors are returned immediately.
+///
+/// Only the commands which can safely run twice are sent again: finding elements, reading the page, the tabs, the elements or
+/// the cookies, taking screenshots and setting the timeouts. The driver may have run the other commands (a click, a navigation
+/// or a script) before the failure.
+///
+/// When the driver reports a [StaleElementReference](../error/enum.ErrorKind.html#variant.StaleElementReference), which happens while
+/// a document is being replaced, the element is found again with the locator it was found with and the command is retried once,
+/// like with [Session::set_stale_element_recovery()](../session/struct.Session.html#method.set_stale_element_recovery).
+///
+/// Sessions do not retry commands by default.
+///
+/// # Example
+///
+/// ```rust
+/// use lw_webdriver::{session::Session, retry::RetryPolicy, enums::Browser};
+/// use std::time::Duration;
+///
+/// let mut session = Session::new(Browser::Firefox, false).unwrap();
+/// session.set_retry_policy(RetryPolicy::new(4).initial_backoff(Duration::from_millis(200)));
+/// ```
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    initial_backoff: Duration,
+    multiplier: u32,
+    max_backoff: Duration
+}
+
+impl RetryPolicy {
+    /// Send each command at most `max_attempts` times.
+    /// The first retry waits 100ms, and the delay doubles after each attempt up to 5s.
+    pub fn new(max_attempts: usize) -> Self {
+        RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: Duration::from_millis(100),
+            multiplier: 2,
+            max_backoff: Duration::from_secs(5)
+        }
+    }
+
+    /// Never retry commands.
+    pub fn none() -> Self {
+        RetryPolicy::new(1)
+    }
+
+    /// The delay before the first retry.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// The factor applied to the delay after each retry (2 by default).
+    pub fn multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// The longest delay between two attempts.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    pub fn get_max_attempts(&self) -> usize {
+        self.max_attempts
+    }
+
+    /// Return the delay before an attempt (the second attempt is attempt 1).
+    pub(crate) fn backoff(&self, attempt: usize) -> Duration {
+        let mut backoff = self.initial_backoff;
+        for _ in 1..attempt {
+            backoff = backoff.checked_mul(self.multiplier).unwrap_or(self.max_backoff);
+            if backoff >= self.max_backoff {
+                break;
+            }
+        }
+        backoff.min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::none()
+    }
+}
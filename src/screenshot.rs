@@ -0,0 +1,260 @@
+//! Comparison of screenshots, for visual regression testing (requires the `image` feature)
+//!
+//! [diff()](fn.diff.html) compares two png images pixel by pixel, and [Tab::assert_matches_baseline()](../tab/struct.Tab.html#method.assert_matches_baseline)
+//! compares the tab with a golden screenshot saved by a previous run.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::{Browser, Selector}};
+//! use lw_webdriver::screenshot::{DiffOptions, Region};
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.tabs[0].navigate("https://example.com/").unwrap();
+//!
+//! // the first run saves the baseline, the next ones compare the tab with it
+//! session.tabs[0].assert_matches_baseline("tests/baselines/example.png").unwrap();
+//!
+//! // ignore a clock and tolerate antialiasing differences
+//! let clock = session.tabs[0].find(Selector::Css, "#clock").unwrap().unwrap();
+//! let options = DiffOptions::new().threshold(16).ignore(Region::from(clock.rect().unwrap()));
+//! session.tabs[0].assert_matches_baseline_with("tests/baselines/example.png", &options).unwrap();
+//! ```
+
+use std::path::{Path, PathBuf};
+use image::{RgbaImage, Rgba, ImageFormat, ColorType, png::PngEncoder};
+use log::{info, error};
+use crate::tab::Tab;
+use crate::elements::Rect;
+use crate::error::{WebdriverError, ErrorKind};
+
+/// A rectangle of an image, in pixels.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32
+}
+
+impl Region {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Region {
+            x,
+            y,
+            width,
+            height
+        }
+    }
+
+    fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.x && x < self.x.saturating_add(self.width) && y >= self.y && y < self.y.saturating_add(self.height)
+    }
+}
+
+impl From<Rect> for Region {
+    /// The pixels covered by a rectangle, rounded outward.
+    /// The rectangle must be in screenshot pixels: multiply it by `devicePixelRatio` on high density screens.
+    fn from(rect: Rect) -> Self {
+        let x = rect.x.max(0.0).floor();
+        let y = rect.y.max(0.0).floor();
+        Region {
+            x: x as u32,
+            y: y as u32,
+            width: ((rect.x + rect.width).ceil() - x).max(0.0) as u32,
+            height: ((rect.y + rect.height).ceil() - y).max(0.0) as u32
+        }
+    }
+}
+
+/// How screenshots are compared.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct DiffOptions {
+    threshold: u8,
+    ignored_regions: Vec<Region>,
+    max_diff_ratio: f64
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions::new()
+    }
+}
+
+impl DiffOptions {
+    /// Pixels must be identical, and no region is ignored.
+    pub fn new() -> Self {
+        DiffOptions {
+            threshold: 0,
+            ignored_regions: Vec::new(),
+            max_diff_ratio: 0.0
+        }
+    }
+
+    /// The largest difference of a color channel (red, green, blue or alpha) between two pixels considered equal.
+    /// Antialiasing and font rendering often differ slightly between machines.
+    pub fn threshold(mut self, threshold: u8) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Do not compare the pixels of a region (dates, animations, ads...).
+    pub fn ignore(mut self, region: Region) -> Self {
+        self.ignored_regions.push(region);
+        self
+    }
+
+    /// The ratio of different pixels (from 0 to 1) tolerated by [Tab::assert_matches_baseline_with()](../tab/struct.Tab.html#method.assert_matches_baseline_with).
+    pub fn max_diff_ratio(mut self, ratio: f64) -> Self {
+        self.max_diff_ratio = ratio;
+        self
+    }
+}
+
+/// The result of the comparison of two screenshots.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct DiffResult {
+    pub width: u32,
+    pub height: u32,
+    /// The number of pixels which are different, ignored regions excluded.
+    pub different_pixels: u64,
+    /// The number of pixels compared, ignored regions excluded.
+    pub compared_pixels: u64,
+    /// A png image of the first screenshot, faded, with the different pixels in red.
+    pub diff_image: Vec<u8>
+}
+
+impl DiffResult {
+    /// Return true if no pixel is different.
+    pub fn is_identical(&self) -> bool {
+        self.different_pixels == 0
+    }
+
+    /// Return the ratio of different pixels, from 0 to 1.
+    pub fn diff_ratio(&self) -> f64 {
+        if self.compared_pixels == 0 {
+            return 0.0;
+        }
+        self.different_pixels as f64 / self.compared_pixels as f64
+    }
+}
+
+/// Compare two png images pixel by pixel, like two screenshots of a tab.
+/// Return [InvalidArgument](../error/enum.ErrorKind.html#variant.InvalidArgument) if an image is not a png or if the sizes are different.
+pub fn diff(a: &[u8], b: &[u8]) -> Result<DiffResult, WebdriverError> {
+    diff_with_options(a, b, &DiffOptions::new())
+}
+
+/// Compare two png images pixel by pixel, with a threshold and ignored regions.
+pub fn diff_with_options(a: &[u8], b: &[u8], options: &DiffOptions) -> Result<DiffResult, WebdriverError> {
+    let a = decode(a)?;
+    let b = decode(b)?;
+    if a.dimensions() != b.dimensions() {
+        return Err(WebdriverError::from(ErrorKind::InvalidArgument).with_message(format!("the images have different sizes ({}x{} and {}x{})", a.width(), a.height(), b.width(), b.height())));
+    }
+
+    let mut diff_image = RgbaImage::new(a.width(), a.height());
+    let mut different_pixels = 0;
+    let mut compared_pixels = 0;
+    for (x, y, pixel) in a.enumerate_pixels() {
+        let other = b.get_pixel(x, y);
+        let faded = |channel: u8| 255 - (255 - channel) / 4;
+        let diff_pixel = if options.ignored_regions.iter().any(|region| region.contains(x, y)) {
+            Rgba([faded(pixel[0]) / 2, faded(pixel[1]) / 2, faded(pixel[2]) / 2, 255])
+        } else {
+            compared_pixels += 1;
+            if pixel.0.iter().zip(other.0.iter()).any(|(a, b)| a.max(b) - a.min(b) > options.threshold) {
+                different_pixels += 1;
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([faded(pixel[0]), faded(pixel[1]), faded(pixel[2]), 255])
+            }
+        };
+        diff_image.put_pixel(x, y, diff_pixel);
+    }
+
+    Ok(DiffResult {
+        width: a.width(),
+        height: a.height(),
+        different_pixels,
+        compared_pixels,
+        diff_image: encode(&diff_image)?
+    })
+}
+
+/// -> take the bytes of a png image
+/// -> return the pixels
+fn decode(png: &[u8]) -> Result<RgbaImage, WebdriverError> {
+    match image::load_from_memory_with_format(png, ImageFormat::Png) {
+        Ok(image) => Ok(image.into_rgba8()),
+        Err(error) => Err(WebdriverError::from(ErrorKind::InvalidArgument).with_message(format!("invalid png image: {}", error))),
+    }
+}
+
+/// -> take pixels
+/// -> return the bytes of a png image
+fn encode(image: &RgbaImage) -> Result<Vec<u8>, WebdriverError> {
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png).encode(image.as_raw(), image.width(), image.height(), ColorType::Rgba8).map_err(|error| {
+        error!("failed to encode the diff image: {}", error);
+        WebdriverError::custom(format!("failed to encode the diff image: {}", error))
+    })?;
+    Ok(png)
+}
+
+/// -> take the path of a baseline and a suffix
+/// -> return the path of a file saved next to the baseline (`example.png` becomes `example.diff.png`)
+fn next_to(baseline: &Path, suffix: &str) -> PathBuf {
+    let stem = baseline.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    baseline.with_file_name(format!("{}.{}.png", stem, suffix))
+}
+
+impl Tab {
+    /// Compare a screenshot of the viewport with a baseline image. The pixels must be identical.
+    ///
+    /// If the baseline does not exist, the screenshot is saved as the baseline (commit it to compare the next runs with it).
+    /// Otherwise, when the screenshot is different, it is saved next to the baseline (`name.actual.png`) with an image
+    /// highlighting the differences (`name.diff.png`), and an error is returned.
+    pub fn assert_matches_baseline<P: AsRef<Path>>(&self, path: P) -> Result<(), WebdriverError> {
+        self.assert_matches_baseline_with(path, &DiffOptions::new())
+    }
+
+    /// Compare a screenshot of the viewport with a baseline image, like [assert_matches_baseline()](#method.assert_matches_baseline),
+    /// with a threshold, ignored regions and a tolerated ratio of different pixels.
+    pub fn assert_matches_baseline_with<P: AsRef<Path>>(&self, path: P, options: &DiffOptions) -> Result<(), WebdriverError> {
+        let path = path.as_ref();
+        let screenshot = self.screenshot()?;
+        let io_error = |error: std::io::Error| WebdriverError::custom(format!("failed to access baseline {}: {}", path.display(), error));
+
+        if !path.exists() {
+            info!("baseline {} does not exist, saving the screenshot as the baseline", path.display());
+            if let Some(directory) = path.parent() {
+                std::fs::create_dir_all(directory).map_err(io_error)?;
+            }
+            return std::fs::write(path, &screenshot).map_err(io_error);
+        }
+
+        let baseline = std::fs::read(path).map_err(io_error)?;
+        let result = match diff_with_options(&baseline, &screenshot, options) {
+            Ok(result) => result,
+            Err(error) => {
+                std::fs::write(next_to(path, "actual"), &screenshot).map_err(io_error)?;
+                return Err(error);
+            }
+        };
+        if result.diff_ratio() <= options.max_diff_ratio {
+            return Ok(());
+        }
+
+        std::fs::write(next_to(path, "actual"), &screenshot).map_err(io_error)?;
+        std::fs::write(next_to(path, "diff"), &result.diff_image).map_err(io_error)?;
+        Err(WebdriverError::custom(format!("screenshot does not match baseline {}: {} pixels are different ({:.2}%), see {}",
+            path.display(), result.different_pixels, result.diff_ratio() * 100.0, next_to(path, "diff").display())))
+    }
+}
@@ -0,0 +1,159 @@
+//! Security audits of the loaded page
+
+use json::JsonValue;
+
+/// Collects the response headers of the current document and every insecure subresource of the page.
+/// The result is a json object read by [SecurityReport::from_json()](struct.SecurityReport.html).
+///
+/// Pages cannot read the headers of their own response, so the document is requested again with `fetch()`:
+/// the headers are those of this second response, which is not read from the cache.
+pub(crate) const SECURITY_AUDIT_SCRIPT: &str = "
+    const mixed = [];
+    if (location.protocol === 'https:') {
+        const seen = new Set();
+        const add = (url, initiator) => {
+            if (typeof url === 'string' && url.startsWith('http:') && !seen.has(url)) {
+                seen.add(url);
+                mixed.push({ url: url, initiator: initiator });
+            }
+        };
+        for (const entry of performance.getEntriesByType('resource')) {
+            add(entry.name, entry.initiatorType);
+        }
+        for (const element of document.querySelectorAll('[src], link[href], form[action], object[data]')) {
+            add(element.src || element.href || element.action || element.data, element.tagName.toLowerCase());
+        }
+    }
+    // a second request of the document, sent with the cookies of the page
+    return fetch(location.href, { credentials: 'include', cache: 'no-store' }).then(response => {
+        const headers = {};
+        response.headers.forEach((value, name) => headers[name] = value);
+        return { url: location.href, headers: headers, mixed: mixed };
+    });
+";
+
+/// Whether an insecure subresource can alter the page or not.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum MixedContentKind {
+    /// Scripts, stylesheets, frames, requests... Blocked by browsers.
+    Active,
+    /// Images, audio and video. Usually loaded with a warning.
+    Passive
+}
+
+impl MixedContentKind {
+    fn from_initiator(initiator: &str) -> Self {
+        match initiator {
+            "img" | "image" | "audio" | "video" | "source" | "track" => MixedContentKind::Passive,
+            _ => MixedContentKind::Active
+        }
+    }
+}
+
+/// A resource loaded (or referenced) over http by a page served over https.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct MixedContent {
+    pub url: String,
+    /// The tag name or the performance initiator type of the resource (`script`, `img`, `xmlhttprequest`...).
+    pub initiator: String,
+    pub kind: MixedContentKind
+}
+
+/// The security-related headers of a response.
+/// A field is `None` when the header was not sent by the server.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Default)]
+pub struct SecurityHeaders {
+    pub strict_transport_security: Option<String>,
+    pub content_security_policy: Option<String>,
+    pub x_frame_options: Option<String>,
+    pub x_content_type_options: Option<String>,
+    pub referrer_policy: Option<String>,
+    pub permissions_policy: Option<String>
+}
+
+impl SecurityHeaders {
+    fn from_json(headers: &JsonValue) -> Self {
+        let header = |name: &str| headers[name].as_str().map(|value| value.to_string());
+        SecurityHeaders {
+            strict_transport_security: header("strict-transport-security"),
+            content_security_policy: header("content-security-policy"),
+            x_frame_options: header("x-frame-options"),
+            x_content_type_options: header("x-content-type-options"),
+            referrer_policy: header("referrer-policy"),
+            permissions_policy: header("permissions-policy")
+        }
+    }
+
+    /// Return the names of the headers which were not sent by the server.
+    pub fn missing(&self) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        if self.strict_transport_security.is_none() {
+            missing.push("strict-transport-security");
+        }
+        if self.content_security_policy.is_none() {
+            missing.push("content-security-policy");
+        }
+        if self.x_frame_options.is_none() {
+            missing.push("x-frame-options");
+        }
+        if self.x_content_type_options.is_none() {
+            missing.push("x-content-type-options");
+        }
+        if self.referrer_policy.is_none() {
+            missing.push("referrer-policy");
+        }
+        if self.permissions_policy.is_none() {
+            missing.push("permissions-policy");
+        }
+        missing
+    }
+}
+
+/// The result of [Tab::security_audit()](../tab/struct.Tab.html#method.security_audit).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct SecurityReport {
+    pub url: String,
+    /// The headers of a second request of the url, which can differ from the response of the loaded page.
+    pub headers: SecurityHeaders,
+    pub mixed_content: Vec<MixedContent>
+}
+
+impl SecurityReport {
+    pub(crate) fn from_json(json: &JsonValue) -> Option<Self> {
+        if !json["url"].is_string() || !json["headers"].is_object() || !json["mixed"].is_array() {
+            return None;
+        }
+
+        let mut mixed_content = Vec::new();
+        for resource in json["mixed"].members() {
+            let initiator = resource["initiator"].as_str().unwrap_or("other").to_string();
+            mixed_content.push(MixedContent {
+                url: resource["url"].as_str()?.to_string(),
+                kind: MixedContentKind::from_initiator(&initiator),
+                initiator
+            });
+        }
+
+        Some(SecurityReport {
+            url: json["url"].to_string(),
+            headers: SecurityHeaders::from_json(&json["headers"]),
+            mixed_content
+        })
+    }
+
+    /// Return true if the page is served over https, sends every security header and has no active mixed content.
+    pub fn is_compliant(&self) -> bool {
+        self.url.starts_with("https:")
+            && self.headers.missing().is_empty()
+            && self.mixed_content.iter().all(|resource| resource.kind == MixedContentKind::Passive)
+    }
+}
@@ -0,0 +1,153 @@
+//! Interaction with dropdowns (`<select>` elements)
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::{Browser, Selector}, select::Select};
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.tabs[0].navigate("https://example.com/signup").unwrap();
+//!
+//! let country = Select::new(session.tabs[0].find(Selector::Css, "select#country").unwrap().unwrap()).unwrap();
+//! country.select_by_visible_text("France").unwrap();
+//! assert_eq!(country.selected_options().unwrap()[0].value, "fr");
+//! ```
+
+use json::JsonValue;
+use log::error;
+use crate::elements::Element;
+use crate::error::*;
+use crate::http_requests::execute_script_sync;
+
+/// Selects an option of a select element (arguments: the select, the criterion, the key).
+/// Returns `selected`, `missing` or `disabled`. Events are dispatched like when the user chooses the option.
+const SELECT_SCRIPT: &str = "
+    const [select, by, key] = arguments;
+    const normalize = text => String(text).replace(/\\s+/g, ' ').trim();
+    const options = Array.from(select.options);
+    const option = by === 'index' ? options[key] : options.find(option => by === 'value' ? option.value === key : normalize(option.text) === normalize(key));
+    if (!option) {
+        return 'missing';
+    }
+    if (option.disabled) {
+        return 'disabled';
+    }
+    if (!option.selected) {
+        option.selected = true;
+        select.dispatchEvent(new Event('input', { bubbles: true }));
+        select.dispatchEvent(new Event('change', { bubbles: true }));
+    }
+    return 'selected';
+";
+
+const OPTIONS_SCRIPT: &str = "
+    return Array.from(arguments[0].options).map((option, index) => ({
+        text: option.text.replace(/\\s+/g, ' ').trim(),
+        value: option.value,
+        index,
+        selected: option.selected,
+        disabled: option.disabled
+    }));
+";
+
+/// An option of a [Select](struct.Select.html).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct SelectOption {
+    /// The visible text, whitespaces being collapsed.
+    pub text: String,
+    pub value: String,
+    pub index: usize,
+    pub selected: bool,
+    pub disabled: bool
+}
+
+/// A `<select>` element.
+pub struct Select {
+    element: Element
+}
+
+impl Select {
+    /// Wrap a select element.
+    /// Return [InvalidArgument](../error/enum.ErrorKind.html#variant.InvalidArgument) if the element is not a select.
+    pub fn new(element: Element) -> Result<Select, WebdriverError> {
+        let tag_name = element.get_tag_name()?;
+        if !tag_name.eq_ignore_ascii_case("select") {
+            error!("a {} element is not a select", tag_name);
+            return Err(WebdriverError::from(ErrorKind::InvalidArgument).with_message(format!("expected a select element, found a {} element", tag_name)));
+        }
+        Ok(Select { element })
+    }
+
+    /// The wrapped element.
+    pub fn element(&self) -> &Element {
+        &self.element
+    }
+
+    /// Return true if several options can be selected.
+    pub fn is_multiple(&self) -> Result<bool, WebdriverError> {
+        Ok(self.element.get_property("multiple")? == "true")
+    }
+
+    /// Return the options of the select.
+    pub fn options(&self) -> Result<Vec<SelectOption>, WebdriverError> {
+        self.element.select_tab()?;
+        let json = execute_script_sync(&self.element.connection, &self.element.session_id, OPTIONS_SCRIPT, vec![self.element.as_json_object()])?;
+
+        let mut options = Vec::new();
+        for option in json.members() {
+            match (option["text"].as_str(), option["value"].as_str(), option["index"].as_usize(), option["selected"].as_bool(), option["disabled"].as_bool()) {
+                (Some(text), Some(value), Some(index), Some(selected), Some(disabled)) => options.push(SelectOption {
+                    text: text.to_string(),
+                    value: value.to_string(),
+                    index,
+                    selected,
+                    disabled
+                }),
+                _ => {
+                    error!("option was not understood: {}", option);
+                    return Err(ErrorKind::InvalidResponse.into());
+                }
+            }
+        }
+        Ok(options)
+    }
+
+    /// Return the selected options.
+    pub fn selected_options(&self) -> Result<Vec<SelectOption>, WebdriverError> {
+        Ok(self.options()?.into_iter().filter(|option| option.selected).collect())
+    }
+
+    /// -> take the criterion and the key identifying the option
+    fn select_by(&self, by: &str, key: JsonValue) -> Result<(), WebdriverError> {
+        self.element.select_tab()?;
+        let result = execute_script_sync(&self.element.connection, &self.element.session_id, SELECT_SCRIPT, vec![self.element.as_json_object(), by.into(), key.clone()])?;
+        match result.as_str() {
+            Some("selected") => Ok(()),
+            Some("missing") => Err(WebdriverError::from(ErrorKind::NoSuchElement).with_message(format!("no option has the {} {}", by, key))),
+            Some("disabled") => Err(WebdriverError::from(ErrorKind::InvalidElementState).with_message(format!("the option with the {} {} is disabled", by, key))),
+            _ => {
+                error!("result of option selection was not understood: {}", result);
+                Err(ErrorKind::InvalidResponse.into())
+            }
+        }
+    }
+
+    /// Select the option displaying a text (whitespaces being collapsed).
+    /// Return [NoSuchElement](../error/enum.ErrorKind.html#variant.NoSuchElement) if there is no such option,
+    /// and [InvalidElementState](../error/enum.ErrorKind.html#variant.InvalidElementState) if the option is disabled.
+    pub fn select_by_visible_text(&self, text: &str) -> Result<(), WebdriverError> {
+        self.select_by("text", text.into())
+    }
+
+    /// Select the option with a value (its `value` attribute, or its text if it has none).
+    pub fn select_by_value(&self, value: &str) -> Result<(), WebdriverError> {
+        self.select_by("value", value.into())
+    }
+
+    /// Select the option at an index, starting at 0.
+    pub fn select_by_index(&self, index: usize) -> Result<(), WebdriverError> {
+        self.select_by("index", index.into())
+    }
+}
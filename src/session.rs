@@ -1,17 +1,85 @@
 //! Sessions allow you to control tabs
 
-use json::*;
 use std::time::Duration;
 use std::result::Result;
 use crate::enums::*;
+use crate::capabilities::*;
+use crate::config::*;
+use crate::status::*;
 use crate::timeouts::*;
+use crate::retry::RetryPolicy;
+use crate::wait::Wait;
+use crate::compatibility::{VersionCheck, check_versions, check_failed_versions};
 use crate::tab::*;
 use crate::error::*;
-use std::process::{Command, Stdio};
+use std::process::{self, Stdio};
+use crate::command::Command;
+use crate::transcript::Transcript;
+use crate::downloads::Downloads;
+use crate::permissions::{Permission, PermissionState};
+use crate::hooks::Hook;
+use crate::keep_alive::KeepAlive;
+use crate::driver_log::DriverLog;
+use crate::recovery::SessionRestarted;
+use std::path::{Path, PathBuf};
 use std::thread;
 use log::{debug, info, warn, error};
-use std::rc::Rc;
+use json::{JsonValue, object};
+use std::sync::{Arc, Mutex};
 use crate::http_requests::*;
+use crate::responses::to_json_value;
+
+/// The port of the driver the sessions are created on, unless another one is configured
+const DEFAULT_PORT: u16 = 4444;
+
+/// -> return a port no program is listening on, for a launched driver
+fn free_port() -> Result<u16, WebdriverError> {
+    std::net::TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|address| address.port())
+        .map_err(|error| WebdriverError::custom(format!("failed to find a free port for the driver: {}", error)))
+}
+
+/// -> take the launched driver
+/// stop it with the browsers it opened (on Windows, they would be left running)
+fn kill_driver(process: &mut process::Child) -> std::io::Result<()> {
+    if cfg!(windows) && kill_pid(process.id()).is_ok() {
+        return process.wait().map(|_| ());
+    }
+    process.kill().and_then(|()| process.wait().map(|_| ()))
+}
+
+/// -> take the id of a process which is not a child
+/// stop it (and its child processes on Windows)
+fn kill_pid(pid: u32) -> std::io::Result<()> {
+    let mut command = if cfg!(windows) {
+        let mut command = process::Command::new("taskkill");
+        command.args(["/PID", &pid.to_string(), "/T", "/F"]);
+        command
+    } else {
+        let mut command = process::Command::new("kill");
+        command.arg(pid.to_string());
+        command
+    };
+    let status = command.stdout(Stdio::null()).stderr(Stdio::null()).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!("failed to kill process {} ({})", pid, status)))
+    }
+}
+
+/// -> take the id of a process which is not a child
+/// -> return true if it is still running
+fn is_running(pid: u32) -> bool {
+    if cfg!(windows) {
+        process::Command::new("tasklist").args(["/FI", &format!("PID eq {}", pid), "/NH"]).stderr(Stdio::null()).output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).split_whitespace().any(|word| word == pid.to_string()))
+            .unwrap_or(false)
+    } else {
+        process::Command::new("kill").args(["-0", &pid.to_string()]).stdout(Stdio::null()).stderr(Stdio::null()).status().map(|status| status.success()).unwrap_or(false)
+    }
+}
 
 /// This is the more important object.
 /// Tabs can be accessed within the session.
@@ -31,19 +99,41 @@ use crate::http_requests::*;
 /// session.tabs[1].navigate("https://mubelotix.dev/").unwrap();
 /// ```
 pub struct Session {
-    id: Rc<String>,
+    pub(crate) id: Arc<String>,
+    pub(crate) connection: Arc<Connection>,
+    browser: Browser,
+    blocked_urls: Vec<String>,
+    /// the timezone set on every tab with the Chrome DevTools Protocol
+    timezone: Option<String>,
     /// Contains every manually created tabs and default tab.
     /// Do not contains tabs created by web pages with javascript unless you call [update_tabs()](https://to.do/).
     pub tabs: Vec<Tab>,
-    webdriver_process: Option<std::process::Child>,
+    /// the driver launched by the session, shared with the closure restarting the session after a crash
+    webdriver_process: Arc<Mutex<Option<std::process::Child>>>,
+    /// the driver launched by the persisted session this session was resumed from
+    resumed_driver_pid: Option<u32>,
+    /// true if the browser must stay open to be resumed
+    persisted: bool,
+    /// true if the session was already ended by quit()
+    ended: bool,
+    /// how long the driver is given to exit by itself before it is killed
+    shutdown_grace_period: Duration,
+    /// the capabilities returned by the driver (null for resumed sessions)
+    capabilities: JsonValue,
+    /// the download directory set in the capabilities
+    downloads: Option<Arc<Downloads>>,
+    /// the thread pinging the session when it is idle (None if disabled)
+    keep_alive: Option<KeepAlive>,
+    /// the output of the launched driver
+    driver_log: Arc<DriverLog>,
 }
 
 impl Session {
     /// Create a session of a specific [browser](https://to.do/).
     /// Headless mean that the browser will be opened but not displayed (useful for servers).
     /// The crate will request a webdriver server at http://localhost:4444.
-    /// If no webdriver is listening, one will be launched, but the program ([geckodriver](https://to.do/) or [chromedriver](https://to.do/))
-    /// must be located at the same place than the running program.
+    /// If no webdriver is listening, one will be launched on a free port (so that several programs can launch their own driver at the same time), but the program ([geckodriver](https://to.do/) or [chromedriver](https://to.do/))
+    /// must be in the current directory, next to the running program or in the `PATH` (see [SessionConfig::driver_path()](../config/struct.SessionConfig.html#method.driver_path) to use another location).
     /// 
     /// # Example
     /// 
@@ -52,50 +142,87 @@ impl Session {
     /// let mut session = Session::new(Browser::Firefox, false).unwrap();
     /// ```
     pub fn new(browser: Browser, headless: bool) -> Result<Self, WebdriverError> {
+        Session::new_with_capabilities(Capabilities::new(browser).headless(headless))
+    }
+
+    /// Create a session with custom [capabilities](../capabilities/struct.Capabilities.html).
+    /// Works like [new()](#method.new).
+    /// 
+    /// # Example
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, capabilities::Capabilities, enums::Browser};
+    /// let mut session = Session::new_with_capabilities(Capabilities::new(Browser::Firefox).headless(true)).unwrap();
+    /// ```
+    pub fn new_with_capabilities(capabilities: Capabilities) -> Result<Self, WebdriverError> {
+        Session::new_with_config(SessionConfig::new(capabilities))
+    }
+
+    /// Create a session with a custom [configuration](../config/struct.SessionConfig.html).
+    /// Works like [new()](#method.new), but no driver is launched when a remote server or a custom transport is configured.
+    pub fn new_with_config(mut config: SessionConfig) -> Result<Self, WebdriverError> {
+        // without its binary, chromedriver would launch Chrome instead of the requested browser
+        if let Browser::ChromiumBased { binary } = &config.capabilities.browser {
+            if binary.as_os_str().is_empty() {
+                return Err(WebdriverError::from(ErrorKind::InvalidArgument).with_message("the binary of the Chromium-based browser is missing".to_string()));
+            }
+        }
+        let mut session = Session::connect(&mut config)?;
+        session.shutdown_grace_period = config.shutdown_grace_period;
+        session.connection.set_request_timeout(config.request_timeout);
+        if let Some(timeouts) = config.timeouts {
+            session.set_timeouts(timeouts)?;
+        }
+        session.set_keep_alive(config.keep_alive);
+        if config.crash_recovery {
+            session.enable_crash_recovery(&config);
+        }
+        Ok(session)
+    }
+
+    /// -> take a configuration, receiving the port of the driver if one is launched
+    /// -> return a session created on the configured driver, launched if needed
+    fn connect(config: &mut SessionConfig) -> Result<Self, WebdriverError> {
         info!{"Creating a session..."};
-        let result = Session::new_session(browser, headless);
+        let capabilities = &config.capabilities;
+
+        if config.dry_run {
+            return Session::new_session(Connection::dry_run(), capabilities, VersionCheck::Off);
+        }
+        if let Some(transport) = &config.transport {
+            return Session::new_session(Connection::with_transport(Arc::clone(transport), true).max_in_flight(config.max_in_flight), capabilities, config.version_check);
+        }
+        if let Some(url) = &config.remote_url {
+            let credentials = config.credentials.as_ref().map(|(username, password)| (username.as_str(), password.as_str()));
+            return Session::new_session(Connection::new(url, credentials).max_in_flight(config.max_in_flight), capabilities, config.version_check);
+        }
+
+        let result = Session::new_session(Connection::local(config.port.unwrap_or(DEFAULT_PORT)).max_in_flight(config.max_in_flight), capabilities, config.version_check);
 
-        if let Err(WebdriverError::FailedRequest) = result {
+        if matches!(&result, Err(error) if *error == ErrorKind::FailedRequest) {
             warn!{"No webdriver launched."}
-            if cfg!(unix) {
-                if browser == Browser::Firefox {
-                    info!{"Launching geckodriver..."}
-                    let p = Command::new("./geckodriver")
-                        .stdout(Stdio::null())
-                        .stderr(Stdio::null())
-                        .spawn()
-                        .expect("Failed to start process.");
-                    thread::sleep(Duration::from_millis(2000));
-                    let result = Session::new_session(browser, headless);
-                    if let Ok(mut result) = result {
-                        info!{"Session created successfully."}
-                        result.webdriver_process = Some(p);
-                        return Ok(result);
-                    } else if let Err(e) = result {
-                        error!("Failed to create session. error : {:?}.", e);
-                        return Err(e);
-                    }
-                } else {
-                    info!{"Launching chromedriver..."}
-                    let p = Command::new("./chromedriver")
-                        .arg("--port=4444")
-                        .stdout(Stdio::null())
-                        .stderr(Stdio::null())
-                        .spawn()
-                        .expect("Failed to start process");
-                    thread::sleep(Duration::from_millis(2000));
-                    let result = Session::new_session(browser, headless);
-                    if let Ok(mut result) = result {
-                        info!{"Session created successfully."}
-                        result.webdriver_process = Some(p);
-                        return Ok(result);
-                    } else if let Err(e) = result{
-                        error!("Failed to create session. error : {:?}.", e);
-                        return Err(e);
-                    }
+            // the port is kept in the configuration, so that a driver launched again after a crash listens on it too
+            let port = match config.port {
+                Some(port) => port,
+                None => free_port()?,
+            };
+            config.port = Some(port);
+            let driver_log = Arc::new(DriverLog::new(config.driver_log_lines, config.driver_log_file.clone()));
+            let mut p = Session::launch_driver(config, &driver_log)?;
+            let result = Session::new_session(Connection::local(port).max_in_flight(config.max_in_flight), capabilities, config.version_check);
+            if let Ok(mut result) = result {
+                info!{"Session created successfully."}
+                result.webdriver_process = Arc::new(Mutex::new(Some(p)));
+                result.driver_log = driver_log;
+                return Ok(result);
+            } else if let Err(e) = result {
+                error!("Failed to create session. error : {:?}.", e);
+                for line in driver_log.lines() {
+                    error!("{}: {}", capabilities.browser.driver_name(), line);
                 }
-            } else {
-                panic!("Please launch the webdriver manually.")
+                // the driver would be left running
+                let _ = kill_driver(&mut p);
+                return Err(e);
             }
         } else {
             return result;
@@ -104,75 +231,209 @@ impl Session {
         result
     }
 
-    fn new_session(browser: Browser, headless: bool)  -> Result<Self, WebdriverError> {
-        // Detect platform
-        let platform = Platform::current();
-        if let Platform::Unknow = platform {
-            return Err(WebdriverError::UnsupportedPlatform);
-        }
-
-        // Generate capabilities
-        let post_data = match browser {
-            Browser::Firefox => {
-                if headless {
-                    object!{
-                        "capabilities" => object!{
-                            "alwaysMatch" => object!{
-                                "platformName" => platform.to_string(),
-                                "browserName" => browser.to_string(),
-                                "moz:firefoxOptions" => object! {
-                                    "args" => array!{"-headless"}
-                                },
-                            }
-                        }
-                    }
-                } else {
-                    object!{
-                        "capabilities" => object!{
-                            "alwaysMatch" => object!{
-                                "platformName" => platform.to_string(),
-                                "browserName" => browser.to_string()
-                            }
-                        }
-                    }
-                }
-            },
-            Browser::Chrome => {
-                if headless {
-                    object!{
-                        "capabilities" => object!{
-                            "alwaysMatch" => object!{
-                                "platformName" => platform.to_string(),
-                                "browserName" => browser.to_string(),
-                                "goog:chromeOptions" => object! {
-                                    "args" => array!{"-headless"}
-                                }
-                            }
-                        }
-                    }
-                } else {
-                    object!{
-                        "capabilities" => object!{
-                            "alwaysMatch" => object!{
-                                "platformName" => platform.to_string(),
-                                "browserName" => browser.to_string()
-                            }
+    /// -> take the connection to the server and capabilities
+    /// -> return the body of the request creating a session
+    fn new_session_request(connection: &Connection, capabilities: &Capabilities) -> Result<JsonValue, WebdriverError> {
+        let mut post_data = capabilities.to_json();
+        if connection.remote {
+            // the platform of the remote node is chosen by the server
+            post_data["capabilities"]["alwaysMatch"].remove("platformName");
+        } else if let Platform::Unknow = Platform::current() {
+            return Err(ErrorKind::UnsupportedPlatform.into());
+        }
+        Ok(post_data)
+    }
+
+    /// create a new session when the browser or the launched driver crashes
+    fn enable_crash_recovery(&self, config: &SessionConfig) {
+        let config = config.clone();
+        let process = Arc::clone(&self.webdriver_process);
+        let driver_log = Arc::clone(&self.driver_log);
+        self.connection.set_restarter(Some(Arc::new(move |connection: &Connection| {
+            Session::restart(connection, &config, &process, &driver_log)
+        })));
+        let process = Arc::clone(&self.webdriver_process);
+        self.connection.set_exit_check(Some(Arc::new(move || Session::driver_exited(&process))));
+
+        // the settings applied by prepare_tab() are applied again to the tabs opened after a restart
+        // (the download directory is a capability, so the new session keeps it)
+        let mut setup = Vec::new();
+        if let Some(vendor) = self.browser.cdp_vendor() {
+            let cdp_command = |method: &str, params: JsonValue| Command::ExecuteCdpCommand { vendor: vendor.to_string(), method: method.to_string(), params };
+            if !self.blocked_urls.is_empty() {
+                setup.push(cdp_command("Network.enable", object!{}));
+                setup.push(cdp_command("Network.setBlockedURLs", object!{"urls" => self.blocked_urls.clone()}));
+            }
+            if let Some(timezone) = &self.timezone {
+                setup.push(cdp_command("Emulation.setTimezoneOverride", object!{"timezoneId" => timezone.as_str()}));
+            }
+        }
+        self.connection.set_tab_setup(setup);
+    }
+
+    /// -> take the driver launched by a session (None if it was not launched by the session)
+    /// -> return true if the driver exited
+    fn driver_exited(process: &Mutex<Option<process::Child>>) -> bool {
+        match process.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_mut() {
+            Some(child) => !matches!(child.try_wait(), Ok(None)),
+            None => false,
+        }
+    }
+
+    /// -> take the connection of a crashed session, the configuration, the driver of the session and its output
+    /// -> return the id of a new session created with the same capabilities, the driver being launched again if it exited
+    fn restart(connection: &Connection, config: &SessionConfig, process: &Mutex<Option<process::Child>>, driver_log: &Arc<DriverLog>) -> Result<String, WebdriverError> {
+        if Session::driver_exited(process) {
+            warn!("the webdriver process exited, launching it again");
+            *process.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Session::launch_driver(config, driver_log)?);
+        }
+        let (session_id, _) = new_session(connection, &Session::new_session_request(connection, &config.capabilities)?.to_string())?;
+        Ok(session_id)
+    }
+
+    /// -> take a configuration and the log receiving the output of the driver
+    /// -> return the configured driver, launched and ready to create sessions
+    fn launch_driver(config: &SessionConfig, driver_log: &Arc<DriverLog>) -> Result<process::Child, WebdriverError> {
+        let capabilities = &config.capabilities;
+        let port = config.port.unwrap_or(DEFAULT_PORT);
+        info!{"Launching {} on port {}...", capabilities.browser.driver_name(), port}
+        let mut command = process::Command::new(Session::driver_binary(config)?);
+        match capabilities.browser {
+            // safaridriver does not understand --port=4444
+            Browser::Safari => command.arg("--port").arg(port.to_string()),
+            _ => command.arg(format!("--port={}", port)),
+        };
+        command
+            .args(&config.driver_args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        // Firefox uses the timezone of its process, inherited from the driver
+        if let (Browser::Firefox, Some(timezone)) = (&capabilities.browser, &capabilities.timezone) {
+            command.env("TZ", timezone);
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            // the driver must not open a console window (CREATE_NO_WINDOW)
+            command.creation_flags(0x0800_0000);
+        }
+        let mut process = command.spawn().map_err(|error| WebdriverError::custom(format!("failed to launch {}: {}", capabilities.browser.driver_name(), error)))?;
+        driver_log.capture(&mut process);
+        Session::wait_for_driver(port);
+        Ok(process)
+    }
+
+    /// Return the path of the driver to launch: the configured one, or the first one found in the current directory,
+    /// next to the program and in the directories of the `PATH` (`geckodriver.exe` on Windows).
+    /// With the `driver-manager` feature, a driver is downloaded if none was configured and none was found.
+    fn driver_binary(config: &SessionConfig) -> Result<PathBuf, WebdriverError> {
+        if let Some(path) = &config.driver_path {
+            return Ok(path.clone());
+        }
+        if let Some(path) = config.capabilities.browser.system_driver() {
+            return Ok(PathBuf::from(path));
+        }
+
+        let file_name = format!("{}{}", config.capabilities.browser.driver_name(), std::env::consts::EXE_SUFFIX);
+        let path = Path::new(".").join(&file_name);
+        let next_to_program = std::env::current_exe().ok().and_then(|program| program.parent().map(|directory| directory.join(&file_name)));
+        let in_path: Vec<PathBuf> = std::env::var_os("PATH").map(|paths| std::env::split_paths(&paths).map(|directory| directory.join(&file_name)).collect()).unwrap_or_default();
+        if let Some(found) = std::iter::once(path.clone()).chain(next_to_program).chain(in_path).find(|candidate| candidate.is_file()) {
+            return Ok(found);
+        }
+
+        #[cfg(feature = "driver-manager")]
+        let path = crate::driver_manager::ensure_driver(&config.capabilities.browser)?;
+        Ok(path)
+    }
+
+    /// Wait until the launched driver is ready to create sessions (10 seconds at most).
+    fn wait_for_driver(port: u16) {
+        let connection = Connection::local(port);
+        for _ in 0..100 {
+            if let Ok(DriverStatus { ready: true, .. }) = get_status(&connection) {
+                debug!("webdriver is ready");
+                return;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        warn!("webdriver is still not ready after 10 seconds");
+    }
+
+    /// Get the [status](../status/struct.DriverStatus.html) of a webdriver server.
+    /// This can be used to check that a server is running before creating a session.
+    /// 
+    /// # Example
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::session::Session;
+    /// if let Ok(status) = Session::status("http://localhost:4444") {
+    ///     println!("driver is ready: {}, version: {:?}", status.ready, status.build);
+    /// }
+    /// ```
+    pub fn status(url: &str) -> Result<DriverStatus, WebdriverError> {
+        get_status(&Connection::new(url, None))
+    }
+
+    fn new_session(mut connection: Connection, capabilities: &Capabilities, version_check: VersionCheck) -> Result<Self, WebdriverError> {
+        let post_data = Session::new_session_request(&connection, capabilities)?;
+        // built before the session is created, so that the browser is not left open if it fails
+        let downloads = match &capabilities.download_directory {
+            Some(directory) => Some(Arc::new(Downloads::new(directory.clone())?)),
+            None => None,
+        };
+
+        // Send request
+        let (session_id, session_capabilities) = match new_session(&connection, &post_data.to_string()) {
+            Ok(session) => session,
+            Err(error) => {
+                if error == ErrorKind::SessionNotCreated {
+                    if let Ok(DriverStatus { build: Some(version), .. }) = get_status(&connection) {
+                        let requested_version = post_data["capabilities"]["alwaysMatch"]["browserVersion"].as_str();
+                        match check_failed_versions(&capabilities.browser, &version, requested_version, error.message().unwrap_or_default()) {
+                            Err(message) if version_check == VersionCheck::Error => return Err(WebdriverError::from(ErrorKind::SessionNotCreated).with_message(message)),
+                            Err(message) if version_check == VersionCheck::Warn => warn!("{}", message),
+                            _ => warn!("Session creation failed with driver {}, check that it supports the browser", version),
                         }
                     }
                 }
+                return Err(error);
             }
         };
-        
-        // Send request
-        let session_id = new_session(&post_data.to_string())?;
+        connection.debugger_address = ["goog:chromeOptions", "ms:edgeOptions"].iter()
+            .find_map(|key| session_capabilities[*key]["debuggerAddress"].as_str())
+            .map(|address| address.to_string());
+        connection.web_socket_url = session_capabilities["webSocketUrl"].as_str().map(|url| url.to_string());
         let mut session = Session {
-            id: Rc::new(session_id),
+            id: Arc::new(session_id),
+            connection: Arc::new(connection),
+            browser: capabilities.browser.clone(),
+            blocked_urls: capabilities.blocked_url_patterns(),
+            timezone: capabilities.timezone.clone().filter(|_| capabilities.browser.cdp_vendor().is_some()),
             tabs: Vec::new(),
-            webdriver_process: None
+            webdriver_process: Arc::new(Mutex::new(None)),
+            resumed_driver_pid: None,
+            persisted: false,
+            ended: false,
+            shutdown_grace_period: DEFAULT_SHUTDOWN_GRACE_PERIOD,
+            capabilities: session_capabilities,
+            downloads,
+            keep_alive: None,
+            driver_log: Arc::new(DriverLog::new(0, None))
         };
 
         session.update_tabs()?;
 
+        if version_check != VersionCheck::Off {
+            if let Err(message) = check_versions(&session.capabilities) {
+                if version_check == VersionCheck::Error {
+                    // the session is deleted on drop
+                    return Err(WebdriverError::from(ErrorKind::SessionNotCreated).with_message(message));
+                }
+                warn!("{}", message);
+            }
+        }
+
         Ok(session)
     }
 
@@ -190,18 +451,58 @@ impl Session {
     /// assert_eq!(session.tabs.len(), 2); // new tab is accessible
     /// ```
     pub fn open_tab(&mut self) -> Result<usize, WebdriverError> {
-        let tab_id = new_tab(&self.id)?;
-        let new_tab = Tab::new_from(tab_id, Rc::clone(&self.id));
+        self.open(WindowType::Tab)
+    }
+
+    /// Create a new window in the session, like [open_tab()](#method.open_tab).
+    /// Some browsers open a tab instead, see [Tab::window_type()](../tab/struct.Tab.html#method.window_type).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::{Browser, WindowType}};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    ///
+    /// let index = session.open_window().unwrap();
+    /// assert_eq!(session.tabs[index].window_type(), Some(WindowType::Window));
+    /// ```
+    pub fn open_window(&mut self) -> Result<usize, WebdriverError> {
+        self.open(WindowType::Window)
+    }
+
+    /// -> take the type of window to open
+    /// -> return the index of the new tab
+    fn open(&mut self, window_type: WindowType) -> Result<usize, WebdriverError> {
+        let (tab_id, window_type) = new_window(&self.connection, &self.id, window_type)?;
+        let mut new_tab = Tab::with_connection(tab_id, Arc::clone(&self.id), Arc::clone(&self.connection), self.browser.clone());
+        new_tab.window_type = Some(window_type);
+        self.prepare_tab(&mut new_tab)?;
         self.tabs.push(new_tab);
 
         Ok(self.tabs.len() - 1)
     }
 
+    /// -> take a tab which was not known by the session
+    /// -> apply the settings of the session to it
+    fn prepare_tab(&self, tab: &mut Tab) -> Result<(), WebdriverError> {
+        tab.downloads = self.downloads.clone();
+        if !self.blocked_urls.is_empty() {
+            tab.block_urls(&self.blocked_urls)?;
+        }
+        if let Some(timezone) = &self.timezone {
+            tab.set_timezone(timezone)?;
+        }
+        Ok(())
+    }
+
     /// When a tab is created with [open_tab()](https://to.do/) method, it is accessible directly.
     /// But sometimes a tab is created by someone else (from a web page with javascript) and you don't want to care about it!
     /// This tab will not be accessible by your program because you never asked it.
     /// However if you want to access every open tab, call this function.
     /// 
+    /// Tabs closed by someone else are removed (the other tabs keep their order).
+    /// Until then, their methods return [TabClosed](../error/enum.ErrorKind.html#variant.TabClosed).
+    /// 
     /// # Example
     /// 
     /// ```rust
@@ -231,24 +532,486 @@ impl Session {
     /// assert_eq!(session.tabs.len(), 2);
     /// ```
     pub fn update_tabs(&mut self) -> Result<(), WebdriverError> {
-        let tabs_id = get_open_tabs(&self.id)?;
+        let tabs_id = self.window_handles()?;
+
+        // forget the tabs which were closed (by web pages for example)
+        for tab in self.tabs.iter_mut().filter(|tab| !tabs_id.contains(&tab.id)) {
+            debug!("tab {} was closed", tab.id);
+            tab.close_on_drop = false;
+        }
+        self.tabs.retain(|tab| tabs_id.contains(&tab.id));
+
         for tab_id in tabs_id {
             if self.tabs.iter().position(|element| *element.id == tab_id).is_none() {
-                self.tabs.push(Tab::new_from(tab_id, Rc::clone(&self.id)));
+                let mut new_tab = Tab::with_connection(tab_id.0, Arc::clone(&self.id), Arc::clone(&self.connection), self.browser.clone());
+                self.prepare_tab(&mut new_tab)?;
+                self.tabs.push(new_tab);
             }
         }
 
         Ok(())
     }
 
+    /// Find the first tab whose title matches a predicate, [updating the tabs](#method.update_tabs) first.
+    /// The tab is left selected. Tabs closed while they are checked are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::{Browser, Selector}};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://example.com/").unwrap();
+    /// session.tabs[0].find(Selector::Css, "#login").unwrap().unwrap().click().unwrap();
+    ///
+    /// // the login page opened a popup
+    /// let popup = session.find_tab_by_title(|title| title.starts_with("Sign in")).unwrap().unwrap();
+    /// popup.find(Selector::Css, "#password").unwrap().unwrap().type_text("password").unwrap();
+    /// ```
+    pub fn find_tab_by_title<F: Fn(&str) -> bool>(&mut self, predicate: F) -> Result<Option<&mut Tab>, WebdriverError> {
+        self.find_tab(|tab| Ok(predicate(&tab.get_title()?)))
+    }
+
+    /// Find the first tab whose url matches a predicate, like [find_tab_by_title()](#method.find_tab_by_title).
+    pub fn find_tab_by_url<F: Fn(&str) -> bool>(&mut self, predicate: F) -> Result<Option<&mut Tab>, WebdriverError> {
+        self.find_tab(|tab| Ok(predicate(&tab.get_url()?)))
+    }
+
+    /// Return the handles of the windows and tabs open in the browser, including those opened by web pages.
+    pub fn window_handles(&self) -> Result<Vec<WindowHandle>, WebdriverError> {
+        Ok(get_open_tabs(&self.connection, &self.id)?.into_iter().map(WindowHandle).collect())
+    }
+
+    /// Select the window or tab with a handle, [updating the tabs](#method.update_tabs) first.
+    /// Return [TabClosed](../error/enum.ErrorKind.html#variant.TabClosed) if it was closed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::{Browser, Selector}};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// let first = session.tabs[0].get_handle();
+    /// session.open_tab().unwrap();
+    ///
+    /// let tab = session.switch_to_window(&first).unwrap();
+    /// tab.navigate("https://example.com/").unwrap();
+    /// ```
+    pub fn switch_to_window(&mut self, handle: &WindowHandle) -> Result<&mut Tab, WebdriverError> {
+        self.update_tabs()?;
+        let index = match self.tabs.iter().position(|tab| *tab.id == *handle) {
+            Some(index) => index,
+            None => return Err(ErrorKind::TabClosed.into()),
+        };
+        self.tabs[index].select()?;
+        Ok(&mut self.tabs[index])
+    }
+
+    /// -> take a condition checked on each tab
+    /// -> return the first tab meeting it
+    fn find_tab<F: Fn(&Tab) -> Result<bool, WebdriverError>>(&mut self, condition: F) -> Result<Option<&mut Tab>, WebdriverError> {
+        self.update_tabs()?;
+        for index in 0..self.tabs.len() {
+            match condition(&self.tabs[index]) {
+                Ok(true) => return Ok(Some(&mut self.tabs[index])),
+                Ok(false) => (),
+                Err(error) if error == ErrorKind::TabClosed || error == ErrorKind::NoSuchWindow => debug!("tab {} was closed", self.tabs[index].id),
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(None)
+    }
+
+    /// End the session, closing the browser, then stop the driver if it was launched by the session and wait for it to exit.
+    /// Unlike dropping the session, errors are returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    ///
+    /// session.quit().unwrap();
+    /// ```
+    pub fn quit(mut self) -> Result<(), WebdriverError> {
+        self.keep_alive = None;
+        self.connection.set_restarter(None);
+        self.connection.set_exit_check(None);
+        self.ended = true;
+        // the windows are closed with the session
+        for tab in &mut self.tabs {
+            tab.close_on_drop = false;
+        }
+        self.tabs.clear();
+
+        let result = match delete_session(&self.connection, &self.id) {
+            Err(error) if error == ErrorKind::InvalidSessionId => {
+                debug!("session {} was already ended", self.id);
+                Ok(())
+            },
+            result => result,
+        };
+        self.stop_driver()?;
+        result
+    }
+
+    /// Set how long the launched driver is given to exit by itself once the session is ended, before it is killed
+    /// (see [SessionConfig::shutdown_grace_period()](../config/struct.SessionConfig.html#method.shutdown_grace_period)).
+    pub fn set_shutdown_grace_period(&mut self, grace_period: Duration) {
+        self.shutdown_grace_period = grace_period;
+    }
+
+    /// stop the driver if it was launched by the session, once the grace period is over, and wait for it to exit
+    fn stop_driver(&mut self) -> Result<(), WebdriverError> {
+        let grace_period = Wait::new(self.shutdown_grace_period).interval(Duration::from_millis(50));
+
+        let process = self.webdriver_process.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+        if let Some(mut process) = process {
+            let exited = grace_period.until(|| process.try_wait().map_err(|error| WebdriverError::custom(error.to_string())));
+            if exited.is_err() {
+                info!("Killing webdriver process {}", process.id());
+                if let Err(error) = kill_driver(&mut process) {
+                    error!("failed to stop the webdriver process: {}", error);
+                    return Err(WebdriverError::custom(error.to_string()));
+                }
+            }
+        }
+        if let Some(pid) = self.resumed_driver_pid.take() {
+            let running = || is_running(pid);
+            // the pid can have been reused by another process since the driver exited
+            if grace_period.until(|| Ok(if running() { None } else { Some(()) })).is_err() && get_status(&self.connection).is_ok() {
+                info!("Killing webdriver process {} launched before the session was persisted", pid);
+                if let Err(error) = kill_pid(pid) {
+                    error!("failed to stop the webdriver process: {}", error);
+                    return Err(WebdriverError::custom(error.to_string()));
+                }
+                // the process is not a child, its exit cannot be awaited
+                Wait::new(Duration::from_secs(5)).until(|| Ok(if running() { None } else { Some(()) }))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return true if the driver process was launched by this session (and will be killed with it).
+    pub(crate) fn owns_driver(&self) -> bool {
+        self.webdriver_process.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).is_some() || self.resumed_driver_pid.is_some()
+    }
+
+    /// Return the last lines written by the driver on its stdout and stderr (the oldest first), to diagnose the errors reported by the driver.
+    /// Return an empty list if the driver was not launched by the session.
+    /// See [SessionConfig::driver_log_file()](../config/struct.SessionConfig.html#method.driver_log_file) to keep the whole output.
+    pub fn driver_log(&self) -> Vec<String> {
+        self.driver_log.lines()
+    }
+
+    /// Return the address of the DevTools server of the browser (`localhost:37469`), to connect a devtools client to Chrome or Edge.
+    /// Return None for other browsers and for resumed sessions.
+    pub fn debugger_address(&self) -> Option<String> {
+        self.connection.debugger_address.clone()
+    }
+
+    /// Return the browser controlled by the session.
+    pub fn get_browser(&self) -> Browser {
+        self.browser.clone()
+    }
+
+    /// Send a [command](../command/enum.Command.html) to the session and return the value of the response.
+    /// Commands related to a tab apply to the selected tab, use [Tab::select()](../tab/struct.Tab.html#method.select) first.
+    pub fn execute(&self, command: Command) -> Result<JsonValue, WebdriverError> {
+        let value = self.connection.command(&self.id, &command)?;
+        Ok(to_json_value(value))
+    }
+
+    /// Grant or deny a [permission](../permissions/index.html) to the origin of the selected tab, so that its pages never display the prompt.
+    /// The tab must be on the origin (navigate first).
+    /// 
+    /// Chrome and Edge support every permission. Firefox only supports a few permissions and returns
+    /// [InvalidArgument](../error/enum.ErrorKind.html#variant.InvalidArgument) for the others.
+    pub fn set_permission(&self, permission: Permission, state: PermissionState) -> Result<(), WebdriverError> {
+        set_permission(&self.connection, &self.id, permission, state)
+    }
+
+    /// Start recording the commands sent to the session, to export them as a test with [stop_recording()](#method.stop_recording).
+    /// Commands recorded before are forgotten.
+    pub fn start_recording(&self) {
+        self.connection.start_recording();
+    }
+
+    /// Stop recording and return the commands sent since [start_recording()](#method.start_recording).
+    pub fn stop_recording(&self) -> Transcript {
+        Transcript {
+            browser: self.browser.clone(),
+            entries: self.connection.stop_recording()
+        }
+    }
+
     /// This is a simple method getting [timeouts](https://to.do/) of the session.
     pub fn get_timeouts(&self) -> Result<Timeouts, WebdriverError> {
-        Ok(get_timeouts(&self.id)?)
+        Ok(get_timeouts(&self.connection, &self.id)?)
     }
 
     /// This is a simple method setting [timeouts](https://to.do/) of the session.
     pub fn set_timeouts(&mut self, timeouts: Timeouts) -> Result<(), WebdriverError> {
-        Ok(set_timeouts(&self.id, timeouts)?)
+        Ok(set_timeouts(&self.connection, &self.id, timeouts)?)
+    }
+
+    /// Set how long the driver looks for an element before considering that there is none, keeping the other timeouts.
+    pub fn set_implicit_wait(&mut self, timeout: Duration) -> Result<(), WebdriverError> {
+        let timeouts = self.get_timeouts()?;
+        self.set_timeouts(timeouts.implicit(timeout))
+    }
+
+    /// Set how long a navigation can take, keeping the other timeouts.
+    pub fn set_page_load_timeout(&mut self, timeout: Duration) -> Result<(), WebdriverError> {
+        let timeouts = self.get_timeouts()?;
+        self.set_timeouts(timeouts.page_load(timeout))
+    }
+
+    /// Save what is needed to [resume](#method.resume_from) the session from another process: the url of the server,
+    /// the id of the session, the browser and the tabs.
+    /// Long-running jobs can then survive a restart of the program controlling the browser.
+    /// 
+    /// From now on, dropping the session leaves the browser open (and the launched driver running, until the resumed session is dropped).
+    /// Credentials are not saved: the session must be resumed with [resume_with_credentials()](#method.resume_with_credentials).
+    /// Sessions using a custom [transport](../transport/trait.Transport.html) cannot be persisted.
+    /// The output of the driver is no longer [captured](#method.driver_log) once the program exits.
+    /// 
+    /// # Example
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = match Session::resume_from("session.json") {
+    ///     Ok(session) => session,
+    ///     Err(_) => Session::new(Browser::Firefox, false).unwrap(),
+    /// };
+    /// session.persist_to("session.json").unwrap();
+    /// 
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    /// ```
+    pub fn persist_to<P: AsRef<Path>>(&mut self, path: P) -> Result<(), WebdriverError> {
+        let url = match &self.connection.url {
+            Some(url) => url.clone(),
+            None => {
+                error!("sessions using a custom transport cannot be persisted");
+                return Err(ErrorKind::UnsupportedOperation.into());
+            }
+        };
+        let tabs: Vec<&str> = self.tabs.iter().map(|tab| tab.id.as_str()).collect();
+        let mut state = object!{
+            "url" => url,
+            "sessionId" => self.id.as_str(),
+            "browser" => self.browser.to_string(),
+            "tabs" => tabs,
+            "blockedUrls" => self.blocked_urls.clone(),
+            "timezone" => self.timezone.clone(),
+            "authenticated" => self.connection.credentials.is_some(),
+            "driverPid" => self.webdriver_process.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_ref().map(|process| process.id()).or(self.resumed_driver_pid)
+        };
+        match &self.browser {
+            Browser::ChromiumBased { binary } => state["binary"] = binary.to_string_lossy().to_string().into(),
+            Browser::Firefox | Browser::Chrome | Browser::Edge | Browser::Safari => (),
+        }
+
+        if let Err(error) = std::fs::write(path, state.pretty(2)) {
+            error!("failed to write the state of the session: {}", error);
+            return Err(WebdriverError::custom(error.to_string()));
+        }
+        self.persisted = true;
+        Ok(())
+    }
+
+    /// Resume a session saved with [persist_to()](#method.persist_to).
+    /// Fails if the state file cannot be read or if the session does not exist anymore (with [InvalidSessionId](../error/enum.ErrorKind.html#variant.InvalidSessionId) for example).
+    /// 
+    /// Tabs are restored in the saved order. Tabs opened since then are added at the end and closed tabs are removed.
+    /// The resumed session is not persisted anymore until [persist_to()](#method.persist_to) is called again.
+    ///
+    /// The launched driver is stopped when the resumed session is dropped, if it still answers on the saved port.
+    /// Return [InvalidArgument](../error/enum.ErrorKind.html#variant.InvalidArgument) if the server needs credentials.
+    pub fn resume_from<P: AsRef<Path>>(path: P) -> Result<Self, WebdriverError> {
+        Session::resume(path.as_ref(), None)
+    }
+
+    /// Resume a session saved with [persist_to()](#method.persist_to), on a server needing basic authentication credentials.
+    pub fn resume_with_credentials<P: AsRef<Path>>(path: P, username: &str, password: &str) -> Result<Self, WebdriverError> {
+        Session::resume(path.as_ref(), Some((username, password)))
+    }
+
+    /// -> take the state of a persisted session and the credentials of the server
+    /// -> return the session
+    fn resume(path: &Path, credentials: Option<(&str, &str)>) -> Result<Self, WebdriverError> {
+        let state = match std::fs::read_to_string(path).map(|state| json::parse(&state)) {
+            Ok(Ok(state)) => state,
+            Ok(Err(error)) => {
+                error!("state of the session is not json: {}", error);
+                return Err(ErrorKind::InvalidResponse.into());
+            },
+            Err(error) => {
+                error!("failed to read the state of the session: {}", error);
+                return Err(WebdriverError::custom(error.to_string()));
+            }
+        };
+
+        let browser = match (state["browser"].as_str(), state["binary"].as_str()) {
+            (Some("chrome"), Some(binary)) => Browser::ChromiumBased { binary: binary.into() },
+            (Some("chrome"), None) => Browser::Chrome,
+            (Some("firefox"), _) => Browser::Firefox,
+            (Some("MicrosoftEdge"), _) => Browser::Edge,
+            (Some("safari"), _) => Browser::Safari,
+            _ => {
+                error!("state of the session was not understood: {}", state);
+                return Err(ErrorKind::InvalidResponse.into());
+            }
+        };
+        let (url, session_id) = match (state["url"].as_str(), state["sessionId"].as_str()) {
+            (Some(url), Some(session_id)) => (url, session_id),
+            _ => {
+                error!("state of the session was not understood: {}", state);
+                return Err(ErrorKind::InvalidResponse.into());
+            }
+        };
+
+        if state["authenticated"].as_bool() == Some(true) && credentials.is_none() {
+            return Err(WebdriverError::from(ErrorKind::InvalidArgument).with_message("the server needs credentials, use Session::resume_with_credentials()".to_string()));
+        }
+
+        let connection = Connection::new(url, credentials);
+        let open_tabs = get_open_tabs(&connection, session_id)?;
+        let mut session = Session {
+            id: Arc::new(session_id.to_string()),
+            connection: Arc::new(connection),
+            browser,
+            blocked_urls: state["blockedUrls"].members().filter_map(|url| url.as_str()).map(|url| url.to_string()).collect(),
+            timezone: state["timezone"].as_str().map(|timezone| timezone.to_string()),
+            tabs: Vec::new(),
+            webdriver_process: Arc::new(Mutex::new(None)),
+            resumed_driver_pid: state["driverPid"].as_u32(),
+            persisted: false,
+            ended: false,
+            shutdown_grace_period: DEFAULT_SHUTDOWN_GRACE_PERIOD,
+            capabilities: JsonValue::Null,
+            downloads: None,
+            keep_alive: None,
+            driver_log: Arc::new(DriverLog::new(0, None))
+        };
+        for tab_id in state["tabs"].members().filter_map(|tab_id| tab_id.as_str()) {
+            if open_tabs.iter().any(|open_tab| open_tab == tab_id) {
+                session.tabs.push(Tab::with_connection(tab_id.to_string(), Arc::clone(&session.id), Arc::clone(&session.connection), session.browser.clone()));
+            }
+        }
+        session.update_tabs()?;
+
+        info!("Session {} resumed with {} tabs", session.id, session.tabs.len());
+        Ok(session)
+    }
+
+    /// Call a [hook](../hooks/index.html) before and after every request of the session (and of its tabs and elements).
+    /// Hooks are called in the order they were added.
+    pub fn add_hook<H: Hook + 'static>(&mut self, hook: H) {
+        self.connection.add_hook(Arc::new(hook))
+    }
+
+    /// Call a closure when the session is restarted after a crash (see [SessionConfig::crash_recovery()](../config/struct.SessionConfig.html#method.crash_recovery)).
+    pub fn on_restart<F: Fn(&SessionRestarted) + Send + Sync + 'static>(&mut self, callback: F) {
+        self.connection.add_restart_callback(Arc::new(callback))
+    }
+
+    /// Set how the commands of the session (and of its tabs and elements) failing for a transient reason are [retried](../retry/struct.RetryPolicy.html).
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.connection.set_retry_policy(policy)
+    }
+
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        self.connection.get_retry_policy()
+    }
+
+    /// Set how long the commands of the session (and of its tabs and elements) wait for a response
+    /// before failing (None to wait forever, see [SessionConfig::request_timeout()](../config/struct.SessionConfig.html#method.request_timeout)).
+    pub fn set_request_timeout(&mut self, timeout: Option<Duration>) {
+        self.connection.set_request_timeout(timeout)
+    }
+
+    pub fn get_request_timeout(&self) -> Option<Duration> {
+        self.connection.get_request_timeout()
+    }
+
+    /// Send a cheap command (getting the timeouts) whenever the session has been idle for `interval`, from a background thread (None to stop).
+    /// Drivers and grids end the sessions which received no command for a while, and long-running programs waiting between two uses
+    /// of the browser would then get [InvalidSessionId](../error/enum.ErrorKind.html#variant.InvalidSessionId).
+    ///
+    /// The interval must be shorter than the idle timeout of the server (5 minutes by default on Selenium Grid).
+    /// See [SessionConfig::keep_alive()](../config/struct.SessionConfig.html#method.keep_alive).
+    pub fn set_keep_alive(&mut self, interval: Option<Duration>) {
+        // the previous thread is stopped first
+        self.keep_alive = None;
+        self.keep_alive = interval.map(|interval| KeepAlive::start(Arc::clone(&self.connection), Arc::clone(&self.id), interval));
+    }
+
+    /// Find [elements](../elements/struct.Element.html) again when they are stale (removed from the page, often
+    /// because a single page application rendered them again), instead of returning [StaleElementReference](../error/enum.ErrorKind.html#variant.StaleElementReference).
+    /// The element is found with the locator it was found with, and the command is retried once. Disabled by default.
+    ///
+    /// The element found again can be another element matching the locator.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::{Browser, Selector}};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.set_stale_element_recovery(true);
+    /// session.tabs[0].navigate("https://example.com/").unwrap();
+    ///
+    /// let counter = session.tabs[0].find(Selector::Css, "#counter").unwrap().unwrap();
+    /// // the page renders the counter again every second
+    /// std::thread::sleep(std::time::Duration::from_secs(2));
+    /// counter.get_text().unwrap();
+    /// ```
+    pub fn set_stale_element_recovery(&mut self, recovery: bool) {
+        self.connection.set_stale_element_recovery(recovery)
+    }
+
+    pub fn get_stale_element_recovery(&self) -> bool {
+        self.connection.get_stale_element_recovery()
+    }
+
+    /// Apply [timeouts](../timeouts/struct.Timeouts.html) while a closure is running, then restore the previous ones.
+    /// Useful to relax a timeout for a single slow operation.
+    ///
+    /// The previous timeouts are restored even if the closure panics. A failure to restore them is logged,
+    /// and the result of the closure is returned anyway.
+    /// 
+    /// # Example
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// # use std::time::Duration;
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// 
+    /// let timeouts = session.get_timeouts().unwrap().page_load(Duration::from_secs(600));
+    /// 
+    /// session.with_timeouts(timeouts, |session| {
+    ///     session.tabs[0].navigate("https://mubelotix.dev/")
+    /// }).unwrap().unwrap();
+    /// ```
+    pub fn with_timeouts<T, F: FnOnce(&mut Session) -> T>(&mut self, timeouts: Timeouts, f: F) -> Result<T, WebdriverError> {
+        let previous_timeouts = self.get_timeouts()?;
+        self.set_timeouts(timeouts)?;
+
+        // the timeouts are restored even if the closure panics
+        let restored = RestoredTimeouts { session: self, timeouts: previous_timeouts };
+        Ok(f(restored.session))
+    }
+}
+
+struct RestoredTimeouts<'a> {
+    session: &'a mut Session,
+    timeouts: Timeouts
+}
+
+impl<'a> Drop for RestoredTimeouts<'a> {
+    fn drop(&mut self) {
+        if let Err(error) = self.session.set_timeouts(self.timeouts) {
+            error!("failed to restore the timeouts: {:?}", error);
+        }
     }
 }
 
@@ -259,18 +1022,42 @@ impl PartialEq for Session {
 }
 
 impl WebdriverObject for Session {
+    type Id = String;
+
     fn get_id(&self) -> &String {
         &self.id
     }
 }
 
 impl Drop for Session {
-    #[allow(unused_must_use)]
     fn drop(&mut self) {
-        self.tabs.clear();
-        if self.webdriver_process.is_some() {
-            warn!("Killing webdriver process (may fail silently)");
-            self.webdriver_process.take().unwrap().kill();
+        self.keep_alive = None;
+        // the tabs and elements outliving the session must not create another one
+        self.connection.set_restarter(None);
+        self.connection.set_exit_check(None);
+        if self.ended {
+            return;
+        }
+        if self.persisted {
+            for tab in &mut self.tabs {
+                tab.close_on_drop = false;
+            }
+            self.tabs.clear();
+            return;
+        }
+
+        // tabs are closed from the last opened, then the session is ended (closing the browser) before the driver is stopped
+        while let Some(tab) = self.tabs.pop() {
+            drop(tab);
+        }
+        match delete_session(&self.connection, &self.id) {
+            // closing the last tab already ended the session
+            Err(error) if error == ErrorKind::InvalidSessionId || error == ErrorKind::NoSuchWindow => (),
+            Err(error) => warn!("failed to delete session {}: {}", self.id, error),
+            Ok(()) => (),
+        }
+        if let Err(error) = self.stop_driver() {
+            warn!("failed to stop the webdriver process: {}", error);
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,16 @@
+//! Status of a webdriver server
+
+/// The state of a webdriver server, returned by [Session::status()](../session/struct.Session.html#method.status).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct DriverStatus {
+    /// Whether the server can create new sessions.
+    pub ready: bool,
+    /// Explanation of the state of the server, given by the driver.
+    pub message: String,
+    /// Version of the driver, if provided.
+    pub build: Option<String>,
+    /// Operating system of the driver, if provided.
+    pub os: Option<String>
+}
@@ -6,11 +6,48 @@ use crate::elements::*;
 use crate::session::*;
 use crate::enums::*;
 use crate::error::*;
-use log::{info, error};
+use log::{debug, info, warn, error};
 use crate::elements::Element;
-use std::rc::Rc;
-use crate::http_requests::{get_selected_tab, select_tab, navigate, close_active_tab, find_element,
-    get_active_tab_url, get_active_tab_title, back, forward, refresh, execute_script_sync, get_all_cookies, set_cookie, get_page_source};
+use crate::security::{SecurityReport, SECURITY_AUDIT_SCRIPT};
+use crate::certificate::Certificate;
+use crate::notifications::{Notification, NOTIFICATION_SHIM_SCRIPT};
+use crate::console::{ConsoleEntry, CONSOLE_SHIM_SCRIPT};
+use crate::performance::{ResourceTiming, PerformanceMetrics, PERFORMANCE_METRICS_SCRIPT};
+use crate::interception::{NetworkInterceptor, InterceptedRequest, FetchInterception};
+use crate::cdp::InitScript;
+use crate::locator::{Locator, Location};
+use crate::downloads::Downloads;
+use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::wait::{Wait, DEFAULT_POLL_INTERVAL, PENDING_REQUESTS_SHIM_SCRIPT, LOADED_SCRIPT};
+use crate::har::{RESOURCE_TIMINGS_SCRIPT, document, entries_from_performance_log, entries_from_resource_timings};
+use crate::http_requests::{Connection, select_tab, navigate, close_active_tab, find_element,
+    get_active_tab_url, execute_cdp_command, take_screenshot, take_full_page_screenshot, get_active_tab_title, back, forward, refresh, execute_script_sync, get_log, get_all_cookies, set_cookie, delete_all_cookies, get_page_source, print_page,
+    perform_actions, release_actions};
+use crate::actions::ActionChain;
+use crate::print::PrintOptions;
+use crate::form::fill_fields;
+
+/// The handle of a window or tab of a session, returned by [Session::window_handles()](../session/struct.Session.html#method.window_handles)
+/// and [Tab::get_handle()](struct.Tab.html#method.get_handle).
+/// Handles are only created from the responses of the driver, so that any string cannot be used as a handle.
+#[derive(PartialEq, Eq, Hash)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct WindowHandle(pub(crate) String);
+
+impl WindowHandle {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for WindowHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 
 /// Tabs are used to load a site and get informations.
 /// 
@@ -23,20 +60,73 @@ use crate::http_requests::{get_selected_tab, select_tab, navigate, close_active_
 /// session.tabs[0].navigate("https://www.mozilla.org/fr/").unwrap();
 /// ```
 pub struct Tab {
-    pub(crate) id: Rc<String>,
-    pub(crate) session_id: Rc<String>
+    pub(crate) id: Arc<WindowHandle>,
+    pub(crate) session_id: Arc<String>,
+    pub(crate) connection: Arc<Connection>,
+    pub(crate) browser: Browser,
+    /// false if the window must stay open when the tab is dropped
+    pub(crate) close_on_drop: bool,
+    /// the BiDi preload script installing the network interception rules in new documents (Firefox)
+    pub(crate) interception_script: Option<InitScript>,
+    /// the requests paused with the Chrome DevTools Protocol (None if not intercepting)
+    pub(crate) fetch_interception: Option<FetchInterception>,
+    /// when the HAR recording started (None if not recording)
+    pub(crate) har_started: Option<SystemTime>,
+    /// the download directory of the session (None if not set in the capabilities)
+    pub(crate) downloads: Option<Arc<Downloads>>,
+    /// the delay between two checks of the waiting methods
+    pub(crate) poll_interval: Duration,
+    /// how long navigation methods wait for the page to be loaded (None if they do not wait)
+    pub(crate) wait_after_navigation: Option<Duration>,
+    /// whether the tab was opened as a tab or as a window (None if it was not opened by the session)
+    pub(crate) window_type: Option<WindowType>
 }
 
 impl Tab {
-    pub fn new_from(id: String, session_id: Rc<String>) -> Tab {
+    /// Create a tab of a session from the handle of a window given by the driver.
+    pub fn new_from(handle: WindowHandle, session: &Session) -> Tab {
+        Tab::with_connection(handle.0, Arc::clone(&session.id), Arc::clone(&session.connection), session.get_browser())
+    }
+
+    pub(crate) fn with_connection(id: String, session_id: Arc<String>, connection: Arc<Connection>, browser: Browser) -> Tab {
         Tab {
-            id: Rc::new(id),
-            session_id
+            id: Arc::new(WindowHandle(id)),
+            session_id,
+            connection,
+            browser,
+            close_on_drop: true,
+            interception_script: None,
+            fetch_interception: None,
+            har_started: None,
+            downloads: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            wait_after_navigation: None,
+            window_type: None
+        }
+    }
+
+    /// Execute a command of the Chrome DevTools Protocol.
+    /// Return [UnknowCommand](../error/enum.ErrorKind.html#variant.UnknowCommand) if the browser is not based on Chromium.
+    pub(crate) fn execute_cdp_command(&self, method: &str, params: JsonValue) -> Result<JsonValue, WebdriverError> {
+        match self.browser.cdp_vendor() {
+            Some(vendor) => execute_cdp_command(&self.connection, &self.session_id, vendor, method, params),
+            None => Err(ErrorKind::UnknowCommand.into()),
         }
     }
 
-    pub fn get_session_id(&self) -> Rc<String> {
-        Rc::clone(&self.session_id)
+    /// Return whether the tab was opened as a tab or as a separate window.
+    /// Return None for tabs which were not opened by the session (the default tab and the tabs opened by web pages).
+    pub fn window_type(&self) -> Option<WindowType> {
+        self.window_type
+    }
+
+    /// Return the handle of the tab, to switch to it with [Session::switch_to_window()](../session/struct.Session.html#method.switch_to_window).
+    pub fn get_handle(&self) -> WindowHandle {
+        WindowHandle::clone(&self.id)
+    }
+
+    pub fn get_session_id(&self) -> Arc<String> {
+        Arc::clone(&self.session_id)
     }
 
     /// Create a new tab in a session.
@@ -48,32 +138,89 @@ impl Tab {
 
     /// Select this tab.
     /// Selection is done automatically by this crate when you get informations.
+    /// The selected tab is remembered by the session, so no request is sent if this tab is already selected.
     pub fn select(&self) -> Result<(), WebdriverError> {
-        // check if it is needed to select the tab
-        if let Ok(id) = get_selected_tab(&self.session_id) {
-            if id == *self.id {
-                return Ok(());
-            }
-        }
-
-        // select tab
-        select_tab(&self.session_id, &self.id)
+        select_tab(&self.connection, &self.session_id, self.id.as_str())
     }
 
     /// Load a website
     pub fn navigate(&mut self, url: &str) -> Result<(), WebdriverError> {
         self.select()?;
-        navigate(&self.session_id, url)
+        navigate(&self.connection, &self.session_id, url, self.connection.get_request_timeout())?;
+        self.wait_after_navigation()
+    }
+
+    /// Load a website like [navigate()](#method.navigate), giving up if the server does not respond within `timeout`
+    /// (instead of the [request timeout](../session/struct.Session.html#method.set_request_timeout) of the session).
+    /// Useful for pages known to load slowly.
+    pub fn navigate_with_timeout(&mut self, url: &str, timeout: Duration) -> Result<(), WebdriverError> {
+        self.select()?;
+        navigate(&self.connection, &self.session_id, url, Some(timeout))?;
+        self.wait_after_navigation()
+    }
+
+    /// Make [navigate()](#method.navigate), [back()](#method.back), [forward()](#method.forward) and [refresh()](#method.refresh)
+    /// wait until the page is [loaded](#method.wait_until_loaded), at most `timeout` (None to stop waiting).
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// # use std::time::Duration;
+    /// let mut session = Session::new(Browser::Chrome, false).unwrap();
+    /// session.tabs[0].track_pending_requests().unwrap();
+    /// session.tabs[0].set_wait_after_navigation(Some(Duration::from_secs(30)));
+    ///
+    /// // returns once the requests sent by the scripts of the page are done
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    /// ```
+    pub fn set_wait_after_navigation(&mut self, timeout: Option<Duration>) {
+        self.wait_after_navigation = timeout;
+    }
+
+    fn wait_after_navigation(&self) -> Result<(), WebdriverError> {
+        match self.wait_after_navigation {
+            Some(timeout) => self.wait_until_loaded(timeout),
+            None => Ok(()),
+        }
+    }
+
+    /// Wait until `document.readyState` is `complete` and, if [tracked](#method.track_pending_requests),
+    /// until no fetch or XMLHttpRequest request is in progress.
+    /// Return [Timeout](../error/enum.ErrorKind.html#variant.Timeout) if the page is still loading after the timeout.
+    pub fn wait_until_loaded(&self, timeout: Duration) -> Result<(), WebdriverError> {
+        self.select()?;
+        self.wait_until(|tab| Ok(execute_script_sync(&tab.connection, &tab.session_id, LOADED_SCRIPT, Vec::new())? == true), timeout).map_err(|error| {
+            if error == ErrorKind::Timeout {
+                error.with_message(format!("page was still loading after {:?}", timeout))
+            } else {
+                error
+            }
+        })
+    }
+
+    /// Count the fetch and XMLHttpRequest requests sent by the page, so that [wait_until_loaded()](#method.wait_until_loaded)
+    /// waits for them.
+    /// On Chrome and Edge, requests of the next pages are counted too. On Firefox, only the current page is tracked
+    /// and this must be called again after navigating.
+    pub fn track_pending_requests(&self) -> Result<(), WebdriverError> {
+        self.select()?;
+        if self.browser.cdp_vendor().is_some() {
+            self.execute_cdp_command("Page.addScriptToEvaluateOnNewDocument", object!{
+                "source" => PENDING_REQUESTS_SHIM_SCRIPT
+            })?;
+        }
+        execute_script_sync(&self.connection, &self.session_id, PENDING_REQUESTS_SHIM_SCRIPT, Vec::new())?;
+        Ok(())
     }
 
     /// Find an element in the tab, selected by a [Selector](../enums/enum.Selector.html).
     pub fn find(&mut self, selector: Selector, tofind: &str) -> Result<Option<Element>, WebdriverError> {
         self.select()?;
-        match find_element(&self.session_id, selector, &tofind) {
+        match find_element(&self.connection, &self.session_id, selector, &tofind) {
             Ok(id) => {
-                Ok(Some(Element::new(id, Rc::clone(&self.session_id), Rc::clone(&self.id))))
+                let location = Location::Selector { selector, value: tofind.to_string() };
+                Ok(Some(Element::with_connection(id, Arc::clone(&self.session_id), Arc::clone(&self.id), Arc::clone(&self.connection)).located(location)))
             },
-            Err(error) if error == WebdriverError::NoSuchElement => {
+            Err(error) if error == ErrorKind::NoSuchElement => {
                 Ok(None)
             },
             Err(error) => {
@@ -82,62 +229,890 @@ impl Tab {
         }
     }
 
+    /// Find an element in the tab with a css selector.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://example.com/").unwrap();
+    ///
+    /// let paragraph = session.tabs[0].find_by_css("body > div > p").unwrap().unwrap();
+    /// let link = session.tabs[0].find_by_link_text("More information...").unwrap().unwrap();
+    /// ```
+    pub fn find_by_css(&mut self, selector: &str) -> Result<Option<Element>, WebdriverError> {
+        self.find(Selector::Css, selector)
+    }
+
+    /// Find an element in the tab with an xpath expression.
+    pub fn find_by_xpath(&mut self, xpath: &str) -> Result<Option<Element>, WebdriverError> {
+        self.find(Selector::XPath, xpath)
+    }
+
+    /// Find a link in the tab by its visible text.
+    pub fn find_by_link_text(&mut self, text: &str) -> Result<Option<Element>, WebdriverError> {
+        self.find(Selector::LinkText, text)
+    }
+
+    /// Find a link in the tab whose visible text contains `text`.
+    pub fn find_by_partial_link_text(&mut self, text: &str) -> Result<Option<Element>, WebdriverError> {
+        self.find(Selector::PartialLinkText, text)
+    }
+
+    /// Fill fields of the page, found with css selectors (see the [form module](../form/index.html) for the supported fields).
+    /// Return [NoSuchElement](../error/enum.ErrorKind.html#variant.NoSuchElement) if a field does not exist.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::{Browser, Selector}};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://example.com/login").unwrap();
+    ///
+    /// session.tabs[0].fill_form(&[("#username", "admin"), ("#password", "hunter2"), ("#remember", "true")]).unwrap();
+    /// session.tabs[0].find(Selector::Css, "button[type=submit]").unwrap().unwrap().click().unwrap();
+    /// ```
+    pub fn fill_form(&self, fields: &[(&str, &str)]) -> Result<(), WebdriverError> {
+        self.select()?;
+        fill_fields(&self.connection, &self.session_id, &self.id, JsonValue::Null, fields)
+    }
+
+    /// Set the delay between two checks of the waiting methods of the tab ([wait_until()](#method.wait_until),
+    /// [wait_for_element()](#method.wait_for_element), [wait_for_element_by()](#method.wait_for_element_by)), [100ms](../wait/constant.DEFAULT_POLL_INTERVAL.html) by default.
+    pub fn set_poll_interval(&mut self, interval: Duration) {
+        self.poll_interval = interval;
+    }
+
+    /// Wait until an element exists in the tab and return it.
+    /// The element is searched again at every [poll interval](#method.set_poll_interval),
+    /// and [Timeout](../error/enum.ErrorKind.html#variant.Timeout) is returned if it still does not exist after the timeout.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::{Browser, Selector}};
+    /// # use std::time::Duration;
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    ///
+    /// let mut link = session.tabs[0].wait_for_element(Selector::Css, "a", Duration::from_secs(5)).unwrap();
+    /// link.click().unwrap();
+    /// ```
+    pub fn wait_for_element(&mut self, selector: Selector, tofind: &str, timeout: Duration) -> Result<Element, WebdriverError> {
+        self.wait_for_element_by((selector, tofind), timeout)
+    }
+
+    /// Wait until an element located by any [Locator](../locator/trait.Locator.html) exists in the tab and return it,
+    /// like [wait_for_element()](#method.wait_for_element).
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser, locator::Role};
+    /// # use std::time::Duration;
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://www.mozilla.org/en-US/").unwrap();
+    ///
+    /// let heading = session.tabs[0].wait_for_element_by(Role::new("heading"), Duration::from_secs(5)).unwrap();
+    /// ```
+    pub fn wait_for_element_by<L: Locator>(&mut self, locator: L, timeout: Duration) -> Result<Element, WebdriverError> {
+        let location = locator.locate();
+        let interval = self.poll_interval;
+        Wait::new(timeout).interval(interval).until(|| self.find_by(location.clone())).map_err(|error| {
+            if error == ErrorKind::Timeout {
+                let tofind = match &location {
+                    Location::Selector { value, .. } => value.as_str(),
+                    Location::Script { .. } => "the locator script",
+                };
+                debug!("element {:?} was not found after {:?}", tofind, timeout);
+                error.with_message(format!("no element matched {:?} in {:?}", tofind, timeout))
+            } else {
+                error
+            }
+        })
+    }
+
+    /// Wait until a condition is met, checking it at every [poll interval](#method.set_poll_interval).
+    /// Return the error of the condition if it fails, and [Timeout](../error/enum.ErrorKind.html#variant.Timeout)
+    /// if it is still not met after the timeout.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// # use std::time::Duration;
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    ///
+    /// // wait until the loading spinner is gone
+    /// session.tabs[0].wait_until(|tab| {
+    ///     Ok(tab.execute_script("return document.querySelector('.spinner') === null;", Vec::new())? == true)
+    /// }, Duration::from_secs(10)).unwrap();
+    /// ```
+    pub fn wait_until<F: Fn(&Tab) -> Result<bool, WebdriverError>>(&self, condition: F, timeout: Duration) -> Result<(), WebdriverError> {
+        Wait::new(timeout).interval(self.poll_interval).until(|| {
+            Ok(if condition(self)? { Some(()) } else { None })
+        })
+    }
+
+    /// Find an element in the tab with any [Locator](../locator/trait.Locator.html), including custom strategies.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::{Browser, Selector}, locator::Role};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://www.mozilla.org/en-US/").unwrap();
+    ///
+    /// let heading = session.tabs[0].find_by(Role::new("heading")).unwrap();
+    /// let footer = session.tabs[0].find_by((Selector::Css, "footer")).unwrap();
+    /// ```
+    pub fn find_by<L: Locator>(&mut self, locator: L) -> Result<Option<Element>, WebdriverError> {
+        match locator.locate() {
+            Location::Selector { selector, value } => self.find(selector, &value),
+            Location::Script { script, args } => {
+                self.select()?;
+                let json = execute_script_sync(&self.connection, &self.session_id, &script, args.clone())?;
+
+                if let Some(id) = json["element-6066-11e4-a52e-4f735466cecf"].as_str() {
+                    let location = Location::Script { script, args };
+                    Ok(Some(Element::with_connection(id.to_string(), Arc::clone(&self.session_id), Arc::clone(&self.id), Arc::clone(&self.connection)).located(location)))
+                } else if json.is_null() {
+                    Ok(None)
+                } else {
+                    error!("locator script did not return an element: {}", json);
+                    Err(WebdriverError::from(ErrorKind::InvalidResponse).with_message(format!("locator script did not return an element or null: {}", json)))
+                }
+            }
+        }
+    }
+
     /// Return the url of the current web page.
     pub fn get_url(&self) -> Result<String, WebdriverError> {
         self.select()?;
-        get_active_tab_url(&self.session_id)
+        get_active_tab_url(&self.connection, &self.session_id)
     }
 
     /// Return the title of the tab.
     pub fn get_title(&self) -> Result<String, WebdriverError> {
         self.select()?;
-        get_active_tab_title(&self.session_id)
+        get_active_tab_title(&self.connection, &self.session_id)
     }
 
     /// Navigate to the previous page.
     pub fn back(&mut self) -> Result<(), WebdriverError> {
         self.select()?;
-        back(&self.session_id)
+        back(&self.connection, &self.session_id)?;
+        self.wait_after_navigation()
     }
 
     /// Navigate forward.
     pub fn forward(&mut self) -> Result<(), WebdriverError> {
         self.select()?;
-        forward(&self.session_id)
+        forward(&self.connection, &self.session_id)?;
+        self.wait_after_navigation()
     }
 
     /// Refresh the page.
     pub fn refresh(&mut self) -> Result<(), WebdriverError> {
         self.select()?;
-        refresh(&self.session_id)
+        refresh(&self.connection, &self.session_id)?;
+        self.wait_after_navigation()
+    }
+
+    /// Execute a script in the tab and return the value it returns (`null` if nothing is returned).
+    /// If the script returns a promise, the driver waits for it to be resolved.
+    pub fn execute_script(&self, script: &str, args: Vec<JsonValue>) -> Result<JsonValue, WebdriverError> {
+        self.select()?;
+        execute_script_sync(&self.connection, &self.session_id, script, args)
+    }
+
+    /// Execute a script returning elements (an element, an array or a NodeList of elements, or null),
+    /// and return them as [elements](../elements/struct.Element.html).
+    /// Queries which are easier to write in javascript can then be used with the methods of elements.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://example.com/").unwrap();
+    ///
+    /// // the paragraphs containing a link
+    /// let paragraphs = session.tabs[0].execute_script_elements("return Array.from(document.querySelectorAll('p')).filter(p => p.querySelector('a'));", Vec::new()).unwrap();
+    /// for paragraph in paragraphs {
+    ///     println!("{}", paragraph.get_text().unwrap());
+    /// }
+    /// ```
+    pub fn execute_script_elements(&self, script: &str, args: Vec<JsonValue>) -> Result<Vec<Element>, WebdriverError> {
+        self.select()?;
+        let json = execute_script_sync(&self.connection, &self.session_id, script, args)?;
+
+        let references: Vec<&JsonValue> = match &json {
+            JsonValue::Null => Vec::new(),
+            JsonValue::Array(members) => members.iter().collect(),
+            reference => vec![reference],
+        };
+        let mut elements = Vec::new();
+        for reference in references {
+            match reference["element-6066-11e4-a52e-4f735466cecf"].as_str() {
+                Some(id) => elements.push(Element::with_connection(id.to_string(), Arc::clone(&self.session_id), Arc::clone(&self.id), Arc::clone(&self.connection))),
+                None => {
+                    error!("script did not return elements: {}", json);
+                    return Err(WebdriverError::from(ErrorKind::InvalidResponse).with_message(format!("script did not return elements: {}", json)));
+                }
+            }
+        }
+        Ok(elements)
+    }
+
+    /// Select every element matching a CSS selector and map them with a javascript function, in a single request.
+    /// The function receives an element and its index. Elements for which it returns `undefined` are skipped.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    ///
+    /// // get the url of every external link
+    /// let links = session.tabs[0].query_all_map("a", "a => a.host !== location.host ? a.href : undefined").unwrap();
+    /// ```
+    pub fn query_all_map(&self, selector: &str, js_mapper: &str) -> Result<Vec<JsonValue>, WebdriverError> {
+        self.select()?;
+        let script = format!("return Array.from(document.querySelectorAll(arguments[0])).map({}).filter(value => value !== undefined);", js_mapper);
+        let json = execute_script_sync(&self.connection, &self.session_id, &script, vec![selector.into()])?;
+
+        if json.is_array() {
+            Ok(json.members().cloned().collect())
+        } else {
+            error!("query_all_map script did not return an array: {}", json);
+            Err(ErrorKind::InvalidResponse.into())
+        }
+    }
+
+    /// Return the scroll position of the document in pixels (x, y).
+    pub fn scroll_position(&self) -> Result<(f64, f64), WebdriverError> {
+        self.select()?;
+        let json = execute_script_sync(&self.connection, &self.session_id, "return [window.scrollX, window.scrollY];", Vec::new())?;
+
+        match (json[0].as_f64(), json[1].as_f64()) {
+            (Some(x), Some(y)) => Ok((x, y)),
+            _ => {
+                error!("scroll position was not understood: {}", json);
+                Err(ErrorKind::InvalidResponse.into())
+            }
+        }
     }
 
-    pub fn execute_script(&self, script: &str, args: Vec<JsonValue>) -> Result<(), WebdriverError> {
+    /// Scroll the document to a position in pixels.
+    pub fn set_scroll_position(&self, x: f64, y: f64) -> Result<(), WebdriverError> {
+        self.scroll_to(x, y)
+    }
+
+    /// Scroll the document to a position in pixels, like [set_scroll_position()](#method.set_scroll_position).
+    pub fn scroll_to(&self, x: f64, y: f64) -> Result<(), WebdriverError> {
         self.select()?;
-        execute_script_sync(&self.session_id, script, args)
+        execute_script_sync(&self.connection, &self.session_id, "window.scrollTo(arguments[0], arguments[1]);", vec![x.into(), y.into()])?;
+        Ok(())
+    }
+
+    /// Scroll the document by a number of pixels, positive values scrolling to the right and to the bottom.
+    pub fn scroll_by(&self, dx: f64, dy: f64) -> Result<(), WebdriverError> {
+        self.select()?;
+        execute_script_sync(&self.connection, &self.session_id, "window.scrollBy(arguments[0], arguments[1]);", vec![dx.into(), dy.into()])?;
+        Ok(())
+    }
+
+    /// Perform the actions of an [action chain](../actions/struct.ActionChain.html) in the tab.
+    pub fn perform(&self, actions: &ActionChain) -> Result<(), WebdriverError> {
+        self.select()?;
+        perform_actions(&self.connection, &self.session_id, actions.to_json())
+    }
+
+    /// Release the keys and the buttons still pressed by action chains.
+    pub fn release_actions(&self) -> Result<(), WebdriverError> {
+        self.select()?;
+        release_actions(&self.connection, &self.session_id)
+    }
+
+    /// Run a closure, then scroll back to the position the document had before.
+    /// Useful to return to the same viewport after refreshing the page.
+    ///
+    /// The position is restored even if the closure panics. A failure to restore it is logged,
+    /// and the result of the closure is returned anyway.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    /// session.tabs[0].set_scroll_position(0.0, 200.0).unwrap();
+    ///
+    /// session.tabs[0].preserve_scroll(|tab| tab.refresh()).unwrap().unwrap();
+    /// ```
+    pub fn preserve_scroll<T, F: FnOnce(&mut Tab) -> T>(&mut self, f: F) -> Result<T, WebdriverError> {
+        let (x, y) = self.scroll_position()?;
+
+        // the position is restored even if the closure panics
+        let restored = RestoredScroll { tab: self, x, y };
+        Ok(f(restored.tab))
+    }
+
+    /// Zoom the page (`1.5` for 150%).
+    /// The CSS `zoom` property of the document is used, so the layout is computed again like with the zoom of the browser.
+    /// The zoom is reset when another page is loaded.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Chrome, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    ///
+    /// for zoom in &[0.8, 1.0, 1.5, 2.0] {
+    ///     session.tabs[0].set_zoom(*zoom).unwrap();
+    ///     let png = session.tabs[0].screenshot().unwrap();
+    ///     std::fs::write(format!("zoom_{}.png", zoom), png).unwrap();
+    /// }
+    /// ```
+    pub fn set_zoom(&self, factor: f64) -> Result<(), WebdriverError> {
+        if factor.is_nan() || factor <= 0.0 {
+            error!("invalid zoom factor: {}", factor);
+            return Err(ErrorKind::InvalidArgument.into());
+        }
+        self.select()?;
+        execute_script_sync(&self.connection, &self.session_id, "document.documentElement.style.zoom = arguments[0];", vec![factor.into()])?;
+        Ok(())
+    }
+
+    /// Remove the zoom set by [set_zoom()](#method.set_zoom).
+    pub fn reset_zoom(&self) -> Result<(), WebdriverError> {
+        self.select()?;
+        execute_script_sync(&self.connection, &self.session_id, "document.documentElement.style.zoom = '';", Vec::new())?;
+        Ok(())
+    }
+
+    /// Wait until the page has nothing left to render: the fonts are loaded, two animation frames are painted and the browser reports an idle period
+    /// (with `requestIdleCallback`, or after a short delay on browsers without it).
+    /// Taking screenshots after this wait makes them consistent, as the layout is done.
+    ///
+    /// Return [Timeout](../error/enum.ErrorKind.html#variant.Timeout) if the page is still busy after `timeout`.
+    /// The script timeout of the session must be longer than `timeout`.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// # use std::time::Duration;
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    ///
+    /// session.tabs[0].wait_for_render_idle(Duration::from_secs(5)).unwrap();
+    /// let png = session.tabs[0].screenshot().unwrap();
+    /// ```
+    pub fn wait_for_render_idle(&self, timeout: Duration) -> Result<(), WebdriverError> {
+        self.select()?;
+        let script = "return new Promise(resolve => {
+            const timer = setTimeout(() => resolve(false), arguments[0]);
+            const whenIdle = window.requestIdleCallback || (callback => setTimeout(callback, 50));
+            document.fonts.ready.then(() => requestAnimationFrame(() => requestAnimationFrame(() => whenIdle(() => {
+                clearTimeout(timer);
+                resolve(true);
+            }))));
+        });";
+        let idle = execute_script_sync(&self.connection, &self.session_id, script, vec![(timeout.as_millis() as u64).into()])?;
+
+        if idle.as_bool() == Some(true) {
+            Ok(())
+        } else {
+            error!("the page is still rendering after {:?}", timeout);
+            Err(ErrorKind::Timeout.into())
+        }
+    }
+
+    /// Record the notifications created by the page from now on. They can be read with [notifications()](#method.notifications).
+    /// The permission to display notifications must be granted with [Capabilities::allow_notifications()](../capabilities/struct.Capabilities.html#method.allow_notifications).
+    ///
+    /// On Firefox, recording stops when another page is loaded. Chrome and Edge keep recording on the next pages of the tab.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, capabilities::Capabilities, enums::Browser};
+    /// let mut session = Session::new_with_capabilities(Capabilities::new(Browser::Chrome).allow_notifications(true)).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    ///
+    /// session.tabs[0].capture_notifications().unwrap();
+    /// session.tabs[0].execute_script("new Notification('Hello', { body: 'World' });", Vec::new()).unwrap();
+    /// assert_eq!(session.tabs[0].notifications().unwrap()[0].title, "Hello");
+    /// ```
+    pub fn capture_notifications(&self) -> Result<(), WebdriverError> {
+        self.select()?;
+        if self.browser.cdp_vendor().is_some() {
+            self.execute_cdp_command("Page.addScriptToEvaluateOnNewDocument", object!{
+                "source" => NOTIFICATION_SHIM_SCRIPT
+            })?;
+        }
+        execute_script_sync(&self.connection, &self.session_id, NOTIFICATION_SHIM_SCRIPT, Vec::new())?;
+        Ok(())
+    }
+
+    /// Return the notifications created by the current page since [capture_notifications()](#method.capture_notifications) was called.
+    pub fn notifications(&self) -> Result<Vec<Notification>, WebdriverError> {
+        self.select()?;
+        let json = execute_script_sync(&self.connection, &self.session_id, "return window.__lwNotifications || [];", Vec::new())?;
+
+        let mut notifications = Vec::new();
+        for notification in json.members() {
+            match Notification::from_json(notification) {
+                Some(notification) => notifications.push(notification),
+                None => {
+                    error!("captured notification was not understood: {}", notification);
+                    return Err(ErrorKind::InvalidResponse.into());
+                }
+            }
+        }
+        Ok(notifications)
+    }
+
+    /// Start recording the messages logged in the console of the current page (Firefox only).
+    /// Recording stops when another page is loaded.
+    ///
+    /// Chrome and Edge always record the console, so this does nothing on them.
+    pub fn capture_console_logs(&self) -> Result<(), WebdriverError> {
+        if self.browser.cdp_vendor().is_some() {
+            return Ok(());
+        }
+        self.select()?;
+        execute_script_sync(&self.connection, &self.session_id, CONSOLE_SHIM_SCRIPT, Vec::new())?;
+        Ok(())
+    }
+
+    /// Return the messages logged in the console (including uncaught errors) since the last call.
+    ///
+    /// On Chrome and Edge, messages of every page of the session are returned.
+    /// On Firefox, only the messages of the current page logged after [capture_console_logs()](#method.capture_console_logs) are returned.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser, console::LogLevel};
+    /// let mut session = Session::new(Browser::Chrome, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    ///
+    /// let errors: Vec<_> = session.tabs[0].get_console_logs().unwrap().into_iter().filter(|entry| entry.level == LogLevel::Error).collect();
+    /// assert!(errors.is_empty(), "javascript errors: {:?}", errors);
+    /// ```
+    pub fn get_console_logs(&self) -> Result<Vec<ConsoleEntry>, WebdriverError> {
+        self.select()?;
+        if self.browser.cdp_vendor().is_some() {
+            return Ok(get_log(&self.connection, &self.session_id, "browser")?.into_iter().map(ConsoleEntry::from).collect());
+        }
+        let json = execute_script_sync(&self.connection, &self.session_id, "return window.__lwConsole ? window.__lwConsole.splice(0) : [];", Vec::new())?;
+
+        let mut entries = Vec::new();
+        for entry in json.members() {
+            match ConsoleEntry::from_json(entry) {
+                Some(entry) => entries.push(entry),
+                None => {
+                    error!("console entry was not understood: {}", entry);
+                    return Err(ErrorKind::InvalidResponse.into());
+                }
+            }
+        }
+        Ok(entries)
     }
 
     pub fn get_cookies(&self) -> Result<Vec<(String, usize, bool, String, String, bool, String)>, WebdriverError> {
         self.select()?;
-        get_all_cookies(&self.session_id)
+        get_all_cookies(&self.connection, &self.session_id)
     }
 
     pub fn set_cookie(&self, cookie: (String, usize, bool, String, String, bool, String)) -> Result<(), WebdriverError> {
         self.select()?;
-        set_cookie(&self.session_id, cookie)
+        set_cookie(&self.connection, &self.session_id, cookie)
     }
 
     pub fn set_cookies(&self, cookies: Vec<(String, usize, bool, String, String, bool, String)>) -> Result<(), WebdriverError> {
         self.select()?;
         for cookie in cookies {
-            set_cookie(&self.session_id, cookie)?
+            set_cookie(&self.connection, &self.session_id, cookie)?
         }
         Ok(())
     }
 
+    /// Delete every cookie visible by the current page.
+    pub fn delete_cookies(&self) -> Result<(), WebdriverError> {
+        self.select()?;
+        delete_all_cookies(&self.connection, &self.session_id)
+    }
+
     pub fn get_page_source(&self) -> Result<String, WebdriverError> {
         self.select()?;
-        get_page_source(&self.session_id)
+        get_page_source(&self.connection, &self.session_id)
+    }
+
+    /// Audit the security of the loaded page.
+    /// Every resource loaded or referenced over http while the page is served over https is reported as [mixed content](../security/struct.MixedContent.html).
+    ///
+    /// The headers of the original response cannot be read by webdriver, so the page sends a second GET request to its url,
+    /// with its cookies and without using the cache. The server sees a second visit (beware of urls with side effects),
+    /// and the headers come from this second response, which can differ from the one of the loaded page.
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    /// 
+    /// let report = session.tabs[0].security_audit().unwrap();
+    /// println!("missing headers: {:?}", report.headers.missing());
+    /// assert!(report.mixed_content.is_empty());
+    /// ```
+    pub fn security_audit(&self) -> Result<SecurityReport, WebdriverError> {
+        self.select()?;
+        let json = execute_script_sync(&self.connection, &self.session_id, SECURITY_AUDIT_SCRIPT, Vec::new())?;
+
+        match SecurityReport::from_json(&json) {
+            Some(report) => Ok(report),
+            None => {
+                error!("result of the security audit script was not understood: {}", json);
+                Err(ErrorKind::InvalidResponse.into())
+            }
+        }
+    }
+
+    /// Return the timings of every resource loaded by the page, in loading order.
+    /// This gives a waterfall of the page without capturing the whole network traffic.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    ///
+    /// for timing in session.tabs[0].resource_timings().unwrap() {
+    ///     println!("{:>6}ms {:>8} bytes {}", timing.duration.as_millis(), timing.transfer_size, timing.name);
+    /// }
+    /// ```
+    pub fn resource_timings(&self) -> Result<Vec<ResourceTiming>, WebdriverError> {
+        self.select()?;
+        let json = execute_script_sync(&self.connection, &self.session_id, "return performance.getEntriesByType('resource').map(entry => entry.toJSON());", Vec::new())?;
+
+        let mut timings = Vec::new();
+        for entry in json.members() {
+            match ResourceTiming::from_json(entry) {
+                Some(timing) => timings.push(timing),
+                None => {
+                    error!("resource timing entry was not understood: {}", entry);
+                    return Err(ErrorKind::InvalidResponse.into());
+                }
+            }
+        }
+        Ok(timings)
+    }
+
+    /// Return the load times of the page (time to first byte, DOMContentLoaded, load and first contentful paint),
+    /// to track regressions of the page load time.
+    ///
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    ///
+    /// let metrics = session.tabs[0].performance_metrics().unwrap();
+    /// assert!(metrics.load.unwrap().as_secs() < 3, "page loaded in {:?}", metrics.load);
+    /// ```
+    pub fn performance_metrics(&self) -> Result<PerformanceMetrics, WebdriverError> {
+        self.select()?;
+        let json = execute_script_sync(&self.connection, &self.session_id, PERFORMANCE_METRICS_SCRIPT, Vec::new())?;
+        if json.is_null() {
+            return Err(WebdriverError::custom("the page has no navigation timing".to_string()));
+        }
+
+        match PerformanceMetrics::from_json(&json) {
+            Some(metrics) => Ok(metrics),
+            None => {
+                error!("performance metrics were not understood: {}", json);
+                Err(ErrorKind::InvalidResponse.into())
+            }
+        }
+    }
+
+    /// Start recording the requests of the tab, to export them with [stop_har_recording()](#method.stop_har_recording).
+    /// See the [har module](../har/index.html) for the differences between browsers.
+    pub fn start_har_recording(&mut self) -> Result<(), WebdriverError> {
+        self.select()?;
+        if self.browser.cdp_vendor().is_some() {
+            // forget the requests made before
+            if let Err(error) = get_log(&self.connection, &self.session_id, "performance") {
+                debug!("performance log is not available, resource timings will be used: {}", error);
+            }
+        }
+        self.har_started = Some(SystemTime::now());
+        Ok(())
+    }
+
+    /// Stop recording the requests of the tab and return them as a HAR document.
+    pub fn stop_har_recording(&mut self) -> Result<JsonValue, WebdriverError> {
+        let started = match self.har_started.take() {
+            Some(started) => started,
+            None => {
+                error!("HAR recording was not started");
+                return Err(WebdriverError::custom("HAR recording was not started".to_string()));
+            }
+        };
+        self.select()?;
+        let title = get_active_tab_title(&self.connection, &self.session_id)?;
+
+        if self.browser.cdp_vendor().is_some() {
+            if let Ok(log) = get_log(&self.connection, &self.session_id, "performance") {
+                // chromedriver may prefix the id of the devtools target
+                let webview = self.id.as_str().trim_start_matches("CDwindow-");
+                if let Some(entries) = entries_from_performance_log(&log, webview) {
+                    return Ok(document(started, &title, entries));
+                }
+            }
+        }
+        let timings = execute_script_sync(&self.connection, &self.session_id, RESOURCE_TIMINGS_SCRIPT, Vec::new())?;
+        Ok(document(started, &title, entries_from_resource_timings(&timings, started)))
+    }
+
+    /// The download directory of the session, if one was set with
+    /// [Capabilities::download_directory()](../capabilities/struct.Capabilities.html#method.download_directory).
+    pub fn downloads(&self) -> Option<&Downloads> {
+        self.downloads.as_deref()
+    }
+
+    /// Wait for a download to complete and return the path of the file.
+    /// Downloads are shared by the tabs of the session: the file may have been downloaded by another tab.
+    /// Return [InvalidArgument](../error/enum.ErrorKind.html#variant.InvalidArgument) if no download directory was set.
+    pub fn wait_for_download(&self, timeout: Duration) -> Result<PathBuf, WebdriverError> {
+        match &self.downloads {
+            Some(downloads) => downloads.wait(timeout),
+            None => {
+                error!("no download directory was set in the capabilities");
+                Err(WebdriverError::from(ErrorKind::InvalidArgument).with_message("no download directory was set in the capabilities".to_string()))
+            }
+        }
+    }
+
+    /// List the files being downloaded by the browser (an empty list if no download directory was set).
+    pub fn in_progress_downloads(&self) -> Result<Vec<PathBuf>, WebdriverError> {
+        match &self.downloads {
+            Some(downloads) => downloads.in_progress(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Take a screenshot of the visible part of the tab.
+    /// Return the bytes of a png image.
+    pub fn screenshot(&self) -> Result<Vec<u8>, WebdriverError> {
+        self.select()?;
+        take_screenshot(&self.connection, &self.session_id)
+    }
+
+    /// Take a screenshot of the whole document, including the parts which are not scrolled into view.
+    /// Return the bytes of a png image.
+    /// 
+    /// Firefox captures the document natively. Chrome and Edge use the Chrome DevTools Protocol.
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Firefox, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    /// 
+    /// let png = session.tabs[0].screenshot_full_page().unwrap();
+    /// std::fs::write("page.png", png).unwrap();
+    /// ```
+    pub fn screenshot_full_page(&self) -> Result<Vec<u8>, WebdriverError> {
+        self.select()?;
+        match self.browser {
+            Browser::Firefox => take_full_page_screenshot(&self.connection, &self.session_id),
+            Browser::Chrome | Browser::Edge | Browser::ChromiumBased { .. } => {
+                // without a clip covering the document, only the viewport is captured
+                let metrics = self.execute_cdp_command("Page.getLayoutMetrics", object!{})?;
+                let size = if metrics["cssContentSize"].is_object() { &metrics["cssContentSize"] } else { &metrics["contentSize"] };
+                let (width, height) = match (size["width"].as_f64(), size["height"].as_f64()) {
+                    (Some(width), Some(height)) => (width, height),
+                    _ => {
+                        error!("response to devtools layout metrics request was not understood: {}", metrics);
+                        return Err(ErrorKind::InvalidResponse.into());
+                    }
+                };
+                let json = self.execute_cdp_command("Page.captureScreenshot", object!{
+                    "format" => "png",
+                    "captureBeyondViewport" => true,
+                    "clip" => object!{
+                        "x" => 0,
+                        "y" => 0,
+                        "width" => width.ceil(),
+                        "height" => height.ceil(),
+                        "scale" => 1
+                    }
+                })?;
+
+                match json["data"].as_str().map(base64::decode) {
+                    Some(Ok(png)) => Ok(png),
+                    _ => {
+                        error!("response to devtools screenshot request was not understood: {}", json);
+                        Err(ErrorKind::InvalidResponse.into())
+                    }
+                }
+            },
+            Browser::Safari => {
+                error!("Safari cannot take screenshots of a full page");
+                Err(ErrorKind::UnsupportedOperation.into())
+            }
+        }
+    }
+
+    /// Print the tab as a PDF document.
+    /// Return the bytes of the document.
+    /// 
+    /// Chrome and Edge can only print in headless mode.
+    pub fn print(&self, options: PrintOptions) -> Result<Vec<u8>, WebdriverError> {
+        self.select()?;
+        print_page(&self.connection, &self.session_id, options)
+    }
+
+    /// Print the tab as a PDF document and write it to a file (see [print()](#method.print)).
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser, print::PrintOptions};
+    /// let mut session = Session::new(Browser::Firefox, true).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    /// 
+    /// session.tabs[0].print_to_file("page.pdf", PrintOptions::new().background(true)).unwrap();
+    /// ```
+    pub fn print_to_file<P: AsRef<Path>>(&self, path: P, options: PrintOptions) -> Result<(), WebdriverError> {
+        let pdf = self.print(options)?;
+        std::fs::write(path.as_ref(), pdf).map_err(|error| {
+            error!("failed to write {}: {}", path.as_ref().display(), error);
+            WebdriverError::custom(format!("failed to write the printed page: {}", error))
+        })
+    }
+
+    /// Prevent the tab from loading resources whose url matches a pattern (`*` is a wildcard).
+    /// This uses the Chrome DevTools Protocol and is only available on Chrome and Edge.
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Chrome, false).unwrap();
+    /// session.tabs[0].block_urls(&["*.png", "*://ads.example.com/*"]).unwrap();
+    /// ```
+    pub fn block_urls<T: AsRef<str>>(&self, patterns: &[T]) -> Result<(), WebdriverError> {
+        self.select()?;
+        let patterns: Vec<&str> = patterns.iter().map(|pattern| pattern.as_ref()).collect();
+        self.execute_cdp_command("Network.enable", object!{})?;
+        self.execute_cdp_command("Network.setBlockedURLs", object!{
+            "urls" => patterns
+        })?;
+        Ok(())
+    }
+
+    /// Apply the rules of a [NetworkInterceptor](../interception/struct.NetworkInterceptor.html) to the requests of the tab,
+    /// replacing the rules installed before. See the [interception](../interception/index.html) module for the limits outside Chrome and Edge.
+    pub fn intercept_network(&mut self, interceptor: &NetworkInterceptor) -> Result<(), WebdriverError> {
+        self.select()?;
+        if self.browser.cdp_vendor().is_some() {
+            // closing the previous connection resumes the requests it paused
+            self.fetch_interception = None;
+            if interceptor.is_empty() {
+                return Ok(());
+            }
+            let debugger_address = match &self.connection.debugger_address {
+                Some(address) => address.clone(),
+                None => return Err(WebdriverError::from(ErrorKind::UnsupportedOperation).with_message("the address of the DevTools server is unknown".to_string())),
+            };
+            let target = self.execute_cdp_command("Target.getTargetInfo", object!{})?;
+            let target_id = match target["targetInfo"]["targetId"].as_str() {
+                Some(target_id) => target_id,
+                None => return Err(WebdriverError::from(ErrorKind::InvalidResponse).with_message(format!("no target id in {}", target.dump()))),
+            };
+            self.fetch_interception = Some(FetchInterception::start(&debugger_address, target_id, interceptor.clone(), self.connection.get_request_timeout())?);
+            return Ok(());
+        }
+
+        let script = interceptor.script();
+        if self.connection.web_socket_url.is_some() {
+            if let Some(init_script) = self.interception_script.take() {
+                self.remove_init_script(init_script)?;
+            }
+            self.interception_script = Some(self.add_init_script(&script)?);
+        }
+        execute_script_sync(&self.connection, &self.session_id, &script, Vec::new())?;
+        Ok(())
+    }
+
+    /// Remove the rules installed by [intercept_network()](#method.intercept_network).
+    pub fn stop_network_interception(&mut self) -> Result<(), WebdriverError> {
+        self.intercept_network(&NetworkInterceptor::new())?;
+        if let Some(init_script) = self.interception_script.take() {
+            self.remove_init_script(init_script)?;
+        }
+        Ok(())
+    }
+
+    /// Return the requests to which a rule of [intercept_network()](#method.intercept_network) was applied:
+    /// every request since the rules were installed on Chrome and Edge, the requests of the current page on other browsers.
+    pub fn intercepted_requests(&self) -> Result<Vec<InterceptedRequest>, WebdriverError> {
+        if let Some(interception) = &self.fetch_interception {
+            return Ok(interception.requests());
+        }
+        self.select()?;
+        let json = execute_script_sync(&self.connection, &self.session_id, "return window.__lwInterception ? window.__lwInterception.requests : [];", Vec::new())?;
+
+        let mut requests = Vec::new();
+        for request in json.members() {
+            match InterceptedRequest::from_json(request) {
+                Some(request) => requests.push(request),
+                None => {
+                    error!("intercepted request was not understood: {}", request);
+                    return Err(ErrorKind::InvalidResponse.into());
+                }
+            }
+        }
+        Ok(requests)
+    }
+
+    /// Return the certificate chain of the loaded page, starting with the certificate of the server.
+    /// The list is empty if the page is not served over https.
+    /// This uses the Chrome DevTools Protocol and is only available on Chrome and Edge (other browsers return [UnknowCommand](../error/enum.ErrorKind.html#variant.UnknowCommand)).
+    /// 
+    /// ```rust
+    /// # use lw_webdriver::{session::Session, enums::Browser};
+    /// let mut session = Session::new(Browser::Chrome, false).unwrap();
+    /// session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+    /// 
+    /// let certificates = session.tabs[0].get_certificates().unwrap();
+    /// assert!(certificates[0].subject_alt_names.contains(&"mubelotix.dev".to_string()));
+    /// println!("expires in {:?}", certificates[0].expires_in());
+    /// ```
+    pub fn get_certificates(&self) -> Result<Vec<Certificate>, WebdriverError> {
+        self.select()?;
+        let url = get_active_tab_url(&self.connection, &self.session_id)?;
+        if !url.starts_with("https://") {
+            return Ok(Vec::new());
+        }
+        let origin = match url[8..].find('/') {
+            Some(index) => &url[..8 + index],
+            None => &url,
+        };
+
+        self.execute_cdp_command("Network.enable", object!{})?;
+        let json = self.execute_cdp_command("Network.getCertificate", object!{
+            "origin" => origin
+        })?;
+
+        let mut certificates = Vec::new();
+        for certificate in json["tableNames"].members() {
+            match certificate.as_str().map(base64::decode) {
+                Some(Ok(der)) => match Certificate::from_der(der) {
+                    Some(certificate) => certificates.push(certificate),
+                    None => {
+                        error!("a certificate of {} could not be parsed", origin);
+                        return Err(ErrorKind::InvalidResponse.into());
+                    }
+                },
+                _ => {
+                    error!("response to certificate request was not understood: {}", json);
+                    return Err(ErrorKind::InvalidResponse.into());
+                }
+            }
+        }
+
+        Ok(certificates)
+    }
+}
+
+struct RestoredScroll<'a> {
+    tab: &'a mut Tab,
+    x: f64,
+    y: f64
+}
+
+impl<'a> Drop for RestoredScroll<'a> {
+    fn drop(&mut self) {
+        if let Err(error) = self.tab.set_scroll_position(self.x, self.y) {
+            error!("failed to restore the scroll position: {:?}", error);
+        }
     }
 }
 
@@ -148,7 +1123,9 @@ impl PartialEq for Tab {
 }
 
 impl WebdriverObject for Tab {
-    fn get_id(&self) -> &String {
+    type Id = WindowHandle;
+
+    fn get_id(&self) -> &WindowHandle {
         &self.id
     }
 }
@@ -156,8 +1133,14 @@ impl WebdriverObject for Tab {
 impl Drop for Tab {
     #[allow(unused_must_use)]
     fn drop(&mut self) {
-        if let Ok(()) = self.select() {
-            close_active_tab(&self.session_id);
+        if !self.close_on_drop {
+            return;
+        }
+        let result = self.select().and_then(|()| close_active_tab(&self.connection, &self.session_id));
+        match result {
+            Err(error) if error == ErrorKind::TabClosed || error == ErrorKind::NoSuchWindow => debug!("tab {} was already closed", self.id),
+            Err(error) => warn!("failed to close tab {}: {}", self.id, error),
+            Ok(()) => (),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,146 @@
+//! Helpers running tests in a browser
+//!
+//! A [Fixture](struct.Fixture.html) opens a session for a test and closes it afterwards. When the test panics,
+//! a screenshot of every tab is saved before the session is closed, to see what the page looked like.
+//! The session is configured with the environment variables of [SessionConfig::from_env()](../config/struct.SessionConfig.html#method.from_env),
+//! so that the same tests can run on every browser in continuous integration. The tests are headless unless `LW_WEBDRIVER_HEADLESS` is `false`.
+//! The screenshots are saved in `LW_WEBDRIVER_SCREENSHOTS` (`target/webdriver-failures` by default).
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{webdriver_test, enums::Selector};
+//!
+//! webdriver_test! {
+//!     fn title_is_displayed(session) {
+//!         session.tabs[0].navigate("https://example.com/").unwrap();
+//!         assert!(session.tabs[0].find(Selector::TagName, "h1").unwrap().is_some());
+//!     }
+//!
+//!     #[ignore]
+//!     fn slow_test(session) {
+//!         session.tabs[0].navigate("https://example.com/slow").unwrap();
+//!     }
+//! }
+//! ```
+//!
+//! With the `derive` feature, the tests can also be declared with an attribute:
+//!
+//! ```ignore
+//! use lw_webdriver::{testing::webdriver_test, session::Session, enums::Selector};
+//!
+//! #[webdriver_test]
+//! fn title_is_displayed(session: &mut Session) {
+//!     session.tabs[0].navigate("https://example.com/").unwrap();
+//!     assert!(session.tabs[0].find(Selector::TagName, "h1").unwrap().is_some());
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::panic::{self, AssertUnwindSafe};
+use log::{info, warn};
+use crate::session::Session;
+use crate::config::SessionConfig;
+use crate::capabilities::Capabilities;
+use crate::enums::Browser;
+
+#[cfg(feature = "derive")]
+pub use lw_webdriver_derive::webdriver_test;
+
+/// The environment of the tests, opening a session per test.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Fixture {
+    config: SessionConfig,
+    screenshots: PathBuf
+}
+
+impl Fixture {
+    /// Read the configuration of the sessions and the directory of the screenshots from the environment variables.
+    /// Panic if a variable has an invalid value.
+    pub fn from_env() -> Self {
+        let config = match SessionConfig::new(Capabilities::new(Browser::Firefox).headless(true)).with_env() {
+            Ok(config) => config,
+            Err(error) => panic!("invalid test environment: {}", error),
+        };
+        Fixture::with_config(config)
+    }
+
+    /// Open the sessions with a configuration, whatever the environment variables (except the directory of the screenshots).
+    pub fn with_config(config: SessionConfig) -> Self {
+        let screenshots = std::env::var_os("LW_WEBDRIVER_SCREENSHOTS").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("target/webdriver-failures"));
+        Fixture {
+            config,
+            screenshots
+        }
+    }
+
+    /// Use a browser, whatever the environment variables.
+    pub fn browser(mut self, browser: Browser) -> Self {
+        self.config.capabilities.browser = browser;
+        self
+    }
+
+    pub fn headless(mut self, headless: bool) -> Self {
+        self.config.capabilities.headless = headless;
+        self
+    }
+
+    /// Save the screenshots of failed tests in a directory (created if needed).
+    pub fn screenshots<P: AsRef<Path>>(mut self, directory: P) -> Self {
+        self.screenshots = directory.as_ref().to_path_buf();
+        self
+    }
+
+    /// Open a session, run a test with it and close it.
+    /// If the test panics, the tabs are saved as `{test name}-{tab index}.png` and the panic goes on.
+    /// Panic if the session cannot be opened.
+    pub fn run<F: FnOnce(&mut Session)>(&self, test_name: &str, test: F) {
+        let mut session = match Session::new_with_config(self.config.clone()) {
+            Ok(session) => session,
+            Err(error) => panic!("failed to open a {:?} session for {}: {}", self.config.capabilities.browser, test_name, error),
+        };
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| test(&mut session)));
+        if let Err(payload) = result {
+            self.save_screenshots(&session, test_name);
+            drop(session);
+            panic::resume_unwind(payload);
+        }
+    }
+
+    /// -> take the session of a failed test
+    /// save a screenshot of every tab, the failure being reported anyway
+    fn save_screenshots(&self, session: &Session, test_name: &str) {
+        if let Err(error) = std::fs::create_dir_all(&self.screenshots) {
+            warn!("failed to create {}: {}", self.screenshots.display(), error);
+            return;
+        }
+        for (index, tab) in session.tabs.iter().enumerate() {
+            let path = self.screenshots.join(format!("{}-{}.png", test_name, index));
+            match tab.screenshot().map(|png| std::fs::write(&path, png)) {
+                Ok(Ok(())) => info!("{} failed, screenshot saved to {}", test_name, path.display()),
+                Ok(Err(error)) => warn!("failed to save {}: {}", path.display(), error),
+                Err(error) => warn!("failed to take a screenshot of tab {} of {}: {}", index, test_name, error),
+            }
+        }
+    }
+}
+
+/// Declare tests receiving a `&mut Session` opened by [Fixture::from_env()](testing/struct.Fixture.html#method.from_env).
+/// The session is closed after the test, and the tabs are saved as screenshots if the test panics.
+///
+/// The name between parentheses is the name of the session in the body, and the attributes (`#[ignore]`, `#[should_panic]`...) are kept.
+/// With the `derive` feature, the `#[webdriver_test]` attribute of the [testing](testing/index.html) module does the same for a single function.
+#[macro_export]
+macro_rules! webdriver_test {
+    ($($(#[$attribute:meta])* fn $name:ident($session:ident) $body:block)*) => {
+        $(
+            #[test]
+            $(#[$attribute])*
+            fn $name() {
+                $crate::testing::Fixture::from_env().run(stringify!($name), |$session: &mut $crate::session::Session| $body);
+            }
+        )*
+    };
+}
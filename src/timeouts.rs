@@ -1,20 +1,129 @@
 use json::*;
+use std::ops::Mul;
+use std::time::Duration;
 
+/// Timeouts of a session.
+/// `script` is None when scripts never time out.
+///
+/// Timeouts are built from the defaults of the webdriver protocol:
+///
+/// ```rust
+/// # use lw_webdriver::{session::Session, timeouts::Timeouts, enums::Browser};
+/// # use std::time::Duration;
+/// let mut session = Session::new(Browser::Firefox, false).unwrap();
+///
+/// session.set_timeouts(Timeouts::builder().page_load(Duration::from_secs(30)).script(Duration::from_secs(5))).unwrap();
+/// session.set_implicit_wait(Duration::from_millis(500)).unwrap();
+/// ```
+///
+/// Presets are provided for the usual environments and can be multiplied:
+///
+/// ```rust
+/// # use lw_webdriver::{session::Session, timeouts::Timeouts, enums::Browser};
+/// let mut session = Session::new(Browser::Firefox, false).unwrap();
+///
+/// let timeouts = if std::env::var("CI").is_ok() { Timeouts::ci() } else { Timeouts::fast() };
+/// session.set_timeouts(timeouts * 2).unwrap();
+/// ```
 #[derive(PartialEq)]
 #[derive(Debug)]
 #[derive(Copy, Clone)]
 pub struct Timeouts {
-    pub script: Option<usize>,
-    pub page_load: usize,
-    pub implicit: usize
+    pub script: Option<Duration>,
+    pub page_load: Duration,
+    pub implicit: Duration
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts::builder()
+    }
 }
 
 impl Timeouts {
+    /// The defaults of the webdriver protocol, to be changed with the other methods.
+    /// Scripts: 30s, page loads: 5min, implicit waits: none.
+    pub fn builder() -> Self {
+        Timeouts {
+            script: Some(Duration::from_secs(30)),
+            page_load: Duration::from_secs(300),
+            implicit: Duration::from_secs(0)
+        }
+    }
+
+    /// How long a script can run before failing with [ScriptTimeoutError](../error/enum.ErrorKind.html#variant.ScriptTimeoutError).
+    pub fn script(mut self, timeout: Duration) -> Self {
+        self.script = Some(timeout);
+        self
+    }
+
+    /// Let scripts run forever.
+    pub fn no_script_timeout(mut self) -> Self {
+        self.script = None;
+        self
+    }
+
+    /// How long a navigation can take before failing with [Timeout](../error/enum.ErrorKind.html#variant.Timeout).
+    pub fn page_load(mut self, timeout: Duration) -> Self {
+        self.page_load = timeout;
+        self
+    }
+
+    /// How long the driver looks for an element before considering that there is none.
+    pub fn implicit(mut self, timeout: Duration) -> Self {
+        self.implicit = timeout;
+        self
+    }
+
+    /// Short timeouts for a developer machine, where slowness means a bug.
+    /// Scripts: 5s, page loads: 15s, implicit waits: none.
+    pub fn fast() -> Self {
+        Timeouts {
+            script: Some(Duration::from_secs(5)),
+            page_load: Duration::from_secs(15),
+            implicit: Duration::from_secs(0)
+        }
+    }
+
+    /// Timeouts for shared continuous integration runners, which are slower and less predictable.
+    /// Scripts: 30s, page loads: 60s, implicit waits: 500ms.
+    pub fn ci() -> Self {
+        Timeouts {
+            script: Some(Duration::from_secs(30)),
+            page_load: Duration::from_secs(60),
+            implicit: Duration::from_millis(500)
+        }
+    }
+
+    /// Long timeouts for slow sites and networks.
+    /// Scripts: 2min, page loads: 5min, implicit waits: 2s.
+    pub fn patient() -> Self {
+        Timeouts {
+            script: Some(Duration::from_secs(120)),
+            page_load: Duration::from_secs(300),
+            implicit: Duration::from_secs(2)
+        }
+    }
+
+    /// The timeouts in milliseconds, as expected by the webdriver protocol (a null script timeout means no timeout).
     pub fn to_json(&self) -> json::JsonValue {
         object! {
-            "script" => self.script,
-            "pageLoad" => self.page_load,
-            "implicit" => self.implicit
+            "script" => self.script.map(|script| script.as_millis() as u64),
+            "pageLoad" => self.page_load.as_millis() as u64,
+            "implicit" => self.implicit.as_millis() as u64
         }
     }
-}
\ No newline at end of file
+}
+
+impl Mul<u32> for Timeouts {
+    type Output = Timeouts;
+
+    /// Multiply every timeout.
+    fn mul(self, factor: u32) -> Timeouts {
+        Timeouts {
+            script: self.script.map(|script| script * factor),
+            page_load: self.page_load * factor,
+            implicit: self.implicit * factor
+        }
+    }
+}
@@ -0,0 +1,397 @@
+//! Export of recorded sessions as Rust tests or json scripts
+//!
+//! The commands sent while recording are converted to calls of this crate, and the values read from
+//! the page (titles, urls, texts of elements...) become assertions.
+//! They can also be saved as json, with their requests and responses, to reproduce flaky runs or document an interaction.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::{Browser, Selector}};
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.start_recording();
+//!
+//! session.tabs[0].navigate("http://example.com/").unwrap();
+//! let link = session.tabs[0].find(Selector::Css, "a").unwrap().unwrap();
+//! link.get_text().unwrap();
+//!
+//! let transcript = session.stop_recording();
+//! std::fs::write("tests/example.rs", transcript.to_rust("example_link")).unwrap();
+//! std::fs::write("target/example.json", transcript.to_json().pretty(2)).unwrap();
+//! ```
+//!
+//! Saved transcripts can be loaded with [Transcript::from_json()](struct.Transcript.html#method.from_json) and
+//! replayed with the [replay](../replay/index.html) module.
+
+use std::time::Duration;
+use json::{JsonValue, object};
+use crate::command::Command;
+use crate::enums::{Browser, WindowType};
+use crate::permissions::Permission;
+use crate::transport::Method;
+use crate::error::{WebdriverError, ErrorKind};
+
+/// A command sent while recording, with the value of its response.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct TranscriptEntry {
+    pub command: Command,
+    /// When the command was sent, relative to the start of the recording.
+    pub time: Duration,
+    /// Null if the command failed.
+    pub value: JsonValue,
+    /// The error returned by the command, if it failed.
+    pub error: Option<String>
+}
+
+/// The commands recorded between [Session::start_recording()](../session/struct.Session.html#method.start_recording)
+/// and [Session::stop_recording()](../session/struct.Session.html#method.stop_recording).
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Transcript {
+    pub browser: Browser,
+    pub entries: Vec<TranscriptEntry>
+}
+
+/// The tabs and elements of the recorded session, to name them in the generated code
+struct Replay {
+    /// the handle of every tab of `session.tabs` (None until it is known)
+    tabs: Vec<Option<String>>,
+    selected: usize,
+    elements: Vec<String>,
+    lines: Vec<String>
+}
+
+impl Replay {
+    fn tab(&self) -> String {
+        format!("session.tabs[{}]", self.selected)
+    }
+
+    /// -> return the index of the tab with a handle, guessing the handle of the first tab
+    fn tab_index(&mut self, handle: &str) -> Option<usize> {
+        if let Some(index) = self.tabs.iter().position(|tab| tab.as_deref() == Some(handle)) {
+            return Some(index);
+        }
+        let index = self.tabs.iter().position(|tab| tab.is_none())?;
+        self.tabs[index] = Some(handle.to_string());
+        Some(index)
+    }
+
+    fn element(&self, id: &str) -> Option<String> {
+        self.elements.iter().position(|element| element == id).map(|index| format!("element_{}", index + 1))
+    }
+
+    /// -> return an expression building a json value, element references being replaced by the elements
+    fn json(&self, value: &JsonValue) -> String {
+        match value[ELEMENT_KEY].as_str().and_then(|id| self.element(id)) {
+            Some(element) => format!("{}.as_json_object()", element),
+            None => format!("json::parse({:?}).unwrap()", value.dump()),
+        }
+    }
+
+    fn push(&mut self, line: String) {
+        self.lines.push(format!("    {}", line));
+    }
+
+    /// -> take a command and the value of its response
+    /// generate the code sending it again
+    fn command(&mut self, command: &Command, value: &JsonValue) {
+        let tab = self.tab();
+        match command {
+            // tabs are selected automatically
+            Command::GetWindowHandle => {
+                if let Some(handle) = value.as_str() {
+                    if self.tabs[self.selected].is_none() {
+                        self.tabs[self.selected] = Some(handle.to_string());
+                    }
+                }
+            },
+            Command::SwitchToWindow { handle } => match self.tab_index(handle) {
+                Some(index) => self.selected = index,
+                None => self.push(format!("// switched to a tab which was not opened by the session ({})", handle)),
+            },
+            Command::NewWindow { window_type } => {
+                match window_type {
+                    WindowType::Tab => self.push("session.open_tab().unwrap();".to_string()),
+                    WindowType::Window => self.push("session.open_window().unwrap();".to_string()),
+                }
+                self.tabs.push(value["handle"].as_str().map(|handle| handle.to_string()));
+            },
+            Command::CloseWindow => {
+                if self.tabs.len() > 1 {
+                    self.push(format!("session.tabs.remove({});", self.selected));
+                    self.tabs.remove(self.selected);
+                    self.selected = 0;
+                }
+            },
+            Command::Navigate { url } => self.push(format!("{}.navigate({:?}).unwrap();", tab, url)),
+            Command::Back => self.push(format!("{}.back().unwrap();", tab)),
+            Command::Forward => self.push(format!("{}.forward().unwrap();", tab)),
+            Command::Refresh => self.push(format!("{}.refresh().unwrap();", tab)),
+            Command::GetTitle => if let Some(title) = value.as_str() {
+                self.push(format!("assert_eq!({}.get_title().unwrap(), {:?});", tab, title));
+            },
+            Command::GetCurrentUrl => if let Some(url) = value.as_str() {
+                self.push(format!("assert_eq!({}.get_url().unwrap(), {:?});", tab, url));
+            },
+            Command::SetTimeouts(timeouts) => {
+                let script = match timeouts.script {
+                    Some(script) => format!(".script(std::time::Duration::from_millis({}))", script.as_millis()),
+                    None => ".no_script_timeout()".to_string(),
+                };
+                self.push(format!("session.set_timeouts(lw_webdriver::timeouts::Timeouts::builder(){}.page_load(std::time::Duration::from_millis({})).implicit(std::time::Duration::from_millis({}))).unwrap();",
+                    script, timeouts.page_load.as_millis(), timeouts.implicit.as_millis()));
+            },
+            Command::ExecuteScript { script, args } => {
+                let args: Vec<String> = args.iter().map(|arg| self.json(arg)).collect();
+                self.push(format!("{}.execute_script({:?}, vec![{}]).unwrap();", tab, script, args.join(", ")));
+            },
+            Command::FindElement { selector, value: tofind } => match value[ELEMENT_KEY].as_str() {
+                Some(id) => {
+                    self.elements.push(id.to_string());
+                    self.push(format!("let mut element_{} = {}.find(Selector::{:?}, {:?}).unwrap().unwrap();", self.elements.len(), tab, selector, tofind));
+                },
+                None => self.push(format!("assert!({}.find(Selector::{:?}, {:?}).unwrap().is_none());", tab, selector, tofind)),
+            },
+            Command::FindElementFromElement { element_id, selector, value: tofind } => match (self.element(element_id), value[ELEMENT_KEY].as_str()) {
+                (Some(parent), Some(id)) => {
+                    self.elements.push(id.to_string());
+                    self.push(format!("let mut element_{} = {}.find(Selector::{:?}, {:?}).unwrap().unwrap();", self.elements.len(), parent, selector, tofind));
+                },
+                (Some(parent), None) => self.push(format!("assert!({}.find(Selector::{:?}, {:?}).unwrap().is_none());", parent, selector, tofind)),
+                (None, _) => self.push(format!("// searched in an element which was not found by the session ({})", element_id)),
+            },
+            Command::ElementClick { element_id } => match self.element(element_id) {
+                Some(element) => self.push(format!("{}.click().unwrap();", element)),
+                None => self.push(format!("// clicked an element which was not found by the session ({})", element_id)),
+            },
+            Command::ElementSubmit { element_id } => match self.element(element_id) {
+                Some(element) => self.push(format!("{}.submit().unwrap();", element)),
+                None => self.push(format!("// submitted the form of an element which was not found by the session ({})", element_id)),
+            },
+            Command::ElementSendKeys { element_id, text } => match self.element(element_id) {
+                Some(element) => self.push(format!("{}.type_text({:?}).unwrap();", element, text)),
+                None => self.push(format!("// typed {:?} in an element which was not found by the session ({})", text, element_id)),
+            },
+            Command::GetElementText { element_id } => self.assert_element(element_id, "get_text()", value),
+            Command::GetElementAttribute { element_id, name } => self.assert_element(element_id, &format!("get_attribute({:?})", name), value),
+            Command::GetElementProperty { element_id, name } => self.assert_element(element_id, &format!("get_property({:?})", name), value),
+            Command::GetElementCssValue { element_id, property } => self.assert_element(element_id, &format!("get_css_value({:?})", property), value),
+            Command::GetElementTagName { element_id } => self.assert_element(element_id, "get_tag_name()", value),
+            Command::GetComputedRole { element_id } => self.assert_element(element_id, "computed_role()", value),
+            Command::GetComputedLabel { element_id } => self.assert_element(element_id, "accessible_name()", value),
+            Command::IsElementEnabled { element_id } => self.assert_element(element_id, "is_enabled()", value),
+            Command::IsElementSelected { element_id } => self.assert_element(element_id, "is_selected()", value),
+            Command::IsElementDisplayed { element_id } => self.assert_element(element_id, "is_displayed()", value),
+            Command::PerformActions { actions } => match self.mouse_gesture(actions) {
+                Some((element, method)) => self.push(format!("{}.{}().unwrap();", element, method)),
+                None => self.custom(&tab, command),
+            },
+            Command::ReleaseActions => self.push(format!("{}.release_actions().unwrap();", tab)),
+            Command::DeleteAllCookies => self.push(format!("{}.delete_cookies().unwrap();", tab)),
+            Command::TakeScreenshot => self.push(format!("{}.screenshot().unwrap();", tab)),
+            Command::TakeFullPageScreenshot => self.push(format!("{}.screenshot_full_page().unwrap();", tab)),
+            Command::SetPermission { permission, state } if !matches!(permission, Permission::Descriptor(_)) => {
+                self.push(format!("{}.select().unwrap();", tab));
+                self.push(format!("session.set_permission(Permission::{:?}, PermissionState::{:?}).unwrap();", permission, state));
+            },
+            Command::ExecuteCdpCommand { method, params, .. } => self.push(format!("{}.cdp_command({:?}, {}).unwrap();", tab, method, self.json(params))),
+            // commands reading values which are not checked, or ending the session
+            Command::GetWindowHandles | Command::GetTimeouts | Command::GetPageSource | Command::GetElementRect { .. }
+                | Command::GetAllCookies | Command::GetLog { .. } | Command::DeleteSession => (),
+            command => self.custom(&tab, command),
+        }
+    }
+
+    /// -> take a command without a dedicated method
+    /// generate the code sending it as a custom command
+    fn custom(&mut self, tab: &str, command: &Command) {
+        let (method, path, body) = command.to_relative_request();
+        let body = body.and_then(|body| json::parse(&body).ok()).map(|body| format!("Some({})", self.json(&body))).unwrap_or_else(|| "None".to_string());
+        self.push(format!("{}.select().unwrap();", tab));
+        self.push(format!("session.execute(Command::Custom {{ method: Method::{:?}, path: {:?}.to_string(), body: {} }}).unwrap();", method, path, body));
+    }
+
+    /// -> take action sequences
+    /// -> return the element and the method of Element performing them (like `hover`), if any
+    fn mouse_gesture(&self, actions: &JsonValue) -> Option<(String, &'static str)> {
+        if actions.len() != 1 || actions[0]["parameters"]["pointerType"] != "mouse" {
+            return None;
+        }
+        let actions = &actions[0]["actions"];
+        let movement = &actions[0];
+        if movement["type"] != "pointerMove" || movement["x"] != 0 || movement["y"] != 0 {
+            return None;
+        }
+        let element = movement["origin"][ELEMENT_KEY].as_str().and_then(|id| self.element(id))?;
+
+        let buttons: Vec<(&str, u8)> = actions.members().skip(1).filter_map(|action| Some((action["type"].as_str()?, action["button"].as_u8()?))).collect();
+        if buttons.len() + 1 != actions.len() {
+            return None;
+        }
+        let method = match buttons.as_slice() {
+            [] => "hover",
+            [("pointerDown", 2), ("pointerUp", 2)] => "context_click",
+            [("pointerDown", 1), ("pointerUp", 1)] => "middle_click",
+            [("pointerDown", 0), ("pointerUp", 0), ("pointerDown", 0), ("pointerUp", 0)] => "double_click",
+            _ => return None,
+        };
+        Some((element, method))
+    }
+
+    fn assert_element(&mut self, element_id: &str, getter: &str, value: &JsonValue) {
+        let element = match self.element(element_id) {
+            Some(element) => element,
+            None => return,
+        };
+        if let Some(text) = value.as_str() {
+            self.push(format!("assert_eq!({}.{}.unwrap(), {:?});", element, getter, text));
+        } else if let Some(boolean) = value.as_bool() {
+            self.push(format!("assert_eq!({}.{}.unwrap(), {});", element, getter, boolean));
+        }
+    }
+}
+
+const ELEMENT_KEY: &str = "element-6066-11e4-a52e-4f735466cecf";
+
+impl Transcript {
+    /// Generate a Rust test file replaying the session, the test being named `test_name`.
+    /// Place it in the `tests` directory of a crate depending on `lw-webdriver` and `json`.
+    pub fn to_rust(&self, test_name: &str) -> String {
+        let mut replay = Replay {
+            tabs: vec![None],
+            selected: 0,
+            elements: Vec::new(),
+            lines: Vec::new()
+        };
+        for entry in &self.entries {
+            match &entry.error {
+                Some(error) => replay.push(format!("// {} failed: {}", entry.command.name(), error)),
+                None => replay.command(&entry.command, &entry.value),
+            }
+        }
+
+        let browser = match &self.browser {
+            Browser::ChromiumBased { binary } => format!("Browser::ChromiumBased {{ binary: {:?}.into() }}", binary),
+            browser => format!("Browser::{:?}", browser),
+        };
+        format!(
+"//! Generated from a session recorded with lw-webdriver
+#![allow(unused_mut, unused_imports)]
+
+use lw_webdriver::{{session::Session, enums::{{Browser, Selector}}, command::Command, transport::Method}};
+use lw_webdriver::permissions::{{Permission, PermissionState}};
+
+#[test]
+fn {}() {{
+    let mut session = Session::new({}, false).unwrap();
+{}
+}}
+", test_name, browser, replay.lines.join("\n"))
+    }
+
+    /// Export the commands as json, in order, with the requests sent and the responses received.
+    /// Paths are relative to the session (`/url`) and times are in milliseconds since the start of the recording.
+    ///
+    /// ```json
+    /// {
+    ///     "browser": "Firefox",
+    ///     "entries": [
+    ///         { "command": "Navigate", "time": 0, "method": "POST", "path": "/url", "body": { "url": "http://example.com/" }, "value": null, "error": null }
+    ///     ]
+    /// }
+    /// ```
+    pub fn to_json(&self) -> JsonValue {
+        let entries: Vec<JsonValue> = self.entries.iter().map(|entry| {
+            let (method, path, body) = entry.command.to_relative_request();
+            object!{
+                "command" => entry.command.name(),
+                "time" => entry.time.as_millis() as u64,
+                "method" => method.to_string(),
+                "path" => path,
+                "body" => body.and_then(|body| json::parse(&body).ok()),
+                "value" => entry.value.clone(),
+                "error" => entry.error.clone()
+            }
+        }).collect();
+        let mut transcript = object!{
+            "browser" => match &self.browser {
+                Browser::Firefox => "Firefox",
+                Browser::Chrome => "Chrome",
+                Browser::Edge => "Edge",
+                Browser::Safari => "Safari",
+                Browser::ChromiumBased { .. } => "ChromiumBased",
+            },
+            "entries" => entries
+        };
+        if let Browser::ChromiumBased { binary } = &self.browser {
+            transcript["binary"] = binary.to_string_lossy().to_string().into();
+        }
+        transcript
+    }
+
+    /// Load a transcript exported with [to_json()](#method.to_json).
+    /// Commands are parsed from their requests: the commands selecting, opening and closing tabs and navigating are recognized,
+    /// the others are loaded as [Command::Custom](../command/enum.Command.html#variant.Custom).
+    pub fn from_json(transcript: &JsonValue) -> Result<Transcript, WebdriverError> {
+        let invalid = |message: String| WebdriverError::from(ErrorKind::InvalidArgument).with_message(message);
+        let browser = match transcript["browser"].as_str() {
+            Some("Firefox") => Browser::Firefox,
+            Some("Chrome") => Browser::Chrome,
+            Some("Edge") => Browser::Edge,
+            Some("Safari") => Browser::Safari,
+            Some("ChromiumBased") => match transcript["binary"].as_str() {
+                Some(binary) => Browser::ChromiumBased { binary: binary.into() },
+                None => return Err(invalid("the binary of the browser is missing".to_string())),
+            },
+            _ => return Err(invalid(format!("unknown browser: {}", transcript["browser"]))),
+        };
+
+        let mut entries = Vec::new();
+        for (index, entry) in transcript["entries"].members().enumerate() {
+            let method = match entry["method"].as_str() {
+                Some("GET") => Method::Get,
+                Some("POST") => Method::Post,
+                Some("DELETE") => Method::Delete,
+                _ => return Err(invalid(format!("entry {} has an invalid method: {}", index, entry["method"]))),
+            };
+            let path = entry["path"].as_str().ok_or_else(|| invalid(format!("entry {} has no path", index)))?;
+            let body = if entry["body"].is_null() { None } else { Some(entry["body"].clone()) };
+            entries.push(TranscriptEntry {
+                command: command_from_request(method, path, body),
+                time: Duration::from_millis(entry["time"].as_u64().unwrap_or(0)),
+                value: entry["value"].clone(),
+                error: entry["error"].as_str().map(|error| error.to_string())
+            });
+        }
+
+        Ok(Transcript {
+            browser,
+            entries
+        })
+    }
+}
+
+/// -> take a request, the path being relative to the session
+/// -> return the command sending it
+fn command_from_request(method: Method, path: &str, body: Option<JsonValue>) -> Command {
+    let field = |name: &str| body.as_ref().and_then(|body| body[name].as_str()).map(|value| value.to_string());
+    match (method, path) {
+        (Method::Post, "/url") if field("url").is_some() => Command::Navigate { url: field("url").unwrap_or_default() },
+        (Method::Get, "/url") => Command::GetCurrentUrl,
+        (Method::Get, "/title") => Command::GetTitle,
+        (Method::Post, "/window") if field("handle").is_some() => Command::SwitchToWindow { handle: field("handle").unwrap_or_default() },
+        (Method::Get, "/window") => Command::GetWindowHandle,
+        (Method::Delete, "/window") => Command::CloseWindow,
+        (Method::Get, "/window/handles") => Command::GetWindowHandles,
+        (Method::Post, "/window/new") => match field("type").as_deref() {
+            Some("window") => Command::NewWindow { window_type: WindowType::Window },
+            _ => Command::NewWindow { window_type: WindowType::Tab },
+        },
+        (Method::Delete, "") => Command::DeleteSession,
+        (method, path) => Command::Custom { method, path: path.to_string(), body },
+    }
+}
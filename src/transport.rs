@@ -0,0 +1,159 @@
+//! Transports carry the commands to the webdriver server
+
+use std::time::Duration;
+use crate::http_client::HttpClient;
+
+/// The HTTP methods used by the webdriver protocol.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum Method {
+    Get,
+    Post,
+    Delete
+}
+
+impl Method {
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Delete => "DELETE"
+        }
+    }
+}
+
+/// A response of the webdriver server.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Response {
+    pub status_code: u16,
+    /// The json body of the response.
+    pub body: Vec<u8>
+}
+
+impl Response {
+    pub fn as_str(&self) -> Result<&str, std::str::Utf8Error> {
+        std::str::from_utf8(&self.body)
+    }
+}
+
+/// Sends the commands of sessions to a webdriver server.
+/// Every command of this crate goes through a transport, so implementing this trait allows to reach a server
+/// another way (Unix socket, tunnel...), to instrument the commands or to mock the server in tests.
+///
+/// Transports are shared by the sessions, tabs and elements, which can be sent to other threads, so they must be `Send` and `Sync`
+/// (use `Mutex` or atomics rather than `RefCell` or `Cell` to record the commands).
+///
+/// # Example
+///
+/// ```rust
+/// use lw_webdriver::{session::Session, config::SessionConfig, capabilities::Capabilities, enums::Browser};
+/// use lw_webdriver::transport::{Transport, HttpTransport, Method, Response};
+///
+/// /// Print every command sent to the driver.
+/// struct LoggedTransport(HttpTransport);
+///
+/// impl Transport for LoggedTransport {
+///     fn send(&self, method: Method, path: &str, body: Option<&str>) -> Result<Response, String> {
+///         println!("{} {} {}", method.to_string(), path, body.unwrap_or(""));
+///         self.0.send(method, path, body)
+///     }
+/// }
+///
+/// let config = SessionConfig::new(Capabilities::new(Browser::Firefox))
+///     .transport(LoggedTransport(HttpTransport::new("http://localhost:4444")));
+/// let mut session = Session::new_with_config(config).unwrap();
+/// ```
+pub trait Transport: Send + Sync {
+    /// Send a request to the server and return its response.
+    /// The path starts with a slash (`/session/{session id}/url`) and the body is json.
+    /// Return an error message if the server could not be reached.
+    fn send(&self, method: Method, path: &str, body: Option<&str>) -> Result<Response, String>;
+
+    /// Send a request like [send()](#tymethod.send), giving up if the server does not respond in time.
+    /// The timeout is the one set with [SessionConfig::request_timeout()](../config/struct.SessionConfig.html#method.request_timeout)
+    /// or the one of the command (None to wait forever).
+    ///
+    /// The default implementation ignores the timeout.
+    fn send_with_timeout(&self, method: Method, path: &str, body: Option<&str>, timeout: Option<Duration>) -> Result<Response, String> {
+        let _ = timeout;
+        self.send(method, path, body)
+    }
+}
+
+/// The default transport, sending requests over HTTP.
+/// Plain http servers are reached over a persistent connection.
+pub struct HttpTransport {
+    url: String,
+    authorization: Option<String>,
+    /// how long to wait for the server when no timeout is given (forever if None)
+    timeout: Option<Duration>,
+    client: HttpClient
+}
+
+impl HttpTransport {
+    /// Send the requests to a server (`http://localhost:4444`, `https://grid.example.com/wd/hub`).
+    pub fn new(url: &str) -> Self {
+        HttpTransport {
+            url: url.trim_end_matches('/').to_string(),
+            authorization: None,
+            timeout: None,
+            client: HttpClient::new()
+        }
+    }
+
+    /// Basic authentication credentials sent with every request.
+    pub fn credentials(mut self, username: &str, password: &str) -> Self {
+        self.authorization = Some(format!("Basic {}", base64::encode(&format!("{}:{}", username, password))));
+        self
+    }
+
+    /// Give up the requests sent with [send()](trait.Transport.html#tymethod.send) if the server does not respond in time.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl Transport for HttpTransport {
+    fn send(&self, method: Method, path: &str, body: Option<&str>) -> Result<Response, String> {
+        self.send_with_timeout(method, path, body, self.timeout)
+    }
+
+    fn send_with_timeout(&self, method: Method, path: &str, body: Option<&str>, timeout: Option<Duration>) -> Result<Response, String> {
+        let url = format!("{}{}", self.url, path);
+        if url.starts_with("http://") {
+            let mut headers = Vec::new();
+            if let Some(authorization) = &self.authorization {
+                headers.push(("Authorization", authorization.as_str()));
+            }
+            return self.client.send(method.to_string(), &url, &headers, body, timeout).map_err(|error| error.to_string());
+        }
+
+        let method = match method {
+            Method::Get => minreq::Method::Get,
+            Method::Post => minreq::Method::Post,
+            Method::Delete => minreq::Method::Delete,
+        };
+        let mut request = minreq::Request::new(method, url);
+        if let Some(authorization) = &self.authorization {
+            request = request.with_header("Authorization", authorization.as_str());
+        }
+        if let Some(body) = body {
+            request = request.with_body(body.to_string());
+        }
+        if let Some(timeout) = timeout {
+            // minreq counts the timeout in seconds
+            request = request.with_timeout(timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0));
+        }
+        match request.send() {
+            Ok(response) => Ok(Response {
+                status_code: response.status_code as u16,
+                body: response.into_bytes()
+            }),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+}
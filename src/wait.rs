@@ -0,0 +1,119 @@
+//! Waiting for conditions to be met
+//!
+//! Conditions are checked again and again until they are met or until a timeout expires.
+//! Tabs provide waiting methods ([Tab::wait_until()](../tab/struct.Tab.html#method.wait_until),
+//! [Tab::wait_for_element()](../tab/struct.Tab.html#method.wait_for_element)) polling at the interval set with
+//! [Tab::set_poll_interval()](../tab/struct.Tab.html#method.set_poll_interval). A [Wait](struct.Wait.html) can be used for anything else.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lw_webdriver::{session::Session, enums::Browser, wait::Wait};
+//! use std::time::Duration;
+//!
+//! let mut session = Session::new(Browser::Firefox, false).unwrap();
+//! session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+//!
+//! // wait until a global variable is set by the page
+//! session.tabs[0].wait_until(|tab| {
+//!     Ok(tab.execute_script("return window.appReady === true;", Vec::new())? == true)
+//! }, Duration::from_secs(10)).unwrap();
+//!
+//! // wait until the page opens a second tab, checking every 500ms
+//! let tabs = Wait::new(Duration::from_secs(10)).interval(Duration::from_millis(500)).until(|| {
+//!     session.update_tabs()?;
+//!     Ok(if session.tabs.len() > 1 { Some(session.tabs.len()) } else { None })
+//! }).unwrap();
+//! ```
+
+use std::time::{Duration, Instant};
+use std::thread;
+use crate::error::*;
+
+/// Counts the fetch and XMLHttpRequest requests in progress in `window.__lwPendingRequests`.
+/// Installing it twice has no effect.
+pub(crate) const PENDING_REQUESTS_SHIM_SCRIPT: &str = "
+    (() => {
+        if (window.__lwPendingRequests !== undefined) {
+            return;
+        }
+        window.__lwPendingRequests = 0;
+        const done = () => window.__lwPendingRequests = Math.max(0, window.__lwPendingRequests - 1);
+
+        if (window.fetch) {
+            const fetch = window.fetch;
+            window.fetch = function(...args) {
+                window.__lwPendingRequests += 1;
+                try {
+                    const promise = fetch.apply(this, args);
+                    promise.then(done, done);
+                    return promise;
+                } catch (error) {
+                    done();
+                    throw error;
+                }
+            };
+        }
+        const send = XMLHttpRequest.prototype.send;
+        XMLHttpRequest.prototype.send = function(...args) {
+            window.__lwPendingRequests += 1;
+            this.addEventListener('loadend', done, { once: true });
+            try {
+                return send.apply(this, args);
+            } catch (error) {
+                done();
+                throw error;
+            }
+        };
+    })();
+";
+
+/// Returns true when the document is loaded and no tracked request is in progress.
+pub(crate) const LOADED_SCRIPT: &str = "return document.readyState === 'complete' && !(window.__lwPendingRequests > 0);";
+
+/// The delay between two checks of a condition, unless another interval is set.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long and how often a condition is checked.
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct Wait {
+    pub timeout: Duration,
+    pub interval: Duration
+}
+
+impl Wait {
+    /// Wait at most `timeout`, checking at the [default interval](constant.DEFAULT_POLL_INTERVAL.html).
+    pub fn new(timeout: Duration) -> Self {
+        Wait {
+            timeout,
+            interval: DEFAULT_POLL_INTERVAL
+        }
+    }
+
+    /// Set the delay between two checks.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Check a condition until it returns a value, and return the value.
+    /// The condition is checked at least once, even with a null timeout.
+    ///
+    /// Return the error of the condition if it fails, and [Timeout](../error/enum.ErrorKind.html#variant.Timeout)
+    /// if it still returns None after the timeout.
+    pub fn until<T, F: FnMut() -> Result<Option<T>, WebdriverError>>(&self, mut condition: F) -> Result<T, WebdriverError> {
+        let start = Instant::now();
+        loop {
+            if let Some(value) = condition()? {
+                return Ok(value);
+            }
+            let elapsed = start.elapsed();
+            if elapsed >= self.timeout {
+                return Err(WebdriverError::from(ErrorKind::Timeout).with_message(format!("condition was not met in {:?}", self.timeout)));
+            }
+            thread::sleep(self.interval.min(self.timeout - elapsed));
+        }
+    }
+}
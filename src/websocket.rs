@@ -0,0 +1,160 @@
+//! A minimal WebSocket client (unencrypted `ws://` urls only), used by WebDriver BiDi and the Chrome DevTools Protocol
+//!
+//! Like the HTTP client, it avoids a dependency for the few messages the crate exchanges.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use log::debug;
+use crate::error::{WebdriverError, ErrorKind};
+
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+const OPCODE_TEXT: u8 = 0x1;
+
+/// A connection to a WebSocket server, exchanging text messages.
+pub(crate) struct WebSocket {
+    reader: BufReader<TcpStream>
+}
+
+impl WebSocket {
+    /// -> take a `ws://` url and how long to wait for a message (forever if None)
+    pub(crate) fn connect(url: &str, timeout: Option<Duration>) -> Result<Self, WebdriverError> {
+        let failed = |error: std::io::Error| WebdriverError::from(ErrorKind::FailedRequest).with_message(format!("failed to connect to {}: {}", url, error));
+        let address_and_path = match url.strip_prefix("ws://") {
+            Some(address_and_path) => address_and_path,
+            None => return Err(WebdriverError::from(ErrorKind::UnsupportedOperation).with_message(format!("only ws:// urls are supported, not {}", url))),
+        };
+        let (address, path) = match address_and_path.find('/') {
+            Some(index) => address_and_path.split_at(index),
+            None => (address_and_path, "/"),
+        };
+
+        let mut stream = TcpStream::connect(address).map_err(failed)?;
+        stream.set_read_timeout(timeout).map_err(failed)?;
+        stream.set_write_timeout(timeout).map_err(failed)?;
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            path, address, base64::encode(&random_bytes(16))
+        ).map_err(failed)?;
+
+        let mut reader = BufReader::new(stream);
+        let mut status_line = String::new();
+        reader.read_line(&mut status_line).map_err(failed)?;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).map_err(failed)? == 0 || header.trim().is_empty() {
+                break;
+            }
+        }
+        if status_line.split_whitespace().nth(1) != Some("101") {
+            return Err(WebdriverError::from(ErrorKind::FailedRequest).with_message(format!("{} refused the connection: {}", url, status_line.trim())));
+        }
+        debug!("connected to {}", url);
+
+        Ok(WebSocket {
+            reader
+        })
+    }
+
+    /// how long to wait for a message (forever if None)
+    pub(crate) fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        self.reader.get_ref().set_read_timeout(timeout)
+    }
+
+    /// -> return a handle on the connection, to shut it down while another thread waits for a message
+    pub(crate) fn try_clone_stream(&self) -> std::io::Result<TcpStream> {
+        self.reader.get_ref().try_clone()
+    }
+
+    pub(crate) fn send(&mut self, message: &str) -> std::io::Result<()> {
+        write_frame(self.reader.get_mut(), OPCODE_TEXT, message.as_bytes())
+    }
+
+    /// -> return the next message sent by the server, after answering the pings
+    pub(crate) fn receive(&mut self) -> std::io::Result<String> {
+        read_message(&mut self.reader)
+    }
+}
+
+/// -> return bytes which cannot be guessed by the pages, for the handshake key and the masks of the frames
+fn random_bytes(count: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    while bytes.len() < count {
+        bytes.extend_from_slice(&RandomState::new().build_hasher().finish().to_le_bytes());
+    }
+    bytes.truncate(count);
+    bytes
+}
+
+/// -> take the stream, the opcode of the frame and its payload (masked, as required for clients)
+fn write_frame(stream: &mut TcpStream, opcode: u8, payload: &[u8]) -> std::io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        length if length < 126 => frame.push(0x80 | length as u8),
+        length if length <= 0xFFFF => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        },
+        length => {
+            frame.push(0x80 | 127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        },
+    }
+    let mask = random_bytes(4);
+    frame.extend_from_slice(&mask);
+    frame.extend(payload.iter().enumerate().map(|(index, byte)| byte ^ mask[index % 4]));
+    stream.write_all(&frame)
+}
+
+/// -> return the next message sent by the server, after answering the pings
+fn read_message(reader: &mut BufReader<TcpStream>) -> std::io::Result<String> {
+    let mut message = Vec::new();
+    loop {
+        let mut header = [0; 2];
+        reader.read_exact(&mut header)?;
+        let (last, opcode, masked) = (header[0] & 0x80 != 0, header[0] & 0x0F, header[1] & 0x80 != 0);
+        let length = match header[1] & 0x7F {
+            126 => {
+                let mut length = [0; 2];
+                reader.read_exact(&mut length)?;
+                u64::from(u16::from_be_bytes(length))
+            },
+            127 => {
+                let mut length = [0; 8];
+                reader.read_exact(&mut length)?;
+                u64::from_be_bytes(length)
+            },
+            length => u64::from(length),
+        };
+        let mut mask = [0; 4];
+        if masked {
+            reader.read_exact(&mut mask)?;
+        }
+        let mut payload = Vec::new();
+        reader.by_ref().take(length).read_to_end(&mut payload)?;
+        if payload.len() as u64 != length {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        if masked {
+            payload.iter_mut().enumerate().for_each(|(index, byte)| *byte ^= mask[index % 4]);
+        }
+
+        match opcode {
+            OPCODE_PING => write_frame(reader.get_mut(), OPCODE_PONG, &payload)?,
+            OPCODE_PONG => (),
+            OPCODE_CLOSE => return Err(std::io::Error::new(std::io::ErrorKind::ConnectionAborted, "the BiDi server closed the connection")),
+            // text, binary and continuation frames
+            _ => {
+                message.extend_from_slice(&payload);
+                if last {
+                    return Ok(String::from_utf8_lossy(&message).into_owned());
+                }
+            },
+        }
+    }
+}
@@ -79,8 +79,71 @@ fn tabs() {
     }
 }
 
+#[test]
+fn open_window() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+    
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.open_tab().unwrap();
+        let index = session.open_window().unwrap(); // windows are tabs too
+        assert_eq!(session.tabs.len(), 3);
+        assert_eq!(session.tabs[index].window_type(), Some(WindowType::Window));
+        assert_eq!(session.tabs[1].window_type(), Some(WindowType::Tab));
+        assert_eq!(session.tabs[0].window_type(), None); // the tab opened with the session
+        session.tabs[index].navigate("https://mubelotix.dev/").unwrap();
+    }
+}
+
+#[test]
+fn find_tabs() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+    
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+        session.open_tab().unwrap();
+        session.tabs[1].navigate("https://mubelotix.dev/webdriver_tests/open_tab.html").unwrap();
+        session.update_tabs().unwrap();
+
+        let opener = session.find_tab_by_url(|url| url.ends_with("open_tab.html")).unwrap().unwrap().get_id().clone();
+        assert_eq!(&opener, session.tabs[1].get_id());
+        let title = session.tabs[0].get_title().unwrap();
+        let found = session.find_tab_by_title(|tab_title| tab_title == title).unwrap().unwrap().get_id().clone();
+        assert_eq!(&found, session.tabs[0].get_id());
+        assert!(session.find_tab_by_title(|title| title == "no tab has this title").unwrap().is_none());
+    }
+}
+
 #[test]
 fn timeouts() {
+    use lw_webdriver::timeouts::Timeouts;
+    use std::time::Duration;
+
     catch_unwind(|| {
         env_logger::init();
     });
@@ -98,23 +161,314 @@ fn timeouts() {
         };
 
         let mut timeouts = session.get_timeouts().unwrap();
-        assert_eq!(Some(30000), timeouts.script);
-        assert_eq!(300_000, timeouts.page_load);
-        assert_eq!(0, timeouts.implicit);
+        assert_eq!(timeouts, Timeouts::default());
         
-        timeouts.script = None;
-        timeouts.page_load = 299_999;
-        timeouts.implicit = 1;
+        timeouts = timeouts.no_script_timeout().page_load(Duration::from_millis(299_999)).implicit(Duration::from_millis(1));
 
         session.set_timeouts(timeouts).unwrap();
 
         timeouts = session.get_timeouts().unwrap();
         assert_eq!(None, timeouts.script);
-        assert_eq!(299_999, timeouts.page_load);
-        assert_eq!(1, timeouts.implicit);
+        assert_eq!(Duration::from_millis(299_999), timeouts.page_load);
+        assert_eq!(Duration::from_millis(1), timeouts.implicit);
+
+        let relaxed_timeouts = timeouts.implicit(Duration::from_secs(5));
+        let implicit = session.with_timeouts(relaxed_timeouts, |session| session.get_timeouts().unwrap().implicit).unwrap();
+        assert_eq!(Duration::from_secs(5), implicit);
+        assert_eq!(Duration::from_millis(1), session.get_timeouts().unwrap().implicit);
+
+        session.set_implicit_wait(Duration::from_millis(250)).unwrap();
+        session.set_page_load_timeout(Duration::from_secs(60)).unwrap();
+        assert_eq!(session.get_timeouts().unwrap(), Timeouts::builder().no_script_timeout().page_load(Duration::from_secs(60)).implicit(Duration::from_millis(250)));
+    }
+}
+
+/// A request received by a [MockDriver]
+#[derive(Debug)]
+#[derive(Clone)]
+struct MockRequest {
+    method: lw_webdriver::transport::Method,
+    path: String,
+    body: Option<String>,
+    /// the timeout given by the session (None to wait forever)
+    timeout: Option<std::time::Duration>
+}
+
+impl MockRequest {
+    /// -> return the body parsed as json (null if there is none)
+    fn json(&self) -> json::JsonValue {
+        self.body.as_deref().map(|body| json::parse(body).unwrap()).unwrap_or(json::JsonValue::Null)
+    }
+}
+
+impl std::fmt::Display for MockRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.method.to_string(), self.path)
+    }
+}
+
+/// The answer of a [MockDriver] route
+enum Reply {
+    /// a successful response with this value
+    Value(json::JsonValue),
+    /// a response with this status code and value
+    Status(u16, json::JsonValue),
+    /// a response with this status code and raw body
+    Raw(u16, &'static [u8]),
+    /// the server could not be reached
+    Unreachable(String),
+    /// let the routes added before answer
+    Next
+}
+
+impl Reply {
+    /// -> take the status code and the name of a webdriver error
+    fn error(status_code: u16, error: &str) -> Reply {
+        Reply::Status(status_code, json::object!{"error" => error, "message" => "", "stacktrace" => ""})
+    }
+}
+
+impl<T: Into<json::JsonValue>> From<T> for Reply {
+    fn from(value: T) -> Reply {
+        Reply::Value(value.into())
     }
 }
 
+type Route = (lw_webdriver::transport::Method, String, Box<dyn Fn(&MockRequest) -> Reply + Send + Sync>);
+
+/// A webdriver server answering with scripted routes and recording the requests, for the tests which do not need a browser.
+/// It creates the session `mock` with the tab `tab`, and answers null to the requests without a route.
+/// The routes added last are tried first, and a `*` in their path matches any part of a path.
+/// A route answering [Reply::Next] lets the routes added before answer.
+/// Clones share the routes and the requests, so that a clone can be given to the session.
+#[derive(Clone)]
+struct MockDriver {
+    routes: std::sync::Arc<std::sync::Mutex<Vec<Route>>>,
+    requests: std::sync::Arc<std::sync::Mutex<Vec<MockRequest>>>
+}
+
+impl MockDriver {
+    fn new() -> Self {
+        use lw_webdriver::transport::Method;
+
+        MockDriver {
+            routes: Default::default(),
+            requests: Default::default()
+        }
+            .route(Method::Post, "/session", json::object!{"sessionId" => "mock"})
+            .route(Method::Get, "/session/mock/window/handles", json::array!["tab"])
+            .route(Method::Get, "/session/mock/window", "tab")
+    }
+
+    /// Answer the requests to a path with a value.
+    fn route<T: Into<json::JsonValue>>(self, method: lw_webdriver::transport::Method, path: &str, value: T) -> Self {
+        let value = value.into();
+        self.route_with(method, path, move |_| Reply::Value(value.clone()))
+    }
+
+    /// Answer the requests to a path with a closure.
+    fn route_with<F: Fn(&MockRequest) -> Reply + Send + Sync + 'static>(self, method: lw_webdriver::transport::Method, path: &str, reply: F) -> Self {
+        self.routes.lock().unwrap().push((method, path.to_string(), Box::new(reply)));
+        self
+    }
+
+    /// -> return the requests received so far
+    fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// -> return the method and the path of the requests received so far (`GET /session/mock/url`)
+    fn sent(&self) -> Vec<String> {
+        self.requests().iter().map(ToString::to_string).collect()
+    }
+
+    fn clear_requests(&self) {
+        self.requests.lock().unwrap().clear();
+    }
+}
+
+impl lw_webdriver::transport::Transport for MockDriver {
+    fn send(&self, method: lw_webdriver::transport::Method, path: &str, body: Option<&str>) -> Result<lw_webdriver::transport::Response, String> {
+        self.send_with_timeout(method, path, body, None)
+    }
+
+    fn send_with_timeout(&self, method: lw_webdriver::transport::Method, path: &str, body: Option<&str>, timeout: Option<std::time::Duration>) -> Result<lw_webdriver::transport::Response, String> {
+        let request = MockRequest { method, path: path.to_string(), body: body.map(str::to_string), timeout };
+        self.requests.lock().unwrap().push(request.clone());
+
+        let matches = |pattern: &str| match pattern.split_once('*') {
+            Some((prefix, suffix)) => path.len() >= prefix.len() + suffix.len() && path.starts_with(prefix) && path.ends_with(suffix),
+            None => pattern == path,
+        };
+        let routes = self.routes.lock().unwrap();
+        let reply = routes.iter().rev()
+            .filter(|(route_method, pattern, _)| *route_method == method && matches(pattern))
+            .map(|(_, _, reply)| reply(&request))
+            .find(|reply| !matches!(reply, Reply::Next))
+            .unwrap_or(Reply::Value(json::JsonValue::Null));
+        let (status_code, body) = match reply {
+            Reply::Value(value) => (200, json::object!{"value" => value}.dump().into_bytes()),
+            Reply::Status(status_code, value) => (status_code, json::object!{"value" => value}.dump().into_bytes()),
+            Reply::Raw(status_code, body) => (status_code, body.to_vec()),
+            Reply::Unreachable(error) => return Err(error),
+            Reply::Next => unreachable!(),
+        };
+        Ok(lw_webdriver::transport::Response { status_code, body })
+    }
+}
+
+#[test]
+fn full_page_screenshot_clip() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+    use lw_webdriver::transport::Method;
+
+    // a Chrome driver with a document taller than the window
+    let driver = MockDriver::new().route_with(Method::Post, "/session/mock/goog/cdp/execute", |request| match request.json()["cmd"].as_str().unwrap() {
+        "Page.getLayoutMetrics" => json::object!{"cssContentSize" => json::object!{"x" => 0, "y" => 0, "width" => 1280, "height" => 4999.5}}.into(),
+        _ => json::object!{"data" => base64::encode(b"png")}.into(),
+    });
+    let config = SessionConfig::new(Capabilities::new(Browser::Chrome)).transport(driver.clone());
+    let session = Session::new_with_config(config).unwrap();
+    assert_eq!(session.tabs[0].screenshot_full_page().unwrap(), b"png");
+    let clips: Vec<json::JsonValue> = driver.requests().iter()
+        .filter(|request| request.json()["cmd"] == "Page.captureScreenshot")
+        .map(|request| request.json()["params"]["clip"].clone())
+        .collect();
+    assert_eq!(clips, vec![json::object!{"x" => 0, "y" => 0, "width" => 1280.0, "height" => 5000.0, "scale" => 1}]);
+}
+
+/// A WebSocket server accepting a single client, standing for the BiDi and DevTools servers of the browsers
+struct MockWebSocket {
+    reader: std::io::BufReader<std::net::TcpStream>,
+    /// the lines of the handshake request
+    request: Vec<String>
+}
+
+impl MockWebSocket {
+    fn accept(listener: &std::net::TcpListener) -> Self {
+        use std::io::{BufRead, Write};
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = std::io::BufReader::new(stream);
+        let mut request = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim().is_empty() {
+                break;
+            }
+            request.push(line.trim().to_string());
+        }
+        reader.get_mut().write_all(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\n").unwrap();
+        MockWebSocket { reader, request }
+    }
+
+    /// -> return the next message of the client (masked, as the clients must do)
+    fn receive(&mut self) -> json::JsonValue {
+        use std::io::Read;
+
+        let mut header = [0; 2];
+        self.reader.read_exact(&mut header).unwrap();
+        assert_eq!(header[1] & 0x80, 0x80);
+        let length = match header[1] & 0x7F {
+            126 => {
+                let mut length = [0; 2];
+                self.reader.read_exact(&mut length).unwrap();
+                u16::from_be_bytes(length) as usize
+            },
+            length => length as usize,
+        };
+        let mut mask = [0; 4];
+        self.reader.read_exact(&mut mask).unwrap();
+        let mut payload = vec![0; length];
+        self.reader.read_exact(&mut payload).unwrap();
+        let text: String = payload.iter().enumerate().map(|(index, byte)| (byte ^ mask[index % 4]) as char).collect();
+        json::parse(&text).unwrap()
+    }
+
+    fn send(&mut self, message: json::JsonValue) {
+        use std::io::Write;
+
+        let text = message.dump();
+        let mut frame = vec![0x81];
+        if text.len() < 126 {
+            frame.push(text.len() as u8);
+        } else {
+            frame.push(126);
+            frame.extend_from_slice(&(text.len() as u16).to_be_bytes());
+        }
+        frame.extend_from_slice(text.as_bytes());
+        self.reader.get_mut().write_all(&frame).unwrap();
+    }
+}
+
+#[test]
+fn fetch_interception() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+    use lw_webdriver::interception::{NetworkInterceptor, Interception};
+    use lw_webdriver::transport::Method;
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let debugger_address = listener.local_addr().unwrap().to_string();
+    let server = std::thread::spawn(move || {
+        let mut socket = MockWebSocket::accept(&listener);
+        let enable = socket.receive();
+        socket.send(json::object!{"id" => 1, "result" => json::object!{}});
+
+        // a navigation, an image and a script request paused by the browser
+        let mut replies = Vec::new();
+        for (id, url) in ["http://example.com/", "http://example.com/logo.png", "http://example.com/api/user"].iter().enumerate() {
+            socket.send(json::object!{
+                "method" => "Fetch.requestPaused",
+                "params" => json::object!{
+                    "requestId" => id,
+                    "request" => json::object!{"url" => *url, "method" => "GET", "headers" => json::object!{"Accept" => "*/*"}}
+                }
+            });
+            replies.push(socket.receive());
+        }
+        (socket.request, enable, replies)
+    });
+
+    // a Chrome driver returning the address of the DevTools server
+    let driver = MockDriver::new()
+        .route(Method::Post, "/session", json::object!{
+            "sessionId" => "mock",
+            "capabilities" => json::object!{"goog:chromeOptions" => json::object!{"debuggerAddress" => debugger_address}}
+        })
+        .route(Method::Post, "/session/mock/goog/cdp/execute", json::object!{"targetInfo" => json::object!{"targetId" => "target"}});
+    let config = SessionConfig::new(Capabilities::new(Browser::Chrome)).transport(driver);
+    let mut session = Session::new_with_config(config).unwrap();
+    let interceptor = NetworkInterceptor::new()
+        .block("*.png")
+        .respond("*/api/user", 200, "application/json", "{}")
+        .set_header("http://example.com/*", "Accept", "text/html");
+    session.tabs[0].intercept_network(&interceptor).unwrap();
+
+    let (request, enable, replies) = server.join().unwrap();
+    assert_eq!(request[0], "GET /devtools/page/target HTTP/1.1");
+    assert_eq!(enable["method"], "Fetch.enable");
+    assert_eq!(enable["params"]["patterns"], json::array![
+        json::object!{"urlPattern" => "*.png"},
+        json::object!{"urlPattern" => "*/api/user"},
+        json::object!{"urlPattern" => "http://example.com/*"}
+    ]);
+    assert_eq!(replies[0], json::object!{
+        "id" => 2,
+        "method" => "Fetch.continueRequest",
+        "params" => json::object!{"requestId" => 0, "headers" => json::array![json::object!{"name" => "Accept", "value" => "text/html"}]}
+    });
+    assert_eq!(replies[1]["method"], "Fetch.failRequest");
+    assert_eq!(replies[1]["params"]["errorReason"], "BlockedByClient");
+    assert_eq!(replies[2]["method"], "Fetch.fulfillRequest");
+    assert_eq!(replies[2]["params"]["body"], base64::encode("{}").as_str());
+
+    let interceptions: Vec<Interception> = session.tabs[0].intercepted_requests().unwrap().iter().map(|request| request.interception).collect();
+    assert_eq!(interceptions, vec![Interception::Modified, Interception::Blocked, Interception::Stubbed]);
+    session.tabs[0].stop_network_interception().unwrap();
+}
+
 #[test]
 fn elements() {
     catch_unwind(|| {
@@ -156,7 +510,7 @@ fn elements() {
 }
 
 #[test]
-fn execute_javascript() {
+fn element_rect() {
     catch_unwind(|| {
         env_logger::init();
     });
@@ -173,14 +527,20 @@ fn execute_javascript() {
             }
         };
 
-        session.tabs[0].navigate("http://example.com").unwrap();
-        let element = session.tabs[0].find(Selector::Css, "html>body>div>p>a").unwrap().unwrap();
-        session.tabs[0].execute_script("arguments[0].click();", vec![element.as_json_object()]).unwrap();
+        session.tabs[0].navigate("https://www.mozilla.org/fr/").unwrap();
+        let email_input = session.tabs[0].find(Selector::XPath, "//*[@id=\"id_email\"]").unwrap().unwrap();
+
+        let rect = email_input.rect().unwrap();
+        assert!(rect.width > 0.0 && rect.height > 0.0);
+        assert_eq!(email_input.location().unwrap(), rect.location());
+        assert_eq!(email_input.size().unwrap(), (rect.width, rect.height));
+        let (x, y) = rect.center();
+        assert!(rect.contains(x, y));
     }
 }
 
 #[test]
-fn element_obscured() {
+fn element_state() {
     catch_unwind(|| {
         env_logger::init();
     });
@@ -197,9 +557,2785 @@ fn element_obscured() {
             }
         };
 
-        session.tabs[0].navigate("https://mubelotix.dev/webdriver_tests/element_obscured.html").unwrap();
-        
-        let mut element_obscured = session.tabs[0].find(Selector::XPath, "/html/body/p/a").unwrap().unwrap();
-        element_obscured.click().unwrap();
+        session.tabs[0].navigate("https://www.mozilla.org/fr/").unwrap();
+        let email_input = session.tabs[0].find(Selector::XPath, "//*[@id=\"id_email\"]").unwrap().unwrap();
+        assert!(email_input.is_displayed().unwrap());
+        assert!(!email_input.is_selected().unwrap());
+    }
+}
+
+#[test]
+fn find_by_strategy() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+    
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.open_tab();
+        session.tabs[0].navigate("https://www.mozilla.org/fr/").unwrap();
+        session.tabs[1].navigate("https://mubelotix.dev/").unwrap();
+
+        let email_input = session.tabs[0].find(Selector::XPath, "//*[@id=\"id_email\"]").unwrap().unwrap();
+        assert!(session.tabs[0].find_by_css("#id_email").unwrap().unwrap() == email_input);
+
+        let project_element = session.tabs[1].find_by_xpath("/html/body/main/div[1]").unwrap().unwrap();
+        let heading = project_element.find_by_xpath(".//h2").unwrap().unwrap();
+        assert_eq!(heading.get_tag_name().unwrap(), "h2");
+        assert!(project_element.find_by_css("form").unwrap().is_none());
+    }
+}
+
+#[test]
+fn element_html() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+    
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+
+        let project_element = session.tabs[0].find(Selector::XPath, "/html/body/main/div[1]").unwrap().unwrap();
+        let heading = project_element.find(Selector::XPath, ".//h2").unwrap().unwrap();
+        assert!(project_element.outer_html().unwrap().starts_with("<div class=\"project\""));
+        assert!(project_element.inner_html().unwrap().contains(&heading.outer_html().unwrap()));
+    }
+}
+
+#[test]
+fn dropdowns() {
+    use lw_webdriver::{select::Select, error::ErrorKind};
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("data:text/html,<select id='country' onchange='window.changes = (window.changes || 0) %2B 1'><option value='de'>Germany</option><option value='fr'>  France </option><option disabled>Spain</option></select><p>text</p>").unwrap();
+        let select = Select::new(session.tabs[0].find(Selector::Css, "#country").unwrap().unwrap()).unwrap();
+        assert!(!select.is_multiple().unwrap());
+        assert_eq!(select.options().unwrap().len(), 3);
+        assert_eq!(select.selected_options().unwrap()[0].value, "de");
+
+        select.select_by_visible_text("France").unwrap();
+        assert_eq!(select.selected_options().unwrap()[0].index, 1);
+        select.select_by_value("de").unwrap();
+        assert_eq!(select.selected_options().unwrap()[0].text, "Germany");
+        select.select_by_index(1).unwrap();
+        assert!(select.options().unwrap()[1].selected);
+        assert_eq!(session.tabs[0].execute_script("return window.changes;", Vec::new()).unwrap(), 3);
+
+        assert!(select.select_by_value("it").unwrap_err() == ErrorKind::NoSuchElement);
+        assert!(select.select_by_visible_text("Spain").unwrap_err() == ErrorKind::InvalidElementState);
+        assert!(Select::new(session.tabs[0].find(Selector::Css, "p").unwrap().unwrap()).is_err());
+    }
+}
+
+#[test]
+fn hover() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("data:text/html,<style>#menu ul { display: none; } #menu:hover ul { display: block; }</style>\
+            <div style='height: 2000px'></div><div id='menu'>Menu<ul><li id='item'>Item</li></ul></div>").unwrap();
+
+        let menu = session.tabs[0].find(Selector::Css, "#menu").unwrap().unwrap();
+        let item = session.tabs[0].find(Selector::Css, "#item").unwrap().unwrap();
+        assert!(!item.is_displayed().unwrap());
+        menu.hover().unwrap();
+        assert!(item.is_displayed().unwrap());
+    }
+}
+
+#[test]
+fn mouse_buttons() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("data:text/html,<div style='height: 2000px'></div><button>Target</button><script>\
+            window.events = [];\
+            for (const type of ['contextmenu', 'dblclick', 'auxclick']) {\
+                document.querySelector('button').addEventListener(type, event => { event.preventDefault(); window.events.push(type + ' ' + event.button); });\
+            }</script>").unwrap();
+
+        let button = session.tabs[0].find(Selector::Css, "button").unwrap().unwrap();
+        button.context_click().unwrap();
+        button.double_click().unwrap();
+        button.middle_click().unwrap();
+        session.tabs[0].release_actions().unwrap();
+
+        let events = session.tabs[0].execute_script("return window.events.filter(event => event !== 'auxclick 2');", Vec::new()).unwrap();
+        assert_eq!(events, json::array!["contextmenu 2", "dblclick 0", "auxclick 1"]);
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn keyboard() {
+    use lw_webdriver::actions::{ActionChain, Keys, MouseButton};
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("data:text/html,<input value='old text' onkeydown='if (event.key === \"F2\") window.renamed = true'>").unwrap();
+        let mut input = session.tabs[0].find(Selector::Css, "input").unwrap().unwrap();
+
+        session.tabs[0].perform(&ActionChain::new().move_to_element(&input).click(MouseButton::Left).key_chord(&[Keys::Control, Keys::from('a')]).send_keys("new").key_down(Keys::F2).key_up(Keys::F2)).unwrap();
+        assert_eq!(input.get_property("value").unwrap(), "new");
+        assert_eq!(session.tabs[0].execute_script("return window.renamed;", Vec::new()).unwrap(), true);
+
+        input.type_text(&format!("{}{}s", Keys::Backspace.to_char(), Keys::Backspace.to_char())).unwrap();
+        assert_eq!(input.get_property("value").unwrap(), "ns");
+    }
+}
+
+#[test]
+fn scrolling() {
+    use lw_webdriver::actions::ActionChain;
+    use std::time::Duration;
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("data:text/html,<div style='height: 5000px; width: 5000px'></div><button onclick='window.clicked = true'>Below</button>").unwrap();
+
+        session.tabs[0].scroll_to(0.0, 1000.0).unwrap();
+        session.tabs[0].scroll_by(100.0, 500.0).unwrap();
+        assert_eq!(session.tabs[0].scroll_position().unwrap(), (100.0, 1500.0));
+
+        session.tabs[0].perform(&ActionChain::new().scroll(10, 10, 0, 200)).unwrap();
+        session.tabs[0].wait_until(|tab| Ok(tab.scroll_position()?.1 >= 1700.0), Duration::from_secs(2)).unwrap();
+
+        let mut button = session.tabs[0].find(Selector::Css, "button").unwrap().unwrap();
+        button.scroll_into_view().unwrap();
+        assert!(session.tabs[0].scroll_position().unwrap().1 > 4000.0);
+        session.tabs[0].scroll_to(0.0, 0.0).unwrap();
+        button.click().unwrap();
+        assert_eq!(session.tabs[0].execute_script("return window.clicked;", Vec::new()).unwrap(), true);
+    }
+}
+
+#[test]
+fn forms() {
+    use lw_webdriver::{form::Form, error::ErrorKind};
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("data:text/html,<form id='signup' onsubmit='window.submitted = new FormData(this).get(\"email\"); return false;'>\
+            <input name='email'><textarea name='bio'></textarea><input type='checkbox' name='terms'>\
+            <input type='radio' name='plan' value='free' checked><input type='radio' name='plan' value='premium'>\
+            <select name='country'><option value='de'>Germany</option><option value='fr'>France</option></select></form>\
+            <input id='search' oninput='window.searched = this.value'>").unwrap();
+
+        session.tabs[0].fill_form(&[("#search", "query")]).unwrap();
+        assert_eq!(session.tabs[0].execute_script("return window.searched;", Vec::new()).unwrap(), "query");
+        assert!(session.tabs[0].fill_form(&[("#missing", "value")]).unwrap_err() == ErrorKind::NoSuchElement);
+
+        let form = Form::new(session.tabs[0].find(Selector::Css, "#signup").unwrap().unwrap()).unwrap();
+        form.fill(&[("[name=email]", "user@example.com"), ("[name=bio]", "hello"), ("[name=terms]", "true"), ("[name=plan]", "premium"), ("[name=country]", "France")]).unwrap();
+        let values = session.tabs[0].execute_script("const form = document.forms[0]; return [form.bio.value, form.terms.checked, form.plan.value, form.country.value];", Vec::new()).unwrap();
+        assert_eq!(values, json::array!["hello", true, "premium", "fr"]);
+        assert!(form.fill(&[("[name=country]", "Spain")]).unwrap_err() == ErrorKind::InvalidArgument);
+        assert!(form.fill(&[("#search", "outside of the form")]).unwrap_err() == ErrorKind::NoSuchElement);
+
+        form.submit().unwrap();
+        assert_eq!(session.tabs[0].execute_script("return window.submitted;", Vec::new()).unwrap(), "user@example.com");
+
+        // submitting from a field of the form
+        session.tabs[0].execute_script("window.submitted = null;", Vec::new()).unwrap();
+        session.tabs[0].find(Selector::Css, "[name=bio]").unwrap().unwrap().submit().unwrap();
+        assert_eq!(session.tabs[0].execute_script("return window.submitted;", Vec::new()).unwrap(), "user@example.com");
+        assert!(session.tabs[0].find(Selector::Css, "#search").unwrap().unwrap().submit().unwrap_err() == ErrorKind::NoSuchElement);
+    }
+}
+
+#[test]
+fn execute_javascript() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+    
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("http://example.com").unwrap();
+        let element = session.tabs[0].find(Selector::Css, "html>body>div>p>a").unwrap().unwrap();
+        session.tabs[0].execute_script("arguments[0].click();", vec![element.as_json_object()]).unwrap();
+
+        session.tabs[0].navigate("http://example.com").unwrap();
+        let links = session.tabs[0].query_all_map("a", "a => a.href").unwrap();
+        assert_eq!(links.len(), 1);
+        let paragraphs = session.tabs[0].query_all_map("p", "(p, i) => i > 0 ? p.tagName : undefined").unwrap();
+        assert_eq!(paragraphs, vec![json::JsonValue::from("P")]);
+    }
+}
+
+#[test]
+fn locators() {
+    use lw_webdriver::locator::{Locator, Location, TestId, Role};
+    use std::time::Duration;
+
+    /// Locate the last element matching a css selector
+    struct Last(&'static str);
+
+    impl Locator for Last {
+        fn locate(&self) -> Location {
+            Location::Script {
+                script: "return Array.from(document.querySelectorAll(arguments[0])).pop() || null;".to_string(),
+                args: vec![self.0.into()]
+            }
+        }
+    }
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("http://example.com").unwrap();
+        session.tabs[0].execute_script("document.querySelector('p').setAttribute('data-testid', 'intro');", Vec::new()).unwrap();
+
+        let intro = session.tabs[0].find_by(TestId("intro")).unwrap().unwrap();
+        assert_eq!(intro.get_tag_name().unwrap(), "p");
+        let heading = session.tabs[0].find_by(Role::new("heading").name("Example Domain")).unwrap().unwrap();
+        assert_eq!(heading.get_tag_name().unwrap(), "h1");
+        let link = session.tabs[0].find_by(Role::new("link")).unwrap().unwrap();
+        assert_eq!(link.get_tag_name().unwrap(), "a");
+        let last = session.tabs[0].find_by(Last("p")).unwrap().unwrap();
+        assert!(last.get_text().unwrap().contains("More information"));
+        assert!(session.tabs[0].find_by((Selector::Css, "body > div")).unwrap().is_some());
+        let heading = session.tabs[0].find(Selector::Text, "  Example   Domain ").unwrap().unwrap();
+        assert_eq!(heading.get_tag_name().unwrap(), "h1");
+        assert_eq!(heading.computed_role().unwrap(), "heading");
+        assert_eq!(heading.accessible_name().unwrap(), "Example Domain");
+        let link = session.tabs[0].find(Selector::ContainsText, "More information").unwrap().unwrap();
+        assert_eq!(link.get_tag_name().unwrap(), "a");
+        assert!(session.tabs[0].find(Selector::Text, "Example").unwrap().is_none());
+
+        let paragraphs = session.tabs[0].execute_script_elements("return document.querySelectorAll('p');", Vec::new()).unwrap();
+        assert_eq!(paragraphs.len(), 2);
+        assert!(paragraphs[1].get_text().unwrap().contains("More information"));
+        let found = session.tabs[0].execute_script_elements("return document.querySelector('h1');", Vec::new()).unwrap();
+        assert_eq!(found[0].get_tag_name().unwrap(), "h1");
+        assert!(session.tabs[0].execute_script_elements("return null;", Vec::new()).unwrap().is_empty());
+        assert!(session.tabs[0].execute_script_elements("return [1];", Vec::new()).is_err());
+
+        assert!(session.tabs[0].find_by(Role::new("button")).unwrap().is_none());
+        assert!(session.tabs[0].find_by(TestId("missing")).unwrap().is_none());
+
+        let body = session.tabs[0].find_by_css("body > div").unwrap().unwrap();
+        let link = body.find_by(Role::new("link")).unwrap().unwrap();
+        assert_eq!(link.get_tag_name().unwrap(), "a");
+        assert!(body.find_by(TestId("intro")).unwrap().is_some());
+        assert!(link.find_by(Role::new("heading")).unwrap().is_none());
+        let heading = session.tabs[0].wait_for_element_by(Role::new("heading"), Duration::from_secs(1)).unwrap();
+        assert_eq!(heading.get_tag_name().unwrap(), "h1");
+    }
+}
+
+#[test]
+fn relative_locators() {
+    use lw_webdriver::locator::Relative;
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("data:text/html,<style>div { display: flex; gap: 20px; margin-bottom: 100px }</style>\
+            <div><label>Name</label><input name='name'><button>Clear</button></div>\
+            <div><label>Email</label><input name='email'><button>Check</button></div>\
+            <input name='hidden' style='display: none'>").unwrap();
+        let email = session.tabs[0].find(Selector::XPath, "//label[text()='Email']").unwrap().unwrap();
+        let name = session.tabs[0].find(Selector::XPath, "//label[text()='Name']").unwrap().unwrap();
+
+        let input = session.tabs[0].find_by(Relative::new(Selector::TagName, "input").right_of(&email)).unwrap().unwrap();
+        assert_eq!(input.get_attribute("name").unwrap(), "email");
+        let input = session.tabs[0].find_by(Relative::new(Selector::TagName, "input").below(&name).near_within(&name, 200.0)).unwrap().unwrap();
+        assert_eq!(input.get_attribute("name").unwrap(), "email");
+        let button = session.tabs[0].find_by(Relative::new(Selector::Css, "button").above(&email)).unwrap().unwrap();
+        assert_eq!(button.get_text().unwrap(), "Clear");
+        let label = session.tabs[0].find_by(Relative::new(Selector::XPath, "//label").left_of(&input).above(&input)).unwrap().unwrap();
+        assert_eq!(label.get_text().unwrap(), "Name");
+        let label = session.tabs[0].find_by(Relative::new(Selector::TagName, "label").near(&input)).unwrap().unwrap();
+        assert_eq!(label.get_text().unwrap(), "Email");
+
+        assert!(session.tabs[0].find_by(Relative::new(Selector::TagName, "input").below(&email)).unwrap().is_none());
+        assert!(session.tabs[0].find_by(Relative::new(Selector::TagName, "label").above(&name)).unwrap().is_none());
+    }
+}
+
+#[test]
+fn element_obscured() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+    
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("https://mubelotix.dev/webdriver_tests/element_obscured.html").unwrap();
+        
+        let mut element_obscured = session.tabs[0].find(Selector::XPath, "/html/body/p/a").unwrap().unwrap();
+        element_obscured.click().unwrap();
+    }
+}
+
+#[test]
+fn security_audit() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+    
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+        let report = session.tabs[0].security_audit().unwrap();
+        assert_eq!(&report.url, "https://mubelotix.dev/");
+        assert!(report.mixed_content.is_empty());
+    }
+}
+
+#[test]
+fn certificate_parsing() {
+    use lw_webdriver::certificate::Certificate;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let der = base64::decode("MIIB6zCCAZGgAwIBAgIBATAKBggqhkjOPQQDAjA7MQswCQYDVQQGEwJGUjEVMBMGA1UECgwMbHctd2ViZHJpdmVyMRUwEwYDVQQDDAxleGFtcGxlLnRlc3QwHhcNMjYxMDE2MTM1NTU4WhcNMzYxMDEzMTM1NTU4WjA7MQswCQYDVQQGEwJGUjEVMBMGA1UECgwMbHctd2ViZHJpdmVyMRUwEwYDVQQDDAxleGFtcGxlLnRlc3QwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAARsvYIUrO8aJHb4tl2G6+02WBWpaMUHw/ymmDx666qcV+PF8MMxYliJ6c/vWte7rS4laB8MvDGf7tdxl7awTJwwo4GFMIGCMB0GA1UdDgQWBBQMSlls/Paz1gRSCB7Nj+jqoZsBfDAfBgNVHSMEGDAWgBQMSlls/Paz1gRSCB7Nj+jqoZsBfDAPBgNVHRMBAf8EBTADAQH/MC8GA1UdEQQoMCaCDGV4YW1wbGUudGVzdIIQd3d3LmV4YW1wbGUudGVzdIcEfwAAATAKBggqhkjOPQQDAgNIADBFAiEA7uLoRTIONCdTD8t9g0feVrNIYACNDxqlz+XcibltzX0CIC9UFsu8avd4jgQw/SrslDBj08/hGjNT9lHmVdL4Vx8L").unwrap();
+    let certificate = Certificate::from_der(der).unwrap();
+
+    assert_eq!(&certificate.subject, "C=FR, O=lw-webdriver, CN=example.test");
+    assert_eq!(certificate.issuer, certificate.subject);
+    assert_eq!(certificate.not_before, UNIX_EPOCH + Duration::from_secs(1_792_158_958));
+    assert_eq!(certificate.not_after, UNIX_EPOCH + Duration::from_secs(2_107_518_958));
+    assert_eq!(certificate.subject_alt_names, vec!["example.test", "www.example.test", "127.0.0.1"]);
+
+    assert!(Certificate::from_der(vec![0x30, 0x03, 0x02, 0x01]).is_none());
+}
+
+#[test]
+fn capabilities() {
+    use lw_webdriver::capabilities::*;
+    use lw_webdriver::{config::SessionConfig, error::ErrorKind};
+
+    let firefox = Capabilities::new(Browser::Firefox)
+        .headless(true)
+        .firefox_profile(FirefoxProfile::Directory("/tmp/profile".into()))
+        .to_json();
+    let options = &firefox["capabilities"]["alwaysMatch"]["moz:firefoxOptions"];
+    assert_eq!(options["args"], json::array!["-headless", "-profile", "/tmp/profile"]);
+
+    let chrome = Capabilities::new(Browser::Chrome)
+        .chrome_extension(ChromeExtension::Encoded("Q3Iy".to_string()))
+        .chrome_extension(ChromeExtension::Unpacked("/tmp/a".into()))
+        .chrome_extension(ChromeExtension::Unpacked("/tmp/b".into()))
+        .arg("--mute-audio")
+        .to_json();
+    let options = &chrome["capabilities"]["alwaysMatch"]["goog:chromeOptions"];
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["browserName"], "chrome");
+    assert_eq!(options["extensions"], json::array!["Q3Iy"]);
+    assert_eq!(options["args"], json::array!["--load-extension=/tmp/a,/tmp/b", "--mute-audio"]);
+
+    let chrome = Capabilities::new(Browser::Chrome).headless(true).to_json();
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["goog:chromeOptions"]["args"], json::array!["--headless"]);
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["goog:loggingPrefs"]["browser"], "ALL");
+    let chrome = Capabilities::new(Browser::Chrome).network_log(true).to_json();
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["goog:loggingPrefs"]["performance"], "ALL");
+    let chrome = Capabilities::new(Browser::Chrome).download_directory("/tmp/downloads").to_json();
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["goog:chromeOptions"]["prefs"]["download.default_directory"], "/tmp/downloads");
+    let firefox = Capabilities::new(Browser::Firefox).download_directory("/tmp/downloads").to_json();
+    assert_eq!(firefox["capabilities"]["alwaysMatch"]["moz:firefoxOptions"]["prefs"]["browser.download.dir"], "/tmp/downloads");
+    assert_eq!(firefox["capabilities"]["alwaysMatch"]["moz:firefoxOptions"]["prefs"]["browser.download.folderList"], 2);
+    let chrome = Capabilities::new(Browser::Chrome).headless(true).chrome_headless_mode(ChromeHeadlessMode::New).to_json();
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["goog:chromeOptions"]["args"], json::array!["--headless=new"]);
+    let chrome = Capabilities::new(Browser::Chrome).chrome_headless_mode(ChromeHeadlessMode::New).to_json();
+    assert!(chrome["capabilities"]["alwaysMatch"]["goog:chromeOptions"].is_null());
+
+    let chrome = Capabilities::new(Browser::Chrome).headless(true).window_size(1920, 1080).to_json();
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["goog:chromeOptions"]["args"], json::array!["--headless", "--window-size=1920,1080"]);
+    let firefox = Capabilities::new(Browser::Firefox).headless(true).window_size(1920, 1080).to_json();
+    assert_eq!(firefox["capabilities"]["alwaysMatch"]["moz:firefoxOptions"]["args"], json::array!["-headless", "--width=1920", "--height=1080"]);
+
+    let chrome = Capabilities::new(Browser::Chrome).kiosk(true).app("http://example.com/").to_json();
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["goog:chromeOptions"]["args"], json::array!["--kiosk", "--app=http://example.com/"]);
+    let firefox = Capabilities::new(Browser::Firefox).app("http://example.com/").to_json();
+    assert_eq!(firefox["capabilities"]["alwaysMatch"]["moz:firefoxOptions"]["args"], json::array!["--kiosk", "http://example.com/"]);
+
+    let firefox = Capabilities::new(Browser::Firefox).block_resource(Resource::Images).firefox_pref("browser.startup.page", 0).to_json();
+    assert_eq!(firefox["capabilities"]["alwaysMatch"]["moz:firefoxOptions"]["prefs"], json::object!{
+        "permissions.default.image" => 2,
+        "browser.startup.page" => 0
+    });
+    let chrome = Capabilities::new(Browser::Chrome).block_resource(Resource::Images).to_json();
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["goog:chromeOptions"]["prefs"]["profile.managed_default_content_settings.images"], 2);
+
+    let chrome = Capabilities::new(Browser::Chrome).host_resolver_rule("a.test", "127.0.0.1:8080").host_resolver_rule("b.test", "127.0.0.1").to_json();
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["goog:chromeOptions"]["args"], json::array!["--host-resolver-rules=MAP a.test 127.0.0.1:8080, MAP b.test 127.0.0.1"]);
+    let firefox = Capabilities::new(Browser::Firefox).host_resolver_rule("a.test", "127.0.0.1").to_json();
+    assert_eq!(firefox["capabilities"]["alwaysMatch"]["proxy"]["proxyType"], "pac");
+    assert!(firefox["capabilities"]["alwaysMatch"]["proxy"]["proxyAutoconfigUrl"].as_str().unwrap().contains("PROXY%20127.0.0.1%3A80"));
+
+    let firefox = Capabilities::new(Browser::Firefox).allow_notifications(true).to_json();
+    assert_eq!(firefox["capabilities"]["alwaysMatch"]["moz:firefoxOptions"]["prefs"]["permissions.default.desktop-notification"], 1);
+
+    let edge = Capabilities::new(Browser::Edge).headless(true).to_json();
+    assert_eq!(edge["capabilities"]["alwaysMatch"]["browserName"], "MicrosoftEdge");
+    assert_eq!(edge["capabilities"]["alwaysMatch"]["ms:edgeOptions"]["args"], json::array!["--headless"]);
+
+    // Safari has no headless mode nor arguments
+    let safari = Capabilities::new(Browser::Safari).headless(true).arg("--mute-audio").to_json();
+    assert_eq!(safari["capabilities"]["alwaysMatch"].len(), 2);
+    assert_eq!(safari["capabilities"]["alwaysMatch"]["browserName"], "safari");
+
+    let chrome = Capabilities::new(Browser::Chrome).mobile_emulation(MobileEmulation::Device("Pixel 7".to_string())).to_json();
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["goog:chromeOptions"]["mobileEmulation"], json::object!{"deviceName" => "Pixel 7"});
+    let emulation = MobileEmulation::Metrics { width: 390, height: 844, pixel_ratio: 3.0, touch: true, user_agent: Some("iPhone".to_string()) };
+    let edge = Capabilities::new(Browser::Edge).mobile_emulation(emulation.clone()).to_json();
+    assert_eq!(edge["capabilities"]["alwaysMatch"]["ms:edgeOptions"]["mobileEmulation"], json::object!{
+        "deviceMetrics" => json::object!{"width" => 390, "height" => 844, "pixelRatio" => 3.0, "touch" => true},
+        "userAgent" => "iPhone"
+    });
+    let firefox = Capabilities::new(Browser::Firefox).mobile_emulation(emulation).to_json();
+    assert!(firefox["capabilities"]["alwaysMatch"]["moz:firefoxOptions"].is_null());
+
+    let chrome = Capabilities::new(Browser::Chrome).locale("fr-FR, fr").timezone("Europe/Paris").to_json();
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["goog:chromeOptions"]["args"], json::array!["--lang=fr-FR"]);
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["goog:chromeOptions"]["prefs"]["intl.accept_languages"], "fr-FR, fr");
+    let firefox = Capabilities::new(Browser::Firefox).locale("fr-FR").to_json();
+    assert_eq!(firefox["capabilities"]["alwaysMatch"]["moz:firefoxOptions"]["prefs"]["intl.accept_languages"], "fr-FR");
+
+    let firefox = Capabilities::new(Browser::Firefox).accept_insecure_certs(true).to_json();
+    assert_eq!(firefox["capabilities"]["alwaysMatch"]["acceptInsecureCerts"], true);
+    assert!(Capabilities::new(Browser::Firefox).to_json()["capabilities"]["alwaysMatch"]["acceptInsecureCerts"].is_null());
+    let chrome = Capabilities::new(Browser::Chrome).unhandled_prompt_behavior(PromptBehavior::DismissAndNotify).to_json();
+    assert_eq!(chrome["capabilities"]["alwaysMatch"]["unhandledPromptBehavior"], "dismiss and notify");
+
+    let brave = Capabilities::new(Browser::ChromiumBased { binary: "/usr/bin/brave-browser".into() }).to_json();
+    assert_eq!(brave["capabilities"]["alwaysMatch"]["browserName"], "chrome");
+    assert_eq!(brave["capabilities"]["alwaysMatch"]["goog:chromeOptions"]["binary"], "/usr/bin/brave-browser");
+    let config = SessionConfig::new(Capabilities::new(Browser::ChromiumBased { binary: "".into() })).transport(MockDriver::new());
+    assert!(Session::new_with_config(config).err().unwrap() == ErrorKind::InvalidArgument);
+}
+
+#[test]
+fn screenshots() {
+    use std::time::Duration;
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+    
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+        session.tabs[0].execute_script("document.body.style.minHeight = '5000px';", Vec::new()).unwrap();
+        session.tabs[0].wait_for_render_idle(Duration::from_secs(5)).unwrap();
+        let viewport = session.tabs[0].screenshot().unwrap();
+        let full_page = session.tabs[0].screenshot_full_page().unwrap();
+        assert_eq!(&viewport[1..4], b"PNG");
+        assert_eq!(&full_page[1..4], b"PNG");
+        // the height is stored in the header of png images
+        let height = |png: &[u8]| u32::from_be_bytes([png[20], png[21], png[22], png[23]]);
+        assert!(height(&full_page) > height(&viewport));
+
+        session.tabs[0].set_zoom(2.0).unwrap();
+        assert_eq!(session.tabs[0].execute_script("return document.documentElement.style.zoom;", Vec::new()).unwrap(), "2");
+        session.tabs[0].reset_zoom().unwrap();
+        assert_eq!(session.tabs[0].execute_script("return document.documentElement.style.zoom;", Vec::new()).unwrap(), "");
+        assert!(session.tabs[0].set_zoom(0.0).is_err());
+    }
+}
+
+#[test]
+fn session_config() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox))
+        .remote("http://grid.example.com:4444/wd/hub")
+        .credentials("user", "secret");
+    let debug = format!("{:?}", config);
+    assert!(debug.contains(r#"remote_url: Some("http://grid.example.com:4444/wd/hub")"#));
+    assert!(debug.contains(r#"credentials: Some(("user", "<redacted>"))"#));
+    assert!(!debug.contains("secret"));
+}
+
+#[test]
+fn bidi_init_script() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+    use lw_webdriver::transport::Method;
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let web_socket_url = format!("ws://{}/session/mock", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let mut socket = MockWebSocket::accept(&listener);
+        let mut commands = Vec::new();
+        for (id, result) in [(1, json::object!{"script" => "preload"}), (2, json::object!{})].iter() {
+            commands.push(socket.receive());
+            // an event is received before the result
+            socket.send(json::object!{"type" => "event", "method" => "log.entryAdded", "params" => json::object!{}});
+            socket.send(json::object!{"type" => "success", "id" => *id, "result" => result.clone()});
+        }
+        (socket.request, commands)
+    });
+
+    // a Firefox driver returning the url of the BiDi server
+    let driver = MockDriver::new().route(Method::Post, "/session", json::object!{"sessionId" => "mock", "capabilities" => json::object!{"webSocketUrl" => web_socket_url}});
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox).bidi(true)).transport(driver);
+    assert_eq!(config.get_capabilities().to_json()["capabilities"]["alwaysMatch"]["webSocketUrl"], true);
+    let session = Session::new_with_config(config).unwrap();
+    let script = session.tabs[0].add_init_script("window.injected = true;").unwrap();
+    assert_eq!(script.as_str(), "preload");
+    session.tabs[0].remove_init_script(script).unwrap();
+
+    let (request, commands) = server.join().unwrap();
+    assert_eq!(request[0], "GET /session/mock HTTP/1.1");
+    assert!(request.contains(&"Sec-WebSocket-Version: 13".to_string()));
+    assert_eq!(commands[0], json::object!{
+        "id" => 1,
+        "method" => "script.addPreloadScript",
+        "params" => json::object!{"functionDeclaration" => "() => {\nwindow.injected = true;\n}", "contexts" => json::array!["tab"]}
+    });
+    assert_eq!(commands[1], json::object!{"id" => 2, "method" => "script.removePreloadScript", "params" => json::object!{"script" => "preload"}});
+}
+
+#[test]
+fn print() {
+    use lw_webdriver::print::{PrintOptions, Orientation};
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, true).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, true).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("http://example.com").unwrap();
+        let pdf = session.tabs[0].print(PrintOptions::new()).unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+
+        let path = std::env::temp_dir().join("lw_webdriver_print.pdf");
+        session.tabs[0].print_to_file(&path, PrintOptions::new().orientation(Orientation::Landscape).page_range("1").background(true)).unwrap();
+        assert!(std::fs::read(&path).unwrap().starts_with(b"%PDF-"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn page_load() {
+    use std::time::Duration;
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].set_wait_after_navigation(Some(Duration::from_secs(30)));
+        session.tabs[0].navigate("http://example.com").unwrap();
+        assert_eq!(session.tabs[0].execute_script("return document.readyState;", Vec::new()).unwrap(), "complete");
+
+        session.tabs[0].track_pending_requests().unwrap();
+        session.tabs[0].execute_script("fetch('http://example.com/?slow').then(() => window.fetched = true);", Vec::new()).unwrap();
+        session.tabs[0].wait_until_loaded(Duration::from_secs(30)).unwrap();
+        assert_eq!(session.tabs[0].execute_script("return window.fetched === true;", Vec::new()).unwrap(), true);
+    }
+}
+
+#[test]
+fn scroll() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+    
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("http://example.com/").unwrap();
+        session.tabs[0].execute_script("document.body.style.height = '5000px';", Vec::new()).unwrap();
+        session.tabs[0].set_scroll_position(0.0, 300.0).unwrap();
+        assert_eq!(session.tabs[0].scroll_position().unwrap(), (0.0, 300.0));
+
+        session.tabs[0].preserve_scroll(|tab| tab.set_scroll_position(0.0, 0.0)).unwrap().unwrap();
+        assert_eq!(session.tabs[0].scroll_position().unwrap(), (0.0, 300.0));
+    }
+}
+
+#[test]
+fn notifications() {
+    use lw_webdriver::capabilities::Capabilities;
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+    
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new_with_capabilities(Capabilities::new(Browser::Firefox).allow_notifications(true)).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new_with_capabilities(Capabilities::new(Browser::Chrome).allow_notifications(true)).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+        session.tabs[0].capture_notifications().unwrap();
+        session.tabs[0].execute_script("new Notification('Hello', { body: 'World', tag: 'greeting' });", Vec::new()).unwrap();
+
+        let notifications = session.tabs[0].notifications().unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].title, "Hello");
+        assert_eq!(notifications[0].body, "World");
+        assert_eq!(notifications[0].tag, "greeting");
+    }
+}
+
+#[test]
+fn devtools() {
+    use lw_webdriver::cdp::DeviceMetrics;
+    use lw_webdriver::capabilities::Capabilities;
+    use lw_webdriver::error::ErrorKind;
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        if i == 0 {
+            assert!(session.debugger_address().is_none());
+            assert!(session.tabs[0].cdp_command("Page.reload", object!{}).unwrap_err() == ErrorKind::UnknowCommand);
+            assert!(session.tabs[0].add_init_script("window.injected = true;").unwrap_err() == ErrorKind::UnknowCommand);
+
+            // preload scripts of WebDriver BiDi
+            let mut session = Session::new_with_capabilities(Capabilities::new(Browser::Firefox).bidi(true)).unwrap();
+            let script = session.tabs[0].add_init_script("window.injected = document.readyState;").unwrap();
+            session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+            assert_eq!(session.tabs[0].execute_script("return window.injected;", Vec::new()).unwrap(), "loading");
+            session.tabs[0].remove_init_script(script).unwrap();
+            session.tabs[0].refresh().unwrap();
+            assert!(session.tabs[0].execute_script("return window.injected;", Vec::new()).unwrap().is_null());
+            continue;
+        }
+
+        assert!(session.debugger_address().unwrap().contains(':'));
+        session.tabs[0].emulate_device(DeviceMetrics::mobile(390, 844)).unwrap();
+        session.tabs[0].set_user_agent("lw-webdriver").unwrap();
+        session.tabs[0].start_js_coverage().unwrap();
+        let script = session.tabs[0].add_init_script("window.injected = document.readyState;").unwrap();
+        session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+        assert_eq!(session.tabs[0].execute_script("return window.injected;", Vec::new()).unwrap(), "loading");
+
+        assert_eq!(session.tabs[0].execute_script("return window.innerWidth;", Vec::new()).unwrap(), 390);
+        assert_eq!(session.tabs[0].execute_script("return navigator.userAgent;", Vec::new()).unwrap(), "lw-webdriver");
+        assert!(!session.tabs[0].take_js_coverage().unwrap().is_empty());
+
+        session.tabs[0].clear_device_emulation().unwrap();
+        assert_ne!(session.tabs[0].execute_script("return window.innerWidth;", Vec::new()).unwrap(), 390);
+
+        session.tabs[0].remove_init_script(script).unwrap();
+        session.tabs[0].refresh().unwrap();
+        assert!(session.tabs[0].execute_script("return window.injected;", Vec::new()).unwrap().is_null());
+    }
+}
+
+#[test]
+#[cfg(feature = "image")]
+fn screenshot_diff() {
+    use lw_webdriver::screenshot::{diff, diff_with_options, DiffOptions, Region};
+    use lw_webdriver::recorder::{Recording, Frame};
+    use lw_webdriver::error::ErrorKind;
+
+    /// a png image of 4x4 pixels, white except the pixels listed
+    fn image(colored: &[(u32, u32, [u8; 4])]) -> Vec<u8> {
+        let mut image = image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 255, 255, 255]));
+        for (x, y, color) in colored {
+            image.put_pixel(*x, *y, image::Rgba(*color));
+        }
+        let mut png = Vec::new();
+        image::png::PngEncoder::new(&mut png).encode(image.as_raw(), 4, 4, image::ColorType::Rgba8).unwrap();
+        png
+    }
+
+    let white = image(&[]);
+    let result = diff(&white, &white).unwrap();
+    assert!(result.is_identical());
+    assert_eq!((result.width, result.height, result.compared_pixels), (4, 4, 16));
+
+    let dotted = image(&[(0, 0, [0, 0, 0, 255]), (3, 3, [250, 250, 250, 255])]);
+    let result = diff(&white, &dotted).unwrap();
+    assert_eq!(result.different_pixels, 2);
+    assert!((result.diff_ratio() - 0.125).abs() < 1e-9);
+    assert!(!result.diff_image.is_empty());
+
+    let result = diff_with_options(&white, &dotted, &DiffOptions::new().threshold(10)).unwrap();
+    assert_eq!(result.different_pixels, 1);
+    let result = diff_with_options(&white, &dotted, &DiffOptions::new().threshold(10).ignore(Region::new(0, 0, 2, 2))).unwrap();
+    assert!(result.is_identical());
+    assert_eq!(result.compared_pixels, 12);
+
+    let rect = lw_webdriver::elements::Rect { x: 0.5, y: 1.2, width: 1.0, height: 1.0 };
+    assert_eq!(Region::from(rect), Region::new(0, 1, 2, 2));
+
+    let mut large = Vec::new();
+    image::png::PngEncoder::new(&mut large).encode(&[0; 4 * 4 * 5], 4, 5, image::ColorType::Rgba8).unwrap();
+    assert!(diff(&white, &large).unwrap_err() == ErrorKind::InvalidArgument);
+    assert!(diff(&white, b"not a png").unwrap_err() == ErrorKind::InvalidArgument);
+
+    let recording = Recording {
+        frames: vec![
+            Frame { time: std::time::Duration::from_millis(0), png: white },
+            Frame { time: std::time::Duration::from_millis(200), png: dotted }
+        ]
+    };
+    let path = std::env::temp_dir().join("lw_webdriver_recording.gif");
+    recording.save_gif(&path).unwrap();
+    assert!(std::fs::read(&path).unwrap().starts_with(b"GIF89a"));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+#[cfg(feature = "ocr")]
+fn find_text_visually() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("http://example.com").unwrap();
+        let location = session.tabs[0].find_text_visually("Example Domain").unwrap().unwrap();
+        let (x, y) = location.center();
+        let tag = session.tabs[0].execute_script("return document.elementFromPoint(arguments[0], arguments[1]).tagName;", vec![x.into(), y.into()]).unwrap();
+        assert_eq!(tag, "H1");
+        assert!(session.tabs[0].find_text_visually("not displayed anywhere").unwrap().is_none());
+    }
+}
+
+#[test]
+fn permissions() {
+    use lw_webdriver::permissions::{Permission, PermissionState};
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+        let query = "return navigator.permissions.query({name: arguments[0]}).then(status => status.state);";
+        if session.set_permission(Permission::Geolocation, PermissionState::Granted).is_err() {
+            assert_eq!(i, 0, "Chrome supports every permission");
+            continue;
+        }
+        assert_eq!(session.tabs[0].execute_script(query, vec!["geolocation".into()]).unwrap(), "granted");
+        session.set_permission(Permission::Descriptor(object!{"name" => "notifications"}), PermissionState::Denied).unwrap();
+        assert_eq!(session.tabs[0].execute_script(query, vec!["notifications".into()]).unwrap(), "denied");
+    }
+}
+
+#[test]
+fn network_interception() {
+    use lw_webdriver::interception::{NetworkInterceptor, Interception};
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("http://example.com").unwrap();
+        let interceptor = NetworkInterceptor::new()
+            .block("*/analytics*")
+            .respond("*/api/user", 200, "application/json", r#"{"name": "test"}"#)
+            .set_header("*/api/*", "X-Test", "1");
+        session.tabs[0].intercept_network(&interceptor).unwrap();
+
+        let name = session.tabs[0].execute_script("return fetch('/api/user').then(response => response.json()).then(user => user.name);", Vec::new()).unwrap();
+        assert_eq!(name, "test");
+        let text = session.tabs[0].execute_script("
+            return new Promise(resolve => {
+                const request = new XMLHttpRequest();
+                request.open('GET', '/api/user');
+                request.onload = () => resolve(request.status + ' ' + request.responseText);
+                request.send();
+            });
+        ", Vec::new()).unwrap();
+        assert_eq!(text, r#"200 {"name": "test"}"#);
+        let blocked = session.tabs[0].execute_script("return fetch('/analytics.js').then(() => false, () => true);", Vec::new()).unwrap();
+        assert_eq!(blocked, true);
+        session.tabs[0].execute_script("return fetch('/api/other').then(() => null, () => null);", Vec::new()).unwrap();
+
+        let requests = session.tabs[0].intercepted_requests().unwrap();
+        let interceptions: Vec<Interception> = requests.iter().map(|request| request.interception).collect();
+        assert_eq!(interceptions, vec![Interception::Stubbed, Interception::Stubbed, Interception::Blocked, Interception::Modified]);
+        assert_eq!(requests[0].url, "http://example.com/api/user");
+        assert_eq!(requests[0].method, "GET");
+
+        session.tabs[0].stop_network_interception().unwrap();
+        let status = session.tabs[0].execute_script("return fetch('/api/user').then(response => response.status);", Vec::new()).unwrap();
+        assert_eq!(status, 404);
+
+        // on Chrome, the rules also apply to the next pages and to navigations
+        if i == 1 {
+            session.tabs[0].intercept_network(&NetworkInterceptor::new().respond("*/stubbed", 200, "text/html", "<h1>stubbed</h1>")).unwrap();
+            session.tabs[0].navigate("http://example.com/stubbed").unwrap();
+            assert_eq!(session.tabs[0].execute_script("return document.querySelector('h1').textContent;", Vec::new()).unwrap(), "stubbed");
+            assert_eq!(session.tabs[0].intercepted_requests().unwrap()[0].url, "http://example.com/stubbed");
+        }
+    }
+}
+
+#[test]
+fn scraping_pipeline() {
+    use lw_webdriver::pipeline::{Pipeline, Field, FieldType, Source, PostProcessor};
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Product {
+        name: String,
+        price: Option<f64>,
+        stock: i64,
+        tags: Vec<String>,
+        available: bool
+    }
+
+    fn data_url(html: &str) -> String {
+        let mut url = String::from("data:text/html,");
+        for byte in html.bytes() {
+            match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => url.push(byte as char),
+                _ => url.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        url
+    }
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    let second_page = data_url("<div class='product' data-available='no'><h2> Lamp </h2><span class='price'>unknown</span><span class='stock'>1,204 left</span></div>");
+    let first_page = data_url(&format!("
+        <div class='product' data-available='yes'><h2>Chair</h2><span class='price'>$12.50</span><span class='stock'>3 left</span><i>wood</i><i>brown</i></div>
+        <div class='product' data-available='yes'><h2>Table</h2><span class='price'>$80</span><span class='stock'>0 left</span></div>
+        <a class='next' href='{}'>next</a>
+    ", second_page));
+
+    let pipeline = Pipeline::new(".product")
+        .field(Field::text("name", "h2").post_process(PostProcessor::Uppercase))
+        .field(Field::text("price", ".price").float())
+        .field(Field::text("stock", ".stock").integer())
+        .field(Field::text("tags", "i").multiple(true))
+        .field(Field::new("available", None, Source::Attribute("data-available".to_string())).field_type(FieldType::Boolean))
+        .next_page("a.next");
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate(&first_page).unwrap();
+        let products: Vec<Product> = pipeline.run(&mut session.tabs[0]).unwrap();
+        assert_eq!(products, vec![
+            Product { name: "CHAIR".to_string(), price: Some(12.5), stock: 3, tags: vec!["wood".to_string(), "brown".to_string()], available: true },
+            Product { name: "TABLE".to_string(), price: Some(80.0), stock: 0, tags: Vec::new(), available: true },
+            Product { name: "LAMP".to_string(), price: None, stock: 1204, tags: Vec::new(), available: false },
+        ]);
+
+        session.tabs[0].navigate(&first_page).unwrap();
+        let first_page_only: Vec<Product> = pipeline.clone().max_pages(1).run(&mut session.tabs[0]).unwrap();
+        assert_eq!(first_page_only.len(), 2);
+    }
+}
+
+#[test]
+fn console_logs() {
+    use lw_webdriver::console::LogLevel;
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("http://example.com").unwrap();
+        session.tabs[0].capture_console_logs().unwrap();
+        session.tabs[0].get_console_logs().unwrap();
+        session.tabs[0].execute_script("console.warn('careful'); console.error('failure'); setTimeout(() => { throw new Error('uncaught'); });", Vec::new()).unwrap();
+        session.tabs[0].execute_script("return new Promise(resolve => setTimeout(resolve, 100));", Vec::new()).unwrap();
+
+        let logs = session.tabs[0].get_console_logs().unwrap();
+        let levels: Vec<LogLevel> = logs.iter().map(|entry| entry.level).collect();
+        assert_eq!(levels, vec![LogLevel::Warning, LogLevel::Error, LogLevel::Error]);
+        assert!(logs[0].text.contains("careful"));
+        assert_eq!(logs[0].source, "console-api");
+        assert_eq!(logs[2].source, "javascript");
+        assert!(logs[2].text.contains("uncaught"));
+        assert!(session.tabs[0].get_console_logs().unwrap().is_empty());
+    }
+}
+
+#[test]
+fn har_recording() {
+    use lw_webdriver::capabilities::Capabilities;
+
+    catch_unwind(|| {
+        env_logger::init();
+    });
+
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new_with_capabilities(Capabilities::new(Browser::Chrome).network_log(true)).unwrap()
+            }
+        };
+
+        session.tabs[0].start_har_recording().unwrap();
+        session.tabs[0].navigate("http://example.com").unwrap();
+        let har = session.tabs[0].stop_har_recording().unwrap();
+        assert_eq!(har["log"]["version"], "1.2");
+        assert_eq!(har["log"]["pages"][0]["title"], "Example Domain");
+        let entry = har["log"]["entries"].members().find(|entry| entry["request"]["url"] == "http://example.com/").unwrap();
+        if i == 1 {
+            assert_eq!(entry["response"]["status"], 200);
+            assert!(!entry["response"]["headers"].is_empty());
+        }
+        assert!(session.tabs[0].stop_har_recording().is_err());
+    }
+}
+
+#[test]
+fn resource_timings() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+    
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+        let timings = session.tabs[0].resource_timings().unwrap();
+        assert!(!timings.is_empty());
+        for timing in timings {
+            assert!(timing.name.starts_with("http"));
+            assert!(timing.waiting + timing.download <= timing.duration);
+        }
+
+        let metrics = session.tabs[0].performance_metrics().unwrap();
+        let dom_content_loaded = metrics.dom_content_loaded.unwrap();
+        assert!(metrics.time_to_first_byte <= dom_content_loaded);
+        assert!(dom_content_loaded <= metrics.load.unwrap());
+        assert!(metrics.first_contentful_paint.is_some());
+    }
+}
+
+#[test]
+fn mock_transport() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+    use lw_webdriver::transport::Method;
+
+    let driver = MockDriver::new().route(Method::Get, "/session/mock/url", "http://example.com/");
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone());
+    let session = Session::new_with_config(config).unwrap();
+    assert_eq!(session.tabs.len(), 1);
+    assert_eq!(session.tabs[0].get_url().unwrap(), "http://example.com/");
+    assert_eq!(session.tabs[0].get_url().unwrap(), "http://example.com/");
+    drop(session);
+
+    // the tab is selected once
+    let requests = driver.sent();
+    assert_eq!(requests.iter().filter(|request| request.as_str() == "POST /session/mock/window").count(), 1);
+    assert!(!requests.contains(&"GET /session/mock/window".to_string()));
+    assert_eq!(requests[0], "POST /session");
+    assert!(requests.contains(&"DELETE /session/mock/window".to_string()));
+    assert_eq!(requests.last().unwrap(), "DELETE /session/mock");
+}
+
+#[test]
+fn timezone_override() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+    use lw_webdriver::transport::Method;
+
+    let cdp_commands = |driver: &MockDriver| -> Vec<json::JsonValue> {
+        driver.requests().iter().filter(|request| request.path == "/session/mock/goog/cdp/execute").map(MockRequest::json).collect()
+    };
+
+    let driver = MockDriver::new().route(Method::Post, "/session/mock/window/new", json::object!{"handle" => "tab-2", "type" => "tab"});
+    let capabilities = Capabilities::new(Browser::Chrome).timezone("Asia/Tokyo");
+    let mut session = Session::new_with_config(SessionConfig::new(capabilities).transport(driver.clone())).unwrap();
+    session.open_tab().unwrap();
+    let expected = json::object!{"cmd" => "Emulation.setTimezoneOverride", "params" => json::object!{"timezoneId" => "Asia/Tokyo"}};
+    assert_eq!(cdp_commands(&driver).iter().filter(|command| **command == expected).count(), 2);
+
+    let driver = MockDriver::new();
+    let capabilities = Capabilities::new(Browser::Firefox).timezone("Asia/Tokyo");
+    let _session = Session::new_with_config(SessionConfig::new(capabilities).transport(driver.clone())).unwrap();
+    assert!(cdp_commands(&driver).is_empty());
+}
+
+#[test]
+fn file_upload() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, command::Command, error::ErrorKind};
+    use lw_webdriver::transport::Method;
+
+    let file = std::env::temp_dir().join("report.txt");
+    std::fs::write(&file, "uploaded content").unwrap();
+
+    // local drivers receive the path
+    let mut session = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).dry_run(true)).unwrap();
+    session.start_recording();
+    session.tabs[0].find(Selector::Css, "input[type=file]").unwrap().unwrap().upload_file(&file).unwrap();
+    let transcript = session.stop_recording();
+    assert!(transcript.entries.iter().any(|entry| matches!(&entry.command, Command::ElementSendKeys { text, .. } if *text == file.canonicalize().unwrap().to_string_lossy())));
+    let mut element = session.tabs[0].find(Selector::Css, "input[type=file]").unwrap().unwrap();
+    assert!(element.upload_file("missing/file.txt").unwrap_err() == ErrorKind::InvalidArgument);
+    drop(session);
+
+    // remote nodes receive the file first
+    let driver = MockDriver::new()
+        .route(Method::Post, "/session/mock/element", json::object!{"element-6066-11e4-a52e-4f735466cecf" => "input"})
+        .route(Method::Post, "/session/mock/se/file", "/remote/upload/report.txt");
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone());
+    let mut session = Session::new_with_config(config).unwrap();
+    session.tabs[0].find(Selector::Css, "input[type=file]").unwrap().unwrap().upload_file(&file).unwrap();
+    drop(session);
+
+    let requests = driver.requests();
+    let upload = requests.iter().find(|request| request.to_string() == "POST /session/mock/se/file").unwrap();
+    let archive = base64::decode(upload.json()["file"].as_str().unwrap()).unwrap();
+    assert_eq!(&archive[0..4], b"PK\x03\x04");
+    assert!(archive.windows(16).any(|window| window == b"uploaded content"));
+    let keys = requests.iter().find(|request| request.to_string() == "POST /session/mock/element/input/value").unwrap();
+    assert_eq!(keys.json()["text"], "/remote/upload/report.txt");
+}
+
+#[test]
+fn action_chains() {
+    use lw_webdriver::{actions::{ActionChain, MouseButton, Keys, Pointer, PointerProperties}, config::SessionConfig, capabilities::Capabilities};
+    use std::time::Duration;
+
+    let mut session = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).dry_run(true)).unwrap();
+    let element = session.tabs[0].find(Selector::Css, "a").unwrap().unwrap();
+
+    let actions = ActionChain::new()
+        .move_to_element(&element)
+        .click(MouseButton::Right)
+        .pause(Duration::from_millis(500))
+        .send_keys("a")
+        .scroll(0, 0, 0, 100)
+        .to_json();
+    assert_eq!(actions.len(), 3);
+    let (mouse, keyboard, wheel) = (&actions[0], &actions[1], &actions[2]);
+    assert_eq!(mouse["id"], "mouse");
+    assert_eq!(mouse["parameters"]["pointerType"], "mouse");
+    assert_eq!(keyboard["type"], "key");
+    assert_eq!(wheel["type"], "wheel");
+
+    // every source has an action at every tick
+    for source in actions.members() {
+        assert_eq!(source["actions"].len(), 7);
+    }
+    assert_eq!(mouse["actions"][0]["origin"], element.as_json_object());
+    assert_eq!(mouse["actions"][1], json::object!{"type" => "pointerDown", "button" => 2});
+    assert_eq!(mouse["actions"][3], json::object!{"type" => "pause", "duration" => 500});
+    assert_eq!(keyboard["actions"][4], json::object!{"type" => "keyDown", "value" => "a"});
+    assert_eq!(keyboard["actions"][0], json::object!{"type" => "pause", "duration" => 0});
+    assert_eq!(wheel["actions"][6]["deltaY"], 100);
+
+    let chord = ActionChain::new().key_chord(&[Keys::Control, Keys::Shift, Keys::from('k')]).to_json();
+    let values: Vec<(&str, &str)> = chord[0]["actions"].members().map(|action| (action["type"].as_str().unwrap(), action["value"].as_str().unwrap())).collect();
+    assert_eq!(values, vec![("keyDown", "\u{E009}"), ("keyDown", "\u{E008}"), ("keyDown", "k"), ("keyUp", "k"), ("keyUp", "\u{E008}"), ("keyUp", "\u{E009}")]);
+    assert_eq!(Keys::F5.to_char(), '\u{E035}');
+
+    // pens and fingers
+    let pen = Pointer::pen("pen");
+    let stroke = ActionChain::new()
+        .pointer_down(&pen, &PointerProperties::new().pressure(0.5).tilt(30, -10))
+        .pointer_move(&pen, 100, 50, Duration::from_millis(200), &PointerProperties::new().pressure(0.8).twist(90))
+        .pointer_up(&pen)
+        .to_json();
+    assert_eq!(stroke[0]["parameters"]["pointerType"], "pen");
+    assert_eq!(stroke[0]["actions"][0], json::object!{"type" => "pointerDown", "button" => 0, "pressure" => 0.5, "tiltX" => 30, "tiltY" => -10});
+    assert_eq!(stroke[0]["actions"][1]["duration"], 200);
+    assert_eq!(stroke[0]["actions"][1]["twist"], 90);
+
+    let pinch = ActionChain::new().move_to(0, 0).pinch(200, 100, 20, 120, Duration::from_millis(300)).to_json();
+    assert_eq!(pinch.len(), 3);
+    for finger in pinch.members().skip(1) {
+        assert_eq!(finger["parameters"]["pointerType"], "touch");
+        assert_eq!(finger["actions"].len(), 5);
+        assert_eq!(finger["actions"][0]["type"], "pause");
+    }
+    assert_eq!(pinch[0]["actions"].len(), 5);
+    assert_eq!((pinch[1]["actions"][1]["x"].as_i64(), pinch[2]["actions"][1]["x"].as_i64()), (Some(190), Some(210)));
+    assert_eq!((pinch[1]["actions"][3]["x"].as_i64(), pinch[2]["actions"][3]["x"].as_i64()), (Some(140), Some(260)));
+
+    session.tabs[0].perform(&ActionChain::new().double_click()).unwrap();
+    session.tabs[0].release_actions().unwrap();
+}
+
+#[test]
+fn quit() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+    use lw_webdriver::transport::Method;
+    use std::time::Duration;
+
+    let driver = MockDriver::new().route(Method::Post, "/session/mock/window/new", json::object!{"handle" => "other", "type" => "tab"});
+    let mut session = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone())).unwrap();
+    session.open_tab().unwrap();
+    driver.clear_requests();
+    session.quit().unwrap();
+
+    // the windows are closed with the session, which is deleted once
+    assert_eq!(driver.sent(), vec!["DELETE /session/mock".to_string()]);
+
+    // dropped sessions are deleted too, after their tabs are closed
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone()).shutdown_grace_period(Duration::from_millis(10));
+    let session = Session::new_with_config(config).unwrap();
+    driver.clear_requests();
+    drop(session);
+    assert_eq!(driver.sent().last().unwrap(), "DELETE /session/mock");
+    assert!(driver.sent().iter().any(|request| request == "DELETE /session/mock/window"));
+}
+
+#[test]
+fn closed_tabs() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, error::ErrorKind, enums::WebdriverObject};
+    use lw_webdriver::transport::Method;
+    use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
+
+    // a popup closed by the page once `closed` is set
+    let closed = Arc::new(AtomicBool::new(false));
+    let selected = Arc::new(Mutex::new("tab".to_string()));
+    let driver = MockDriver::new()
+        .route_with(Method::Get, "/session/mock/window/handles", {
+            let closed = Arc::clone(&closed);
+            move |_| if closed.load(Ordering::SeqCst) { json::array!["tab"].into() } else { json::array!["tab", "popup"].into() }
+        })
+        .route_with(Method::Post, "/session/mock/window", {
+            let (closed, selected) = (Arc::clone(&closed), Arc::clone(&selected));
+            move |request| {
+                let handle = request.json()["handle"].to_string();
+                if closed.load(Ordering::SeqCst) && handle == "popup" {
+                    return Reply::error(404, "no such window");
+                }
+                *selected.lock().unwrap() = handle;
+                Reply::Value(json::JsonValue::Null)
+            }
+        })
+        .route_with(Method::Get, "/session/mock/url", {
+            let (closed, selected) = (Arc::clone(&closed), Arc::clone(&selected));
+            move |_| if closed.load(Ordering::SeqCst) && *selected.lock().unwrap() == "popup" {
+                Reply::error(404, "no such window")
+            } else {
+                "https://example.com/".into()
+            }
+        });
+    let mut session = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver)).unwrap();
+    session.update_tabs().unwrap();
+    assert_eq!(session.tabs.len(), 2);
+    assert_eq!(session.tabs[1].get_url().unwrap(), "https://example.com/");
+    let handles = session.window_handles().unwrap();
+    assert_eq!(handles, vec![session.tabs[0].get_handle(), session.tabs[1].get_handle()]);
+    assert_eq!(handles[1].as_str(), "popup");
+    assert_eq!(WebdriverObject::get_id(&session.tabs[1]), &handles[1]);
+    assert_eq!(session.switch_to_window(&handles[1]).unwrap().get_handle(), handles[1]);
+
+    closed.store(true, Ordering::SeqCst);
+    assert!(session.tabs[1].get_url().unwrap_err() == ErrorKind::TabClosed);
+    assert!(session.switch_to_window(&handles[1]).err().unwrap() == ErrorKind::TabClosed);
+    session.update_tabs().unwrap();
+    assert_eq!(session.tabs.len(), 1);
+    assert_eq!(session.tabs[0].get_url().unwrap(), "https://example.com/");
+}
+
+#[test]
+fn stale_elements() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, error::ErrorKind, enums::WebdriverObject};
+    use lw_webdriver::transport::Method;
+    use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+
+    // a page rendering the element again after it is found
+    let renders = Arc::new(AtomicUsize::new(0));
+    let rendered = {
+        let renders = Arc::clone(&renders);
+        move || format!("render-{}", renders.load(Ordering::SeqCst))
+    };
+    let driver = MockDriver::new()
+        .route_with(Method::Post, "/session/mock/element", {
+            let rendered = rendered.clone();
+            move |_| json::object!{"element-6066-11e4-a52e-4f735466cecf" => rendered()}.into()
+        })
+        .route_with(Method::Get, "/session/mock/element/*/text", move |request| if request.path == format!("/session/mock/element/{}/text", rendered()) {
+            "counter".into()
+        } else {
+            Reply::error(404, "stale element reference")
+        });
+    let mut session = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver)).unwrap();
+    let counter = session.tabs[0].find(Selector::Css, "#counter").unwrap().unwrap();
+    assert_eq!(counter.get_text().unwrap(), "counter");
+
+    renders.store(1, Ordering::SeqCst);
+    assert!(counter.get_text().unwrap_err() == ErrorKind::StaleElementReference);
+    assert!(!session.get_stale_element_recovery());
+    session.set_stale_element_recovery(true);
+    assert_eq!(counter.get_text().unwrap(), "counter");
+    assert_eq!(counter.get_id(), "render-0");
+    assert_eq!(counter.as_json_object()["element-6066-11e4-a52e-4f735466cecf"], "render-1");
+}
+
+#[test]
+fn text_selectors() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+    use lw_webdriver::transport::Method;
+
+    let driver = MockDriver::new().route(Method::Post, "/session/mock/element", json::object!{"element-6066-11e4-a52e-4f735466cecf" => "element"});
+    let mut session = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone())).unwrap();
+    session.tabs[0].find(Selector::Text, " Sign  in ").unwrap().unwrap();
+    session.tabs[0].find(Selector::ContainsText, "it's").unwrap().unwrap();
+    session.tabs[0].find(Selector::Text, "say \"it's\"").unwrap().unwrap();
+    session.tabs[0].find(Selector::Css, "p").unwrap().unwrap();
+
+    let searches: Vec<json::JsonValue> = driver.requests().iter().filter(|request| request.path == "/session/mock/element").map(MockRequest::json).collect();
+    assert_eq!(searches[0], json::object!{"using" => "xpath", "value" => ".//*[normalize-space()='Sign in'][not(.//*[normalize-space()='Sign in'])]"});
+    assert_eq!(searches[1]["value"], ".//*[contains(normalize-space(), \"it's\")][not(.//*[contains(normalize-space(), \"it's\")])]");
+    assert_eq!(searches[2]["value"], ".//*[normalize-space()=concat('say \"it', \"'\", 's\"')][not(.//*[normalize-space()=concat('say \"it', \"'\", 's\"')])]");
+    assert_eq!(searches[3], json::object!{"using" => "css selector", "value" => "p"});
+    assert_eq!(Selector::Text.to_string(), "xpath");
+    assert_eq!(Selector::ContainsText.to_string(), "xpath");
+}
+
+#[test]
+fn screen_recorder() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, recorder::Recorder};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::{net::TcpListener, thread, time::Duration, fs};
+
+    // a driver answering over http, as the recorder opens its own connection
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut request_line = String::new();
+                    if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+                        return;
+                    }
+                    let mut length = 0;
+                    loop {
+                        let mut header = String::new();
+                        reader.read_line(&mut header).unwrap();
+                        if header.trim().is_empty() {
+                            break;
+                        }
+                        if let Some(value) = header.to_ascii_lowercase().strip_prefix("content-length:") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    reader.by_ref().take(length).read_to_end(&mut Vec::new()).unwrap();
+
+                    let value = match request_line.split(' ').take(2).collect::<Vec<&str>>().as_slice() {
+                        ["POST", "/session"] => json::object!{"sessionId" => "mock", "capabilities" => json::object!{}},
+                        ["GET", "/session/mock/window/handles"] => json::array!["tab"],
+                        ["GET", "/session/mock/window"] => "tab".into(),
+                        ["GET", "/session/mock/screenshot"] => base64::encode(b"frame").into(),
+                        _ => json::JsonValue::Null,
+                    };
+                    let body = json::object!{"value" => value}.dump();
+                    write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+                }
+            });
+        }
+    });
+
+    let session = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).remote(&url)).unwrap();
+    let recorder = Recorder::new(Duration::from_millis(10)).max_frames(3).start(&session).unwrap();
+    thread::sleep(Duration::from_millis(300));
+    let recording = recorder.stop();
+    assert_eq!(recording.frames.len(), 3);
+    assert!(recording.frames[0].time < recording.frames[2].time);
+    assert_eq!(recording.frames[0].png, b"frame");
+
+    let directory = std::env::temp_dir().join("lw_webdriver_recording");
+    let _ = fs::remove_dir_all(&directory);
+    recording.save_frames(&directory).unwrap();
+    assert_eq!(fs::read(directory.join("frame-00003.png")).unwrap(), b"frame");
+    assert!(!directory.join("frame-00004.png").exists());
+    fs::remove_dir_all(&directory).unwrap();
+
+    let dry_run = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).dry_run(true)).unwrap();
+    assert!(Recorder::new(Duration::from_millis(10)).start(&dry_run).is_err());
+}
+
+#[test]
+fn waits() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, error::ErrorKind, wait::Wait, locator::TestId};
+    use lw_webdriver::transport::Method;
+    use std::{cell::Cell, sync::{Arc, atomic::{AtomicUsize, Ordering}}, time::{Duration, Instant}};
+
+    // an element appearing at the third search, and a page loaded at the third check
+    let searches = Arc::new(AtomicUsize::new(0));
+    let loading_checks = Arc::new(AtomicUsize::new(0));
+    let driver = MockDriver::new()
+        .route_with(Method::Post, "/session/mock/element", {
+            let searches = Arc::clone(&searches);
+            move |_| if searches.fetch_add(1, Ordering::SeqCst) + 1 < 3 {
+                Reply::error(404, "no such element")
+            } else {
+                json::object!{"element-6066-11e4-a52e-4f735466cecf" => "spinner"}.into()
+            }
+        })
+        .route_with(Method::Post, "/session/mock/execute/sync", {
+            let checks = Arc::clone(&loading_checks);
+            // loaded at every third check
+            move |_| [false, false, true][checks.fetch_add(1, Ordering::SeqCst) % 3].into()
+        });
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver);
+    let mut session = Session::new_with_config(config).unwrap();
+    let tab = &mut session.tabs[0];
+
+    tab.set_poll_interval(Duration::from_millis(10));
+    let start = Instant::now();
+    tab.wait_for_element(Selector::Css, ".spinner", Duration::from_secs(5)).unwrap();
+    assert_eq!(searches.load(Ordering::SeqCst), 3);
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    searches.store(0, Ordering::SeqCst);
+    tab.set_poll_interval(Duration::from_millis(100));
+    assert!(matches!(tab.wait_for_element(Selector::Css, ".spinner", Duration::from_millis(50)), Err(error) if error == ErrorKind::Timeout));
+    assert_eq!(searches.load(Ordering::SeqCst), 2);
+
+    searches.store(0, Ordering::SeqCst);
+    tab.set_poll_interval(Duration::from_millis(10));
+    tab.wait_for_element_by(TestId("spinner"), Duration::from_secs(5)).unwrap();
+    assert_eq!(searches.load(Ordering::SeqCst), 3);
+
+    let checks = Cell::new(0);
+    tab.set_poll_interval(Duration::from_millis(10));
+    tab.wait_until(|_tab| {
+        checks.set(checks.get() + 1);
+        Ok(checks.get() == 4)
+    }, Duration::from_secs(5)).unwrap();
+    assert_eq!(checks.get(), 4);
+    assert!(tab.wait_until(|_tab| Ok(false), Duration::from_millis(50)).unwrap_err() == ErrorKind::Timeout);
+    assert!(tab.wait_until(|_tab| Err(ErrorKind::JavascriptError.into()), Duration::from_secs(5)).unwrap_err() == ErrorKind::JavascriptError);
+
+    let wait = Wait::new(Duration::from_millis(200)).interval(Duration::from_millis(10));
+    assert_eq!(wait.until(|| Ok(if checks.get() > 6 { Some(checks.get()) } else { checks.set(checks.get() + 1); None })).unwrap(), 7);
+    assert!(Wait::new(Duration::from_millis(0)).until(|| Ok(None::<()>)).unwrap_err() == ErrorKind::Timeout);
+
+    tab.wait_until_loaded(Duration::from_secs(5)).unwrap();
+    assert_eq!(loading_checks.load(Ordering::SeqCst), 3);
+    tab.navigate("http://example.com/").unwrap();
+    assert_eq!(loading_checks.load(Ordering::SeqCst), 3);
+    tab.set_wait_after_navigation(Some(Duration::from_secs(5)));
+    tab.navigate("http://example.com/").unwrap();
+    assert_eq!(loading_checks.load(Ordering::SeqCst), 6);
+    tab.refresh().unwrap();
+    assert_eq!(loading_checks.load(Ordering::SeqCst), 9);
+    tab.set_wait_after_navigation(Some(Duration::from_millis(0)));
+    assert!(tab.back().unwrap_err() == ErrorKind::Timeout);
+}
+
+#[test]
+fn timeouts_presets() {
+    use lw_webdriver::timeouts::Timeouts;
+    use std::time::Duration;
+
+    assert!(Timeouts::fast().page_load < Timeouts::ci().page_load);
+    assert!(Timeouts::ci().page_load < Timeouts::patient().page_load);
+    assert_eq!(Timeouts::fast() * 2, Timeouts {
+        script: Some(Duration::from_secs(10)),
+        page_load: Duration::from_secs(30),
+        implicit: Duration::from_secs(0)
+    });
+    assert_eq!(Timeouts::builder().script(Duration::from_secs(5)).page_load(Duration::from_secs(15)), Timeouts::fast());
+    assert_eq!(Timeouts::builder().no_script_timeout().implicit(Duration::from_millis(1500)).to_json(), json::object!{
+        "script" => json::JsonValue::Null,
+        "pageLoad" => 300_000,
+        "implicit" => 1500
+    });
+}
+
+#[test]
+fn scoped_timeouts() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, timeouts::Timeouts};
+    use lw_webdriver::transport::Method;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let previous = json::object!{"script" => 30000, "pageLoad" => 300000, "implicit" => 0};
+    let restores = AtomicUsize::new(0);
+    let driver = MockDriver::new()
+        .route(Method::Get, "/session/mock/timeouts", previous.clone())
+        .route_with(Method::Post, "/session/mock/timeouts", move |_| match restores.fetch_add(1, Ordering::SeqCst) {
+            3 => Reply::error(500, "unknown error"),
+            _ => Reply::Value(json::JsonValue::Null),
+        });
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone());
+    let mut session = Session::new_with_config(config).unwrap();
+    let set_timeouts = || -> Vec<json::JsonValue> {
+        driver.requests().iter().filter(|request| request.to_string() == "POST /session/mock/timeouts").map(|request| request.json()).collect()
+    };
+
+    // the previous timeouts are restored when the closure panics
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        session.with_timeouts(Timeouts::fast(), |_| panic!("the page is too slow")).unwrap();
+    }));
+    assert!(panicked.is_err());
+    assert_eq!(set_timeouts(), vec![Timeouts::fast().to_json(), previous.clone()]);
+
+    // the result of the closure is returned when the timeouts cannot be restored
+    assert_eq!(session.with_timeouts(Timeouts::patient(), |_| 42).unwrap(), 42);
+    assert_eq!(set_timeouts(), vec![Timeouts::fast().to_json(), previous.clone(), Timeouts::patient().to_json(), previous]);
+}
+
+#[test]
+fn scoped_scroll() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+    use lw_webdriver::transport::Method;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let scrolls = AtomicUsize::new(0);
+    let driver = MockDriver::new().route_with(Method::Post, "/session/mock/execute/sync", move |request| {
+        if request.json()["script"].to_string().contains("scrollX") {
+            json::array![0, 200].into()
+        } else if scrolls.fetch_add(1, Ordering::SeqCst) == 1 {
+            Reply::error(500, "javascript error")
+        } else {
+            Reply::Value(json::JsonValue::Null)
+        }
+    });
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone());
+    let mut session = Session::new_with_config(config).unwrap();
+    let scrolled = || -> Vec<json::JsonValue> {
+        driver.requests().iter()
+            .filter(|request| request.path == "/session/mock/execute/sync" && request.json()["script"].to_string().contains("scrollTo"))
+            .map(|request| request.json()["args"].clone())
+            .collect()
+    };
+
+    // the position is restored when the closure panics
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        session.tabs[0].preserve_scroll(|_| panic!("the page is broken")).unwrap();
+    }));
+    assert!(panicked.is_err());
+    assert_eq!(scrolled(), vec![json::array![0.0, 200.0]]);
+
+    // the result of the closure is returned when the position cannot be restored
+    assert_eq!(session.tabs[0].preserve_scroll(|_| 42).unwrap(), 42);
+    assert_eq!(scrolled().len(), 2);
+}
+
+#[test]
+fn pool_driver_owner() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, pool::SessionPool, enums::WebdriverObject};
+    use lw_webdriver::transport::{Transport, HttpTransport, Method};
+
+    // the first session launches the driver, the second one is created on it
+    let config = SessionConfig::new(Capabilities::new(Browser::Chrome).headless(true)).port(9517);
+    let pool = SessionPool::new(config, 2).unwrap();
+    let other = pool.get().unwrap();
+    let owner = pool.get().unwrap();
+    let owner_id = owner.get_id().clone();
+    drop(other);
+    drop(owner);
+
+    // the browser of the owner stops responding while it is idle
+    HttpTransport::new("http://localhost:9517").send(Method::Delete, &format!("/session/{}", owner_id), None).unwrap();
+
+    let mut session = pool.get().unwrap();
+    assert_ne!(session.get_id(), &owner_id);
+    session.tabs[0].navigate("http://example.com/").unwrap();
+    drop(session);
+    assert_eq!(pool.idle_count(), 2);
+
+    // the other session stopped with the driver of the owner and is replaced by a session created on the new driver
+    let _replacement = pool.get().unwrap();
+    let mut session = pool.get().unwrap();
+    session.tabs[0].navigate("http://example.com/").unwrap();
+}
+
+#[test]
+fn retry_policy() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, retry::RetryPolicy};
+    use lw_webdriver::transport::Method;
+    use std::{sync::{Arc, atomic::{AtomicUsize, Ordering}}, time::Duration};
+
+    // a proxy failing the first requests to the url
+    let failures = Arc::new(AtomicUsize::new(2));
+    let flaky = |value: json::JsonValue| {
+        let failures = Arc::clone(&failures);
+        move |_: &MockRequest| match failures.load(Ordering::SeqCst) {
+            0 => Reply::Value(value.clone()),
+            _ => {
+                failures.fetch_sub(1, Ordering::SeqCst);
+                Reply::Raw(502, b"Bad Gateway")
+            },
+        }
+    };
+    let driver = MockDriver::new()
+        .route_with(Method::Get, "/session/mock/url", flaky("http://example.com/".into()))
+        .route_with(Method::Post, "/session/mock/url", flaky(json::JsonValue::Null));
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone());
+    let mut session = Session::new_with_config(config).unwrap();
+    assert!(session.tabs[0].get_url().is_err());
+    assert_eq!(failures.load(Ordering::SeqCst), 1);
+
+    failures.store(2, Ordering::SeqCst);
+    session.set_retry_policy(RetryPolicy::new(3).initial_backoff(Duration::from_millis(1)));
+    assert_eq!(session.tabs[0].get_url().unwrap(), "http://example.com/");
+    assert_eq!(failures.load(Ordering::SeqCst), 0);
+
+    failures.store(5, Ordering::SeqCst);
+    assert!(session.tabs[0].get_url().is_err());
+    assert_eq!(failures.load(Ordering::SeqCst), 2);
+
+    // navigating twice could have side effects
+    failures.store(2, Ordering::SeqCst);
+    assert!(session.tabs[0].navigate("http://example.com/").is_err());
+    assert_eq!(failures.load(Ordering::SeqCst), 1);
+
+    // a stale element is found again instead of being sent again
+    let found = AtomicUsize::new(0);
+    let driver = driver
+        .route_with(Method::Post, "/session/mock/element", move |_| match found.fetch_add(1, Ordering::SeqCst) {
+            0 => json::object!{"element-6066-11e4-a52e-4f735466cecf" => "replaced"}.into(),
+            _ => json::object!{"element-6066-11e4-a52e-4f735466cecf" => "rendered"}.into(),
+        })
+        .route_with(Method::Get, "/session/mock/element/replaced/text", |_| Reply::error(404, "stale element reference"))
+        .route(Method::Get, "/session/mock/element/rendered/text", "Sign in");
+    let element = session.tabs[0].find(Selector::Css, "button").unwrap().unwrap();
+    driver.clear_requests();
+    assert_eq!(element.get_text().unwrap(), "Sign in");
+    assert_eq!(driver.sent(), vec![
+        "GET /session/mock/element/replaced/text",
+        "POST /session/mock/element",
+        "GET /session/mock/element/rendered/text"
+    ]);
+}
+
+#[test]
+fn persisted_credentials() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, error::ErrorKind};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::{net::TcpListener, thread, sync::{Arc, Mutex}};
+
+    // a remote server recording whether the requests were authenticated
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let authenticated = Arc::new(Mutex::new(Vec::new()));
+    let requests = Arc::clone(&authenticated);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut request_line = String::new();
+                if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+                    break;
+                }
+                let (mut length, mut authorization) = (0, false);
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    let header = header.to_ascii_lowercase();
+                    authorization |= header.starts_with("authorization:");
+                    if let Some(value) = header.strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                reader.by_ref().take(length).read_to_end(&mut Vec::new()).unwrap();
+                requests.lock().unwrap().push(authorization);
+
+                let value = match request_line.split(' ').take(2).collect::<Vec<&str>>().as_slice() {
+                    ["POST", "/session"] => json::object!{"sessionId" => "mock", "capabilities" => json::object!{}},
+                    ["GET", "/session/mock/window/handles"] => json::array!["tab"],
+                    _ => json::JsonValue::Null,
+                };
+                let body = json::object!{"value" => value}.dump();
+                write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+            }
+        }
+    });
+
+    let path = std::env::temp_dir().join("lw_webdriver_authenticated_session.json");
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).remote(&url).credentials("user", "password");
+    let mut session = Session::new_with_config(config).unwrap();
+    session.persist_to(&path).unwrap();
+    drop(session);
+    assert!(!std::fs::read_to_string(&path).unwrap().contains("password"));
+
+    // the credentials are not silently dropped
+    assert!(Session::resume_from(&path).err().unwrap() == ErrorKind::InvalidArgument);
+    let session = Session::resume_with_credentials(&path, "user", "password").unwrap();
+    assert_eq!(session.tabs.len(), 1);
+    assert!(authenticated.lock().unwrap().iter().all(|authorization| *authorization));
+}
+
+#[test]
+fn request_timeout() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+    use lw_webdriver::transport::{Transport, HttpTransport, Method};
+    use std::{time::{Duration, Instant}, net::TcpListener};
+
+    let driver = MockDriver::new()
+        .route(Method::Get, "/session/mock/url", "http://example.com/")
+        .route(Method::Post, "/session/mock/execute/sync", true);
+    let timeouts = || -> Vec<Option<Duration>> { driver.requests().iter().map(|request| request.timeout).collect() };
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox))
+        .transport(driver.clone())
+        .request_timeout(Duration::from_secs(30));
+    let mut session = Session::new_with_config(config).unwrap();
+    assert_eq!(session.get_request_timeout(), Some(Duration::from_secs(30)));
+    assert_eq!(timeouts()[0], None);
+
+    session.tabs[0].get_url().unwrap();
+    assert_eq!(timeouts().last(), Some(&Some(Duration::from_secs(30))));
+
+    session.tabs[0].navigate_with_timeout("http://example.com/", Duration::from_secs(120)).unwrap();
+    assert!(timeouts().contains(&Some(Duration::from_secs(120))));
+
+    session.set_request_timeout(None);
+    session.tabs[0].get_url().unwrap();
+    assert_eq!(timeouts().last(), Some(&None));
+
+    // a server accepting the connection but never answering
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let transport = HttpTransport::new(&format!("http://{}", listener.local_addr().unwrap())).timeout(Duration::from_millis(200));
+    let start = Instant::now();
+    assert!(transport.send(Method::Get, "/status", None).is_err());
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn kept_connections() {
+    use lw_webdriver::transport::{Transport, HttpTransport, Method};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::{net::TcpListener, thread, sync::{Arc, atomic::{AtomicUsize, Ordering}}};
+
+    // a server answering the first request of every connection and closing it during the second one
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let received = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&received);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            for answered in &[true, false] {
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                reader.by_ref().take(length).read_to_end(&mut Vec::new()).unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                if *answered {
+                    write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: 14\r\n\r\n{{\"value\":null}}").unwrap();
+                }
+            }
+        }
+    });
+
+    let transport = HttpTransport::new(&url);
+    assert!(transport.send(Method::Get, "/status", None).is_ok());
+    // the server may have run the command before closing the connection
+    assert!(transport.send(Method::Post, "/session/mock/url", Some("{}")).is_err());
+    assert_eq!(received.load(Ordering::SeqCst), 2);
+
+    assert!(transport.send(Method::Get, "/status", None).is_ok());
+    assert!(transport.send(Method::Get, "/status", None).is_ok());
+    assert_eq!(received.load(Ordering::SeqCst), 5);
+}
+
+#[test]
+fn http_responses() {
+    use lw_webdriver::transport::{Transport, HttpTransport, Method};
+    use std::io::{BufRead, BufReader, Write};
+    use std::{net::TcpListener, thread, sync::{Arc, atomic::{AtomicUsize, Ordering}}};
+
+    // a server answering every request with the same raw response, and closing the connection after it if asked
+    let serve = |response: &'static str, close: bool| -> (HttpTransport, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let transport = HttpTransport::new(&format!("http://{}", listener.local_addr().unwrap()));
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&connections);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                // the requests have no body
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 0 {
+                    if line.trim().is_empty() {
+                        stream.write_all(response.as_bytes()).unwrap();
+                        if close {
+                            break;
+                        }
+                    }
+                    line.clear();
+                }
+            }
+        });
+        (transport, connections)
+    };
+
+    let (transport, connections) = serve("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n6\r\n{\"valu\r\n8\r\ne\":null}\r\n0\r\n\r\n", false);
+    assert_eq!(transport.send(Method::Get, "/status", None).unwrap().body, b"{\"value\":null}");
+    assert_eq!(transport.send(Method::Get, "/status", None).unwrap().body, b"{\"value\":null}");
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+    // the connection is closed in the middle of a chunk
+    let (transport, _) = serve("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\ne\r\n{\"value\"", true);
+    assert!(transport.send(Method::Get, "/status", None).is_err());
+
+    // without a Content-Length, the body ends with the connection
+    let (transport, connections) = serve("HTTP/1.1 200 OK\r\n\r\n{\"value\":null}", true);
+    assert_eq!(transport.send(Method::Get, "/status", None).unwrap().body, b"{\"value\":null}");
+    assert_eq!(transport.send(Method::Get, "/status", None).unwrap().body, b"{\"value\":null}");
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+    // the connection is not kept when the server closes it
+    let (transport, connections) = serve("HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 14\r\n\r\n{\"value\":null}", true);
+    assert!(transport.send(Method::Get, "/status", None).is_ok());
+    assert!(transport.send(Method::Get, "/status", None).is_ok());
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+    // the headers are cut
+    let (transport, _) = serve("HTTP/1.1 200 OK\r\nContent-Le", true);
+    assert!(transport.send(Method::Get, "/status", None).is_err());
+
+    // the body would not fit in memory
+    let (transport, _) = serve("HTTP/1.1 200 OK\r\nContent-Length: 1000000000000\r\n\r\n", true);
+    assert!(transport.send(Method::Get, "/status", None).is_err());
+    let (transport, _) = serve("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nffffffffff\r\n", true);
+    assert!(transport.send(Method::Get, "/status", None).is_err());
+}
+
+#[test]
+fn persistence() {
+    catch_unwind(|| {
+        env_logger::init();
+    });
+    
+    for i in 0..2 {
+        let mut session = match i {
+            0 => {
+                info!("testing with Firefox");
+                Session::new(Browser::Firefox, false).unwrap()
+            },
+            _ => {
+                info!("testing with Chrome");
+                Session::new(Browser::Chrome, false).unwrap()
+            }
+        };
+
+        let path = std::env::temp_dir().join("lw_webdriver_session.json");
+        session.tabs[0].navigate("http://example.com/").unwrap();
+        session.open_tab().unwrap();
+        session.persist_to(&path).unwrap();
+        drop(session);
+
+        let session = Session::resume_from(&path).unwrap();
+        assert_eq!(session.tabs.len(), 2);
+        assert_eq!(&session.tabs[0].get_url().unwrap(), "http://example.com/");
+    }
+}
+
+#[test]
+fn error_details() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, error::ErrorKind};
+    use lw_webdriver::transport::Method;
+
+    // a driver rejecting every navigation
+    let driver = MockDriver::new().route_with(Method::Post, "/session/mock/url", |_| Reply::Status(400, json::object!{
+        "error" => "invalid argument",
+        "message" => "Malformed URL: not a url",
+        "stacktrace" => "RemoteError@chrome://remote/content/shared/RemoteError.sys.mjs:8:8",
+        "data" => json::object!{"url" => "not a url"}
+    }));
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver);
+    let mut session = Session::new_with_config(config).unwrap();
+    let error = session.tabs[0].navigate("not a url").unwrap_err();
+
+    assert!(error == ErrorKind::InvalidArgument);
+    assert_eq!(error.message(), Some("Malformed URL: not a url"));
+    assert_eq!(error.data()["url"], "not a url");
+    assert!(error.stacktrace().unwrap().starts_with("RemoteError"));
+    assert_eq!(error.status(), Some(400));
+    assert_eq!(error.command(), Some("POST /session/mock/url"));
+    assert_eq!(error.to_string(), "invalid argument (400 on POST /session/mock/url): Malformed URL: not a url");
+
+    let error: Box<dyn std::error::Error> = Box::new(error);
+    assert!(error.to_string().starts_with("invalid argument"));
+}
+
+#[test]
+fn execute_command() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, command::Command};
+    use lw_webdriver::transport::Method;
+
+    // a driver echoing the commands
+    let echo = |request: &MockRequest| format!("{} {}", request, request.body.as_deref().unwrap_or("-")).into();
+    let driver = MockDriver::new()
+        .route_with(Method::Post, "/session/mock/*", echo)
+        .route_with(Method::Get, "/session/mock/element/*", echo);
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver);
+    let session = Session::new_with_config(config).unwrap();
+
+    assert_eq!(session.execute(Command::Navigate { url: "http://example.com/".to_string() }).unwrap(), r#"POST /session/mock/url {"url":"http://example.com/"}"#);
+    assert_eq!(session.execute(Command::GetElementText { element_id: "element".to_string() }).unwrap(), "GET /session/mock/element/element/text -");
+    assert_eq!(session.execute(Command::Custom {
+        method: Method::Post,
+        path: "/window/maximize".to_string(),
+        body: Some(json::object!{})
+    }).unwrap(), "POST /session/mock/window/maximize {}");
+}
+
+#[test]
+fn thread_safety() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, tab::Tab, elements::Element, pool::SessionPool};
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Session>();
+    assert_send_sync::<Tab>();
+    assert_send_sync::<Element>();
+    assert_send_sync::<SessionPool>();
+
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).dry_run(true);
+    let mut session = Session::new_with_config(config).unwrap();
+    session.open_tab().unwrap();
+    let session = thread::spawn(move || {
+        session.tabs[1].navigate("https://example.com/").unwrap();
+        session
+    }).join().unwrap();
+
+    thread::scope(|scope| {
+        for tab in &session.tabs {
+            scope.spawn(move || tab.get_url().unwrap());
+        }
+    });
+    assert_eq!(session.tabs[1].get_url().unwrap(), "https://example.com/");
+}
+
+#[test]
+fn command_hooks() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+    use lw_webdriver::hooks::{Hook, Request};
+    use lw_webdriver::transport::{Method, Response};
+    use std::{sync::{Arc, Mutex}, time::Duration};
+
+    /// sends the navigations to a staging server and records the requests
+    struct StagingHook {
+        requests: Arc<Mutex<Vec<Request>>>
+    }
+
+    impl Hook for StagingHook {
+        fn before_command(&self, request: &mut Request) {
+            request.body = request.body.as_ref().map(|body| body.replace("https://example.com", "https://staging.example.com"));
+        }
+
+        fn after_command(&self, request: &Request, response: Result<&Response, &str>, latency: Duration) {
+            assert!(latency < Duration::from_secs(1));
+            assert_eq!(response.unwrap().status_code, 200);
+            self.requests.lock().unwrap().push(request.clone());
+        }
+    }
+
+    /// a hook implementing no method
+    struct NoopHook;
+
+    impl Hook for NoopHook {}
+
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    // only the staging server can be reached
+    let driver = MockDriver::new().route_with(Method::Post, "/session/mock/url", |request| if request.body.as_deref().unwrap().contains("staging") {
+        Reply::Value(json::JsonValue::Null)
+    } else {
+        Reply::Unreachable("unknown host".to_string())
+    });
+    let mut session = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver)).unwrap();
+    session.add_hook(StagingHook { requests: Arc::clone(&requests) });
+    session.add_hook(NoopHook);
+    session.tabs[0].navigate("https://example.com/login").unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].path, "/session/mock/window");
+    assert_eq!(requests[1], Request {
+        method: Method::Post,
+        path: "/session/mock/url".to_string(),
+        body: Some(r#"{"url":"https://staging.example.com/login"}"#.to_string())
+    });
+}
+
+#[test]
+fn hook_commands() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, elements::Element};
+    use lw_webdriver::hooks::{Hook, Request};
+    use lw_webdriver::transport::{Method, Response};
+    use std::{sync::{Arc, Mutex}, time::Duration};
+
+    /// reads the status banner after every navigation
+    struct BannerHook {
+        banner: Element,
+        texts: Arc<Mutex<Vec<String>>>
+    }
+
+    impl Hook for BannerHook {
+        fn after_command(&self, request: &Request, _response: Result<&Response, &str>, _latency: Duration) {
+            if request.path.ends_with("/url") && request.method == Method::Post {
+                self.texts.lock().unwrap().push(self.banner.get_text().unwrap());
+            }
+        }
+    }
+
+    let texts = Arc::new(Mutex::new(Vec::new()));
+    let driver = MockDriver::new()
+        .route(Method::Post, "/session/mock/element", json::object!{"element-6066-11e4-a52e-4f735466cecf" => "banner"})
+        .route(Method::Get, "/session/mock/element/banner/text", "logged in")
+        .route(Method::Post, "/session/mock/url", json::JsonValue::Null);
+    let mut session = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone())).unwrap();
+    let banner = session.tabs[0].find(Selector::Css, "#banner").unwrap().unwrap();
+    session.add_hook(BannerHook { banner, texts: Arc::clone(&texts) });
+    driver.clear_requests();
+
+    // the command sent by the hook waits for the navigation to be over instead of waiting forever
+    session.tabs[0].navigate("https://example.com/").unwrap();
+    session.tabs[0].navigate("https://example.com/account").unwrap();
+    assert_eq!(*texts.lock().unwrap(), vec!["logged in", "logged in"]);
+    assert_eq!(driver.sent(), vec![
+        "POST /session/mock/url",
+        "GET /session/mock/element/banner/text",
+        "POST /session/mock/url",
+        "GET /session/mock/element/banner/text"
+    ]);
+}
+
+#[test]
+fn dry_run() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, error::ErrorKind};
+    use lw_webdriver::permissions::{Permission, PermissionState};
+
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).dry_run(true);
+    let mut session = Session::new_with_config(config).unwrap();
+    session.open_tab().unwrap();
+    assert_eq!(session.tabs.len(), 2);
+    let index = session.open_window().unwrap();
+    assert_eq!(session.tabs[index].window_type(), Some(WindowType::Window));
+    session.tabs.remove(index);
+    assert!(session.find_tab_by_url(|url| url == "about:blank").unwrap().is_some());
+
+    let tab = &mut session.tabs[1];
+    tab.navigate("https://example.com/path?query#fragment").unwrap();
+    assert_eq!(tab.get_url().unwrap(), "https://example.com/path?query#fragment");
+    let element = tab.find(Selector::Css, "div.content > a[href^='https']").unwrap().unwrap();
+    assert_eq!(element.get_text().unwrap(), "");
+    tab.find(Selector::XPath, "//div[@id='content']/a[1]").unwrap();
+    assert!(tab.get_console_logs().unwrap().is_empty());
+    assert!(tab.stop_har_recording().is_err());
+    assert_eq!(element.rect().unwrap(), lw_webdriver::elements::Rect { x: 0.0, y: 0.0, width: 0.0, height: 0.0 });
+    assert!(element.is_displayed().unwrap() && !element.is_selected().unwrap());
+    element.submit().unwrap();
+    element.hover().unwrap();
+    element.context_click().unwrap();
+    assert!(element.find_by_css("span").unwrap().is_some());
+    assert!(element.find_by(lw_webdriver::locator::TestId("icon")).unwrap().is_some());
+    assert_eq!(element.computed_role().unwrap(), "");
+    assert_eq!(element.accessible_name().unwrap(), "");
+    assert_eq!(element.outer_html().unwrap(), "");
+    assert!(tab.execute_script_elements("return document.querySelectorAll('a');", Vec::new()).unwrap().is_empty());
+    assert!(element.find_by_xpath("./[").err().unwrap() == ErrorKind::InvalidSelector);
+    assert!(tab.find_by_link_text("More information...").unwrap().is_some());
+    tab.scroll_by(0.0, 100.0).unwrap();
+    tab.perform(&lw_webdriver::actions::ActionChain::new().scroll(0, 0, 0, 100)).unwrap();
+    assert!(tab.print(lw_webdriver::print::PrintOptions::new()).unwrap().is_empty());
+    assert!(tab.print(lw_webdriver::print::PrintOptions::new().scale(3.0)).unwrap_err() == ErrorKind::InvalidArgument);
+    assert!(tab.print(lw_webdriver::print::PrintOptions::new().margins(15.0, 1.0, 15.0, 1.0)).unwrap_err() == ErrorKind::InvalidArgument);
+    assert!(tab.print(lw_webdriver::print::PrintOptions::new().page_range("2-a")).unwrap_err() == ErrorKind::InvalidArgument);
+
+    assert!(tab.navigate("example.com").unwrap_err() == ErrorKind::InvalidArgument);
+    assert!(tab.navigate("https:///path").unwrap_err() == ErrorKind::InvalidArgument);
+    assert!(matches!(tab.find(Selector::Css, "div > "), Err(error) if error == ErrorKind::InvalidSelector));
+    assert!(matches!(tab.find(Selector::Css, "a[href='x'"), Err(error) if error == ErrorKind::InvalidSelector));
+    assert!(matches!(tab.find(Selector::XPath, "//div[@id='content'"), Err(error) if error == ErrorKind::InvalidSelector));
+    assert!(matches!(tab.find(Selector::TagName, "div p"), Err(error) if error == ErrorKind::InvalidSelector));
+
+    session.set_permission(Permission::Camera, PermissionState::Denied).unwrap();
+    assert!(session.set_permission(Permission::Descriptor(object!{}), PermissionState::Denied).unwrap_err() == ErrorKind::InvalidArgument);
+}
+
+#[test]
+fn downloads() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, error::ErrorKind};
+    use std::{fs, time::Duration};
+
+    let directory = std::env::temp_dir().join("lw_webdriver_downloads");
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("old.txt"), "already there").unwrap();
+
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox).download_directory(&directory)).dry_run(true);
+    let session = Session::new_with_config(config).unwrap();
+    let tab = &session.tabs[0];
+    assert_eq!(tab.downloads().unwrap().directory(), directory.as_path());
+    assert!(tab.in_progress_downloads().unwrap().is_empty());
+
+    fs::write(directory.join("report.csv"), "").unwrap();
+    fs::write(directory.join("report.csv.part"), "a,b").unwrap();
+    assert_eq!(tab.in_progress_downloads().unwrap(), vec![directory.join("report.csv.part")]);
+    assert!(tab.wait_for_download(Duration::from_millis(200)).unwrap_err() == ErrorKind::Timeout);
+
+    fs::rename(directory.join("report.csv.part"), directory.join("report.csv")).unwrap();
+    assert_eq!(tab.wait_for_download(Duration::from_secs(1)).unwrap(), directory.join("report.csv"));
+    assert!(tab.wait_for_download(Duration::from_millis(200)).is_err());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn transcript_export() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).dry_run(true);
+    let mut session = Session::new_with_config(config).unwrap();
+    session.tabs[0].navigate("https://example.com/").unwrap();
+    session.start_recording();
+
+    session.tabs[0].navigate("http://example.com/").unwrap();
+    session.tabs[0].get_url().unwrap();
+    session.open_tab().unwrap();
+    session.tabs[1].navigate("https://example.com/other").unwrap();
+    let mut element = session.tabs[0].find(Selector::Css, "a").unwrap().unwrap();
+    element.type_text("text").unwrap();
+    element.get_text().unwrap();
+    element.double_click().unwrap();
+    session.tabs[0].execute_script("arguments[0].focus();", vec![element.as_json_object()]).unwrap();
+
+    let transcript = session.stop_recording();
+    session.tabs[0].refresh().unwrap();
+    assert_eq!(transcript.browser, Browser::Firefox);
+    assert!(!transcript.entries.is_empty());
+
+    let code = transcript.to_rust("recorded");
+    assert!(code.contains("fn recorded() {"));
+    assert!(code.contains("Session::new(Browser::Firefox, false)"));
+    let lines: Vec<&str> = code.lines().map(|line| line.trim()).filter(|line| line.starts_with("session") || line.starts_with("let") || line.starts_with("element") || line.starts_with("assert")).collect();
+    assert_eq!(lines, vec![
+        "let mut session = Session::new(Browser::Firefox, false).unwrap();",
+        "session.tabs[0].navigate(\"http://example.com/\").unwrap();",
+        "assert_eq!(session.tabs[0].get_url().unwrap(), \"http://example.com/\");",
+        "session.open_tab().unwrap();",
+        "session.tabs[1].navigate(\"https://example.com/other\").unwrap();",
+        "let mut element_1 = session.tabs[0].find(Selector::Css, \"a\").unwrap().unwrap();",
+        "element_1.type_text(\"text\").unwrap();",
+        "assert_eq!(element_1.get_text().unwrap(), \"\");",
+        "session.tabs[0].execute_script(\"arguments[0].scrollIntoView({block: 'center', inline: 'center'});\", vec![element_1.as_json_object()]).unwrap();",
+        "element_1.double_click().unwrap();",
+        "session.tabs[0].execute_script(\"arguments[0].focus();\", vec![element_1.as_json_object()]).unwrap();",
+    ]);
+    assert!(!code.contains("refresh"));
+
+    let exported = transcript.to_json();
+    assert_eq!(exported["browser"], "Firefox");
+    assert_eq!(exported["entries"].len(), transcript.entries.len());
+    let navigation = &exported["entries"][0];
+    assert_eq!(navigation["command"], "Navigate");
+    assert_eq!(navigation["method"], "POST");
+    assert_eq!(navigation["path"], "/url");
+    assert_eq!(navigation["body"]["url"], "http://example.com/");
+    assert!(navigation["error"].is_null());
+    assert!(exported["entries"].members().any(|entry| entry["command"] == "GetCurrentUrl" && entry["value"] == "http://example.com/"));
+    assert!(transcript.entries.windows(2).all(|entries| entries[0].time <= entries[1].time));
+}
+
+#[test]
+fn replay_transcript() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, command::Command, transcript::Transcript, replay::Replay};
+    use lw_webdriver::transport::Method;
+    use std::sync::{Arc, Mutex};
+
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).dry_run(true);
+    let mut session = Session::new_with_config(config).unwrap();
+    session.start_recording();
+    session.tabs[0].navigate("https://example.com/login").unwrap();
+    session.open_tab().unwrap();
+    session.tabs[1].navigate("https://example.com/other").unwrap();
+    let mut element = session.tabs[0].find(Selector::Css, "input").unwrap().unwrap();
+    element.type_text("user").unwrap();
+    let script = session.stop_recording().to_json().dump();
+
+    let transcript = Transcript::from_json(&json::parse(&script).unwrap()).unwrap();
+    assert_eq!(transcript.browser, Browser::Firefox);
+    assert!(transcript.entries.iter().any(|entry| entry.command == Command::Navigate { url: "https://example.com/login".to_string() }));
+    assert!(Transcript::from_json(&json::object!{"browser" => "Netscape", "entries" => json::array![]}).is_err());
+
+    let handles = Arc::new(Mutex::new(vec!["first".to_string()]));
+    let driver = MockDriver::new()
+        .route(Method::Post, "/session", json::object!{"sessionId" => "replay"})
+        .route_with(Method::Get, "/session/replay/window/handles", {
+            let handles = Arc::clone(&handles);
+            move |_| handles.lock().unwrap().clone().into()
+        })
+        .route_with(Method::Post, "/session/replay/window/new", move |_| {
+            handles.lock().unwrap().push("second".to_string());
+            json::object!{"handle" => "second", "type" => "tab"}.into()
+        })
+        .route(Method::Post, "/session/replay/element", json::object!{"element-6066-11e4-a52e-4f735466cecf" => "replayed"});
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone());
+    let mut session = Session::new_with_config(config).unwrap();
+    Replay::new().substitute_url("https://example.com/", "http://localhost:8080/").run(&mut session, &transcript).unwrap();
+    assert_eq!(session.tabs.len(), 2);
+
+    let requests: Vec<String> = driver.requests().iter().map(|request| format!("{} {}", request, request.body.as_deref().unwrap_or_default())).collect();
+    let position = |request: &str| requests.iter().position(|sent| sent == request).unwrap_or_else(|| panic!("{} was not sent", request));
+    assert!(position("POST /session/replay/url {\"url\":\"http://localhost:8080/login\"}") < position("POST /session/replay/window/new {\"type\":\"tab\"}"));
+    assert!(position("POST /session/replay/window {\"handle\":\"second\"}") < position("POST /session/replay/url {\"url\":\"http://localhost:8080/other\"}"));
+    assert!(position("POST /session/replay/window {\"handle\":\"first\"}") < position("POST /session/replay/element/replayed/value {\"text\":\"user\"}"));
+}
+
+#[test]
+#[cfg(feature = "derive")]
+fn page_objects() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, error::ErrorKind};
+    use lw_webdriver::page::{PageObject, LazyElement};
+    use lw_webdriver::transport::Method;
+
+    #[derive(PageObject)]
+    struct Header {
+        #[selector(link_text = "Home")]
+        home: LazyElement,
+    }
+
+    #[derive(PageObject)]
+    struct LoginPage {
+        #[selector(css = "#username")]
+        username: LazyElement,
+        #[selector(xpath = "//p[@class='error']")]
+        error: LazyElement,
+        header: Header,
+    }
+
+    let driver = MockDriver::new().route_with(Method::Post, "/session/mock/element", |request| {
+        if request.json()["using"] == "xpath" {
+            Reply::error(404, "no such element")
+        } else {
+            json::object!{"element-6066-11e4-a52e-4f735466cecf" => "found"}.into()
+        }
+    });
+    let searches = || -> Vec<String> {
+        driver.requests().iter()
+            .filter(|request| request.path == "/session/mock/element")
+            .map(|request| format!("{} {}", request.json()["using"], request.json()["value"]))
+            .collect()
+    };
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone());
+    let session = Session::new_with_config(config).unwrap();
+
+    // nothing is located until the elements are used
+    let page = LoginPage::new(&session.tabs[0]);
+    assert!(searches().is_empty());
+
+    page.username.get().unwrap().type_text("user").unwrap();
+    page.username.get().unwrap();
+    assert!(page.header.home.exists().unwrap());
+    assert!(page.error.find().unwrap().is_none());
+    assert!(page.error.get().err().unwrap() == ErrorKind::NoSuchElement);
+    assert_eq!(searches(), vec![
+        "css selector #username",
+        "css selector #username",
+        "link text Home",
+        "xpath //p[@class='error']",
+        "xpath //p[@class='error']",
+    ]);
+}
+
+#[test]
+fn keep_alive() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+    use lw_webdriver::transport::Method;
+    use std::time::{Duration, Instant};
+
+    let driver = MockDriver::new()
+        .route(Method::Get, "/session/mock/timeouts", json::object!{"script" => 30000, "pageLoad" => 300000, "implicit" => 0})
+        .route(Method::Get, "/session/mock/url", "http://example.com/");
+    let pings = || driver.requests().iter().filter(|request| request.path == "/session/mock/timeouts").count();
+    let wait_for = |condition: &dyn Fn() -> bool| {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !condition() {
+            assert!(Instant::now() < deadline, "the condition was not met in time");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    };
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox))
+        .transport(driver.clone())
+        .keep_alive(Duration::from_millis(100));
+    let mut session = Session::new_with_config(config).unwrap();
+
+    // an idle session is pinged, without waiting longer than the interval for the response
+    wait_for(&|| pings() >= 2);
+    let ping = driver.requests().into_iter().find(|request| request.path == "/session/mock/timeouts").unwrap();
+    assert_eq!(ping.timeout, Some(Duration::from_millis(100)));
+
+    // a busy session is not
+    let before = pings();
+    for _ in 0..10 {
+        session.tabs[0].get_url().unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(pings() <= before + 1);
+
+    session.set_keep_alive(None);
+    let stopped = pings();
+    std::thread::sleep(Duration::from_millis(250));
+    assert_eq!(pings(), stopped);
+
+    session.set_keep_alive(Some(Duration::from_millis(50)));
+    wait_for(&|| pings() > stopped);
+    drop(session);
+    let dropped = pings();
+    std::thread::sleep(Duration::from_millis(150));
+    assert_eq!(pings(), dropped);
+}
+
+#[test]
+fn crash_recovery() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, error::ErrorKind};
+    use lw_webdriver::transport::Method;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    // a browser crashing once `crashed` is set, until a new session is created
+    let crashing_driver = |crashed: &Arc<AtomicBool>| {
+        let sessions = AtomicUsize::new(0);
+        let mut driver = MockDriver::new()
+            .route_with(Method::Post, "/session", move |_| match sessions.fetch_add(1, Ordering::SeqCst) {
+                0 => json::object!{"sessionId" => "first"}.into(),
+                _ => json::object!{"sessionId" => "second"}.into(),
+            })
+            .route(Method::Get, "/session/first/window/handles", json::array!["tab"])
+            .route(Method::Get, "/session/second/window/handles", json::array!["new-tab"])
+            .route(Method::Get, "/session/*/url", "http://example.com/")
+            .route(Method::Post, "/session/*/goog/cdp/execute", json::object!{});
+        for method in [Method::Get, Method::Post, Method::Delete] {
+            let crashed = Arc::clone(crashed);
+            driver = driver.route_with(method, "/session/first/*", move |_| if crashed.load(Ordering::SeqCst) {
+                Reply::error(404, "invalid session id")
+            } else {
+                Reply::Next
+            });
+        }
+        driver
+    };
+    let sent = |driver: &MockDriver, suffix: &str, field: &str| -> Vec<String> {
+        driver.requests().iter()
+            .filter(|request| request.method == Method::Post && request.path.ends_with(suffix))
+            .map(|request| format!("{} {}", request.path, request.json()[field]))
+            .collect()
+    };
+
+    let crashed = Arc::new(AtomicBool::new(false));
+    let driver = crashing_driver(&crashed);
+    let config = SessionConfig::new(Capabilities::new(Browser::Chrome).timezone("Europe/Paris"))
+        .transport(driver.clone())
+        .crash_recovery(true);
+    let mut session = Session::new_with_config(config).unwrap();
+    let restarts = Arc::new(Mutex::new(Vec::new()));
+    let restarted = Arc::clone(&restarts);
+    session.on_restart(move |restart| restarted.lock().unwrap().push((restart.previous_id.clone(), restart.id.clone(), restart.reopened_urls.clone())));
+
+    session.tabs[0].navigate("http://example.com/").unwrap();
+    crashed.store(true, Ordering::SeqCst);
+
+    // the command is sent again to a new session, on which the tab was reopened
+    assert_eq!(session.tabs[0].get_url().unwrap(), "http://example.com/");
+    assert_eq!(sent(&driver, "/url", "url"), vec!["/session/first/url http://example.com/", "/session/second/url http://example.com/"]);
+    assert_eq!(*restarts.lock().unwrap(), vec![("first".to_string(), "second".to_string(), vec!["http://example.com/".to_string()])]);
+    session.tabs[0].get_url().unwrap();
+    assert_eq!(restarts.lock().unwrap().len(), 1);
+
+    // the reopened tab keeps its settings and its handle
+    assert_eq!(sent(&driver, "/goog/cdp/execute", "cmd").last().unwrap(), "/session/second/goog/cdp/execute Emulation.setTimezoneOverride");
+    let handle = session.tabs[0].get_handle();
+    assert_eq!(session.window_handles().unwrap(), vec![handle.clone()]);
+    session.update_tabs().unwrap();
+    assert_eq!(session.tabs.len(), 1);
+    assert_eq!(session.tabs[0].get_handle(), handle);
+
+    // a command which may have been executed before the crash is not sent again
+    crashed.store(false, Ordering::SeqCst);
+    let driver = crashing_driver(&crashed);
+    let mut session = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone()).crash_recovery(true)).unwrap();
+    session.tabs[0].navigate("http://example.com/").unwrap();
+    crashed.store(true, Ordering::SeqCst);
+    match session.tabs[0].navigate("http://example.com/checkout").unwrap_err().kind {
+        ErrorKind::SessionRestarted(restarted) => assert_eq!((restarted.previous_id.as_str(), restarted.id.as_str()), ("first", "second")),
+        kind => panic!("unexpected error: {}", kind),
+    }
+    assert_eq!(sent(&driver, "/url", "url"), vec![
+        "/session/first/url http://example.com/",
+        "/session/first/url http://example.com/checkout",
+        "/session/second/url http://example.com/"
+    ]);
+    session.tabs[0].navigate("http://example.com/checkout").unwrap();
+
+    // a request failing while the driver is running is not a crash
+    let driver = MockDriver::new().route_with(Method::Get, "/session/mock/url", |_| Reply::Unreachable("connection reset".to_string()));
+    let session = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(driver.clone()).crash_recovery(true)).unwrap();
+    assert!(session.tabs[0].get_url().unwrap_err() == ErrorKind::FailedRequest);
+    assert_eq!(driver.requests().iter().filter(|request| request.method == Method::Post && request.path == "/session").count(), 1);
+
+    // without recovery, the crash is reported
+    crashed.store(false, Ordering::SeqCst);
+    let session = Session::new_with_config(SessionConfig::new(Capabilities::new(Browser::Firefox)).transport(crashing_driver(&crashed))).unwrap();
+    crashed.store(true, Ordering::SeqCst);
+    assert!(session.tabs[0].get_url().err().unwrap() == ErrorKind::InvalidSessionId);
+}
+
+#[test]
+fn driver_log() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+
+    let path = std::env::temp_dir().join("lw-webdriver-driver.log");
+    let _ = std::fs::remove_file(&path);
+    let config = SessionConfig::new(Capabilities::new(Browser::Firefox).headless(true))
+        .port(4451)
+        .driver_arg("--log=debug")
+        .driver_log_file(&path)
+        .driver_log_lines(5);
+    let mut session = Session::new_with_config(config).unwrap();
+    session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+
+    // the last lines are kept, the whole output is written to the file
+    let lines = session.driver_log();
+    assert!(!lines.is_empty() && lines.len() <= 5);
+    let file = std::fs::read_to_string(&path).unwrap();
+    assert!(file.lines().count() >= lines.len());
+    assert!(file.contains("Listening on"));
+    session.quit().unwrap();
+}
+
+#[test]
+fn parallel_drivers() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities};
+
+    // each session launches its own driver, on a free port
+    let threads: Vec<_> = (0..2).map(|_| std::thread::spawn(|| {
+        let config = SessionConfig::new(Capabilities::new(Browser::Firefox).headless(true));
+        let mut session = Session::new_with_config(config).unwrap();
+        session.tabs[0].navigate("https://mubelotix.dev/").unwrap();
+        assert_eq!(session.tabs[0].get_url().unwrap(), "https://mubelotix.dev/");
+        session.quit().unwrap();
+    })).collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+#[test]
+fn config_sources() {
+    use lw_webdriver::{config::SessionConfig, error::ErrorKind};
+
+    std::env::set_var("LW_WEBDRIVER_BROWSER", "chrome");
+    std::env::set_var("LW_WEBDRIVER_HEADLESS", "true");
+    std::env::set_var("LW_WEBDRIVER_URL", "http://grid.example.com:4444/wd/hub");
+    std::env::set_var("LW_WEBDRIVER_DRIVER_PATH", "");
+    let config = SessionConfig::from_env().unwrap();
+    assert_eq!(config.get_capabilities().get_browser(), Browser::Chrome);
+    let debug = format!("{:?}", config);
+    assert!(debug.contains("headless: true"));
+    assert!(debug.contains(r#"remote_url: Some("http://grid.example.com:4444/wd/hub")"#));
+    assert!(debug.contains("driver_path: None"));
+
+    std::env::set_var("LW_WEBDRIVER_HEADLESS", "maybe");
+    assert!(SessionConfig::from_env().err().unwrap() == ErrorKind::InvalidArgument);
+    std::env::remove_var("LW_WEBDRIVER_HEADLESS");
+
+    // the names are not case sensitive, and a path is a Chromium-based browser
+    std::env::set_var("LW_WEBDRIVER_BROWSER", "Firefox");
+    assert_eq!(SessionConfig::from_env().unwrap().get_capabilities().get_browser(), Browser::Firefox);
+    std::env::set_var("LW_WEBDRIVER_BROWSER", "/usr/bin/brave-browser");
+    assert_eq!(SessionConfig::from_env().unwrap().get_capabilities().get_browser(), Browser::ChromiumBased { binary: "/usr/bin/brave-browser".into() });
+    std::env::set_var("LW_WEBDRIVER_BROWSER", "chorme");
+    assert!(SessionConfig::from_env().err().unwrap() == ErrorKind::InvalidArgument);
+
+    for name in ["LW_WEBDRIVER_BROWSER", "LW_WEBDRIVER_HEADLESS", "LW_WEBDRIVER_URL", "LW_WEBDRIVER_DRIVER_PATH"] {
+        std::env::remove_var(name);
+    }
+    let config = SessionConfig::from_env().unwrap();
+    assert_eq!(config.get_capabilities().get_browser(), Browser::Firefox);
+    assert!(format!("{:?}", config).contains("remote_url: None"));
+
+    // the environment variables override the file (in the same test, as the variables are shared by the tests)
+    #[cfg(feature = "toml")]
+    {
+        let path = std::env::temp_dir().join("lw-webdriver-config.toml");
+        std::fs::write(&path, r#"
+            browser = "edge"
+            headless = true
+            url = "http://grid.example.com:4444/wd/hub"
+
+            [timeouts]
+            implicit = 2000
+            request = 60000
+
+            [capabilities]
+            acceptInsecureCerts = true
+            "se:options" = { recordVideo = false }
+        "#).unwrap();
+        let config = SessionConfig::from_file(&path).unwrap();
+        assert_eq!(config.get_capabilities().get_browser(), Browser::Edge);
+        let capabilities = config.get_capabilities().to_json();
+        assert_eq!(capabilities["capabilities"]["alwaysMatch"]["acceptInsecureCerts"], true);
+        assert_eq!(capabilities["capabilities"]["alwaysMatch"]["se:options"], object!{"recordVideo" => false});
+        let debug = format!("{:?}", config);
+        assert!(debug.contains("headless: true"));
+        assert!(debug.contains("implicit: 2s"));
+        assert!(debug.contains("request_timeout: Some(60s)"));
+
+        std::env::set_var("LW_WEBDRIVER_BROWSER", "firefox");
+        let config = SessionConfig::from_file(&path).unwrap().remote("http://localhost:4445");
+        std::env::remove_var("LW_WEBDRIVER_BROWSER");
+        assert_eq!(config.get_capabilities().get_browser(), Browser::Firefox);
+        assert!(format!("{:?}", config).contains(r#"remote_url: Some("http://localhost:4445")"#));
+
+        std::fs::write(&path, "browser = \"chrome\"\nheadles = true\n").unwrap();
+        assert!(SessionConfig::from_file(&path).err().unwrap() == ErrorKind::InvalidArgument);
+        std::fs::write(&path, "browser = \"Chrome\"\n").unwrap();
+        assert_eq!(SessionConfig::from_file(&path).unwrap().get_capabilities().get_browser(), Browser::Chrome);
+        std::fs::write(&path, "browser = \"chorme\"\n").unwrap();
+        assert!(SessionConfig::from_file(&path).err().unwrap() == ErrorKind::InvalidArgument);
+    }
+}
+
+#[test]
+fn version_check() {
+    use lw_webdriver::{config::SessionConfig, capabilities::Capabilities, error::ErrorKind, compatibility::VersionCheck};
+    use lw_webdriver::transport::Method;
+
+    // a chromedriver which does not support the browser
+    let outdated_driver = MockDriver::new().route(Method::Post, "/session", json::object!{
+        "sessionId" => "mock",
+        "capabilities" => json::object!{
+            "browserName" => "chrome",
+            "browserVersion" => "120.0.6099.109",
+            "chrome" => json::object!{"chromedriverVersion" => "114.0.5735.90 (386bc09e8f4f2e025eddae123f36f6263096ae49-refs/branch-heads/5735@{#1052})"}
+        }
+    });
+    let config = SessionConfig::new(Capabilities::new(Browser::Chrome)).transport(outdated_driver);
+    assert!(Session::new_with_config(config.clone()).is_ok());
+
+    let error = Session::new_with_config(config.version_check(VersionCheck::Error)).err().unwrap();
+    assert!(error == ErrorKind::SessionNotCreated);
+    assert_eq!(error.message(), Some("chromedriver 114.0.5735.90 supports Chrome 114, but Chrome 120.0.6099.109 is used"));
+
+    // a chromedriver refusing to create the session
+    let refusing_driver = MockDriver::new()
+        .route(Method::Get, "/status", json::object!{"ready" => true, "message" => "", "build" => json::object!{"version" => "114.0.5735.90"}})
+        .route_with(Method::Post, "/session", |_| Reply::Status(500, json::object!{
+            "error" => "session not created",
+            "message" => "session not created: This version of ChromeDriver only supports Chrome version 114\nCurrent browser version is 120.0.6099.109 with binary path /usr/bin/google-chrome"
+        }));
+    let config = SessionConfig::new(Capabilities::new(Browser::Chrome)).transport(refusing_driver);
+    let error = Session::new_with_config(config.clone()).err().unwrap();
+    assert!(error.message().unwrap().starts_with("session not created: This version of ChromeDriver"));
+
+    let error = Session::new_with_config(config.version_check(VersionCheck::Error)).err().unwrap();
+    assert!(error == ErrorKind::SessionNotCreated);
+    assert_eq!(error.message(), Some("chromedriver 114.0.5735.90 supports Chrome 114, but Chrome 120.0.6099.109 is used"));
+}
+
+lw_webdriver::webdriver_test! {
+    fn fixture_session(session) {
+        session.tabs[0].navigate("http://example.com/").unwrap();
+        assert_eq!(&session.tabs[0].get_url().unwrap(), "http://example.com/");
+        session.open_tab().unwrap();
+        assert_eq!(session.tabs.len(), 2);
+    }
+
+    #[should_panic(expected = "the page is broken")]
+    fn fixture_screenshot_on_panic(session) {
+        session.tabs[0].navigate("http://example.com/").unwrap();
+        panic!("the page is broken");
+    }
+}
+
+#[cfg(feature = "derive")]
+#[lw_webdriver::testing::webdriver_test]
+fn fixture_attribute(session: &mut Session) {
+    session.tabs[0].navigate("http://example.com/").unwrap();
+    assert_eq!(&session.tabs[0].get_url().unwrap(), "http://example.com/");
+}